@@ -0,0 +1,60 @@
+use anyhow::Error;
+
+use proxmox_router::cli::{CliCommand, CliCommandMap, CommandLineInterface};
+use proxmox_schema::api;
+
+use pdm_api_types::remotes::REMOTE_ID_SCHEMA;
+use pdm_api_types::Authid;
+
+use server::audit::AuditLogFilter;
+
+pub fn cli() -> CommandLineInterface {
+    CliCommandMap::new()
+        .insert("export", CliCommand::new(&API_METHOD_EXPORT_AUDIT_LOG))
+        .into()
+}
+
+#[api(
+    input: {
+        properties: {
+            since: {
+                type: Integer,
+                description: "Only export calls made since this UNIX epoch.",
+                optional: true,
+            },
+            until: {
+                type: Integer,
+                description: "Only export calls made until this UNIX epoch.",
+                optional: true,
+            },
+            user: {
+                type: Authid,
+                optional: true,
+            },
+            remote: {
+                schema: REMOTE_ID_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+)]
+/// Export the audit log as JSON lines, oldest entry first.
+fn export_audit_log(
+    since: Option<i64>,
+    until: Option<i64>,
+    user: Option<Authid>,
+    remote: Option<String>,
+) -> Result<(), Error> {
+    let filter = AuditLogFilter {
+        since,
+        until,
+        user,
+        remote,
+    };
+
+    for entry in server::audit::read_entries(&filter, None)?.iter().rev() {
+        println!("{}", serde_json::to_string(entry)?);
+    }
+
+    Ok(())
+}
@@ -8,6 +8,7 @@ use proxmox_router::RpcEnvironment;
 
 use proxmox_schema::api;
 
+mod audit;
 mod remotes;
 mod support_status;
 
@@ -18,11 +19,7 @@ fn main() {
     let priv_user = pdm_config::priv_user().expect("cannot get privileged user");
     proxmox_product_config::init(api_user, priv_user);
 
-    proxmox_access_control::init::init(
-        &pdm_api_types::AccessControlConfig,
-        pdm_buildcfg::configdir!("/access"),
-    )
-    .expect("failed to setup access control config");
+    server::acl::init();
 
     proxmox_log::Logger::from_env("PDM_LOG", proxmox_log::LevelFilter::INFO)
         .stderr()
@@ -32,6 +29,7 @@ fn main() {
     server::context::init().expect("could not set up server context");
 
     let cmd_def = CliCommandMap::new()
+        .insert("audit-log", audit::cli())
         .insert("remote", remotes::cli())
         .insert(
             "report",
@@ -4,7 +4,7 @@ use proxmox_config_digest::PROXMOX_CONFIG_DIGEST_SCHEMA;
 use proxmox_router::cli::{CliCommand, CliCommandMap, CommandLineInterface, OutputFormat};
 use proxmox_schema::api;
 
-use pdm_api_types::{AclUgidType, Authid, Role, ACL_PATH_SCHEMA, ACL_PROPAGATE_SCHEMA};
+use pdm_api_types::{AclUgidType, Authid, ACL_PATH_SCHEMA, ACL_PROPAGATE_SCHEMA, ROLE_ID_SCHEMA};
 use pdm_client::{AclRecipient, ConfigDigest};
 
 use crate::{client, env};
@@ -82,7 +82,7 @@ async fn list_acls(path: Option<String>, exact: bool) -> Result<(), Error> {
             path: {
                 schema: ACL_PATH_SCHEMA,
             },
-            role: { type: Role },
+            role: { schema: ROLE_ID_SCHEMA },
             propagate: {
                 schema: ACL_PROPAGATE_SCHEMA,
                 optional: true,
@@ -131,7 +131,7 @@ async fn update_acl(
             path: {
                 schema: ACL_PATH_SCHEMA,
             },
-            role: { type: Role },
+            role: { schema: ROLE_ID_SCHEMA },
             digest: {
                 schema: PROXMOX_CONFIG_DIGEST_SCHEMA,
                 optional: true,
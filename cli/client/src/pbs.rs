@@ -17,6 +17,7 @@ use crate::{client, env};
 pub fn cli() -> CommandLineInterface {
     CliCommandMap::new()
         .insert("datastore", datastore_cli())
+        .insert("job", job_cli())
         .insert("snapshot", snapshot_cli())
         .insert("node", node_cli())
         .insert("task", task_cli())
@@ -38,6 +39,28 @@ fn datastore_cli() -> CommandLineInterface {
                 "timeframe",
             ]),
         )
+        .insert(
+            "gc",
+            CliCommand::new(&API_METHOD_START_GARBAGE_COLLECTION)
+                .arg_param(&["remote", "datastore"]),
+        )
+        .insert(
+            "verify",
+            CliCommand::new(&API_METHOD_VERIFY_DATASTORE).arg_param(&["remote", "datastore"]),
+        )
+        .into()
+}
+
+fn job_cli() -> CommandLineInterface {
+    CliCommandMap::new()
+        .insert(
+            "list",
+            CliCommand::new(&API_METHOD_LIST_JOBS).arg_param(&["remote", "datastore"]),
+        )
+        .insert(
+            "run",
+            CliCommand::new(&API_METHOD_RUN_JOB).arg_param(&["remote", "datastore", "kind", "id"]),
+        )
         .into()
 }
 
@@ -107,6 +130,174 @@ async fn list_datastores(remote: String) -> Result<(), Error> {
     Ok(())
 }
 
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            datastore: { schema: DATASTORE_SCHEMA },
+        }
+    }
+)]
+/// Start a garbage collection on a datastore.
+async fn start_garbage_collection(remote: String, datastore: String) -> Result<(), Error> {
+    let upid = client()?
+        .pbs_datastore_start_gc(&remote, &datastore)
+        .await?;
+    println!("upid: {upid}");
+    Ok(())
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            datastore: { schema: DATASTORE_SCHEMA },
+            "ignore-verified": {
+                description: "Skip backups whose last verification is not outdated.",
+                optional: true,
+            },
+            "outdated-after": {
+                description: "Days after which a verification becomes outdated.",
+                optional: true,
+            },
+        }
+    }
+)]
+/// Verify the backups of a datastore.
+async fn verify_datastore(
+    remote: String,
+    datastore: String,
+    ignore_verified: Option<bool>,
+    outdated_after: Option<i64>,
+) -> Result<(), Error> {
+    let upid = client()?
+        .pbs_datastore_verify(&remote, &datastore, ignore_verified, outdated_after)
+        .await?;
+    println!("upid: {upid}");
+    Ok(())
+}
+
+#[api]
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// The kind of a datastore job.
+enum JobKind {
+    /// A prune job.
+    Prune,
+    /// A sync job.
+    Sync,
+    /// A verification job.
+    Verification,
+}
+
+fn print_job(kind: &str, id: &str, last_state: Option<&str>, next_run: Option<i64>) {
+    let last_state = last_state.unwrap_or("-");
+    let next_run = match next_run {
+        Some(next_run) => crate::time::format_epoch_lossy(next_run),
+        None => "-".to_string(),
+    };
+    println!("{kind} {id}: last state: {last_state}, next run: {next_run}");
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            datastore: { schema: DATASTORE_SCHEMA },
+        }
+    }
+)]
+/// List the garbage collection, prune, sync and verification jobs of a datastore.
+async fn list_jobs(remote: String, datastore: String) -> Result<(), Error> {
+    let client = client()?;
+
+    let gc = client.pbs_datastore_gc_status(&remote, &datastore).await?;
+    let prune = client.pbs_list_prune_jobs(&remote, &datastore).await?;
+    let sync = client.pbs_list_sync_jobs(&remote, &datastore).await?;
+    let verification = client
+        .pbs_list_verification_jobs(&remote, &datastore)
+        .await?;
+
+    let output_format = env().format_args.output_format;
+    if output_format == OutputFormat::Text {
+        for job in &gc {
+            print_job(
+                "gc",
+                &job.store,
+                job.last_run_state.as_deref(),
+                job.next_run,
+            );
+        }
+        for job in &prune {
+            let status = &job.status;
+            print_job(
+                "prune",
+                &job.config.id,
+                status.last_run_state.as_deref(),
+                status.next_run,
+            );
+        }
+        for job in &sync {
+            let status = &job.status;
+            print_job(
+                "sync",
+                &job.config.id,
+                status.last_run_state.as_deref(),
+                status.next_run,
+            );
+        }
+        for job in &verification {
+            let status = &job.status;
+            print_job(
+                "verification",
+                &job.config.id,
+                status.last_run_state.as_deref(),
+                status.next_run,
+            );
+        }
+    } else {
+        let data = serde_json::json!({
+            "gc": gc,
+            "prune": prune,
+            "sync": sync,
+            "verification": verification,
+        });
+        format_and_print_result(&data, &output_format.to_string());
+    }
+    Ok(())
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            datastore: { schema: DATASTORE_SCHEMA },
+            kind: { type: JobKind },
+            id: { schema: pbs_api_types::JOB_ID_SCHEMA },
+        }
+    }
+)]
+/// Run a prune, sync or verification job of a datastore now.
+async fn run_job(
+    remote: String,
+    datastore: String,
+    kind: JobKind,
+    id: String,
+) -> Result<(), Error> {
+    let client = client()?;
+    let upid = match kind {
+        JobKind::Prune => client.pbs_run_prune_job(&remote, &datastore, &id).await?,
+        JobKind::Sync => client.pbs_run_sync_job(&remote, &datastore, &id).await?,
+        JobKind::Verification => {
+            client
+                .pbs_run_verification_job(&remote, &datastore, &id)
+                .await?
+        }
+    };
+    println!("upid: {upid}");
+    Ok(())
+}
+
 #[api(
     input: {
         properties: {
@@ -13,7 +13,10 @@ use proxmox_rrd_api_types::{RrdMode, RrdTimeframe};
 use proxmox_schema::{api, ApiType, ArraySchema, ReturnType, Schema};
 
 use pdm_api_types::remotes::REMOTE_ID_SCHEMA;
-use pdm_api_types::{CIDR_FORMAT, NODE_SCHEMA, SNAPSHOT_NAME_SCHEMA, VMID_SCHEMA};
+use pdm_api_types::{
+    GuestBackupMode, CIDR_FORMAT, NODE_SCHEMA, PVE_STORAGE_ID_SCHEMA, SNAPSHOT_NAME_SCHEMA,
+    VMID_SCHEMA,
+};
 use pve_api_types::StartQemuMigrationType;
 
 use crate::{client, env};
@@ -80,6 +83,19 @@ fn qemu_cli() -> CommandLineInterface {
             CliCommand::new(&API_METHOD_REMOTE_MIGRATE_QEMU)
                 .arg_param(&["remote", "vmid", "target"]),
         )
+        .insert(
+            "clone",
+            CliCommand::new(&API_METHOD_CLONE_QEMU).arg_param(&["remote", "vmid", "newid"]),
+        )
+        .insert(
+            "destroy",
+            CliCommand::new(&API_METHOD_DESTROY_QEMU).arg_param(&["remote", "vmid"]),
+        )
+        .insert("snapshot", qemu_snapshot_cli())
+        .insert(
+            "backup",
+            CliCommand::new(&API_METHOD_BACKUP_QEMU).arg_param(&["remote", "vmid", "storage"]),
+        )
         .insert(
             "rrddata",
             CliCommand::new(&API_METHOD_GET_QEMU_RRD_DATA).arg_param(&[
@@ -92,6 +108,26 @@ fn qemu_cli() -> CommandLineInterface {
         .into()
 }
 
+fn qemu_snapshot_cli() -> CommandLineInterface {
+    CliCommandMap::new()
+        .insert(
+            "create",
+            CliCommand::new(&API_METHOD_CREATE_QEMU_SNAPSHOT)
+                .arg_param(&["remote", "vmid", "snapname"]),
+        )
+        .insert(
+            "delete",
+            CliCommand::new(&API_METHOD_DELETE_QEMU_SNAPSHOT)
+                .arg_param(&["remote", "vmid", "snapname"]),
+        )
+        .insert(
+            "rollback",
+            CliCommand::new(&API_METHOD_ROLLBACK_QEMU_SNAPSHOT)
+                .arg_param(&["remote", "vmid", "snapname"]),
+        )
+        .into()
+}
+
 fn lxc_cli() -> CommandLineInterface {
     CliCommandMap::new()
         .insert(
@@ -123,6 +159,19 @@ fn lxc_cli() -> CommandLineInterface {
             CliCommand::new(&API_METHOD_REMOTE_MIGRATE_LXC)
                 .arg_param(&["remote", "vmid", "target"]),
         )
+        .insert(
+            "clone",
+            CliCommand::new(&API_METHOD_CLONE_LXC).arg_param(&["remote", "vmid", "newid"]),
+        )
+        .insert(
+            "destroy",
+            CliCommand::new(&API_METHOD_DESTROY_LXC).arg_param(&["remote", "vmid"]),
+        )
+        .insert("snapshot", lxc_snapshot_cli())
+        .insert(
+            "backup",
+            CliCommand::new(&API_METHOD_BACKUP_LXC).arg_param(&["remote", "vmid", "storage"]),
+        )
         .insert(
             "rrddata",
             CliCommand::new(&API_METHOD_GET_LXC_RRD_DATA).arg_param(&[
@@ -135,6 +184,26 @@ fn lxc_cli() -> CommandLineInterface {
         .into()
 }
 
+fn lxc_snapshot_cli() -> CommandLineInterface {
+    CliCommandMap::new()
+        .insert(
+            "create",
+            CliCommand::new(&API_METHOD_CREATE_LXC_SNAPSHOT)
+                .arg_param(&["remote", "vmid", "snapname"]),
+        )
+        .insert(
+            "delete",
+            CliCommand::new(&API_METHOD_DELETE_LXC_SNAPSHOT)
+                .arg_param(&["remote", "vmid", "snapname"]),
+        )
+        .insert(
+            "rollback",
+            CliCommand::new(&API_METHOD_ROLLBACK_LXC_SNAPSHOT)
+                .arg_param(&["remote", "vmid", "snapname"]),
+        )
+        .into()
+}
+
 fn task_cli() -> CommandLineInterface {
     CliCommandMap::new()
         .insert(
@@ -671,6 +740,305 @@ async fn get_qemu_rrd_data(
     Ok(())
 }
 
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            newid: {
+                schema: VMID_SCHEMA,
+            },
+            name: {
+                type: String,
+                optional: true,
+                description: "Name of the new VM.",
+            },
+            target: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            full: {
+                type: bool,
+                optional: true,
+                description: "Create a full copy of all disks instead of a linked clone.",
+            },
+            storage: {
+                schema: PVE_STORAGE_ID_SCHEMA,
+                optional: true,
+            },
+            pool: {
+                type: String,
+                optional: true,
+                description: "Add the new VM to this resource pool.",
+            },
+        }
+    }
+)]
+#[allow(clippy::too_many_arguments)]
+/// Clone a VM, usually from a template.
+async fn clone_qemu(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    newid: u32,
+    name: Option<String>,
+    target: Option<String>,
+    full: Option<bool>,
+    storage: Option<String>,
+    pool: Option<String>,
+) -> Result<(), Error> {
+    let mut params = pdm_client::CloneGuest::new();
+    if let Some(target) = target {
+        params = params.target(target);
+    }
+    if let Some(full) = full {
+        params = params.full(full);
+    }
+    if let Some(storage) = storage {
+        params = params.storage(storage);
+    }
+    if let Some(pool) = pool {
+        params = params.pool(pool);
+    }
+
+    let client = client()?;
+    let upid = client
+        .pve_qemu_clone(
+            &remote,
+            node.as_deref(),
+            vmid,
+            newid,
+            name.as_deref(),
+            params,
+        )
+        .await?;
+    println!("upid: {upid}");
+    let status = client.pve_wait_for_task(&upid).await?;
+    println!("{status:#?}");
+
+    Ok(())
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            purge: {
+                description: "Remove the VM from backup jobs, replication jobs and HA.",
+                optional: true,
+            },
+        }
+    }
+)]
+/// Destroy a VM including its disks.
+async fn destroy_qemu(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    purge: Option<bool>,
+) -> Result<(), Error> {
+    let client = client()?;
+    let upid = client
+        .pve_qemu_destroy(&remote, node.as_deref(), vmid, purge)
+        .await?;
+    println!("upid: {upid}");
+    let status = client.pve_wait_for_task(&upid).await?;
+    println!("{status:#?}");
+
+    Ok(())
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            snapname: { schema: SNAPSHOT_NAME_SCHEMA },
+            description: {
+                type: String,
+                optional: true,
+                description: "A description of the snapshot.",
+            },
+            vmstate: {
+                type: bool,
+                optional: true,
+                description: "Include the RAM of the VM in the snapshot.",
+            },
+        }
+    }
+)]
+/// Create a snapshot of a VM.
+async fn create_qemu_snapshot(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    snapname: String,
+    description: Option<String>,
+    vmstate: Option<bool>,
+) -> Result<(), Error> {
+    let client = client()?;
+    let upid = client
+        .pve_qemu_create_snapshot(
+            &remote,
+            node.as_deref(),
+            vmid,
+            &snapname,
+            description.as_deref(),
+            vmstate,
+        )
+        .await?;
+    println!("upid: {upid}");
+    let status = client.pve_wait_for_task(&upid).await?;
+    println!("{status:#?}");
+
+    Ok(())
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            snapname: { schema: SNAPSHOT_NAME_SCHEMA },
+            start: {
+                description: "Start the VM after the rollback.",
+                optional: true,
+            },
+        }
+    }
+)]
+/// Roll a VM back to a snapshot.
+async fn rollback_qemu_snapshot(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    snapname: String,
+    start: Option<bool>,
+) -> Result<(), Error> {
+    let client = client()?;
+    let upid = client
+        .pve_qemu_rollback_snapshot(&remote, node.as_deref(), vmid, &snapname, start)
+        .await?;
+    println!("upid: {upid}");
+    let status = client.pve_wait_for_task(&upid).await?;
+    println!("{status:#?}");
+
+    Ok(())
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            snapname: { schema: SNAPSHOT_NAME_SCHEMA },
+            force: {
+                description: "Remove the snapshot from the config even if removing its disk snapshots fails.",
+                optional: true,
+            },
+        }
+    }
+)]
+/// Delete a snapshot of a VM.
+async fn delete_qemu_snapshot(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    snapname: String,
+    force: Option<bool>,
+) -> Result<(), Error> {
+    let client = client()?;
+    let upid = client
+        .pve_qemu_delete_snapshot(&remote, node.as_deref(), vmid, &snapname, force)
+        .await?;
+    println!("upid: {upid}");
+    let status = client.pve_wait_for_task(&upid).await?;
+    println!("{status:#?}");
+
+    Ok(())
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            storage: {
+                schema: PVE_STORAGE_ID_SCHEMA,
+            },
+            mode: {
+                type: GuestBackupMode,
+                optional: true,
+            },
+            "notes-template": {
+                type: String,
+                optional: true,
+                description: "Template for the notes of the backup.",
+            },
+            protected: {
+                description: "Protect the backup from being pruned.",
+                optional: true,
+            },
+        }
+    }
+)]
+/// Back up a VM now.
+async fn backup_qemu(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    storage: String,
+    mode: Option<GuestBackupMode>,
+    notes_template: Option<String>,
+    protected: Option<bool>,
+) -> Result<(), Error> {
+    let mut params = pdm_client::BackupGuest::new();
+    if let Some(mode) = mode {
+        params = params.mode(mode);
+    }
+    if let Some(notes_template) = notes_template {
+        params = params.notes_template(notes_template);
+    }
+    if let Some(protected) = protected {
+        params = params.protected(protected);
+    }
+
+    let client = client()?;
+    let upid = client
+        .pve_qemu_backup(&remote, node.as_deref(), vmid, &storage, params)
+        .await?;
+    println!("upid: {upid}");
+    let status = client.pve_wait_for_task(&upid).await?;
+    println!("{status:#?}");
+
+    Ok(())
+}
+
 #[api(
     input: {
         properties: {
@@ -1057,6 +1425,298 @@ async fn get_lxc_rrd_data(
     Ok(())
 }
 
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            newid: {
+                schema: VMID_SCHEMA,
+            },
+            hostname: {
+                type: String,
+                optional: true,
+                description: "Hostname of the new container.",
+            },
+            target: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            full: {
+                type: bool,
+                optional: true,
+                description: "Create a full copy of all disks instead of a linked clone.",
+            },
+            storage: {
+                schema: PVE_STORAGE_ID_SCHEMA,
+                optional: true,
+            },
+            pool: {
+                type: String,
+                optional: true,
+                description: "Add the new container to this resource pool.",
+            },
+        }
+    }
+)]
+#[allow(clippy::too_many_arguments)]
+/// Clone a container, usually from a template.
+async fn clone_lxc(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    newid: u32,
+    hostname: Option<String>,
+    target: Option<String>,
+    full: Option<bool>,
+    storage: Option<String>,
+    pool: Option<String>,
+) -> Result<(), Error> {
+    let mut params = pdm_client::CloneGuest::new();
+    if let Some(target) = target {
+        params = params.target(target);
+    }
+    if let Some(full) = full {
+        params = params.full(full);
+    }
+    if let Some(storage) = storage {
+        params = params.storage(storage);
+    }
+    if let Some(pool) = pool {
+        params = params.pool(pool);
+    }
+
+    let client = client()?;
+    let upid = client
+        .pve_lxc_clone(
+            &remote,
+            node.as_deref(),
+            vmid,
+            newid,
+            hostname.as_deref(),
+            params,
+        )
+        .await?;
+    println!("upid: {upid}");
+    let status = client.pve_wait_for_task(&upid).await?;
+    println!("{status:#?}");
+
+    Ok(())
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            purge: {
+                description: "Remove the container from backup jobs, replication jobs and HA.",
+                optional: true,
+            },
+        }
+    }
+)]
+/// Destroy a container including its disks.
+async fn destroy_lxc(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    purge: Option<bool>,
+) -> Result<(), Error> {
+    let client = client()?;
+    let upid = client
+        .pve_lxc_destroy(&remote, node.as_deref(), vmid, purge)
+        .await?;
+    println!("upid: {upid}");
+    let status = client.pve_wait_for_task(&upid).await?;
+    println!("{status:#?}");
+
+    Ok(())
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            snapname: { schema: SNAPSHOT_NAME_SCHEMA },
+            description: {
+                type: String,
+                optional: true,
+                description: "A description of the snapshot.",
+            },
+        }
+    }
+)]
+/// Create a snapshot of a container.
+async fn create_lxc_snapshot(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    snapname: String,
+    description: Option<String>,
+) -> Result<(), Error> {
+    let client = client()?;
+    let upid = client
+        .pve_lxc_create_snapshot(
+            &remote,
+            node.as_deref(),
+            vmid,
+            &snapname,
+            description.as_deref(),
+        )
+        .await?;
+    println!("upid: {upid}");
+    let status = client.pve_wait_for_task(&upid).await?;
+    println!("{status:#?}");
+
+    Ok(())
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            snapname: { schema: SNAPSHOT_NAME_SCHEMA },
+            start: {
+                description: "Start the container after the rollback.",
+                optional: true,
+            },
+        }
+    }
+)]
+/// Roll a container back to a snapshot.
+async fn rollback_lxc_snapshot(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    snapname: String,
+    start: Option<bool>,
+) -> Result<(), Error> {
+    let client = client()?;
+    let upid = client
+        .pve_lxc_rollback_snapshot(&remote, node.as_deref(), vmid, &snapname, start)
+        .await?;
+    println!("upid: {upid}");
+    let status = client.pve_wait_for_task(&upid).await?;
+    println!("{status:#?}");
+
+    Ok(())
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            snapname: { schema: SNAPSHOT_NAME_SCHEMA },
+            force: {
+                description: "Remove the snapshot from the config even if removing its disk snapshots fails.",
+                optional: true,
+            },
+        }
+    }
+)]
+/// Delete a snapshot of a container.
+async fn delete_lxc_snapshot(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    snapname: String,
+    force: Option<bool>,
+) -> Result<(), Error> {
+    let client = client()?;
+    let upid = client
+        .pve_lxc_delete_snapshot(&remote, node.as_deref(), vmid, &snapname, force)
+        .await?;
+    println!("upid: {upid}");
+    let status = client.pve_wait_for_task(&upid).await?;
+    println!("{status:#?}");
+
+    Ok(())
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            storage: {
+                schema: PVE_STORAGE_ID_SCHEMA,
+            },
+            mode: {
+                type: GuestBackupMode,
+                optional: true,
+            },
+            "notes-template": {
+                type: String,
+                optional: true,
+                description: "Template for the notes of the backup.",
+            },
+            protected: {
+                description: "Protect the backup from being pruned.",
+                optional: true,
+            },
+        }
+    }
+)]
+/// Back up a container now.
+async fn backup_lxc(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    storage: String,
+    mode: Option<GuestBackupMode>,
+    notes_template: Option<String>,
+    protected: Option<bool>,
+) -> Result<(), Error> {
+    let mut params = pdm_client::BackupGuest::new();
+    if let Some(mode) = mode {
+        params = params.mode(mode);
+    }
+    if let Some(notes_template) = notes_template {
+        params = params.notes_template(notes_template);
+    }
+    if let Some(protected) = protected {
+        params = params.protected(protected);
+    }
+
+    let client = client()?;
+    let upid = client
+        .pve_lxc_backup(&remote, node.as_deref(), vmid, &storage, params)
+        .await?;
+    println!("upid: {upid}");
+    let status = client.pve_wait_for_task(&upid).await?;
+    println!("{status:#?}");
+
+    Ok(())
+}
+
 #[api(
     input: {
         properties: {
@@ -1,4 +1,4 @@
-use anyhow::Error;
+use anyhow::{format_err, Error};
 
 use proxmox_router::cli::{
     format_and_print_result, format_and_print_result_full, CliCommand, CliCommandMap,
@@ -7,6 +7,7 @@ use proxmox_router::cli::{
 use proxmox_schema::{api, property_string, ApiType, ReturnType, Schema};
 
 use pdm_api_types::remotes::{Remote, RemoteType, RemoteUpdater, REMOTE_ID_SCHEMA};
+use pdm_api_types::CERT_FINGERPRINT_SHA256_SCHEMA;
 
 use crate::{client, env};
 
@@ -17,14 +18,26 @@ pub fn cli() -> CommandLineInterface {
             "add",
             CliCommand::new(&API_METHOD_ADD_REMOTE).arg_param(&["type", "id"]),
         )
+        .insert(
+            "accept-certificate",
+            CliCommand::new(&API_METHOD_ACCEPT_CERTIFICATE).arg_param(&["id", "hostname"]),
+        )
         .insert(
             "delete",
             CliCommand::new(&API_METHOD_DELETE_REMOTE).arg_param(&["id"]),
         )
+        .insert(
+            "pending-certificates",
+            CliCommand::new(&API_METHOD_PENDING_CERTIFICATES).arg_param(&["id"]),
+        )
         .insert(
             "update",
             CliCommand::new(&API_METHOD_UPDATE_REMOTE).arg_param(&["id"]),
         )
+        .insert(
+            "rotate-token",
+            CliCommand::new(&API_METHOD_ROTATE_TOKEN).arg_param(&["id"]),
+        )
         .insert(
             "version",
             CliCommand::new(&API_METHOD_REMOTE_VERSION).arg_param(&["id"]),
@@ -51,6 +64,26 @@ async fn list_remotes() -> Result<(), Error> {
             }
             println!("    auth id: {}", entry.authid);
             println!("    token: {}", entry.token);
+            if let Some(http_proxy) = &entry.http_proxy {
+                println!("    http proxy: {http_proxy}");
+            }
+            let maintenance = if entry.maintenance.unwrap_or(false) {
+                Some("all nodes".to_string())
+            } else {
+                entry
+                    .maintenance_nodes
+                    .as_ref()
+                    .map(|nodes| format!("nodes {}", nodes.join(", ")))
+            };
+            if let Some(scope) = maintenance {
+                match entry.maintenance_end {
+                    Some(end) => println!(
+                        "    maintenance: {scope} until {}",
+                        proxmox_time::strftime_local("%a, %d %b %Y %T %z", end)?
+                    ),
+                    None => println!("    maintenance: {scope}"),
+                }
+            }
             if entry.nodes.len() == 1 {
                 println!("    node: {}", property_string::print(&*entry.nodes[0])?);
             } else {
@@ -130,6 +163,98 @@ async fn delete_remote(id: String, delete_token: bool) -> Result<(), Error> {
     Ok(())
 }
 
+#[api(
+    input: {
+        properties: {
+            id: { schema: REMOTE_ID_SCHEMA },
+            password: {
+                schema: proxmox_schema::api_types::PASSWORD_SCHEMA,
+                description: "Password of the token's user on the remote.",
+                optional: true,
+            },
+        }
+    }
+)]
+/// Replace the API token of a remote with a new one.
+async fn rotate_token(id: String, password: Option<String>) -> Result<(), Error> {
+    let password = match password {
+        Some(password) => password,
+        None => {
+            let password =
+                proxmox_sys::linux::tty::read_password("Password of the token's user: ")?;
+            String::from_utf8(password).map_err(|_| format_err!("password must be valid utf-8"))?
+        }
+    };
+
+    client()?.rotate_remote_token(&id, &password).await?;
+    Ok(())
+}
+
+#[api(
+    input: {
+        properties: {
+            id: { schema: REMOTE_ID_SCHEMA },
+        }
+    }
+)]
+/// List certificates of a remote's nodes which do not match the pinned fingerprint.
+async fn pending_certificates(id: String) -> Result<(), Error> {
+    let entries = client()?.pending_remote_certificates(&id).await?;
+
+    let output_format = env().format_args.output_format;
+    if output_format == OutputFormat::Text {
+        if entries.is_empty() {
+            println!("No pending certificates");
+            return Ok(());
+        }
+
+        for entry in entries {
+            match &entry.node {
+                Some(node) => println!("{} (node {node}):", entry.hostname),
+                None => println!("{}:", entry.hostname),
+            }
+            if let Some(pinned) = &entry.pinned_fingerprint {
+                println!("    pinned fingerprint: {pinned}");
+            }
+            if let Some(fingerprint) = &entry.certificate.fingerprint {
+                println!("    new fingerprint: {fingerprint}");
+            }
+            println!("    subject: {}", entry.certificate.subject);
+            println!("    issuer: {}", entry.certificate.issuer);
+            if entry.trusted_by_ca {
+                println!("    issued by a trusted CA, will be accepted automatically");
+            }
+        }
+    } else {
+        format_and_print_result(&entries, &output_format.to_string());
+    }
+    Ok(())
+}
+
+#[api(
+    input: {
+        properties: {
+            id: { schema: REMOTE_ID_SCHEMA },
+            hostname: {
+                type: String,
+                description: "The address of the node, as configured in the remote.",
+            },
+            fingerprint: { schema: CERT_FINGERPRINT_SHA256_SCHEMA },
+        }
+    }
+)]
+/// Trust the pending certificate of a remote's node by pinning its fingerprint.
+async fn accept_certificate(
+    id: String,
+    hostname: String,
+    fingerprint: String,
+) -> Result<(), Error> {
+    client()?
+        .accept_remote_certificate(&id, &hostname, &fingerprint)
+        .await?;
+    Ok(())
+}
+
 #[api(
     input: {
         properties: {
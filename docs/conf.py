@@ -99,7 +99,12 @@ man_pages = [
     ('proxmox-datacenter-manager-admin/man1', 'proxmox-datacenter-manager-admin', 'Command line tool for managing Proxmox Datacenter Manager hosts.', [author], 1),
     ('proxmox-datacenter-manager-client/man1', 'proxmox-datacenter-manager-client', 'Command line tool for connecting and controlling the remotes and resources of a Proxmox Datacenter Manager hosts.', [author], 1),
     # configs
+    ('config/alerts/man5', 'alerts.cfg', 'Proxmox Datacenter Manager Alert Rules Configuration', [author], 5),
+    ('config/jobs/man5', 'jobs.cfg', 'Proxmox Datacenter Manager Scheduled Jobs Configuration', [author], 5),
+    ('config/metricserver/man5', 'metricserver.cfg', 'Proxmox Datacenter Manager Metric Server Configuration', [author], 5),
     ('config/remotes/man5', 'remotes.cfg', 'Proxmox Datacenter Manager Remotes Configuration', [author], 5),
+    ('config/roles/man5', 'roles.cfg', 'Proxmox Datacenter Manager Access Roles Configuration', [author], 5),
+    ('config/subscription-keys/man5', 'subscription-keys.cfg', 'Proxmox Datacenter Manager Subscription Key Pool Configuration', [author], 5),
     ('config/views/man5', 'views.cfg', 'Proxmox Datacenter Manager Views Configuration', [author], 5),
 ]
 
@@ -1,8 +1,8 @@
 use std::collections::HashMap;
 use std::str::FromStr;
-use std::sync::LazyLock;
+use std::sync::{LazyLock, OnceLock};
 
-use anyhow::{format_err, Context, Error};
+use anyhow::{bail, format_err, Context, Error};
 use const_format::concatcp;
 use serde::de::{value, IntoDeserializer};
 use serde::{Deserialize, Serialize};
@@ -11,11 +11,22 @@ use proxmox_access_control::types::User;
 use proxmox_auth_api::types::Authid;
 use proxmox_lang::constnamedbitmap;
 use proxmox_schema::api_types::SAFE_ID_REGEX_STR;
-use proxmox_schema::{api, const_regex, ApiStringFormat, BooleanSchema, Schema, StringSchema};
-use proxmox_section_config::SectionConfigData;
+use proxmox_schema::{
+    api, const_regex, ApiStringFormat, ApiType, BooleanSchema, Schema, StringSchema,
+};
+use proxmox_section_config::typed::ApiSectionDataEntry;
+use proxmox_section_config::{SectionConfig, SectionConfigData, SectionConfigPlugin};
+
+use crate::{PROXMOX_SAFE_ID_FORMAT, SINGLE_LINE_COMMENT_SCHEMA};
+
+/// Tags as accepted by PVE, which, unlike safe IDs, may contain a `+`.
+const PVE_TAG_REGEX_STR: &str = r"(?:[A-Za-z0-9_][A-Za-z0-9_\-+.]*)";
 
 const_regex! {
-    pub ACL_PATH_REGEX = concatcp!(r"^(?:/|", r"(?:/", SAFE_ID_REGEX_STR, ")+", r")$");
+    pub ACL_PATH_REGEX = concatcp!(
+        r"^(?:/|", r"(?:/", SAFE_ID_REGEX_STR, ")+", r"|/tag/", PVE_TAG_REGEX_STR, r")$"
+    );
+    pub PVE_TAG_REGEX = concatcp!(r"^", PVE_TAG_REGEX_STR, r"$");
 }
 
 // define Privilege bitfield
@@ -68,6 +79,24 @@ pub fn privs_to_priv_names(privs: u64) -> Vec<&'static str> {
         })
 }
 
+/// Parse a comma separated list of privilege names into the combined privilege bits.
+pub fn priv_names_to_privs(priv_names: &str) -> Result<u64, Error> {
+    priv_names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .try_fold(0, |privs, name| {
+            match PRIVILEGES.iter().find(|(priv_name, _)| *priv_name == name) {
+                Some((_, value)) => Ok(privs | value),
+                None => bail!("unknown privilege '{name}'"),
+            }
+        })
+}
+
+fn verify_privilege_list(priv_names: &str) -> Result<(), Error> {
+    priv_names_to_privs(priv_names).map(|_| ())
+}
+
 #[rustfmt::skip]
 #[allow(clippy::identity_op)]
 mod roles {
@@ -139,6 +168,130 @@ impl FromStr for Role {
     }
 }
 
+impl Role {
+    /// Check if a role ID is taken by one of the built-in roles.
+    pub fn is_builtin(roleid: &str) -> bool {
+        roleid.parse::<Role>().is_ok()
+    }
+}
+
+pub const ROLE_ID_SCHEMA: Schema = StringSchema::new("Role ID.")
+    .format(&PROXMOX_SAFE_ID_FORMAT)
+    .min_length(2)
+    .max_length(32)
+    .schema();
+
+pub const PRIVILEGE_LIST_FORMAT: ApiStringFormat = ApiStringFormat::VerifyFn(verify_privilege_list);
+
+pub const PRIVILEGE_LIST_SCHEMA: Schema = StringSchema::new("Comma separated list of privileges.")
+    .format(&PRIVILEGE_LIST_FORMAT)
+    .schema();
+
+#[api(
+    properties: {
+        roleid: { schema: ROLE_ID_SCHEMA },
+        privs: { schema: PRIVILEGE_LIST_SCHEMA },
+        comment: {
+            schema: SINGLE_LINE_COMMENT_SCHEMA,
+            optional: true,
+        },
+    },
+)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// A user-defined role, composed from [PRIVILEGES].
+pub struct RoleConfig {
+    /// The role ID.
+    pub roleid: String,
+
+    /// The privileges granted by the role.
+    pub privs: String,
+
+    /// Comment.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+impl RoleConfig {
+    /// The combined privilege bits of the role.
+    ///
+    /// Unknown privileges, for example ones dropped in a newer version, are ignored.
+    pub fn privileges(&self) -> u64 {
+        self.privs
+            .split(',')
+            .filter_map(|name| priv_names_to_privs(name).ok())
+            .fold(0, |privs, value| privs | value)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// Enum for the different sections in the 'roles.cfg' file.
+pub enum RoleConfigEntry {
+    /// 'role' section
+    Role(RoleConfig),
+}
+
+const ROLE_SECTION_NAME: &str = "role";
+
+impl ApiSectionDataEntry for RoleConfigEntry {
+    fn section_config() -> &'static SectionConfig {
+        static CONFIG: OnceLock<SectionConfig> = OnceLock::new();
+
+        CONFIG.get_or_init(|| {
+            let mut this = SectionConfig::new(&ROLE_ID_SCHEMA);
+
+            this.register_plugin(SectionConfigPlugin::new(
+                ROLE_SECTION_NAME.into(),
+                Some("roleid".to_string()),
+                RoleConfig::API_SCHEMA.unwrap_object_schema(),
+            ));
+            this
+        })
+    }
+
+    fn section_type(&self) -> &'static str {
+        match self {
+            RoleConfigEntry::Role(_) => ROLE_SECTION_NAME,
+        }
+    }
+}
+
+#[api(
+    properties: {
+        roleid: { schema: ROLE_ID_SCHEMA },
+        privs: {
+            type: Array,
+            items: {
+                type: String,
+                description: "A privilege name.",
+            },
+        },
+        comment: {
+            schema: SINGLE_LINE_COMMENT_SCHEMA,
+            optional: true,
+        },
+    },
+)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// Role list entry, for built-in as well as user-defined roles.
+pub struct RoleListItem {
+    /// The role ID.
+    pub roleid: String,
+
+    /// The privileges granted by the role.
+    pub privs: Vec<String>,
+
+    /// Comment.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+
+    /// Built-in roles cannot be modified or removed.
+    #[serde(default)]
+    pub builtin: bool,
+}
+
 pub const ACL_PATH_FORMAT: ApiStringFormat = ApiStringFormat::Pattern(&ACL_PATH_REGEX);
 
 pub const ACL_PATH_SCHEMA: Schema = StringSchema::new("Access control path.")
@@ -174,7 +327,7 @@ serde_plain::derive_fromstr_from_deserialize!(AclUgidType);
             type: String,
             description: "User or Group ID.",
         },
-        roleid: { type: Role }
+        roleid: { schema: ROLE_ID_SCHEMA }
     }
 )]
 #[derive(Serialize, Deserialize)]
@@ -267,7 +420,7 @@ impl proxmox_access_control::init::AccessControlConfig for AccessControlConfig {
                     return Ok(());
                 }
                 match components[1] {
-                    "acl" | "users" | "realm" => {
+                    "acl" | "roles" | "users" | "realm" => {
                         if components_len == 2 {
                             return Ok(());
                         }
@@ -289,6 +442,10 @@ impl proxmox_access_control::init::AccessControlConfig for AccessControlConfig {
                             return Ok(());
                         }
                     }
+                    "pool" => {
+                        // /resource/{remote-id}/pool/{pool-id}, nested pools add more components
+                        return Ok(());
+                    }
                     _ => {}
                 }
             }
@@ -331,6 +488,14 @@ impl proxmox_access_control::init::AccessControlConfig for AccessControlConfig {
                     _ => {}
                 }
             }
+            "tag" => {
+                // `/tag` and `/tag/{tag}`, using the tag format of PVE
+                if components_len == 1
+                    || (components_len == 2 && PVE_TAG_REGEX.is_match(components[1]))
+                {
+                    return Ok(());
+                }
+            }
             "view" => {
                 // `/view` and `/view/{view-id}`
                 if components_len <= 2 {
@@ -343,3 +508,43 @@ impl proxmox_access_control::init::AccessControlConfig for AccessControlConfig {
         Err(format_err!("invalid acl path '{}'.", path))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use proxmox_access_control::init::AccessControlConfig as _;
+
+    use super::*;
+
+    #[test]
+    fn parse_privilege_list() {
+        assert_eq!(
+            priv_names_to_privs("Resource.Audit,Resource.Manage").unwrap(),
+            PRIV_RESOURCE_AUDIT | PRIV_RESOURCE_MANAGE
+        );
+        assert_eq!(priv_names_to_privs("").unwrap(), 0);
+        assert!(priv_names_to_privs("Resource.Audit,VM.Audit").is_err());
+    }
+
+    #[test]
+    fn role_ignores_unknown_privileges() {
+        let role = RoleConfig {
+            roleid: "GuestOperator".to_string(),
+            privs: "Resource.Audit,Gone.Privilege".to_string(),
+            comment: None,
+        };
+        assert_eq!(role.privileges(), PRIV_RESOURCE_AUDIT);
+    }
+
+    #[test]
+    fn tag_acl_paths() {
+        let config = AccessControlConfig;
+        assert!(config.check_acl_path("/tag").is_ok());
+        assert!(config.check_acl_path("/tag/prod").is_ok());
+        assert!(config.check_acl_path("/tag/c++").is_ok());
+        assert!(config.check_acl_path("/tag/prod/web").is_err());
+        assert!(config.check_acl_path("/tag/-prod").is_err());
+
+        assert!(ACL_PATH_REGEX.is_match("/tag/c++"));
+        assert!(!ACL_PATH_REGEX.is_match("/resource/c++"));
+    }
+}
@@ -0,0 +1,257 @@
+//! Types for threshold based alerting on collected metrics.
+
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use proxmox_schema::{api, ApiType, IntegerSchema, NumberSchema, Schema, StringSchema, Updater};
+use proxmox_section_config::{typed::ApiSectionDataEntry, SectionConfig, SectionConfigPlugin};
+
+use crate::remotes::REMOTE_ID_SCHEMA;
+use crate::resource::{ResourceType, PBS_DATASTORE_HIGH_USAGE_THRESHOLD};
+use crate::{PROXMOX_SAFE_ID_FORMAT, SINGLE_LINE_COMMENT_SCHEMA};
+
+pub const ALERT_RULE_ID_SCHEMA: Schema = StringSchema::new("Alert rule name.")
+    .format(&PROXMOX_SAFE_ID_FORMAT)
+    .min_length(2)
+    .max_length(32)
+    .schema();
+
+/// Threshold used if a rule does not define one, in percent.
+pub const DEFAULT_ALERT_THRESHOLD: f64 = PBS_DATASTORE_HIGH_USAGE_THRESHOLD * 100.0;
+
+/// Maximum duration a threshold needs to be exceeded before an alert fires.
+///
+/// Alerts are evaluated on the hourly RRD data, so we cannot look further back than that.
+pub const MAX_ALERT_DURATION: u64 = 3600;
+
+pub const ALERT_THRESHOLD_SCHEMA: Schema =
+    NumberSchema::new("Threshold in percent. An alert fires when the usage is above it.")
+        .minimum(0.0)
+        .maximum(100.0)
+        .default(DEFAULT_ALERT_THRESHOLD)
+        .schema();
+
+pub const ALERT_DURATION_SCHEMA: Schema = IntegerSchema::new(
+    "Time in seconds the threshold needs to be exceeded before the alert fires.",
+)
+.minimum(0)
+.maximum(MAX_ALERT_DURATION as isize)
+.default(0)
+.schema();
+
+#[api]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// The metric an alert rule is evaluated on.
+pub enum AlertMetric {
+    /// CPU usage.
+    Cpu,
+    /// Memory usage (used memory in relation to total memory).
+    Memory,
+    /// Disk usage (used space in relation to total space).
+    Disk,
+}
+
+serde_plain::derive_display_from_serialize!(AlertMetric);
+serde_plain::derive_fromstr_from_deserialize!(AlertMetric);
+
+impl AlertMetric {
+    /// Check if the metric is collected for the given resource type.
+    pub fn supports(&self, resource_type: ResourceType) -> bool {
+        match resource_type {
+            ResourceType::Node | ResourceType::PveLxc => true,
+            ResourceType::PveQemu => *self != AlertMetric::Disk,
+            ResourceType::PveStorage | ResourceType::PbsDatastore => *self == AlertMetric::Disk,
+            ResourceType::PveNetwork => false,
+        }
+    }
+}
+
+#[api(
+    properties: {
+        id: {
+            schema: ALERT_RULE_ID_SCHEMA,
+        },
+        "resource-type": {
+            type: ResourceType,
+        },
+        metric: {
+            type: AlertMetric,
+        },
+        threshold: {
+            schema: ALERT_THRESHOLD_SCHEMA,
+            optional: true,
+        },
+        duration: {
+            schema: ALERT_DURATION_SCHEMA,
+            optional: true,
+        },
+        remote: {
+            schema: REMOTE_ID_SCHEMA,
+            optional: true,
+        },
+        comment: {
+            schema: SINGLE_LINE_COMMENT_SCHEMA,
+            optional: true,
+        },
+        disable: {
+            optional: true,
+            default: false,
+        },
+    }
+)]
+#[derive(Clone, Debug, Deserialize, Serialize, Updater, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// Alert rule definition.
+pub struct AlertRule {
+    /// Rule name.
+    #[updater(skip)]
+    pub id: String,
+
+    /// The type of resources this rule applies to.
+    #[updater(skip)]
+    pub resource_type: ResourceType,
+
+    /// The metric to check.
+    pub metric: AlertMetric,
+
+    /// Usage threshold in percent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<f64>,
+
+    /// Seconds the threshold needs to be exceeded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u64>,
+
+    /// Only check resources of this remote.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
+
+    /// Comment.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+
+    /// Disable this rule.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disable: Option<bool>,
+}
+
+impl AlertRule {
+    /// The threshold of this rule in percent, falling back to [`DEFAULT_ALERT_THRESHOLD`].
+    pub fn threshold(&self) -> f64 {
+        self.threshold.unwrap_or(DEFAULT_ALERT_THRESHOLD)
+    }
+
+    /// The number of seconds the threshold needs to be exceeded.
+    pub fn duration(&self) -> u64 {
+        self.duration.unwrap_or(0)
+    }
+
+    /// Check if the rule is enabled.
+    pub fn enabled(&self) -> bool {
+        !self.disable.unwrap_or(false)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// Enum for the different sections in the 'alerts.cfg' file.
+pub enum AlertRuleEntry {
+    /// 'rule' section
+    Rule(AlertRule),
+}
+
+const ALERT_RULE_SECTION_NAME: &str = "rule";
+
+impl ApiSectionDataEntry for AlertRuleEntry {
+    fn section_config() -> &'static SectionConfig {
+        static CONFIG: OnceLock<SectionConfig> = OnceLock::new();
+
+        CONFIG.get_or_init(|| {
+            let mut this = SectionConfig::new(&ALERT_RULE_ID_SCHEMA);
+
+            this.register_plugin(SectionConfigPlugin::new(
+                ALERT_RULE_SECTION_NAME.into(),
+                Some("id".to_string()),
+                AlertRule::API_SCHEMA.unwrap_object_schema(),
+            ));
+            this
+        })
+    }
+
+    fn section_type(&self) -> &'static str {
+        match self {
+            AlertRuleEntry::Rule(_) => ALERT_RULE_SECTION_NAME,
+        }
+    }
+}
+
+#[api]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// State of an alert.
+pub enum AlertState {
+    /// The threshold is currently exceeded.
+    Firing,
+    /// The usage went back below the threshold.
+    Resolved,
+}
+
+#[api(
+    properties: {
+        rule: {
+            schema: ALERT_RULE_ID_SCHEMA,
+        },
+        remote: {
+            schema: REMOTE_ID_SCHEMA,
+        },
+        "resource-type": {
+            type: ResourceType,
+        },
+        metric: {
+            type: AlertMetric,
+        },
+        state: {
+            type: AlertState,
+        },
+    }
+)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// An alert raised by an alert rule for a single resource.
+pub struct Alert {
+    /// The rule which raised the alert.
+    pub rule: String,
+
+    /// The remote the resource belongs to.
+    pub remote: String,
+
+    /// The global ID of the resource.
+    pub resource_id: String,
+
+    /// The type of the resource.
+    pub resource_type: ResourceType,
+
+    /// The name of the resource.
+    pub resource_name: String,
+
+    /// The metric which exceeded the threshold.
+    pub metric: AlertMetric,
+
+    /// The most recent usage value in percent.
+    pub value: f64,
+
+    /// The threshold in percent.
+    pub threshold: f64,
+
+    /// The alert's state.
+    pub state: AlertState,
+
+    /// Time the alert started firing (epoch).
+    pub since: i64,
+
+    /// Time the alert was resolved (epoch).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved: Option<i64>,
+}
@@ -0,0 +1,66 @@
+//! Types for the audit log of state-changing API calls.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use proxmox_schema::api;
+
+use crate::remotes::REMOTE_ID_SCHEMA;
+use crate::Authid;
+
+#[api(
+    properties: {
+        user: {
+            type: Authid,
+            optional: true,
+        },
+        remote: {
+            schema: REMOTE_ID_SCHEMA,
+            optional: true,
+        },
+        parameters: {
+            type: Object,
+            description: "The parameters of the call, with secrets redacted.",
+            properties: {},
+            additional_properties: true,
+        },
+    },
+)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// A state-changing API call.
+pub struct AuditLogEntry {
+    /// Time of the call (UNIX epoch).
+    pub time: i64,
+
+    /// The user or token which made the call.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<Authid>,
+
+    /// The address the call came from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_ip: Option<String>,
+
+    /// The HTTP method.
+    pub method: String,
+
+    /// The API path, relative to `/api2/json`.
+    pub path: String,
+
+    /// The remote the call acts on, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
+
+    pub parameters: Value,
+
+    /// Whether the call succeeded.
+    pub success: bool,
+
+    /// The error message of a failed call.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// The UPID of the worker task started by the call.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upid: Option<String>,
+}
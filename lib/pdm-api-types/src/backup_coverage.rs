@@ -0,0 +1,137 @@
+//! Types for the backup coverage report of PVE guests.
+
+use serde::{Deserialize, Serialize};
+
+use proxmox_schema::{api, IntegerSchema, Schema};
+
+use crate::firewall::GuestKind;
+use crate::remotes::REMOTE_ID_SCHEMA;
+use crate::resource::FailedRemote;
+use crate::VMID_SCHEMA;
+
+/// Default for the maximum age of the last backup of a guest, 7 days.
+pub const DEFAULT_MAX_BACKUP_AGE: u64 = 7 * 24 * 3600;
+
+pub const MAX_BACKUP_AGE_SCHEMA: Schema = IntegerSchema::new(
+    "Guests whose last backup is older than this (in seconds) are reported as outdated.",
+)
+.minimum(0)
+.default(DEFAULT_MAX_BACKUP_AGE as isize)
+.schema();
+
+#[api]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// How well a guest is covered by backups.
+pub enum BackupCoverageStatus {
+    /// The last backup is recent enough.
+    Covered,
+    /// The last backup is older than the maximum backup age.
+    Outdated,
+    /// There is no backup of the guest on any PBS remote.
+    Missing,
+}
+
+#[api(
+    properties: {
+        remote: {
+            schema: REMOTE_ID_SCHEMA,
+        },
+        vmid: {
+            schema: VMID_SCHEMA,
+        },
+        "guest-type": {
+            type: GuestKind,
+        },
+        status: {
+            type: BackupCoverageStatus,
+        },
+        "backup-remote": {
+            schema: REMOTE_ID_SCHEMA,
+            optional: true,
+        },
+        datastore: {
+            schema: pbs_api_types::DATASTORE_SCHEMA,
+            optional: true,
+        },
+        namespace: {
+            schema: pbs_api_types::BACKUP_NAMESPACE_SCHEMA,
+            optional: true,
+        },
+    },
+)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// The backup coverage of a single guest.
+pub struct GuestBackupCoverage {
+    /// The remote the guest is located on.
+    pub remote: String,
+
+    /// The VMID of the guest.
+    pub vmid: u32,
+
+    /// The type of the guest.
+    pub guest_type: GuestKind,
+
+    /// The name of the guest.
+    pub name: String,
+
+    /// The coverage status of the guest.
+    pub status: BackupCoverageStatus,
+
+    /// Time of the most recent backup (epoch).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_backup: Option<i64>,
+
+    /// The PBS remote holding the most recent backup.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup_remote: Option<String>,
+
+    /// The datastore holding the most recent backup.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub datastore: Option<String>,
+
+    /// The namespace of the most recent backup, empty for the root namespace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+}
+
+#[api(
+    properties: {
+        guests: {
+            type: Array,
+            items: { type: GuestBackupCoverage },
+        },
+        "failed-remotes": {
+            type: Array,
+            items: { type: FailedRemote },
+        },
+    },
+)]
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// Backup coverage report of all guests.
+///
+/// Backups are matched to guests by their type and VMID, as PBS does not know which remote a
+/// backup was made from.
+pub struct BackupCoverage {
+    /// The maximum age of the last backup (in seconds) the report is based on.
+    pub max_backup_age: u64,
+
+    /// Amount of guests with a recent backup.
+    pub covered: u64,
+
+    /// Amount of guests whose last backup is too old.
+    pub outdated: u64,
+
+    /// Amount of guests without any backup.
+    pub missing: u64,
+
+    /// Guests that are not covered, or all guests if requested.
+    pub guests: Vec<GuestBackupCoverage>,
+
+    /// Remotes which could not be queried. Guests of failed PBS remotes may be reported as not
+    /// covered.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed_remotes: Vec<FailedRemote>,
+}
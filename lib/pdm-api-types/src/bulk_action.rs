@@ -0,0 +1,85 @@
+//! Types for running an action on many guests at once.
+
+use serde::{Deserialize, Serialize};
+
+use proxmox_schema::{api, ApiType};
+
+use crate::remotes::REMOTE_ID_SCHEMA;
+use crate::{NODE_SCHEMA, UPID};
+
+#[api]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// An action which can be run on many guests at once.
+pub enum GuestAction {
+    /// Start the guest.
+    Start,
+    /// Stop the guest immediately.
+    Stop,
+    /// Shut the guest down cleanly.
+    Shutdown,
+    /// Migrate the guest to another node of the same cluster.
+    Migrate,
+}
+
+serde_plain::derive_display_from_serialize!(GuestAction);
+serde_plain::derive_fromstr_from_deserialize!(GuestAction);
+
+#[api(
+    properties: {
+        remote: {
+            schema: REMOTE_ID_SCHEMA,
+        },
+        node: {
+            schema: NODE_SCHEMA,
+        },
+        target: {
+            schema: NODE_SCHEMA,
+            optional: true,
+        },
+    },
+)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// A guest affected by a bulk action.
+pub struct BulkActionGuest {
+    /// The remote the guest belongs to.
+    pub remote: String,
+
+    /// The local ID of the guest, e.g. `qemu/100`.
+    pub id: String,
+
+    /// The name of the guest.
+    pub name: String,
+
+    /// The node the guest is currently located on.
+    pub node: String,
+
+    /// The node the guest is migrated to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+}
+
+#[api(
+    properties: {
+        guests: {
+            type: Array,
+            items: { type: BulkActionGuest },
+        },
+        upid: {
+            schema: UPID::API_SCHEMA,
+            optional: true,
+        },
+    },
+)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// Result of a bulk action.
+pub struct BulkActionResult {
+    /// The guests the action is run on.
+    pub guests: Vec<BulkActionGuest>,
+
+    /// The UPID of the worker task running the action, not set for dry-runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upid: Option<String>,
+}
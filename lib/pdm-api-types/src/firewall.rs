@@ -1,9 +1,52 @@
-use proxmox_schema::{api, Schema};
+use proxmox_schema::{api, const_regex, ApiStringFormat, IntegerSchema, Schema, StringSchema};
 use serde::{Deserialize, Serialize};
 
+use crate::guest_config::PVE_CONFIG_DIGEST_SCHEMA;
 use crate::remotes::REMOTE_ID_SCHEMA;
 use crate::{NODE_SCHEMA, VMID_SCHEMA};
 
+const_regex! {
+    pub FIREWALL_NAME_REGEX = r"^[A-Za-z][A-Za-z0-9_-]+$";
+    pub FIREWALL_RULE_DELETABLE_REGEX =
+        r"^(?:iface|source|dest|proto|dport|sport|macro|icmp-type|log|comment)$";
+}
+
+pub const FIREWALL_NAME_FORMAT: ApiStringFormat = ApiStringFormat::Pattern(&FIREWALL_NAME_REGEX);
+
+pub const FIREWALL_ALIAS_NAME_SCHEMA: Schema = StringSchema::new("Firewall alias name.")
+    .format(&FIREWALL_NAME_FORMAT)
+    .min_length(2)
+    .max_length(64)
+    .schema();
+
+pub const FIREWALL_IPSET_NAME_SCHEMA: Schema = StringSchema::new("Firewall IP set name.")
+    .format(&FIREWALL_NAME_FORMAT)
+    .min_length(2)
+    .max_length(64)
+    .schema();
+
+pub const FIREWALL_GROUP_NAME_SCHEMA: Schema = StringSchema::new("Security group name.")
+    .format(&FIREWALL_NAME_FORMAT)
+    .min_length(2)
+    .max_length(18)
+    .schema();
+
+pub const FIREWALL_CIDR_SCHEMA: Schema = StringSchema::new(
+    "Network or IP address in CIDR notation, or the name of an alias, as accepted by PVE.",
+)
+.max_length(128)
+.schema();
+
+pub const FIREWALL_RULE_DELETABLE_SCHEMA: Schema =
+    StringSchema::new("A firewall rule property which can be deleted.")
+        .format(&ApiStringFormat::Pattern(&FIREWALL_RULE_DELETABLE_REGEX))
+        .schema();
+
+pub const FIREWALL_RULE_POS_SCHEMA: Schema =
+    IntegerSchema::new("Position of the rule in the rule list, starting at 0.")
+        .minimum(0)
+        .schema();
+
 const FIREWALL_RULES_COUNT: Schema =
     proxmox_schema::IntegerSchema::new("The total amount of rules present")
         .minimum(0)
@@ -108,7 +151,7 @@ pub struct NodeFirewallStatus {
 }
 
 #[api]
-#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "lowercase")]
 /// The type of the guest
 pub enum GuestKind {
@@ -171,3 +214,484 @@ pub struct GuestFirewallStatus {
     pub status: Option<FirewallStatus>,
     pub kind: GuestKind,
 }
+
+#[api]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+/// The direction of a firewall rule.
+pub enum FirewallRuleType {
+    /// Incoming traffic.
+    In,
+    /// Outgoing traffic.
+    Out,
+    /// Forwarded traffic.
+    Forward,
+    /// Insert the rules of a security group.
+    Group,
+}
+serde_plain::derive_display_from_serialize!(FirewallRuleType);
+
+#[api]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+/// Log level of a firewall rule.
+pub enum FirewallLogLevel {
+    /// Emergency.
+    Emerg,
+    /// Alert.
+    Alert,
+    /// Critical.
+    Crit,
+    /// Error.
+    Err,
+    /// Warning.
+    Warning,
+    /// Notice.
+    Notice,
+    /// Informational.
+    Info,
+    /// Debug.
+    Debug,
+    /// Do not log.
+    Nolog,
+}
+serde_plain::derive_display_from_serialize!(FirewallLogLevel);
+
+#[api(
+    properties: {
+        "type": {
+            type: FirewallRuleType,
+            optional: true,
+        },
+        log: {
+            type: FirewallLogLevel,
+            optional: true,
+        },
+    },
+)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// Properties of a firewall rule.
+///
+/// When creating a rule, `type` and `action` are required. When updating a rule, properties which
+/// are not set are left untouched.
+pub struct FirewallRuleParams {
+    /// Rule type.
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub ty: Option<FirewallRuleType>,
+
+    /// Rule action (`ACCEPT`, `DROP`, `REJECT`) or the name of a security group.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+
+    /// Whether the rule is active.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enable: Option<bool>,
+
+    /// Network interface the rule applies to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iface: Option<String>,
+
+    /// Source address, IP set or alias.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+
+    /// Destination address, IP set or alias.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dest: Option<String>,
+
+    /// IP protocol.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proto: Option<String>,
+
+    /// Destination port(s).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dport: Option<String>,
+
+    /// Source port(s).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sport: Option<String>,
+
+    /// Use a predefined standard macro.
+    #[serde(rename = "macro", default, skip_serializing_if = "Option::is_none")]
+    pub r#macro: Option<String>,
+
+    /// ICMP type, only valid if `proto` is an ICMP protocol.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icmp_type: Option<String>,
+
+    /// Log level for the rule.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log: Option<FirewallLogLevel>,
+
+    /// Descriptive comment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+#[api(
+    properties: {
+        name: {
+            schema: FIREWALL_ALIAS_NAME_SCHEMA,
+        },
+        cidr: {
+            schema: FIREWALL_CIDR_SCHEMA,
+        },
+        digest: {
+            schema: PVE_CONFIG_DIGEST_SCHEMA,
+            optional: true,
+        },
+    },
+)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// A firewall alias of a PVE remote.
+pub struct FirewallAlias {
+    pub name: String,
+
+    pub cidr: String,
+
+    /// Descriptive comment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+}
+
+#[api(
+    properties: {
+        name: {
+            schema: FIREWALL_IPSET_NAME_SCHEMA,
+        },
+        digest: {
+            schema: PVE_CONFIG_DIGEST_SCHEMA,
+            optional: true,
+        },
+    },
+)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// A firewall IP set of a PVE remote.
+pub struct FirewallIpSet {
+    pub name: String,
+
+    /// Descriptive comment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+}
+
+#[api(
+    properties: {
+        cidr: {
+            schema: FIREWALL_CIDR_SCHEMA,
+        },
+        digest: {
+            schema: PVE_CONFIG_DIGEST_SCHEMA,
+            optional: true,
+        },
+    },
+)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// An entry of a firewall IP set.
+pub struct FirewallIpSetEntry {
+    pub cidr: String,
+
+    /// Descriptive comment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+
+    /// Exclude the address from the IP set.
+    #[serde(default)]
+    pub nomatch: bool,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+}
+
+#[api(
+    properties: {
+        group: {
+            schema: FIREWALL_GROUP_NAME_SCHEMA,
+        },
+        digest: {
+            schema: PVE_CONFIG_DIGEST_SCHEMA,
+            optional: true,
+        },
+    },
+)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// A firewall security group of a PVE remote.
+pub struct FirewallSecurityGroup {
+    pub group: String,
+
+    /// Descriptive comment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+}
+
+#[api]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+/// The kind of firewall object to push to other remotes.
+pub enum FirewallPushKind {
+    /// A security group including its rules.
+    SecurityGroup,
+    /// An IP set including its entries.
+    Ipset,
+}
+serde_plain::derive_display_from_serialize!(FirewallPushKind);
+
+#[api]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+/// A cluster firewall option.
+pub struct FirewallOption {
+    /// Name of the option.
+    pub key: String,
+
+    /// Value of the option.
+    pub value: String,
+}
+
+#[api(
+    properties: {
+        name: {
+            schema: FIREWALL_IPSET_NAME_SCHEMA,
+        },
+        entries: {
+            type: Array,
+            items: { type: FirewallIpSetEntry },
+        },
+    },
+)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// An IP set including its entries.
+pub struct FirewallIpSetConfig {
+    pub name: String,
+
+    /// Descriptive comment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+
+    /// Entries of the IP set.
+    #[serde(default)]
+    pub entries: Vec<FirewallIpSetEntry>,
+}
+
+#[api(
+    properties: {
+        group: {
+            schema: FIREWALL_GROUP_NAME_SCHEMA,
+        },
+        rules: {
+            type: Array,
+            items: { type: FirewallRuleParams },
+        },
+    },
+)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// A security group including its rules.
+pub struct FirewallSecurityGroupConfig {
+    pub group: String,
+
+    /// Descriptive comment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+
+    /// Rules of the security group, in order.
+    #[serde(default)]
+    pub rules: Vec<FirewallRuleParams>,
+}
+
+#[api(
+    properties: {
+        options: {
+            type: Array,
+            items: { type: FirewallOption },
+        },
+        rules: {
+            type: Array,
+            items: { type: FirewallRuleParams },
+        },
+        ipsets: {
+            type: Array,
+            items: { type: FirewallIpSetConfig },
+        },
+        groups: {
+            type: Array,
+            items: { type: FirewallSecurityGroupConfig },
+        },
+    },
+)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// The cluster level firewall configuration of a PVE remote.
+pub struct ClusterFirewallConfig {
+    /// Cluster firewall options, sorted by name.
+    #[serde(default)]
+    pub options: Vec<FirewallOption>,
+
+    /// Cluster firewall rules, in order.
+    #[serde(default)]
+    pub rules: Vec<FirewallRuleParams>,
+
+    /// IP sets, sorted by name.
+    #[serde(default)]
+    pub ipsets: Vec<FirewallIpSetConfig>,
+
+    /// Security groups, sorted by name.
+    #[serde(default)]
+    pub groups: Vec<FirewallSecurityGroupConfig>,
+}
+
+#[api(
+    properties: {
+        remote: {
+            schema: REMOTE_ID_SCHEMA,
+            optional: true,
+        },
+        config: {
+            type: ClusterFirewallConfig,
+        },
+    },
+)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// A stored cluster firewall configuration other remotes are compared against.
+pub struct FirewallBaseline {
+    /// The remote the baseline was taken from, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
+
+    /// Time the baseline was stored (epoch).
+    pub time: i64,
+
+    pub config: ClusterFirewallConfig,
+}
+
+#[api]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+/// The kind of firewall object that differs from the reference.
+pub enum FirewallDriftObject {
+    /// A cluster firewall option.
+    Option,
+    /// A cluster firewall rule.
+    Rule,
+    /// An IP set.
+    Ipset,
+    /// An entry of an IP set.
+    IpsetEntry,
+    /// A security group.
+    Group,
+    /// A rule of a security group.
+    GroupRule,
+}
+
+#[api]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+/// How a firewall object differs from the reference.
+pub enum FirewallDriftKind {
+    /// The object only exists in the reference.
+    Missing,
+    /// The object does not exist in the reference.
+    Unexpected,
+    /// The object exists in both, but differs.
+    Changed,
+}
+
+#[api(
+    properties: {
+        object: {
+            type: FirewallDriftObject,
+        },
+        kind: {
+            type: FirewallDriftKind,
+        },
+    },
+)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// A single difference between the firewall configuration of a remote and the reference.
+pub struct FirewallDrift {
+    pub object: FirewallDriftObject,
+
+    /// Identifies the object, for example the option name, the rule position or the IP set name
+    /// and CIDR separated by a slash.
+    pub id: String,
+
+    pub kind: FirewallDriftKind,
+
+    /// The object in the reference configuration.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected: Option<String>,
+
+    /// The object on the remote.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actual: Option<String>,
+}
+
+#[api(
+    properties: {
+        remote: {
+            schema: REMOTE_ID_SCHEMA,
+        },
+        differences: {
+            type: Array,
+            items: { type: FirewallDrift },
+        },
+    },
+)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// Differences of the cluster firewall configuration of a remote to the reference.
+pub struct RemoteFirewallDrift {
+    pub remote: String,
+
+    /// The differences, empty if the remote matches the reference.
+    #[serde(default)]
+    pub differences: Vec<FirewallDrift>,
+
+    /// Set if the firewall configuration of the remote could not be queried.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[api(
+    properties: {
+        reference: {
+            schema: REMOTE_ID_SCHEMA,
+            optional: true,
+        },
+        remotes: {
+            type: Array,
+            items: { type: RemoteFirewallDrift },
+        },
+    },
+)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// Result of comparing the cluster firewall configuration of remotes.
+pub struct FirewallDriftReport {
+    /// The reference remote, or none if the remotes were compared against the stored baseline.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+
+    /// Time the stored baseline was taken (epoch), if it was used as reference.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline_time: Option<i64>,
+
+    pub remotes: Vec<RemoteFirewallDrift>,
+}
@@ -0,0 +1,121 @@
+//! Types for updating the configuration of guests on PVE remotes.
+
+use serde::{Deserialize, Serialize};
+
+use proxmox_schema::{api, const_regex, ApiStringFormat, Schema, StringSchema};
+
+const_regex! {
+    pub GUEST_NET_CONFIG_REGEX = r"^net\d+=\S+$";
+    pub GUEST_CONFIG_DELETABLE_REGEX = r"^(?:tags|description|boot|net\d+)$";
+    pub PVE_CONFIG_DIGEST_REGEX = r"^[0-9a-f]{40}$";
+}
+
+pub const GUEST_NET_CONFIG_SCHEMA: Schema = StringSchema::new(
+    "A network device, in the form 'netN=<config>' with the property string of the device as \
+    used by PVE.",
+)
+.format(&ApiStringFormat::Pattern(&GUEST_NET_CONFIG_REGEX))
+.schema();
+
+pub const GUEST_CONFIG_DELETABLE_SCHEMA: Schema =
+    StringSchema::new("A guest configuration property which can be deleted.")
+        .format(&ApiStringFormat::Pattern(&GUEST_CONFIG_DELETABLE_REGEX))
+        .schema();
+
+pub const PVE_CONFIG_DIGEST_SCHEMA: Schema = StringSchema::new(
+    "Digest of the current guest configuration on the remote. Prevents concurrent modifications.",
+)
+.format(&ApiStringFormat::Pattern(&PVE_CONFIG_DIGEST_REGEX))
+.schema();
+
+#[api(
+    properties: {
+        digest: {
+            schema: PVE_CONFIG_DIGEST_SCHEMA,
+            optional: true,
+        },
+        cores: {
+            minimum: 1,
+            maximum: 8192,
+            optional: true,
+        },
+        sockets: {
+            minimum: 1,
+            maximum: 16,
+            optional: true,
+        },
+        memory: {
+            minimum: 16,
+            optional: true,
+        },
+        swap: {
+            optional: true,
+        },
+        tags: {
+            optional: true,
+        },
+        description: {
+            optional: true,
+        },
+        boot: {
+            optional: true,
+        },
+        net: {
+            type: Array,
+            optional: true,
+            items: { schema: GUEST_NET_CONFIG_SCHEMA },
+        },
+        delete: {
+            type: Array,
+            optional: true,
+            items: { schema: GUEST_CONFIG_DELETABLE_SCHEMA },
+        },
+    },
+)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// Changes to the configuration of a guest.
+///
+/// Properties which are not set are left untouched. For running guests, PVE may only apply some
+/// of the changes on the next restart, they show up as pending changes until then.
+pub struct GuestConfigUpdate {
+    /// Only update the configuration if its digest still matches.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+
+    /// Number of CPU cores (per socket for VMs).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cores: Option<u64>,
+
+    /// Number of CPU sockets, only supported for VMs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sockets: Option<u64>,
+
+    /// Memory in MiB.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory: Option<u64>,
+
+    /// Swap in MiB, only supported for containers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub swap: Option<u64>,
+
+    /// Tags of the guest, separated by semicolons.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<String>,
+
+    /// Description of the guest, shown as notes in PVE.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Boot order, e.g. `order=scsi0;net0`, only supported for VMs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub boot: Option<String>,
+
+    /// Network devices to add or replace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub net: Option<Vec<String>>,
+
+    /// Properties to delete.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delete: Option<Vec<String>>,
+}
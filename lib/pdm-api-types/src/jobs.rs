@@ -0,0 +1,196 @@
+//! Types for jobs which are run on a schedule by the Datacenter Manager itself.
+
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use proxmox_schema::{api, ApiStringFormat, ApiType, Schema, StringSchema, Updater};
+use proxmox_section_config::{typed::ApiSectionDataEntry, SectionConfig, SectionConfigPlugin};
+
+use pbs_api_types::JobScheduleStatus;
+
+use crate::bulk_action::GuestAction;
+use crate::remotes::REMOTE_ID_SCHEMA;
+use crate::{PROXMOX_SAFE_ID_FORMAT, SINGLE_LINE_COMMENT_SCHEMA};
+
+pub const JOB_ID_SCHEMA: Schema = StringSchema::new("Job ID.")
+    .format(&PROXMOX_SAFE_ID_FORMAT)
+    .min_length(3)
+    .max_length(32)
+    .schema();
+
+pub const JOB_SCHEDULE_SCHEMA: Schema = StringSchema::new("Run the job at the given times.")
+    .format(&ApiStringFormat::VerifyFn(
+        proxmox_time::verify_calendar_event,
+    ))
+    .type_text("<calendar-event>")
+    .schema();
+
+#[api]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// The action a scheduled job runs.
+pub enum JobType {
+    /// Refresh the package database on all nodes of the remotes.
+    AptRefresh,
+    /// Run an action on all guests matching a search.
+    GuestAction,
+    /// Start garbage collection on all datastores of PBS remotes.
+    GarbageCollection,
+    /// Generate a system report and send it as notification.
+    Report,
+}
+
+serde_plain::derive_display_from_serialize!(JobType);
+serde_plain::derive_fromstr_from_deserialize!(JobType);
+
+impl JobType {
+    /// Check if jobs of this type act on remotes.
+    pub fn uses_remotes(&self) -> bool {
+        !matches!(self, JobType::Report)
+    }
+}
+
+#[api(
+    properties: {
+        id: {
+            schema: JOB_ID_SCHEMA,
+        },
+        "job-type": {
+            type: JobType,
+        },
+        schedule: {
+            schema: JOB_SCHEDULE_SCHEMA,
+        },
+        remotes: {
+            type: Array,
+            description: "Remotes the job acts on. All remotes of the fitting type if not set.",
+            optional: true,
+            items: {
+                schema: REMOTE_ID_SCHEMA,
+            },
+        },
+        search: {
+            description: "Search term to select the guests, uses the same syntax as the resource list.",
+            optional: true,
+        },
+        action: {
+            type: GuestAction,
+            optional: true,
+        },
+        comment: {
+            schema: SINGLE_LINE_COMMENT_SCHEMA,
+            optional: true,
+        },
+        disable: {
+            optional: true,
+            default: false,
+        },
+    }
+)]
+#[derive(Clone, Debug, Deserialize, Serialize, Updater, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// Scheduled job definition.
+pub struct ScheduledJob {
+    /// Job ID.
+    #[updater(skip)]
+    pub id: String,
+
+    /// What the job does.
+    #[updater(skip)]
+    pub job_type: JobType,
+
+    /// When the job runs.
+    pub schedule: String,
+
+    /// Limit the job to these remotes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remotes: Option<Vec<String>>,
+
+    /// Guest selection for `guest-action` jobs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search: Option<String>,
+
+    /// The action of `guest-action` jobs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<GuestAction>,
+
+    /// Comment.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+
+    /// Disable this job.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disable: Option<bool>,
+}
+
+impl ScheduledJob {
+    /// Check if the job is enabled.
+    pub fn enabled(&self) -> bool {
+        !self.disable.unwrap_or(false)
+    }
+
+    /// Check if the job acts on the given remote.
+    pub fn includes_remote(&self, remote: &str) -> bool {
+        match &self.remotes {
+            Some(remotes) => remotes.iter().any(|r| r == remote),
+            None => true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// Enum for the different sections in the 'jobs.cfg' file.
+pub enum JobConfigEntry {
+    /// 'job' section
+    Job(ScheduledJob),
+}
+
+const JOB_SECTION_NAME: &str = "job";
+
+impl ApiSectionDataEntry for JobConfigEntry {
+    fn section_config() -> &'static SectionConfig {
+        static CONFIG: OnceLock<SectionConfig> = OnceLock::new();
+
+        CONFIG.get_or_init(|| {
+            let mut this = SectionConfig::new(&JOB_ID_SCHEMA);
+
+            this.register_plugin(SectionConfigPlugin::new(
+                JOB_SECTION_NAME.into(),
+                Some("id".to_string()),
+                ScheduledJob::API_SCHEMA.unwrap_object_schema(),
+            ));
+            this
+        })
+    }
+
+    fn section_type(&self) -> &'static str {
+        match self {
+            JobConfigEntry::Job(_) => JOB_SECTION_NAME,
+        }
+    }
+}
+
+#[api(
+    properties: {
+        config: {
+            type: ScheduledJob,
+            flatten: true,
+        },
+        status: {
+            type: JobScheduleStatus,
+            flatten: true,
+        },
+    }
+)]
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+/// A scheduled job together with the state of its last and next run.
+pub struct ScheduledJobStatus {
+    #[serde(flatten)]
+    pub config: ScheduledJob,
+
+    #[serde(flatten)]
+    pub status: JobScheduleStatus,
+}
@@ -100,14 +100,32 @@ pub use proxmox_schema::upid::*;
 mod openid;
 pub use openid::*;
 
+pub mod alerts;
+
+pub mod audit;
+
+pub mod backup_coverage;
+
+pub mod bulk_action;
+
 pub mod firewall;
 
+pub mod guest_config;
+
+pub mod jobs;
+
+pub mod load_balancing;
+
+pub mod metric_server;
+
 pub mod remotes;
 
 pub mod remote_updates;
 
 pub mod resource;
 
+pub mod rolling_upgrade;
+
 pub mod rrddata;
 
 pub mod subscription;
@@ -320,6 +338,26 @@ pub struct TaskStatistics {
     pub by_remote: HashMap<String, TaskCount>,
 }
 
+#[api(
+    properties: {
+        task: {
+            type: TaskListItem,
+            flatten: true,
+        },
+    },
+)]
+/// A line of a cached task log which matched a search pattern.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TaskLogMatch {
+    #[serde(flatten)]
+    pub task: TaskListItem,
+    /// The number of the matching line, starting at 1.
+    pub line_number: u64,
+    /// The matching line.
+    pub line: String,
+}
+
 pub const NODE_TASKS_LIST_TASKS_RETURN_TYPE: ReturnType = ReturnType {
     optional: false,
     schema: &ArraySchema::new("A list of tasks.", &TaskListItem::API_SCHEMA).schema(),
@@ -399,6 +437,25 @@ impl ConfigurationState {
 serde_plain::derive_display_from_serialize!(ConfigurationState);
 serde_plain::derive_fromstr_from_deserialize!(ConfigurationState);
 
+#[api]
+/// How a guest is backed up.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GuestBackupMode {
+    /// Back up the running guest using a live snapshot.
+    #[default]
+    Snapshot,
+
+    /// Suspend the guest while it is backed up.
+    Suspend,
+
+    /// Stop the guest while it is backed up.
+    Stop,
+}
+
+serde_plain::derive_display_from_serialize!(GuestBackupMode);
+serde_plain::derive_fromstr_from_deserialize!(GuestBackupMode);
+
 fn limit_default() -> u64 {
     50
 }
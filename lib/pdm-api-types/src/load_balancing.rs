@@ -0,0 +1,138 @@
+//! Types for guest placement recommendations across PVE remotes.
+
+use serde::{Deserialize, Serialize};
+
+use proxmox_schema::api;
+
+use crate::remotes::REMOTE_ID_SCHEMA;
+use crate::{NODE_SCHEMA, VMID_SCHEMA};
+
+#[api]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// The kind of a proposed migration.
+pub enum MigrationKind {
+    /// Migration to another node of the same cluster.
+    Local,
+    /// Migration to a node of another remote.
+    Remote,
+}
+
+#[api(
+    properties: {
+        remote: {
+            schema: REMOTE_ID_SCHEMA,
+        },
+        node: {
+            schema: NODE_SCHEMA,
+        },
+    },
+)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// Load of a single node.
+pub struct NodeLoad {
+    /// The remote the node belongs to.
+    pub remote: String,
+
+    /// The name of the node.
+    pub node: String,
+
+    /// Average CPU usage in relation to the node's CPUs.
+    pub cpu: f64,
+
+    /// Average memory usage in relation to the node's memory.
+    pub memory: f64,
+
+    /// The node's load, the higher one of the CPU and memory usage.
+    pub load: f64,
+
+    /// The node's load after all proposed migrations were done.
+    pub planned_load: f64,
+}
+
+#[api(
+    properties: {
+        remote: {
+            schema: REMOTE_ID_SCHEMA,
+        },
+        vmid: {
+            schema: VMID_SCHEMA,
+        },
+        node: {
+            schema: NODE_SCHEMA,
+        },
+        "target-remote": {
+            schema: REMOTE_ID_SCHEMA,
+        },
+        "target-node": {
+            schema: NODE_SCHEMA,
+        },
+        kind: {
+            type: MigrationKind,
+        },
+    },
+)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// A proposed guest migration.
+pub struct ProposedMigration {
+    /// The remote the guest is located on.
+    pub remote: String,
+
+    /// The VMID of the guest.
+    pub vmid: u32,
+
+    /// The name of the guest.
+    pub name: String,
+
+    /// The node the guest is located on.
+    pub node: String,
+
+    /// The remote the guest should be migrated to.
+    pub target_remote: String,
+
+    /// The node the guest should be migrated to.
+    pub target_node: String,
+
+    /// Whether this is a migration within a cluster or to another remote.
+    pub kind: MigrationKind,
+
+    /// Average CPU usage of the guest in number of CPUs.
+    pub cpu: f64,
+
+    /// Average memory usage of the guest in bytes.
+    pub memory: u64,
+
+    /// By how much this migration reduces the imbalance.
+    pub score: f64,
+}
+
+#[api(
+    properties: {
+        migrations: {
+            type: Array,
+            items: { type: ProposedMigration },
+        },
+        nodes: {
+            type: Array,
+            items: { type: NodeLoad },
+        },
+    },
+)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// A migration plan which evens out the load of the nodes.
+pub struct MigrationPlan {
+    /// Standard deviation of the node loads.
+    pub imbalance: f64,
+
+    /// Standard deviation of the node loads after all proposed migrations were done.
+    pub planned_imbalance: f64,
+
+    /// The proposed migrations, in the order they should be done.
+    pub migrations: Vec<ProposedMigration>,
+
+    /// The load of all considered nodes.
+    pub nodes: Vec<NodeLoad>,
+}
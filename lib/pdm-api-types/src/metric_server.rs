@@ -0,0 +1,363 @@
+//! Types for exporting collected metrics to external metric servers.
+
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use proxmox_schema::{api, ApiType, Schema, StringSchema, Updater};
+use proxmox_section_config::{typed::ApiSectionDataEntry, SectionConfig, SectionConfigPlugin};
+
+use crate::{PROXMOX_SAFE_ID_FORMAT, SINGLE_LINE_COMMENT_SCHEMA};
+
+pub const METRIC_SERVER_ID_SCHEMA: Schema = StringSchema::new("Metric server name.")
+    .format(&PROXMOX_SAFE_ID_FORMAT)
+    .min_length(3)
+    .max_length(32)
+    .schema();
+
+pub const INFLUXDB_BUCKET_SCHEMA: Schema = StringSchema::new("InfluxDB bucket.")
+    .format(&PROXMOX_SAFE_ID_FORMAT)
+    .min_length(3)
+    .max_length(32)
+    .default("proxmox")
+    .schema();
+
+pub const INFLUXDB_ORGANIZATION_SCHEMA: Schema = StringSchema::new("InfluxDB organization.")
+    .format(&PROXMOX_SAFE_ID_FORMAT)
+    .min_length(3)
+    .max_length(32)
+    .default("proxmox")
+    .schema();
+
+pub const GRAPHITE_PATH_SCHEMA: Schema =
+    StringSchema::new("Root path for all metrics sent to the Graphite server.")
+        .format(&PROXMOX_SAFE_ID_FORMAT)
+        .max_length(64)
+        .default("proxmox-datacenter-manager")
+        .schema();
+
+fn return_true() -> bool {
+    true
+}
+
+fn is_true(b: &bool) -> bool {
+    *b
+}
+
+#[api(
+    properties: {
+        name: {
+            schema: METRIC_SERVER_ID_SCHEMA,
+        },
+        enable: {
+            optional: true,
+            default: true,
+        },
+        host: {
+            type: String,
+            description: "The host and port of the InfluxDB server, e.g. 'influx.example.com:8089'.",
+        },
+        mtu: {
+            optional: true,
+            default: 1500,
+        },
+        comment: {
+            optional: true,
+            schema: SINGLE_LINE_COMMENT_SCHEMA,
+        },
+    },
+)]
+#[derive(Clone, Debug, Deserialize, Serialize, Updater, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// InfluxDB server reachable via UDP.
+pub struct InfluxDbUdp {
+    #[updater(skip)]
+    pub name: String,
+
+    /// Enables or disables the metric server.
+    #[serde(default = "return_true", skip_serializing_if = "is_true")]
+    #[updater(serde(skip_serializing_if = "Option::is_none"))]
+    pub enable: bool,
+
+    pub host: String,
+
+    /// The MTU of the network, used to split data into datagrams.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mtu: Option<u16>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+#[api(
+    properties: {
+        name: {
+            schema: METRIC_SERVER_ID_SCHEMA,
+        },
+        enable: {
+            optional: true,
+            default: true,
+        },
+        url: {
+            type: String,
+            description: "The base URL of the InfluxDB server, e.g. 'https://influx.example.com:8086'.",
+        },
+        token: {
+            type: String,
+            optional: true,
+            description: "The API token used to authenticate against the InfluxDB server.",
+        },
+        bucket: {
+            schema: INFLUXDB_BUCKET_SCHEMA,
+            optional: true,
+        },
+        organization: {
+            schema: INFLUXDB_ORGANIZATION_SCHEMA,
+            optional: true,
+        },
+        "max-body-size": {
+            optional: true,
+            default: 25_000_000,
+        },
+        "verify-tls": {
+            optional: true,
+            default: true,
+        },
+        comment: {
+            optional: true,
+            schema: SINGLE_LINE_COMMENT_SCHEMA,
+        },
+    },
+)]
+#[derive(Clone, Debug, Deserialize, Serialize, Updater, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// InfluxDB server reachable via HTTP(S).
+pub struct InfluxDbHttp {
+    #[updater(skip)]
+    pub name: String,
+
+    /// Enables or disables the metric server.
+    #[serde(default = "return_true", skip_serializing_if = "is_true")]
+    #[updater(serde(skip_serializing_if = "Option::is_none"))]
+    pub enable: bool,
+
+    pub url: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bucket: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub organization: Option<String>,
+
+    /// The maximum size of the HTTP request body in bytes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_body_size: Option<usize>,
+
+    /// Verify the TLS certificate of the server.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verify_tls: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+#[api]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// Transport protocol used to send data to a Graphite server.
+pub enum GraphiteProtocol {
+    /// Send data via UDP.
+    #[default]
+    Udp,
+    /// Send data via TCP.
+    Tcp,
+}
+
+#[api(
+    properties: {
+        name: {
+            schema: METRIC_SERVER_ID_SCHEMA,
+        },
+        enable: {
+            optional: true,
+            default: true,
+        },
+        server: {
+            type: String,
+            description: "The host name or IP address of the Graphite server.",
+        },
+        port: {
+            optional: true,
+            default: 2003,
+        },
+        proto: {
+            type: GraphiteProtocol,
+            optional: true,
+        },
+        path: {
+            schema: GRAPHITE_PATH_SCHEMA,
+            optional: true,
+        },
+        mtu: {
+            optional: true,
+            default: 1500,
+        },
+        timeout: {
+            optional: true,
+            default: 1,
+            minimum: 1,
+            maximum: 10,
+        },
+        comment: {
+            optional: true,
+            schema: SINGLE_LINE_COMMENT_SCHEMA,
+        },
+    },
+)]
+#[derive(Clone, Debug, Deserialize, Serialize, Updater, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// Graphite server using the plaintext protocol.
+pub struct Graphite {
+    #[updater(skip)]
+    pub name: String,
+
+    /// Enables or disables the metric server.
+    #[serde(default = "return_true", skip_serializing_if = "is_true")]
+    #[updater(serde(skip_serializing_if = "Option::is_none"))]
+    pub enable: bool,
+
+    pub server: String,
+
+    /// The port of the Graphite server.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proto: Option<GraphiteProtocol>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+
+    /// The MTU of the network, used to split data into datagrams when using UDP.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mtu: Option<u16>,
+
+    /// Timeout in seconds for establishing a TCP connection.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// Enum for the different sections in the 'metricserver.cfg' file.
+pub enum MetricServerEntry {
+    /// 'influxdb-udp' section
+    #[serde(rename = "influxdb-udp")]
+    InfluxDbUdp(InfluxDbUdp),
+    /// 'influxdb-http' section
+    #[serde(rename = "influxdb-http")]
+    InfluxDbHttp(InfluxDbHttp),
+    /// 'graphite' section
+    Graphite(Graphite),
+}
+
+impl MetricServerEntry {
+    /// The name of the metric server.
+    pub fn name(&self) -> &str {
+        match self {
+            MetricServerEntry::InfluxDbUdp(config) => &config.name,
+            MetricServerEntry::InfluxDbHttp(config) => &config.name,
+            MetricServerEntry::Graphite(config) => &config.name,
+        }
+    }
+
+    /// Check if the metric server is enabled.
+    pub fn enabled(&self) -> bool {
+        match self {
+            MetricServerEntry::InfluxDbUdp(config) => config.enable,
+            MetricServerEntry::InfluxDbHttp(config) => config.enable,
+            MetricServerEntry::Graphite(config) => config.enable,
+        }
+    }
+}
+
+impl ApiSectionDataEntry for MetricServerEntry {
+    fn section_config() -> &'static SectionConfig {
+        static CONFIG: OnceLock<SectionConfig> = OnceLock::new();
+
+        CONFIG.get_or_init(|| {
+            let mut this = SectionConfig::new(&METRIC_SERVER_ID_SCHEMA);
+
+            this.register_plugin(SectionConfigPlugin::new(
+                "influxdb-udp".into(),
+                Some("name".to_string()),
+                InfluxDbUdp::API_SCHEMA.unwrap_object_schema(),
+            ));
+            this.register_plugin(SectionConfigPlugin::new(
+                "influxdb-http".into(),
+                Some("name".to_string()),
+                InfluxDbHttp::API_SCHEMA.unwrap_object_schema(),
+            ));
+            this.register_plugin(SectionConfigPlugin::new(
+                "graphite".into(),
+                Some("name".to_string()),
+                Graphite::API_SCHEMA.unwrap_object_schema(),
+            ));
+            this
+        })
+    }
+
+    fn section_type(&self) -> &'static str {
+        match self {
+            MetricServerEntry::InfluxDbUdp(_) => "influxdb-udp",
+            MetricServerEntry::InfluxDbHttp(_) => "influxdb-http",
+            MetricServerEntry::Graphite(_) => "graphite",
+        }
+    }
+}
+
+#[api]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// Basic information about a metric server.
+pub struct MetricServerInfo {
+    /// The name of the metric server.
+    pub name: String,
+    /// The type of the metric server.
+    #[serde(rename = "type")]
+    pub ty: String,
+    /// The target server (host, URL or address).
+    pub server: String,
+    /// Whether the metric server is enabled.
+    pub enable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Comment.
+    pub comment: Option<String>,
+}
+
+impl From<&MetricServerEntry> for MetricServerInfo {
+    fn from(entry: &MetricServerEntry) -> Self {
+        let (server, comment) = match entry {
+            MetricServerEntry::InfluxDbUdp(config) => (config.host.clone(), &config.comment),
+            MetricServerEntry::InfluxDbHttp(config) => (config.url.clone(), &config.comment),
+            MetricServerEntry::Graphite(config) => (
+                format!("{}:{}", config.server, config.port.unwrap_or(2003)),
+                &config.comment,
+            ),
+        };
+
+        Self {
+            name: entry.name().to_string(),
+            ty: entry.section_type().to_string(),
+            server,
+            enable: entry.enabled(),
+            comment: comment.clone(),
+        }
+    }
+}
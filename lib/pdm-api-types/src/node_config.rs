@@ -29,6 +29,10 @@ use crate::{
             schema: Translation::API_SCHEMA,
             optional: true,
         },
+        "public-metrics": {
+            optional: true,
+            default: false,
+        },
     },
 )]
 #[derive(Deserialize, Serialize, Updater)]
@@ -52,4 +56,8 @@ pub struct NodeConfig {
     /// Default language used in the GUI
     #[serde(skip_serializing_if = "Option::is_none")]
     pub default_lang: Option<String>,
+
+    /// Allow unauthenticated access to the OpenMetrics endpoint at '/metrics'.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_metrics: Option<bool>,
 }
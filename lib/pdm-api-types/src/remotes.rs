@@ -4,11 +4,11 @@ use http::Uri;
 use serde::{Deserialize, Serialize};
 
 use proxmox_schema::property_string::PropertyString;
-use proxmox_schema::{api, ApiType, Schema, StringSchema, Updater};
+use proxmox_schema::{api, ApiStringFormat, ApiType, ArraySchema, Schema, StringSchema, Updater};
 use proxmox_section_config::typed::ApiSectionDataEntry;
 use proxmox_section_config::{SectionConfig, SectionConfigPlugin};
 
-use crate::{Authid, HOST_OPTIONAL_PORT_FORMAT};
+use crate::{Authid, HOST_OPTIONAL_PORT_FORMAT, NODE_SCHEMA};
 
 pub const REMOTE_ID_SCHEMA: Schema = StringSchema::new("Remote ID.")
     .format(&crate::PROXMOX_SAFE_ID_FORMAT)
@@ -16,6 +16,20 @@ pub const REMOTE_ID_SCHEMA: Schema = StringSchema::new("Remote ID.")
     .max_length(32)
     .schema();
 
+pub const MAINTENANCE_NODE_ARRAY_SCHEMA: Schema = ArraySchema::new(
+    "Cluster nodes in maintenance mode (PVE only).",
+    &NODE_SCHEMA,
+)
+.schema();
+
+pub const MAINTENANCE_NODE_LIST_FORMAT: ApiStringFormat =
+    ApiStringFormat::PropertyString(&MAINTENANCE_NODE_ARRAY_SCHEMA);
+
+pub const MAINTENANCE_NODE_LIST_SCHEMA: Schema =
+    StringSchema::new("List of cluster nodes in maintenance mode (PVE only).")
+        .format(&MAINTENANCE_NODE_LIST_FORMAT)
+        .schema();
+
 #[api(
     properties: {
         hostname: {
@@ -81,6 +95,31 @@ serde_plain::derive_fromstr_from_deserialize!(RemoteType);
             type: String,
             optional: true,
         },
+        "http-proxy": {
+            schema: crate::HTTP_PROXY_SCHEMA,
+            optional: true,
+        },
+        "connect-timeout": {
+            optional: true,
+            minimum: 1,
+            maximum: 300,
+        },
+        "request-timeout": {
+            optional: true,
+            default: 60,
+            minimum: 1,
+            maximum: 3600,
+        },
+        "max-connections": {
+            optional: true,
+            default: 5,
+            minimum: 1,
+            maximum: 64,
+        },
+        "maintenance-nodes": {
+            schema: MAINTENANCE_NODE_ARRAY_SCHEMA,
+            optional: true,
+        },
     },
 )]
 /// The information required to connect to a remote instance.
@@ -120,6 +159,77 @@ pub struct Remote {
         skip_serializing_if = "Option::is_none"
     )]
     pub web_url: Option<Uri>,
+
+    /// Creation time of the API token, if the token was created by PDM.
+    #[updater(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_created: Option<i64>,
+
+    /// Expiration time of the API token, if the token was created by PDM.
+    ///
+    /// Such tokens have to be rotated with the password of their user before they expire, the
+    /// admin is notified about this.
+    #[updater(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_expire: Option<i64>,
+
+    /// Automatically add nodes joining and remove nodes leaving the cluster (PVE only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_nodes: Option<bool>,
+
+    /// HTTP CONNECT proxy used to connect to the nodes of the remote.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_proxy: Option<String>,
+
+    /// Timeout in seconds for establishing a connection to a node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_timeout: Option<u64>,
+
+    /// Timeout in seconds for an API request, before another node is tried.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_timeout: Option<u64>,
+
+    /// Maximum number of parallel connections to the remote.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_connections: Option<usize>,
+
+    /// The remote is in maintenance mode. It is not polled and errors are not reported.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maintenance: Option<bool>,
+
+    /// Cluster nodes in maintenance mode (PVE only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maintenance_nodes: Option<Vec<String>>,
+
+    /// End of the maintenance (UNIX epoch). Maintenance mode stays active until it is disabled if
+    /// this is not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maintenance_end: Option<i64>,
+}
+
+impl Remote {
+    /// Check if the end time of the maintenance, if any, is not reached at `now`.
+    fn maintenance_window_open(&self, now: i64) -> bool {
+        self.maintenance_end.is_none_or(|end| now < end)
+    }
+
+    /// Check if the whole remote is in maintenance mode at `now`.
+    pub fn in_maintenance(&self, now: i64) -> bool {
+        self.maintenance.unwrap_or(false) && self.maintenance_window_open(now)
+    }
+
+    /// The cluster nodes configured to be in maintenance mode.
+    pub fn nodes_in_maintenance(&self) -> impl Iterator<Item = &str> {
+        self.maintenance_nodes.iter().flatten().map(String::as_str)
+    }
+
+    /// Check if a cluster node is in maintenance mode at `now`, either by itself or because the
+    /// whole remote is.
+    pub fn node_in_maintenance(&self, node: &str, now: i64) -> bool {
+        self.in_maintenance(now)
+            || (self.maintenance_window_open(now)
+                && self.nodes_in_maintenance().any(|name| name == node))
+    }
 }
 
 impl ApiSectionDataEntry for Remote {
@@ -246,6 +356,65 @@ pub enum TlsProbeOutcome {
     UntrustedCertificate(proxmox_acme_api::CertificateInfo),
 }
 
+#[api]
+/// How the cluster membership of a node changed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeMembershipChange {
+    /// The node joined the cluster, but is missing in the node list of the remote.
+    Joined,
+    /// The node left the cluster, but is still in the node list of the remote.
+    Left,
+}
+
+#[api]
+/// A node whose cluster membership is not reflected in the node list of a remote.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct NodeMembershipEntry {
+    pub change: NodeMembershipChange,
+
+    /// The cluster node name.
+    pub node: String,
+
+    /// The address to add for joined nodes, or the configured address to remove for left nodes.
+    pub hostname: String,
+}
+
+#[api(
+    properties: {
+        "pinned-fingerprint": {
+            schema: crate::CERT_FINGERPRINT_SHA256_SCHEMA,
+            optional: true,
+        },
+        certificate: { type: crate::CertificateInfo },
+    },
+)]
+/// A certificate presented by a node of a remote which does not match the pinned fingerprint.
+#[derive(Clone, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct PendingCertificate {
+    /// The address of the node, as configured in the remote.
+    pub hostname: String,
+
+    /// The cluster node name, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node: Option<String>,
+
+    /// The fingerprint currently pinned for the node.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pinned_fingerprint: Option<String>,
+
+    pub certificate: crate::CertificateInfo,
+
+    /// When the certificate was first presented by the node.
+    pub first_seen: i64,
+
+    /// The certificate chains to a trusted CA configured on PDM and gets accepted automatically.
+    #[serde(default)]
+    pub trusted_by_ca: bool,
+}
+
 #[api(
     properties: {
         "remote": { schema: REMOTE_ID_SCHEMA },
@@ -257,4 +426,23 @@ pub enum TlsProbeOutcome {
 pub struct RemoteListEntry {
     /// An id for this entry.
     pub remote: String,
+
+    /// Age of the API token in seconds, if the token was created by PDM.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_age: Option<i64>,
+
+    /// Expiration time of the API token, if the token was created by PDM.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_expire: Option<i64>,
+}
+
+impl RemoteListEntry {
+    /// Create the list entry of a remote, with the token age relative to `now`.
+    pub fn new(remote: &Remote, now: i64) -> Self {
+        Self {
+            remote: remote.id.clone(),
+            token_age: remote.token_created.map(|created| now - created),
+            token_expire: remote.token_expire,
+        }
+    }
 }
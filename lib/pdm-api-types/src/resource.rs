@@ -589,6 +589,10 @@ pub struct RemoteResources {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
 
+    /// The remote is in maintenance mode and was not queried.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub maintenance: bool,
+
     /// Array of resources found at this remote.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub resources: Vec<Resource>,
@@ -683,6 +687,9 @@ pub struct ResourcesStatus {
     pub remotes: u64,
     /// Amount of remotes that returned an error during querying
     pub failed_remotes: u64,
+    /// Amount of remotes in maintenance mode, which are not queried
+    #[serde(default)]
+    pub maintenance_remotes: u64,
     /// Status of PVE nodes
     pub pve_nodes: NodeStatusCount,
     /// Status of QEMU Guests
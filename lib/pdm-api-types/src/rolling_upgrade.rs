@@ -0,0 +1,111 @@
+//! Types for upgrading the nodes of a PVE cluster one after another.
+
+use serde::{Deserialize, Serialize};
+
+use proxmox_schema::api;
+
+use crate::remotes::REMOTE_ID_SCHEMA;
+use crate::NODE_SCHEMA;
+
+#[api]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// How running guests are moved off a node before it is upgraded.
+pub enum GuestPolicy {
+    /// Migrate virtual machines online and containers in restart mode to other nodes.
+    #[default]
+    Migrate,
+    /// Shut the guests down and start them again once the node is back.
+    Shutdown,
+}
+
+serde_plain::derive_display_from_serialize!(GuestPolicy);
+serde_plain::derive_fromstr_from_deserialize!(GuestPolicy);
+
+#[api]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// Progress of the upgrade of a single node.
+pub enum NodeUpgradeState {
+    /// The node was not handled yet.
+    Pending,
+    /// The node is put into maintenance and its guests are moved away.
+    Evacuating,
+    /// The packages are upgraded.
+    Upgrading,
+    /// The node reboots, waiting for it to rejoin the cluster.
+    Rebooting,
+    /// Maintenance mode is disabled and guests are started again.
+    Restoring,
+    /// The node was upgraded successfully.
+    Done,
+    /// The node had no pending updates.
+    Skipped,
+    /// Upgrading the node failed.
+    Failed,
+}
+
+serde_plain::derive_display_from_serialize!(NodeUpgradeState);
+serde_plain::derive_fromstr_from_deserialize!(NodeUpgradeState);
+
+#[api(
+    properties: {
+        node: {
+            schema: NODE_SCHEMA,
+        },
+        state: {
+            type: NodeUpgradeState,
+        },
+    },
+)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// Upgrade progress of a node.
+pub struct NodeUpgradeStatus {
+    /// The node name.
+    pub node: String,
+
+    /// The current state of the node.
+    pub state: NodeUpgradeState,
+
+    /// Additional information, for example why the upgrade failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[api(
+    properties: {
+        remote: {
+            schema: REMOTE_ID_SCHEMA,
+        },
+        "guest-policy": {
+            type: GuestPolicy,
+        },
+        nodes: {
+            type: Array,
+            items: { type: NodeUpgradeStatus },
+        },
+    },
+)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// Status of a rolling upgrade of a PVE cluster.
+pub struct RollingUpgradeStatus {
+    /// The remote which is upgraded.
+    pub remote: String,
+
+    /// The UPID of the worker task running the upgrade.
+    pub upid: String,
+
+    /// Whether the worker task is still running.
+    pub running: bool,
+
+    /// Whether the upgrade is paused. A paused upgrade stops before the next step.
+    pub paused: bool,
+
+    /// How guests are moved off the nodes.
+    pub guest_policy: GuestPolicy,
+
+    /// The nodes in the order they are upgraded.
+    pub nodes: Vec<NodeUpgradeStatus>,
+}
@@ -1,8 +1,13 @@
-use proxmox_schema::{api, ApiStringFormat, IntegerSchema, Schema, StringSchema};
+use proxmox_schema::{api, const_regex, ApiStringFormat, IntegerSchema, Schema, StringSchema};
 use pve_api_types::{SdnController, SdnVnet, SdnZone};
 use serde::{Deserialize, Serialize};
 
 use crate::remotes::REMOTE_ID_SCHEMA;
+use crate::{CIDR_FORMAT, IP_FORMAT};
+
+const_regex! {
+    pub SDN_SUBNET_ID_REGEX = r"^[0-9a-fA-F.:]+-[0-9]{1,3}$";
+}
 
 pub const VXLAN_ID_SCHEMA: Schema = IntegerSchema::new("VXLAN VNI")
     .minimum(1)
@@ -22,6 +27,23 @@ pub const SDN_CONTROLLER_ID_SCHEMA: Schema = StringSchema::new("The name for an
     ))
     .schema();
 
+pub const SDN_SUBNET_SCHEMA: Schema = StringSchema::new("The subnet in CIDR notation.")
+    .format(&CIDR_FORMAT)
+    .schema();
+
+pub const SDN_SUBNET_ID_SCHEMA: Schema = StringSchema::new(
+    "The subnet in CIDR notation, with the slash replaced by a dash (for example 10.0.0.0-24).",
+)
+.format(&ApiStringFormat::Pattern(&SDN_SUBNET_ID_REGEX))
+.schema();
+
+pub const SDN_IP_SCHEMA: Schema = StringSchema::new("IP address.").format(&IP_FORMAT).schema();
+
+pub const SDN_MTU_SCHEMA: Schema = IntegerSchema::new("MTU of the zone.")
+    .minimum(512)
+    .maximum(65535)
+    .schema();
+
 #[api(
     properties: {
         remote: {
@@ -169,3 +191,171 @@ pub struct ListZone {
     #[serde(flatten)]
     pub zone: SdnZone,
 }
+
+#[api]
+/// A zone property which can be deleted.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SdnZoneDeletableProperty {
+    /// Delete the MTU, falling back to the default.
+    Mtu,
+    /// Delete the node restriction, making the zone available on all nodes.
+    Nodes,
+}
+serde_plain::derive_display_from_serialize!(SdnZoneDeletableProperty);
+
+#[api(
+    properties: {
+        "vrf-vxlan": {
+            schema: VXLAN_ID_SCHEMA,
+            optional: true,
+        },
+        controller: {
+            schema: SDN_CONTROLLER_ID_SCHEMA,
+            optional: true,
+        },
+        mtu: {
+            schema: SDN_MTU_SCHEMA,
+            optional: true,
+        },
+        delete: {
+            type: Array,
+            optional: true,
+            items: { type: SdnZoneDeletableProperty },
+        },
+    }
+)]
+/// Changes to apply to a zone. Properties which are not set are left untouched.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct UpdateZone {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vrf_vxlan: Option<u32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub controller: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mtu: Option<u32>,
+
+    /// Comma separated list of nodes the zone is available on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nodes: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delete: Option<Vec<SdnZoneDeletableProperty>>,
+}
+
+#[api]
+/// A VNet property which can be deleted.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SdnVnetDeletableProperty {
+    /// Delete the alias.
+    Alias,
+    /// Delete the port isolation flag.
+    IsolatePorts,
+}
+serde_plain::derive_display_from_serialize!(SdnVnetDeletableProperty);
+
+#[api(
+    properties: {
+        tag: {
+            schema: VXLAN_ID_SCHEMA,
+            optional: true,
+        },
+        delete: {
+            type: Array,
+            optional: true,
+            items: { type: SdnVnetDeletableProperty },
+        },
+    }
+)]
+/// Changes to apply to a VNet. Properties which are not set are left untouched.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct UpdateVnet {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<u32>,
+
+    /// Alias name of the VNet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+
+    /// Isolate the ports of the VNet from each other.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub isolate_ports: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delete: Option<Vec<SdnVnetDeletableProperty>>,
+}
+
+#[api(
+    properties: {
+        "start-address": {
+            schema: SDN_IP_SCHEMA,
+        },
+        "end-address": {
+            schema: SDN_IP_SCHEMA,
+        },
+    }
+)]
+/// A range of addresses handed out by the DHCP server of a subnet.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct SdnDhcpRange {
+    pub start_address: String,
+    pub end_address: String,
+}
+
+#[api]
+/// A subnet property which can be deleted.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SdnSubnetDeletableProperty {
+    /// Delete the gateway.
+    Gateway,
+    /// Delete the SNAT flag.
+    Snat,
+    /// Delete all DHCP ranges.
+    DhcpRange,
+    /// Delete the DNS server handed out via DHCP.
+    DhcpDnsServer,
+}
+serde_plain::derive_display_from_serialize!(SdnSubnetDeletableProperty);
+
+#[api(
+    properties: {
+        gateway: {
+            schema: SDN_IP_SCHEMA,
+            optional: true,
+        },
+        "dhcp-range": {
+            type: Array,
+            optional: true,
+            items: { type: SdnDhcpRange },
+        },
+        "dhcp-dns-server": {
+            schema: SDN_IP_SCHEMA,
+            optional: true,
+        },
+    }
+)]
+/// Properties of a subnet.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SubnetProperties {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gateway: Option<String>,
+
+    /// Enable source NAT for traffic leaving the subnet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snat: Option<bool>,
+
+    /// DHCP ranges of the subnet. When updating, the given ranges replace the existing ones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dhcp_range: Option<Vec<SdnDhcpRange>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dhcp_dns_server: Option<String>,
+}
@@ -1,14 +1,31 @@
+use std::sync::OnceLock;
 use std::{collections::HashMap, str::FromStr};
 
 use anyhow::Error;
 use serde::{Deserialize, Serialize};
 
-use proxmox_schema::api;
+use proxmox_schema::{api, const_regex, ApiStringFormat, ApiType, Schema, StringSchema};
+use proxmox_section_config::{typed::ApiSectionDataEntry, SectionConfig, SectionConfigPlugin};
 use proxmox_subscription::{SubscriptionInfo, SubscriptionStatus};
 
+use crate::remotes::{RemoteType, REMOTE_ID_SCHEMA};
+use crate::{NODE_SCHEMA, SINGLE_LINE_COMMENT_SCHEMA};
+
+const_regex! {
+    pub SUBSCRIPTION_KEY_REGEX = r"^(?:pve[0-9]+|pbs)[cbsp]-[0-9a-f]{10}$";
+}
+
+pub const SUBSCRIPTION_KEY_FORMAT: ApiStringFormat =
+    ApiStringFormat::Pattern(&SUBSCRIPTION_KEY_REGEX);
+
+pub const SUBSCRIPTION_KEY_SCHEMA: Schema =
+    StringSchema::new("Proxmox VE or Proxmox Backup Server subscription key.")
+        .format(&SUBSCRIPTION_KEY_FORMAT)
+        .schema();
+
 #[api]
 // order is important here, since we use that for determining if a node has a valid subscription
-#[derive(Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 /// Describes the level of subscription
 pub enum SubscriptionLevel {
     #[default]
@@ -111,9 +128,22 @@ pub struct NodeSubscriptionInfo {
     /// The subscription level of the node
     pub level: SubscriptionLevel,
 
+    /// The next due date of the subscription (if any)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nextduedate: Option<String>,
+
     /// Serverid of the node, if accessible
     #[serde(skip_serializing)]
     pub serverid: Option<String>,
+
+    /// Message of the last subscription check, for example why the key is invalid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// The key is from the key pool, but was activated on a different server ID and needs to be
+    /// reissued
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub reissue_required: bool,
 }
 
 #[api(
@@ -174,3 +204,215 @@ pub struct PdmSubscriptionInfo {
     /// PDM subscription statistics
     pub statistics: SubscriptionStatistics,
 }
+
+#[api(
+    properties: {
+        key: {
+            schema: SUBSCRIPTION_KEY_SCHEMA,
+        },
+        remote: {
+            schema: REMOTE_ID_SCHEMA,
+            optional: true,
+        },
+        node: {
+            schema: NODE_SCHEMA,
+            optional: true,
+        },
+        comment: {
+            schema: SINGLE_LINE_COMMENT_SCHEMA,
+            optional: true,
+        },
+    },
+)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// A subscription key in the key pool.
+pub struct SubscriptionKey {
+    /// The subscription key.
+    pub key: String,
+
+    /// The remote the key is assigned to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
+
+    /// The node the key is assigned to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node: Option<String>,
+
+    /// The server ID of the node the key was activated on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub serverid: Option<String>,
+
+    /// Comment.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+impl SubscriptionKey {
+    /// The type of remote the key is for.
+    pub fn product(&self) -> RemoteType {
+        if self.key.starts_with("pbs") {
+            RemoteType::Pbs
+        } else {
+            RemoteType::Pve
+        }
+    }
+
+    /// The subscription level of the key.
+    pub fn level(&self) -> SubscriptionLevel {
+        SubscriptionLevel::from_key(Some(&self.key))
+    }
+
+    /// The number of CPU sockets a PVE key covers, `None` for PBS keys.
+    pub fn sockets(&self) -> Option<i64> {
+        let (key_type, _) = self.key.split_once('-')?;
+        key_type
+            .strip_prefix("pve")
+            .and_then(|rest| rest.get(..rest.len().checked_sub(1)?))
+            .and_then(|sockets| sockets.parse().ok())
+    }
+
+    /// Check if the key is assigned to a node.
+    pub fn is_assigned(&self) -> bool {
+        self.remote.is_some()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// Enum for the different sections in the 'subscription-keys.cfg' file.
+pub enum SubscriptionKeyEntry {
+    /// 'key' section
+    Key(SubscriptionKey),
+}
+
+const SUBSCRIPTION_KEY_SECTION_NAME: &str = "key";
+
+impl ApiSectionDataEntry for SubscriptionKeyEntry {
+    fn section_config() -> &'static SectionConfig {
+        static CONFIG: OnceLock<SectionConfig> = OnceLock::new();
+
+        CONFIG.get_or_init(|| {
+            let mut this = SectionConfig::new(&SUBSCRIPTION_KEY_SCHEMA);
+
+            this.register_plugin(SectionConfigPlugin::new(
+                SUBSCRIPTION_KEY_SECTION_NAME.into(),
+                Some("key".to_string()),
+                SubscriptionKey::API_SCHEMA.unwrap_object_schema(),
+            ));
+            this
+        })
+    }
+
+    fn section_type(&self) -> &'static str {
+        match self {
+            SubscriptionKeyEntry::Key(_) => SUBSCRIPTION_KEY_SECTION_NAME,
+        }
+    }
+}
+
+#[api]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+/// State of a key in the key pool.
+pub enum SubscriptionKeyState {
+    /// The key is not assigned to any node.
+    Free,
+    /// The key is active on the node it is assigned to.
+    Active,
+    /// The node the key is assigned to reports a problem with the key.
+    Invalid,
+    /// The key was activated on a different server ID and needs to be reissued.
+    ReissueRequired,
+    /// The node the key is assigned to could not be queried or uses a different key.
+    Unknown,
+}
+
+#[api(
+    properties: {
+        config: {
+            type: SubscriptionKey,
+            flatten: true,
+        },
+        product: {
+            type: RemoteType,
+        },
+        level: {
+            type: SubscriptionLevel,
+        },
+        state: {
+            type: SubscriptionKeyState,
+        },
+    },
+)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// A key of the key pool together with its state.
+pub struct SubscriptionKeyStatus {
+    #[serde(flatten)]
+    pub config: SubscriptionKey,
+
+    /// The type of remote the key is for.
+    pub product: RemoteType,
+
+    /// The subscription level of the key.
+    pub level: SubscriptionLevel,
+
+    /// The number of CPU sockets the key covers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sockets: Option<i64>,
+
+    /// The state of the key.
+    pub state: SubscriptionKeyState,
+
+    /// Message of the last subscription check of the node the key is assigned to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[api(
+    properties: {
+        key: {
+            schema: SUBSCRIPTION_KEY_SCHEMA,
+        },
+        remote: {
+            schema: REMOTE_ID_SCHEMA,
+        },
+        node: {
+            schema: NODE_SCHEMA,
+        },
+    },
+)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// A key which is assigned to a node.
+pub struct SubscriptionKeyAssignment {
+    /// The subscription key.
+    pub key: String,
+
+    /// The remote of the node.
+    pub remote: String,
+
+    /// The node the key is assigned to.
+    pub node: String,
+}
+
+#[api(
+    properties: {
+        assignments: {
+            type: Array,
+            items: { type: SubscriptionKeyAssignment },
+        },
+    },
+)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// Result of automatically assigning keys of the key pool.
+pub struct SubscriptionAutoAssignResult {
+    /// The keys which are assigned.
+    pub assignments: Vec<SubscriptionKeyAssignment>,
+
+    /// The UPID of the worker task uploading the keys, not set for dry-runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upid: Option<String>,
+}
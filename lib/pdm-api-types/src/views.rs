@@ -295,6 +295,8 @@ pub enum WidgetType {
         grouping: TaskSummaryGrouping,
     },
     ResourceTree,
+    BackupCoverage,
+    FirewallDrift,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Clone, Copy)]
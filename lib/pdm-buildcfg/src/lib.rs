@@ -69,6 +69,10 @@ pub const API_ACCESS_LOG_FN: &str = concat!(PDM_LOG_DIR_M!(), "/api/access.log")
 /// creations. This file can be useful for fail2ban.
 pub const API_AUTH_LOG_FN: &str = concat!(PDM_LOG_DIR_M!(), "/api/auth.log");
 
+/// logfile for all state-changing API calls, with the parameters and the result of the call, one
+/// JSON object per line.
+pub const API_AUDIT_LOG_FN: &str = concat!(PDM_LOG_DIR_M!(), "/api/audit.log");
+
 /// the PID filename for the unprivileged api daemon
 pub const PDM_API_PID_FN: &str = concat!(PDM_RUN_DIR_M!(), "/api.pid");
 
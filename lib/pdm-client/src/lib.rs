@@ -3,8 +3,9 @@
 use std::collections::HashMap;
 use std::time::Duration;
 
+use pdm_api_types::audit::AuditLogEntry;
 use pdm_api_types::remote_updates::RemoteUpdateSummary;
-use pdm_api_types::remotes::{RemoteType, TlsProbeOutcome};
+use pdm_api_types::remotes::{PendingCertificate, RemoteType, TlsProbeOutcome};
 use pdm_api_types::resource::{PveResource, RemoteResources, ResourceType, TopEntities};
 use pdm_api_types::rrddata::{
     LxcDataPoint, NodeDataPoint, PbsDatastoreDataPoint, PbsNodeDataPoint, PveStorageDataPoint,
@@ -25,7 +26,7 @@ pub mod types {
     pub use proxmox_access_control::types::{User, UserWithTokens};
 
     pub use pdm_api_types::remotes::Remote;
-    pub use pdm_api_types::{AclListItem, Authid, ConfigurationState, RemoteUpid};
+    pub use pdm_api_types::{AclListItem, Authid, ConfigurationState, GuestBackupMode, RemoteUpid};
 
     pub use pve_api_types::{ClusterNodeIndexResponse, ClusterNodeIndexResponseStatus};
 
@@ -62,7 +63,9 @@ pub mod types {
     pub use pve_api_types::PveUpid;
 
     pub use pdm_api_types::sdn::{
-        CreateVnetParams, CreateZoneParams, ListController, ListVnet, ListZone, SDN_ID_SCHEMA,
+        CreateVnetParams, CreateZoneParams, ListController, ListVnet, ListZone, SdnDhcpRange,
+        SdnSubnetDeletableProperty, SdnVnetDeletableProperty, SdnZoneDeletableProperty,
+        SubnetProperties, UpdateVnet, UpdateZone, SDN_ID_SCHEMA,
     };
     pub use pve_api_types::{ListControllersType, ListZonesType, SdnObjectState};
 
@@ -70,9 +73,35 @@ pub mod types {
 
     pub use pve_api_types::StorageStatus as PveStorageStatus;
 
-    pub use pdm_api_types::subscription::{RemoteSubscriptionState, RemoteSubscriptions};
+    pub use pdm_api_types::subscription::{
+        RemoteSubscriptionState, RemoteSubscriptions, SubscriptionAutoAssignResult,
+        SubscriptionKeyAssignment, SubscriptionKeyState, SubscriptionKeyStatus,
+    };
 
     pub use pve_api_types::{SdnVnetMacVrf, SdnZoneIpVrf};
+
+    pub use pdm_api_types::bulk_action::{BulkActionGuest, BulkActionResult, GuestAction};
+
+    pub use pdm_api_types::backup_coverage::{
+        BackupCoverage, BackupCoverageStatus, GuestBackupCoverage,
+    };
+
+    pub use pdm_api_types::firewall::{
+        ClusterFirewallConfig, FirewallAlias, FirewallBaseline, FirewallDrift, FirewallDriftKind,
+        FirewallDriftObject, FirewallDriftReport, FirewallIpSet, FirewallIpSetEntry,
+        FirewallLogLevel, FirewallPushKind, FirewallRuleParams, FirewallRuleType,
+        FirewallSecurityGroup, RemoteFirewallDrift,
+    };
+
+    pub use pdm_api_types::guest_config::GuestConfigUpdate;
+
+    pub use pdm_api_types::load_balancing::{
+        MigrationKind, MigrationPlan, NodeLoad, ProposedMigration,
+    };
+
+    pub use pdm_api_types::rolling_upgrade::{
+        GuestPolicy, NodeUpgradeState, NodeUpgradeStatus, RollingUpgradeStatus,
+    };
 }
 
 pub struct PdmClient<T: HttpApiClient>(pub T);
@@ -149,6 +178,46 @@ impl<T: HttpApiClient> PdmClient<T> {
         Ok(())
     }
 
+    /// Replace the API token of a remote with a new one, if the token was created by PDM.
+    ///
+    /// The new token is created with the `password` of the token's user.
+    pub async fn rotate_remote_token(&self, remote: &str, password: &str) -> Result<(), Error> {
+        let path = format!("/api2/extjs/remotes/remote/{remote}/rotate-token");
+        self.0
+            .post(&path, &json!({ "password": password }))
+            .await?
+            .nodata()?;
+        Ok(())
+    }
+
+    /// List the certificates presented by nodes of a remote which do not match the pinned
+    /// fingerprint.
+    pub async fn pending_remote_certificates(
+        &self,
+        remote: &str,
+    ) -> Result<Vec<PendingCertificate>, Error> {
+        let path = format!("/api2/extjs/remotes/remote/{remote}/certificates");
+        Ok(self.0.get(&path).await?.expect_json()?.data)
+    }
+
+    /// Trust the pending certificate of a node of a remote by pinning its fingerprint.
+    pub async fn accept_remote_certificate(
+        &self,
+        remote: &str,
+        hostname: &str,
+        fingerprint: &str,
+    ) -> Result<(), Error> {
+        let path = format!("/api2/extjs/remotes/remote/{remote}/certificates");
+        self.0
+            .post(
+                &path,
+                &json!({ "hostname": hostname, "fingerprint": fingerprint }),
+            )
+            .await?
+            .nodata()?;
+        Ok(())
+    }
+
     pub async fn remote_version(
         &self,
         remote: &str,
@@ -232,6 +301,23 @@ impl<T: HttpApiClient> PdmClient<T> {
         self.0.delete(&path).await?.nodata()
     }
 
+    /// Read the audit log of state-changing API calls, newest entries first.
+    pub async fn read_audit_log(
+        &self,
+        since: Option<i64>,
+        user: Option<&str>,
+        remote: Option<&str>,
+        limit: Option<u64>,
+    ) -> Result<Vec<AuditLogEntry>, Error> {
+        let path = ApiPathBuilder::new("/api2/extjs/access/audit")
+            .maybe_arg("since", &since)
+            .maybe_arg("user", &user)
+            .maybe_arg("remote", &remote)
+            .maybe_arg("limit", &limit)
+            .build();
+        Ok(self.0.get(&path).await?.expect_json()?.data)
+    }
+
     pub async fn list_user_tfa(
         &self,
         userid: &str,
@@ -417,6 +503,40 @@ impl<T: HttpApiClient> PdmClient<T> {
         Ok(self.0.get(path).await?.expect_json()?.data)
     }
 
+    /// Compare the cluster firewall configuration of PVE remotes to the `reference` remote, or
+    /// to the stored baseline if no reference is given.
+    pub async fn pve_firewall_drift(
+        &self,
+        reference: Option<&str>,
+        remotes: &[&str],
+    ) -> Result<FirewallDriftReport, Error> {
+        let mut path = ApiPathBuilder::new("/api2/extjs/pve/firewall/drift")
+            .maybe_arg("reference", &reference);
+        for remote in remotes {
+            path = path.arg("remotes", remote);
+        }
+        Ok(self.0.get(&path.build()).await?.expect_json()?.data)
+    }
+
+    pub async fn pve_get_firewall_baseline(&self) -> Result<FirewallBaseline, Error> {
+        let path = "/api2/extjs/pve/firewall/baseline";
+        Ok(self.0.get(path).await?.expect_json()?.data)
+    }
+
+    /// Store the current cluster firewall configuration of `remote` as baseline.
+    pub async fn pve_set_firewall_baseline(&self, remote: &str) -> Result<(), Error> {
+        let path = "/api2/extjs/pve/firewall/baseline";
+        self.0
+            .put(path, &json!({ "remote": remote }))
+            .await?
+            .nodata()
+    }
+
+    pub async fn pve_delete_firewall_baseline(&self) -> Result<(), Error> {
+        let path = "/api2/extjs/pve/firewall/baseline";
+        self.0.delete(path).await?.nodata()
+    }
+
     pub async fn pve_cluster_firewall_status(
         &self,
         remote: &str,
@@ -543,6 +663,269 @@ impl<T: HttpApiClient> PdmClient<T> {
         self.0.put(&path, &update).await?.nodata()
     }
 
+    pub async fn pve_create_cluster_firewall_rule(
+        &self,
+        remote: &str,
+        rule: FirewallRuleParams,
+        pos: Option<u64>,
+        digest: Option<&str>,
+    ) -> Result<(), Error> {
+        let path = format!("/api2/extjs/pve/remotes/{remote}/firewall/rules");
+        let mut params = serde_json::to_value(rule).expect("failed to build json string");
+        if let Some(pos) = pos {
+            params["pos"] = pos.into();
+        }
+        if let Some(digest) = digest {
+            params["digest"] = digest.into();
+        }
+        self.0.post(&path, &params).await?.nodata()
+    }
+
+    pub async fn pve_update_cluster_firewall_rule(
+        &self,
+        remote: &str,
+        pos: u64,
+        rule: FirewallRuleParams,
+        moveto: Option<u64>,
+        delete: &[&str],
+        digest: Option<&str>,
+    ) -> Result<(), Error> {
+        let path = format!("/api2/extjs/pve/remotes/{remote}/firewall/rules/{pos}");
+        let mut params = serde_json::to_value(rule).expect("failed to build json string");
+        if let Some(moveto) = moveto {
+            params["moveto"] = moveto.into();
+        }
+        if !delete.is_empty() {
+            params["delete"] = delete.into();
+        }
+        if let Some(digest) = digest {
+            params["digest"] = digest.into();
+        }
+        self.0.put(&path, &params).await?.nodata()
+    }
+
+    pub async fn pve_delete_cluster_firewall_rule(
+        &self,
+        remote: &str,
+        pos: u64,
+        digest: Option<&str>,
+    ) -> Result<(), Error> {
+        let path = ApiPathBuilder::new(format!(
+            "/api2/extjs/pve/remotes/{remote}/firewall/rules/{pos}"
+        ))
+        .maybe_arg("digest", &digest)
+        .build();
+        self.0.delete(&path).await?.nodata()
+    }
+
+    pub async fn pve_list_firewall_aliases(
+        &self,
+        remote: &str,
+    ) -> Result<Vec<FirewallAlias>, Error> {
+        let path = format!("/api2/extjs/pve/remotes/{remote}/firewall/aliases");
+        Ok(self.0.get(&path).await?.expect_json()?.data)
+    }
+
+    pub async fn pve_create_firewall_alias(
+        &self,
+        remote: &str,
+        name: &str,
+        cidr: &str,
+        comment: Option<&str>,
+    ) -> Result<(), Error> {
+        let path = format!("/api2/extjs/pve/remotes/{remote}/firewall/aliases");
+        let mut params = json!({ "name": name, "cidr": cidr });
+        if let Some(comment) = comment {
+            params["comment"] = comment.into();
+        }
+        self.0.post(&path, &params).await?.nodata()
+    }
+
+    pub async fn pve_update_firewall_alias(
+        &self,
+        remote: &str,
+        alias: FirewallAlias,
+        rename: Option<&str>,
+    ) -> Result<(), Error> {
+        let path = format!(
+            "/api2/extjs/pve/remotes/{remote}/firewall/aliases/{}",
+            alias.name
+        );
+        let mut params = json!({
+            "cidr": alias.cidr,
+            "comment": alias.comment.unwrap_or_default(),
+        });
+        if let Some(rename) = rename {
+            params["rename"] = rename.into();
+        }
+        if let Some(digest) = alias.digest {
+            params["digest"] = digest.into();
+        }
+        self.0.put(&path, &params).await?.nodata()
+    }
+
+    pub async fn pve_delete_firewall_alias(
+        &self,
+        remote: &str,
+        name: &str,
+        digest: Option<&str>,
+    ) -> Result<(), Error> {
+        let path = ApiPathBuilder::new(format!(
+            "/api2/extjs/pve/remotes/{remote}/firewall/aliases/{name}"
+        ))
+        .maybe_arg("digest", &digest)
+        .build();
+        self.0.delete(&path).await?.nodata()
+    }
+
+    pub async fn pve_list_firewall_ipsets(
+        &self,
+        remote: &str,
+    ) -> Result<Vec<FirewallIpSet>, Error> {
+        let path = format!("/api2/extjs/pve/remotes/{remote}/firewall/ipset");
+        Ok(self.0.get(&path).await?.expect_json()?.data)
+    }
+
+    pub async fn pve_create_firewall_ipset(
+        &self,
+        remote: &str,
+        name: &str,
+        comment: Option<&str>,
+    ) -> Result<(), Error> {
+        let path = format!("/api2/extjs/pve/remotes/{remote}/firewall/ipset");
+        let mut params = json!({ "name": name });
+        if let Some(comment) = comment {
+            params["comment"] = comment.into();
+        }
+        self.0.post(&path, &params).await?.nodata()
+    }
+
+    pub async fn pve_delete_firewall_ipset(&self, remote: &str, name: &str) -> Result<(), Error> {
+        let path = format!("/api2/extjs/pve/remotes/{remote}/firewall/ipset/{name}");
+        self.0.delete(&path).await?.nodata()
+    }
+
+    pub async fn pve_list_firewall_ipset_entries(
+        &self,
+        remote: &str,
+        name: &str,
+    ) -> Result<Vec<FirewallIpSetEntry>, Error> {
+        let path = format!("/api2/extjs/pve/remotes/{remote}/firewall/ipset/{name}");
+        Ok(self.0.get(&path).await?.expect_json()?.data)
+    }
+
+    pub async fn pve_add_firewall_ipset_entry(
+        &self,
+        remote: &str,
+        name: &str,
+        cidr: &str,
+        comment: Option<&str>,
+        nomatch: bool,
+    ) -> Result<(), Error> {
+        let path = format!("/api2/extjs/pve/remotes/{remote}/firewall/ipset/{name}");
+        let mut params = json!({ "cidr": cidr, "nomatch": nomatch });
+        if let Some(comment) = comment {
+            params["comment"] = comment.into();
+        }
+        self.0.post(&path, &params).await?.nodata()
+    }
+
+    pub async fn pve_remove_firewall_ipset_entry(
+        &self,
+        remote: &str,
+        name: &str,
+        cidr: &str,
+        digest: Option<&str>,
+    ) -> Result<(), Error> {
+        let cidr = percent_encoding::utf8_percent_encode(cidr, percent_encoding::NON_ALPHANUMERIC);
+        let path = ApiPathBuilder::new(format!(
+            "/api2/extjs/pve/remotes/{remote}/firewall/ipset/{name}/{cidr}"
+        ))
+        .maybe_arg("digest", &digest)
+        .build();
+        self.0.delete(&path).await?.nodata()
+    }
+
+    pub async fn pve_list_security_groups(
+        &self,
+        remote: &str,
+    ) -> Result<Vec<FirewallSecurityGroup>, Error> {
+        let path = format!("/api2/extjs/pve/remotes/{remote}/firewall/groups");
+        Ok(self.0.get(&path).await?.expect_json()?.data)
+    }
+
+    pub async fn pve_create_security_group(
+        &self,
+        remote: &str,
+        group: &str,
+        comment: Option<&str>,
+    ) -> Result<(), Error> {
+        let path = format!("/api2/extjs/pve/remotes/{remote}/firewall/groups");
+        let mut params = json!({ "group": group });
+        if let Some(comment) = comment {
+            params["comment"] = comment.into();
+        }
+        self.0.post(&path, &params).await?.nodata()
+    }
+
+    pub async fn pve_delete_security_group(&self, remote: &str, group: &str) -> Result<(), Error> {
+        let path = format!("/api2/extjs/pve/remotes/{remote}/firewall/groups/{group}");
+        self.0.delete(&path).await?.nodata()
+    }
+
+    pub async fn pve_security_group_rules(
+        &self,
+        remote: &str,
+        group: &str,
+    ) -> Result<Vec<pve_api_types::ListFirewallRules>, Error> {
+        let path = format!("/api2/extjs/pve/remotes/{remote}/firewall/groups/{group}");
+        Ok(self.0.get(&path).await?.expect_json()?.data)
+    }
+
+    pub async fn pve_create_security_group_rule(
+        &self,
+        remote: &str,
+        group: &str,
+        rule: FirewallRuleParams,
+        pos: Option<u64>,
+    ) -> Result<(), Error> {
+        let path = format!("/api2/extjs/pve/remotes/{remote}/firewall/groups/{group}");
+        let mut params = serde_json::to_value(rule).expect("failed to build json string");
+        if let Some(pos) = pos {
+            params["pos"] = pos.into();
+        }
+        self.0.post(&path, &params).await?.nodata()
+    }
+
+    pub async fn pve_delete_security_group_rule(
+        &self,
+        remote: &str,
+        group: &str,
+        pos: u64,
+        digest: Option<&str>,
+    ) -> Result<(), Error> {
+        let path = ApiPathBuilder::new(format!(
+            "/api2/extjs/pve/remotes/{remote}/firewall/groups/{group}/{pos}"
+        ))
+        .maybe_arg("digest", &digest)
+        .build();
+        self.0.delete(&path).await?.nodata()
+    }
+
+    /// Copy a security group or IP set of `remote` to the `targets`, returns the UPID of the
+    /// worker task.
+    pub async fn pve_push_firewall_object(
+        &self,
+        remote: &str,
+        kind: FirewallPushKind,
+        name: &str,
+        targets: &[&str],
+    ) -> Result<String, Error> {
+        let path = format!("/api2/extjs/pve/remotes/{remote}/firewall/push");
+        let params = json!({ "kind": kind, "name": name, "targets": targets });
+        Ok(self.0.post(&path, &params).await?.expect_json()?.data)
+    }
+
     pub async fn pve_node_rrddata(
         &self,
         remote: &str,
@@ -623,6 +1006,19 @@ impl<T: HttpApiClient> PdmClient<T> {
         Ok(self.0.get(&path).await?.expect_json()?.data)
     }
 
+    /// Update the configuration of a VM, pass the digest of the config it was based on to avoid
+    /// overwriting concurrent changes.
+    pub async fn pve_qemu_update_config(
+        &self,
+        remote: &str,
+        node: Option<&str>,
+        vmid: u32,
+        update: &GuestConfigUpdate,
+    ) -> Result<(), Error> {
+        self.pve_update_guest_config(remote, node, vmid, "qemu", update)
+            .await
+    }
+
     pub async fn pve_qemu_status(
         &self,
         remote: &str,
@@ -735,6 +1131,110 @@ impl<T: HttpApiClient> PdmClient<T> {
         Ok(self.0.post(&path, &request).await?.expect_json()?.data)
     }
 
+    /// Run an action on all guests matching a search or view.
+    ///
+    /// With `dry_run` set, only the list of affected guests is returned.
+    pub async fn pve_bulk_guest_action(
+        &self,
+        action: GuestAction,
+        search: Option<&str>,
+        view: Option<&str>,
+        target: Option<&str>,
+        dry_run: bool,
+    ) -> Result<BulkActionResult, Error> {
+        let path = "/api2/extjs/pve/bulk-action";
+        let mut request = json!({
+            "action": action,
+            "dry-run": dry_run,
+        });
+        if let Some(search) = search {
+            request["search"] = search.into();
+        }
+        if let Some(view) = view {
+            request["view"] = view.into();
+        }
+        if let Some(target) = target {
+            request["target"] = target.into();
+        }
+        Ok(self.0.post(path, &request).await?.expect_json()?.data)
+    }
+
+    /// Get proposed guest migrations which even out the load of the PVE nodes.
+    pub async fn pve_load_balancing_plan(
+        &self,
+        timeframe: Option<RrdTimeframe>,
+        view: Option<&str>,
+        allow_remote_migration: bool,
+        max_migrations: Option<u64>,
+    ) -> Result<MigrationPlan, Error> {
+        let path = ApiPathBuilder::new("/api2/extjs/pve/load-balancing")
+            .maybe_arg("timeframe", &timeframe)
+            .maybe_arg("view", &view)
+            .arg("allow-remote-migration", allow_remote_migration)
+            .maybe_arg("max-migrations", &max_migrations)
+            .build();
+        Ok(self.0.get(&path).await?.expect_json()?.data)
+    }
+
+    /// Compute a migration plan and run it in a worker task, returns the task's UPID.
+    pub async fn pve_balance_load(
+        &self,
+        timeframe: Option<RrdTimeframe>,
+        view: Option<&str>,
+        allow_remote_migration: bool,
+        max_migrations: Option<u64>,
+    ) -> Result<String, Error> {
+        let path = "/api2/extjs/pve/load-balancing";
+        let mut request = json!({
+            "allow-remote-migration": allow_remote_migration,
+        });
+        if let Some(timeframe) = timeframe {
+            request["timeframe"] = timeframe.to_string().into();
+        }
+        if let Some(view) = view {
+            request["view"] = view.into();
+        }
+        if let Some(max_migrations) = max_migrations {
+            request["max-migrations"] = max_migrations.into();
+        }
+        Ok(self.0.post(path, &request).await?.expect_json()?.data)
+    }
+
+    /// Get the status of the last rolling upgrade of a PVE remote.
+    pub async fn pve_rolling_upgrade_status(
+        &self,
+        remote: &str,
+    ) -> Result<RollingUpgradeStatus, Error> {
+        let path = format!("/api2/extjs/pve/remotes/{remote}/rolling-upgrade");
+        Ok(self.0.get(&path).await?.expect_json()?.data)
+    }
+
+    /// Upgrade the nodes of a PVE remote one after another, returns the task's UPID.
+    pub async fn pve_rolling_upgrade(
+        &self,
+        remote: &str,
+        guest_policy: Option<GuestPolicy>,
+        reboot: Option<bool>,
+    ) -> Result<String, Error> {
+        let path = format!("/api2/extjs/pve/remotes/{remote}/rolling-upgrade");
+        let mut request = json!({});
+        if let Some(guest_policy) = guest_policy {
+            request["guest-policy"] = guest_policy.to_string().into();
+        }
+        if let Some(reboot) = reboot {
+            request["reboot"] = reboot.into();
+        }
+        Ok(self.0.post(&path, &request).await?.expect_json()?.data)
+    }
+
+    /// Pause or resume the running rolling upgrade of a PVE remote.
+    pub async fn pve_rolling_upgrade_pause(&self, remote: &str, pause: bool) -> Result<(), Error> {
+        let action = if pause { "pause" } else { "resume" };
+        let path = format!("/api2/extjs/pve/remotes/{remote}/rolling-upgrade/{action}");
+        self.0.post(&path, &json!({})).await?.nodata()?;
+        Ok(())
+    }
+
     pub async fn pve_qemu_rrddata(
         &self,
         remote: &str,
@@ -766,72 +1266,375 @@ impl<T: HttpApiClient> PdmClient<T> {
         Ok(self.0.get(&path).await?.expect_json()?.data)
     }
 
-    pub async fn pve_lxc_start(
+    /// Update the configuration of a container, pass the digest of the config it was based on to
+    /// avoid overwriting concurrent changes.
+    pub async fn pve_lxc_update_config(
+        &self,
+        remote: &str,
+        node: Option<&str>,
+        vmid: u32,
+        update: &GuestConfigUpdate,
+    ) -> Result<(), Error> {
+        self.pve_update_guest_config(remote, node, vmid, "lxc", update)
+            .await
+    }
+
+    pub async fn pve_lxc_start(
+        &self,
+        remote: &str,
+        node: Option<&str>,
+        vmid: u32,
+    ) -> Result<RemoteUpid, Error> {
+        self.pve_change_guest_status(remote, node, vmid, "lxc", "start")
+            .await
+    }
+
+    pub async fn pve_lxc_shutdown(
+        &self,
+        remote: &str,
+        node: Option<&str>,
+        vmid: u32,
+    ) -> Result<RemoteUpid, Error> {
+        self.pve_change_guest_status(remote, node, vmid, "lxc", "shutdown")
+            .await
+    }
+
+    pub async fn pve_lxc_stop(
+        &self,
+        remote: &str,
+        node: Option<&str>,
+        vmid: u32,
+    ) -> Result<RemoteUpid, Error> {
+        self.pve_change_guest_status(remote, node, vmid, "lxc", "stop")
+            .await
+    }
+
+    pub async fn pve_lxc_migrate(
+        &self,
+        remote: &str,
+        node: Option<&str>,
+        vmid: u32,
+        target: String,
+        params: MigrateLxc,
+    ) -> Result<RemoteUpid, Error> {
+        let path = format!("/api2/extjs/pve/remotes/{remote}/lxc/{vmid}/migrate");
+        let mut request = serde_json::to_value(&params).expect("failed to build json string");
+        request["target"] = target.into();
+        if let Some(node) = node {
+            request["node"] = node.into();
+        }
+        Ok(self.0.post(&path, &request).await?.expect_json()?.data)
+    }
+
+    pub async fn pve_lxc_remote_migrate(
+        &self,
+        remote: &str,
+        node: Option<&str>,
+        vmid: u32,
+        target: String,
+        target_endpoint: Option<&str>,
+        params: RemoteMigrateLxc,
+    ) -> Result<RemoteUpid, Error> {
+        let path = format!("/api2/extjs/pve/remotes/{remote}/lxc/{vmid}/remote-migrate");
+        let mut request = serde_json::to_value(&params).expect("failed to build json string");
+        request["target"] = target.into();
+        if let Some(node) = node {
+            request["node"] = node.into();
+        }
+        if let Some(target_endpoint) = target_endpoint {
+            request["target-endpoint"] = target_endpoint.into();
+        }
+        Ok(self.0.post(&path, &request).await?.expect_json()?.data)
+    }
+
+    async fn pve_clone_guest(
+        &self,
+        remote: &str,
+        node: Option<&str>,
+        vmid: u32,
+        vmtype: &str,
+        newid: u32,
+        mut request: Value,
+    ) -> Result<RemoteUpid, Error> {
+        let path = format!("/api2/extjs/pve/remotes/{remote}/{vmtype}/{vmid}/clone");
+        request["newid"] = newid.into();
+        if let Some(node) = node {
+            request["node"] = node.into();
+        }
+        Ok(self.0.post(&path, &request).await?.expect_json()?.data)
+    }
+
+    async fn pve_destroy_guest(
+        &self,
+        remote: &str,
+        node: Option<&str>,
+        vmid: u32,
+        vmtype: &str,
+        purge: Option<bool>,
+    ) -> Result<RemoteUpid, Error> {
+        let path = ApiPathBuilder::new(format!("/api2/extjs/pve/remotes/{remote}/{vmtype}/{vmid}"))
+            .maybe_arg("node", &node)
+            .maybe_arg("purge", &purge)
+            .build();
+        Ok(self.0.delete(&path).await?.expect_json()?.data)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn pve_create_guest_snapshot(
+        &self,
+        remote: &str,
+        node: Option<&str>,
+        vmid: u32,
+        vmtype: &str,
+        snapname: &str,
+        description: Option<&str>,
+        vmstate: Option<bool>,
+    ) -> Result<RemoteUpid, Error> {
+        let path = format!("/api2/extjs/pve/remotes/{remote}/{vmtype}/{vmid}/snapshot");
+        let mut request = json!({ "snapname": snapname });
+        if let Some(node) = node {
+            request["node"] = node.into();
+        }
+        if let Some(description) = description {
+            request["description"] = description.into();
+        }
+        if let Some(vmstate) = vmstate {
+            request["vmstate"] = vmstate.into();
+        }
+        Ok(self.0.post(&path, &request).await?.expect_json()?.data)
+    }
+
+    async fn pve_rollback_guest_snapshot(
+        &self,
+        remote: &str,
+        node: Option<&str>,
+        vmid: u32,
+        vmtype: &str,
+        snapname: &str,
+        start: Option<bool>,
+    ) -> Result<RemoteUpid, Error> {
+        let path = format!(
+            "/api2/extjs/pve/remotes/{remote}/{vmtype}/{vmid}/snapshot/{snapname}/rollback"
+        );
+        let mut request = json!({});
+        if let Some(node) = node {
+            request["node"] = node.into();
+        }
+        if let Some(start) = start {
+            request["start"] = start.into();
+        }
+        Ok(self.0.post(&path, &request).await?.expect_json()?.data)
+    }
+
+    async fn pve_delete_guest_snapshot(
+        &self,
+        remote: &str,
+        node: Option<&str>,
+        vmid: u32,
+        vmtype: &str,
+        snapname: &str,
+        force: Option<bool>,
+    ) -> Result<RemoteUpid, Error> {
+        let path = ApiPathBuilder::new(format!(
+            "/api2/extjs/pve/remotes/{remote}/{vmtype}/{vmid}/snapshot/{snapname}"
+        ))
+        .maybe_arg("node", &node)
+        .maybe_arg("force", &force)
+        .build();
+        Ok(self.0.delete(&path).await?.expect_json()?.data)
+    }
+
+    async fn pve_backup_guest(
+        &self,
+        remote: &str,
+        node: Option<&str>,
+        vmid: u32,
+        vmtype: &str,
+        storage: &str,
+        params: BackupGuest,
+    ) -> Result<RemoteUpid, Error> {
+        let path = format!("/api2/extjs/pve/remotes/{remote}/{vmtype}/{vmid}/backup");
+        let mut request = serde_json::to_value(&params).expect("failed to build json string");
+        request["storage"] = storage.into();
+        if let Some(node) = node {
+            request["node"] = node.into();
+        }
+        Ok(self.0.post(&path, &request).await?.expect_json()?.data)
+    }
+
+    async fn pve_update_guest_config(
+        &self,
+        remote: &str,
+        node: Option<&str>,
+        vmid: u32,
+        vmtype: &str,
+        update: &GuestConfigUpdate,
+    ) -> Result<(), Error> {
+        let path = format!("/api2/extjs/pve/remotes/{remote}/{vmtype}/{vmid}/config");
+        let mut request = serde_json::to_value(update).expect("failed to build json string");
+        if let Some(node) = node {
+            request["node"] = node.into();
+        }
+        self.0.put(&path, &request).await?.nodata()
+    }
+
+    /// Clone a VM, usually from a template.
+    pub async fn pve_qemu_clone(
+        &self,
+        remote: &str,
+        node: Option<&str>,
+        vmid: u32,
+        newid: u32,
+        name: Option<&str>,
+        params: CloneGuest,
+    ) -> Result<RemoteUpid, Error> {
+        let mut request = serde_json::to_value(&params).expect("failed to build json string");
+        if let Some(name) = name {
+            request["name"] = name.into();
+        }
+        self.pve_clone_guest(remote, node, vmid, "qemu", newid, request)
+            .await
+    }
+
+    /// Destroy a VM including its disks.
+    pub async fn pve_qemu_destroy(
+        &self,
+        remote: &str,
+        node: Option<&str>,
+        vmid: u32,
+        purge: Option<bool>,
+    ) -> Result<RemoteUpid, Error> {
+        self.pve_destroy_guest(remote, node, vmid, "qemu", purge)
+            .await
+    }
+
+    pub async fn pve_qemu_create_snapshot(
+        &self,
+        remote: &str,
+        node: Option<&str>,
+        vmid: u32,
+        snapname: &str,
+        description: Option<&str>,
+        vmstate: Option<bool>,
+    ) -> Result<RemoteUpid, Error> {
+        self.pve_create_guest_snapshot(remote, node, vmid, "qemu", snapname, description, vmstate)
+            .await
+    }
+
+    pub async fn pve_qemu_rollback_snapshot(
+        &self,
+        remote: &str,
+        node: Option<&str>,
+        vmid: u32,
+        snapname: &str,
+        start: Option<bool>,
+    ) -> Result<RemoteUpid, Error> {
+        self.pve_rollback_guest_snapshot(remote, node, vmid, "qemu", snapname, start)
+            .await
+    }
+
+    pub async fn pve_qemu_delete_snapshot(
+        &self,
+        remote: &str,
+        node: Option<&str>,
+        vmid: u32,
+        snapname: &str,
+        force: Option<bool>,
+    ) -> Result<RemoteUpid, Error> {
+        self.pve_delete_guest_snapshot(remote, node, vmid, "qemu", snapname, force)
+            .await
+    }
+
+    /// Back up a VM to a storage of its cluster now.
+    pub async fn pve_qemu_backup(
+        &self,
+        remote: &str,
+        node: Option<&str>,
+        vmid: u32,
+        storage: &str,
+        params: BackupGuest,
+    ) -> Result<RemoteUpid, Error> {
+        self.pve_backup_guest(remote, node, vmid, "qemu", storage, params)
+            .await
+    }
+
+    /// Clone a container, usually from a template.
+    pub async fn pve_lxc_clone(
         &self,
         remote: &str,
         node: Option<&str>,
         vmid: u32,
+        newid: u32,
+        hostname: Option<&str>,
+        params: CloneGuest,
     ) -> Result<RemoteUpid, Error> {
-        self.pve_change_guest_status(remote, node, vmid, "lxc", "start")
+        let mut request = serde_json::to_value(&params).expect("failed to build json string");
+        if let Some(hostname) = hostname {
+            request["hostname"] = hostname.into();
+        }
+        self.pve_clone_guest(remote, node, vmid, "lxc", newid, request)
             .await
     }
 
-    pub async fn pve_lxc_shutdown(
+    /// Destroy a container including its disks.
+    pub async fn pve_lxc_destroy(
         &self,
         remote: &str,
         node: Option<&str>,
         vmid: u32,
+        purge: Option<bool>,
     ) -> Result<RemoteUpid, Error> {
-        self.pve_change_guest_status(remote, node, vmid, "lxc", "shutdown")
+        self.pve_destroy_guest(remote, node, vmid, "lxc", purge)
             .await
     }
 
-    pub async fn pve_lxc_stop(
+    pub async fn pve_lxc_create_snapshot(
         &self,
         remote: &str,
         node: Option<&str>,
         vmid: u32,
+        snapname: &str,
+        description: Option<&str>,
     ) -> Result<RemoteUpid, Error> {
-        self.pve_change_guest_status(remote, node, vmid, "lxc", "stop")
+        self.pve_create_guest_snapshot(remote, node, vmid, "lxc", snapname, description, None)
             .await
     }
 
-    pub async fn pve_lxc_migrate(
+    pub async fn pve_lxc_rollback_snapshot(
         &self,
         remote: &str,
         node: Option<&str>,
         vmid: u32,
-        target: String,
-        params: MigrateLxc,
+        snapname: &str,
+        start: Option<bool>,
     ) -> Result<RemoteUpid, Error> {
-        let path = format!("/api2/extjs/pve/remotes/{remote}/lxc/{vmid}/migrate");
-        let mut request = serde_json::to_value(&params).expect("failed to build json string");
-        request["target"] = target.into();
-        if let Some(node) = node {
-            request["node"] = node.into();
-        }
-        Ok(self.0.post(&path, &request).await?.expect_json()?.data)
+        self.pve_rollback_guest_snapshot(remote, node, vmid, "lxc", snapname, start)
+            .await
     }
 
-    pub async fn pve_lxc_remote_migrate(
+    pub async fn pve_lxc_delete_snapshot(
         &self,
         remote: &str,
         node: Option<&str>,
         vmid: u32,
-        target: String,
-        target_endpoint: Option<&str>,
-        params: RemoteMigrateLxc,
+        snapname: &str,
+        force: Option<bool>,
     ) -> Result<RemoteUpid, Error> {
-        let path = format!("/api2/extjs/pve/remotes/{remote}/lxc/{vmid}/remote-migrate");
-        let mut request = serde_json::to_value(&params).expect("failed to build json string");
-        request["target"] = target.into();
-        if let Some(node) = node {
-            request["node"] = node.into();
-        }
-        if let Some(target_endpoint) = target_endpoint {
-            request["target-endpoint"] = target_endpoint.into();
-        }
-        Ok(self.0.post(&path, &request).await?.expect_json()?.data)
+        self.pve_delete_guest_snapshot(remote, node, vmid, "lxc", snapname, force)
+            .await
+    }
+
+    /// Back up a container to a storage of its cluster now.
+    pub async fn pve_lxc_backup(
+        &self,
+        remote: &str,
+        node: Option<&str>,
+        vmid: u32,
+        storage: &str,
+        params: BackupGuest,
+    ) -> Result<RemoteUpid, Error> {
+        self.pve_backup_guest(remote, node, vmid, "lxc", storage, params)
+            .await
     }
 
     pub async fn pve_lxc_rrddata(
@@ -1020,6 +1823,114 @@ impl<T: HttpApiClient> PdmClient<T> {
         Ok(self.0.get(&path).await?.expect_json()?.data)
     }
 
+    /// Get the garbage collection status of a datastore.
+    pub async fn pbs_datastore_gc_status(
+        &self,
+        remote: &str,
+        store: &str,
+    ) -> Result<Vec<pbs_api_types::GarbageCollectionJobStatus>, Error> {
+        let path = format!("/api2/extjs/pbs/remotes/{remote}/datastore/{store}/gc");
+        Ok(self.0.get(&path).await?.expect_json()?.data)
+    }
+
+    /// Start a garbage collection on a datastore.
+    pub async fn pbs_datastore_start_gc(
+        &self,
+        remote: &str,
+        store: &str,
+    ) -> Result<RemoteUpid, Error> {
+        let path = format!("/api2/extjs/pbs/remotes/{remote}/datastore/{store}/gc");
+        Ok(self.0.post_without_body(&path).await?.expect_json()?.data)
+    }
+
+    /// Verify the backups of a datastore.
+    pub async fn pbs_datastore_verify(
+        &self,
+        remote: &str,
+        store: &str,
+        ignore_verified: Option<bool>,
+        outdated_after: Option<i64>,
+    ) -> Result<RemoteUpid, Error> {
+        let path = format!("/api2/extjs/pbs/remotes/{remote}/datastore/{store}/verify");
+        let mut request = json!({});
+        if let Some(ignore_verified) = ignore_verified {
+            request["ignore-verified"] = ignore_verified.into();
+        }
+        if let Some(outdated_after) = outdated_after {
+            request["outdated-after"] = outdated_after.into();
+        }
+        Ok(self.0.post(&path, &request).await?.expect_json()?.data)
+    }
+
+    pub async fn pbs_list_prune_jobs(
+        &self,
+        remote: &str,
+        store: &str,
+    ) -> Result<Vec<pbs_api_types::PruneJobStatus>, Error> {
+        let path = format!("/api2/extjs/pbs/remotes/{remote}/datastore/{store}/prune-jobs");
+        Ok(self.0.get(&path).await?.expect_json()?.data)
+    }
+
+    pub async fn pbs_run_prune_job(
+        &self,
+        remote: &str,
+        store: &str,
+        id: &str,
+    ) -> Result<RemoteUpid, Error> {
+        self.pbs_run_datastore_job(remote, store, "prune-jobs", id)
+            .await
+    }
+
+    pub async fn pbs_list_sync_jobs(
+        &self,
+        remote: &str,
+        store: &str,
+    ) -> Result<Vec<pbs_api_types::SyncJobStatus>, Error> {
+        let path = format!("/api2/extjs/pbs/remotes/{remote}/datastore/{store}/sync-jobs");
+        Ok(self.0.get(&path).await?.expect_json()?.data)
+    }
+
+    pub async fn pbs_run_sync_job(
+        &self,
+        remote: &str,
+        store: &str,
+        id: &str,
+    ) -> Result<RemoteUpid, Error> {
+        self.pbs_run_datastore_job(remote, store, "sync-jobs", id)
+            .await
+    }
+
+    pub async fn pbs_list_verification_jobs(
+        &self,
+        remote: &str,
+        store: &str,
+    ) -> Result<Vec<pbs_api_types::VerificationJobStatus>, Error> {
+        let path = format!("/api2/extjs/pbs/remotes/{remote}/datastore/{store}/verification-jobs");
+        Ok(self.0.get(&path).await?.expect_json()?.data)
+    }
+
+    pub async fn pbs_run_verification_job(
+        &self,
+        remote: &str,
+        store: &str,
+        id: &str,
+    ) -> Result<RemoteUpid, Error> {
+        self.pbs_run_datastore_job(remote, store, "verification-jobs", id)
+            .await
+    }
+
+    async fn pbs_run_datastore_job(
+        &self,
+        remote: &str,
+        store: &str,
+        job_type: &str,
+        id: &str,
+    ) -> Result<RemoteUpid, Error> {
+        let path =
+            format!("/api2/extjs/pbs/remotes/{remote}/datastore/{store}/{job_type}/{id}/run");
+        Ok(self.0.post_without_body(&path).await?.expect_json()?.data)
+    }
+
     pub async fn pbs_list_tasks(
         &self,
         remote: &str,
@@ -1083,6 +1994,88 @@ impl<T: HttpApiClient> PdmClient<T> {
         Ok(self.0.get(&path).await?.expect_json()?.data)
     }
 
+    /// List the keys of the subscription key pool.
+    pub async fn list_subscription_keys(
+        &self,
+        max_age: Option<u64>,
+    ) -> Result<Vec<SubscriptionKeyStatus>, Error> {
+        let path = ApiPathBuilder::new("/api2/extjs/subscriptions/keys")
+            .maybe_arg("max-age", &max_age)
+            .build();
+        Ok(self.0.get(&path).await?.expect_json()?.data)
+    }
+
+    /// Add a key to the subscription key pool.
+    pub async fn add_subscription_key(
+        &self,
+        key: &str,
+        comment: Option<&str>,
+    ) -> Result<(), Error> {
+        let mut request = json!({ "key": key });
+        if let Some(comment) = comment {
+            request["comment"] = comment.into();
+        }
+        self.0
+            .post("/api2/extjs/subscriptions/keys", &request)
+            .await?
+            .nodata()?;
+        Ok(())
+    }
+
+    /// Upload a key of the key pool to a node of a remote.
+    pub async fn assign_subscription_key(
+        &self,
+        key: &str,
+        remote: &str,
+        node: Option<&str>,
+    ) -> Result<SubscriptionKeyStatus, Error> {
+        let path = format!("/api2/extjs/subscriptions/keys/{key}/assign");
+        let mut request = json!({ "remote": remote });
+        if let Some(node) = node {
+            request["node"] = node.into();
+        }
+        Ok(self.0.post(&path, &request).await?.expect_json()?.data)
+    }
+
+    /// Assign free keys of the key pool to all nodes without a subscription.
+    pub async fn auto_assign_subscription_keys(
+        &self,
+        remotes: Option<Vec<String>>,
+        dry_run: bool,
+    ) -> Result<SubscriptionAutoAssignResult, Error> {
+        let mut request = json!({ "dry-run": dry_run });
+        if let Some(remotes) = remotes {
+            request["remotes"] = remotes.into();
+        }
+        Ok(self
+            .0
+            .post("/api2/extjs/subscriptions/auto-assign", &request)
+            .await?
+            .expect_json()?
+            .data)
+    }
+
+    /// Get the backup coverage report of all guests.
+    ///
+    /// Only guests without a backup newer than `max_backup_age` seconds are listed, unless `all`
+    /// is set.
+    pub async fn get_backup_coverage(
+        &self,
+        max_age: Option<u64>,
+        max_backup_age: Option<u64>,
+        all: Option<bool>,
+        view: Option<&str>,
+    ) -> Result<BackupCoverage, Error> {
+        let path = ApiPathBuilder::new("/api2/extjs/resources/backup-coverage")
+            .maybe_arg("max-age", &max_age)
+            .maybe_arg("max-backup-age", &max_backup_age)
+            .maybe_arg("all", &all)
+            .maybe_arg("view", &view)
+            .build();
+
+        Ok(self.0.get(&path).await?.expect_json()?.data)
+    }
+
     pub async fn pve_list_networks(
         &self,
         remote: &str,
@@ -1255,6 +2248,103 @@ impl<T: HttpApiClient> PdmClient<T> {
         Ok(self.0.post(path, &params).await?.expect_json()?.data)
     }
 
+    /// Update a zone on the given remotes, returns the UPID of the worker task.
+    pub async fn pve_sdn_update_zone(
+        &self,
+        zone: &str,
+        remotes: &[String],
+        update: &UpdateZone,
+    ) -> Result<String, Error> {
+        let path = format!("/api2/extjs/sdn/zones/{zone}");
+        let mut params = serde_json::to_value(update).expect("failed to build json string");
+        params["remotes"] = remotes.into();
+        Ok(self.0.put(&path, &params).await?.expect_json()?.data)
+    }
+
+    /// Delete a zone from the given remotes, returns the UPID of the worker task.
+    pub async fn pve_sdn_delete_zone(
+        &self,
+        zone: &str,
+        remotes: &[String],
+    ) -> Result<String, Error> {
+        let path = sdn_remotes_path(format!("/api2/extjs/sdn/zones/{zone}"), remotes);
+        Ok(self.0.delete(&path).await?.expect_json()?.data)
+    }
+
+    /// Update a VNet on the given remotes, returns the UPID of the worker task.
+    pub async fn pve_sdn_update_vnet(
+        &self,
+        vnet: &str,
+        remotes: &[String],
+        update: &UpdateVnet,
+    ) -> Result<String, Error> {
+        let path = format!("/api2/extjs/sdn/vnets/{vnet}");
+        let mut params = serde_json::to_value(update).expect("failed to build json string");
+        params["remotes"] = remotes.into();
+        Ok(self.0.put(&path, &params).await?.expect_json()?.data)
+    }
+
+    /// Delete a VNet from the given remotes, returns the UPID of the worker task.
+    pub async fn pve_sdn_delete_vnet(
+        &self,
+        vnet: &str,
+        remotes: &[String],
+    ) -> Result<String, Error> {
+        let path = sdn_remotes_path(format!("/api2/extjs/sdn/vnets/{vnet}"), remotes);
+        Ok(self.0.delete(&path).await?.expect_json()?.data)
+    }
+
+    /// Create a subnet, given in CIDR notation, in a VNet on the given remotes.
+    pub async fn pve_sdn_create_subnet(
+        &self,
+        vnet: &str,
+        subnet: &str,
+        remotes: &[String],
+        properties: &SubnetProperties,
+    ) -> Result<String, Error> {
+        let path = format!("/api2/extjs/sdn/vnets/{vnet}/subnets");
+        let mut params = serde_json::to_value(properties).expect("failed to build json string");
+        params["subnet"] = subnet.into();
+        params["remotes"] = remotes.into();
+        Ok(self.0.post(&path, &params).await?.expect_json()?.data)
+    }
+
+    /// Update a subnet of a VNet on the given remotes.
+    ///
+    /// The subnet is given in CIDR notation with the slash replaced by a dash, e.g. `10.0.0.0-24`.
+    pub async fn pve_sdn_update_subnet(
+        &self,
+        vnet: &str,
+        subnet: &str,
+        remotes: &[String],
+        properties: &SubnetProperties,
+        delete: &[SdnSubnetDeletableProperty],
+    ) -> Result<String, Error> {
+        let path = format!("/api2/extjs/sdn/vnets/{vnet}/subnets/{subnet}");
+        let mut params = serde_json::to_value(properties).expect("failed to build json string");
+        params["remotes"] = remotes.into();
+        if !delete.is_empty() {
+            params["delete"] = serde_json::to_value(delete).expect("failed to build json string");
+        }
+        Ok(self.0.put(&path, &params).await?.expect_json()?.data)
+    }
+
+    /// Delete a subnet of a VNet from the given remotes.
+    ///
+    /// The subnet is given in CIDR notation with the slash replaced by a dash, e.g. `10.0.0.0-24`.
+    pub async fn pve_sdn_delete_subnet(
+        &self,
+        vnet: &str,
+        subnet: &str,
+        remotes: &[String],
+    ) -> Result<String, Error> {
+        let path = sdn_remotes_path(
+            format!("/api2/extjs/sdn/vnets/{vnet}/subnets/{subnet}"),
+            remotes,
+        );
+        Ok(self.0.delete(&path).await?.expect_json()?.data)
+    }
+
     pub async fn pve_sdn_zone_get_ip_vrf(
         &self,
         remote: &str,
@@ -1497,6 +2587,84 @@ impl MigrateLxc {
     }
 }
 
+/// Builder for clone parameters.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CloneGuest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    full: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    storage: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pool: Option<String>,
+}
+
+impl CloneGuest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn target(mut self, target: String) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    pub fn full(mut self, full: bool) -> Self {
+        self.full = Some(full);
+        self
+    }
+
+    pub fn storage(mut self, storage: String) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    pub fn pool(mut self, pool: String) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+}
+
+/// Builder for backup parameters.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BackupGuest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mode: Option<GuestBackupMode>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    notes_template: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    protected: Option<bool>,
+}
+
+impl BackupGuest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mode(mut self, mode: GuestBackupMode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    pub fn notes_template(mut self, notes_template: String) -> Self {
+        self.notes_template = Some(notes_template);
+        self
+    }
+
+    pub fn protected(mut self, protected: bool) -> Self {
+        self.protected = Some(protected);
+        self
+    }
+}
+
 /// Builder for remote migration parameters - common parameters.
 #[derive(Clone, Debug, Default, Serialize)]
 #[serde(rename_all = "kebab-case")]
@@ -1710,3 +2878,13 @@ impl From<String> for ConfigDigest {
         Self(value.into())
     }
 }
+
+/// Builds the path of an SDN endpoint which takes the list of remotes as query parameter.
+fn sdn_remotes_path(path: String, remotes: &[String]) -> String {
+    remotes
+        .iter()
+        .fold(ApiPathBuilder::new(path), |builder, remote| {
+            builder.arg("remotes", remote)
+        })
+        .build()
+}
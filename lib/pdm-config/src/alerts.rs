@@ -0,0 +1,33 @@
+use anyhow::Error;
+
+use proxmox_product_config::{open_api_lockfile, replace_config, ApiLockGuard};
+use proxmox_section_config::typed::{ApiSectionDataEntry, SectionConfigData};
+
+use pdm_api_types::{alerts::AlertRuleEntry, ConfigDigest};
+
+use pdm_buildcfg::configdir;
+
+const ALERTS_CFG_FILENAME: &str = configdir!("/alerts.cfg");
+const ALERTS_CFG_LOCKFILE: &str = configdir!("/.alerts.lock");
+
+/// Get the `alerts.cfg` config file contents.
+pub fn config() -> Result<(SectionConfigData<AlertRuleEntry>, ConfigDigest), Error> {
+    let content =
+        proxmox_sys::fs::file_read_optional_string(ALERTS_CFG_FILENAME)?.unwrap_or_default();
+
+    let digest = openssl::sha::sha256(content.as_bytes());
+
+    let data = AlertRuleEntry::parse_section_config(ALERTS_CFG_FILENAME, &content)?;
+    Ok((data, digest.into()))
+}
+
+/// Get exclusive lock
+pub fn lock_config() -> Result<ApiLockGuard, Error> {
+    open_api_lockfile(ALERTS_CFG_LOCKFILE, None, true)
+}
+
+pub fn save_config(config: &SectionConfigData<AlertRuleEntry>) -> Result<(), Error> {
+    let raw = AlertRuleEntry::write_section_config(ALERTS_CFG_FILENAME, config)?;
+    replace_config(ALERTS_CFG_FILENAME, raw.as_bytes())?;
+    Ok(())
+}
@@ -27,6 +27,8 @@ struct ConfigVersionCacheDataInner {
     traffic_control_generation: AtomicUsize,
     // Tracks updates to the remote/hostname/nodename mapping cache.
     remote_mapping_cache: AtomicUsize,
+    // User-defined roles (roles.cfg) generation/version.
+    roles_generation: AtomicUsize,
     // Add further atomics here
 }
 
@@ -172,4 +174,17 @@ impl ConfigVersionCache {
             .fetch_add(1, Ordering::Relaxed)
             + 1
     }
+
+    /// Returns the user-defined roles generation number.
+    pub fn roles_generation(&self) -> usize {
+        self.shmem.data().roles_generation.load(Ordering::Acquire)
+    }
+
+    /// Increase the user-defined roles generation number.
+    pub fn increase_roles_generation(&self) {
+        self.shmem
+            .data()
+            .roles_generation
+            .fetch_add(1, Ordering::AcqRel);
+    }
 }
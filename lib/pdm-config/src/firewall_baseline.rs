@@ -0,0 +1,47 @@
+use anyhow::{format_err, Error};
+
+use proxmox_product_config::{open_api_lockfile, replace_config, ApiLockGuard};
+
+use pdm_api_types::firewall::FirewallBaseline;
+use pdm_api_types::ConfigDigest;
+
+use pdm_buildcfg::configdir;
+
+const BASELINE_FILENAME: &str = configdir!("/firewall-baseline.json");
+const BASELINE_LOCKFILE: &str = configdir!("/.firewall-baseline.lock");
+
+/// Get the stored firewall baseline, if there is one.
+pub fn config() -> Result<(Option<FirewallBaseline>, ConfigDigest), Error> {
+    let content =
+        proxmox_sys::fs::file_read_optional_string(BASELINE_FILENAME)?.unwrap_or_default();
+
+    let digest = openssl::sha::sha256(content.as_bytes());
+
+    if content.trim().is_empty() {
+        return Ok((None, digest.into()));
+    }
+
+    let baseline = serde_json::from_str(&content)
+        .map_err(|err| format_err!("failed to parse {BASELINE_FILENAME} - {err}"))?;
+    Ok((Some(baseline), digest.into()))
+}
+
+/// Get exclusive lock
+pub fn lock_config() -> Result<ApiLockGuard, Error> {
+    open_api_lockfile(BASELINE_LOCKFILE, None, true)
+}
+
+/// Store the firewall baseline, requires the lock to be held.
+pub fn save_config(baseline: &FirewallBaseline) -> Result<(), Error> {
+    let raw = serde_json::to_vec_pretty(baseline)?;
+    replace_config(BASELINE_FILENAME, &raw)
+}
+
+/// Remove the stored firewall baseline, requires the lock to be held.
+pub fn remove_config() -> Result<(), Error> {
+    match std::fs::remove_file(BASELINE_FILENAME) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(format_err!("failed to remove {BASELINE_FILENAME} - {err}")),
+    }
+}
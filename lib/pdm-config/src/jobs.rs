@@ -0,0 +1,33 @@
+use anyhow::Error;
+
+use proxmox_product_config::{open_api_lockfile, replace_config, ApiLockGuard};
+use proxmox_section_config::typed::{ApiSectionDataEntry, SectionConfigData};
+
+use pdm_api_types::{jobs::JobConfigEntry, ConfigDigest};
+
+use pdm_buildcfg::configdir;
+
+const JOBS_CFG_FILENAME: &str = configdir!("/jobs.cfg");
+const JOBS_CFG_LOCKFILE: &str = configdir!("/.jobs.lock");
+
+/// Get the `jobs.cfg` config file contents.
+pub fn config() -> Result<(SectionConfigData<JobConfigEntry>, ConfigDigest), Error> {
+    let content =
+        proxmox_sys::fs::file_read_optional_string(JOBS_CFG_FILENAME)?.unwrap_or_default();
+
+    let digest = openssl::sha::sha256(content.as_bytes());
+
+    let data = JobConfigEntry::parse_section_config(JOBS_CFG_FILENAME, &content)?;
+    Ok((data, digest.into()))
+}
+
+/// Get exclusive lock
+pub fn lock_config() -> Result<ApiLockGuard, Error> {
+    open_api_lockfile(JOBS_CFG_LOCKFILE, None, true)
+}
+
+pub fn save_config(config: &SectionConfigData<JobConfigEntry>) -> Result<(), Error> {
+    let raw = JobConfigEntry::write_section_config(JOBS_CFG_FILENAME, config)?;
+    replace_config(JOBS_CFG_FILENAME, raw.as_bytes())?;
+    Ok(())
+}
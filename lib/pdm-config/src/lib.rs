@@ -2,11 +2,18 @@ use anyhow::{format_err, Error};
 use nix::unistd::{Gid, Group, Uid, User};
 pub use pdm_buildcfg::{BACKUP_GROUP_NAME, BACKUP_USER_NAME};
 
+pub mod alerts;
 pub mod certificate_config;
 pub mod domains;
+pub mod firewall_baseline;
+pub mod jobs;
+pub mod metric_server;
 pub mod node;
+pub mod notifications;
 pub mod remotes;
+pub mod roles;
 pub mod setup;
+pub mod subscription_keys;
 pub mod views;
 
 mod config_version_cache;
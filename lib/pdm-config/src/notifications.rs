@@ -0,0 +1,38 @@
+use anyhow::Error;
+
+use proxmox_notify::Config;
+use proxmox_product_config::{
+    open_api_lockfile, replace_config, replace_secret_config, ApiLockGuard,
+};
+
+use pdm_buildcfg::configdir;
+
+/// Configuration file location for notification targets/matchers.
+pub const NOTIFICATION_CONFIG_PATH: &str = configdir!("/notifications.cfg");
+/// Private configuration file location for secrets - only readable by `root`.
+pub const NOTIFICATION_PRIV_CONFIG_PATH: &str = configdir!("/notifications-priv.cfg");
+/// Lockfile to prevent concurrent write access.
+const NOTIFICATION_LOCK_FILE: &str = configdir!("/.notifications.lock");
+
+/// Get exclusive lock for `notifications.cfg`.
+pub fn lock_config() -> Result<ApiLockGuard, Error> {
+    open_api_lockfile(NOTIFICATION_LOCK_FILE, None, true)
+}
+
+/// Load the notification config.
+pub fn config() -> Result<Config, Error> {
+    let content =
+        proxmox_sys::fs::file_read_optional_string(NOTIFICATION_CONFIG_PATH)?.unwrap_or_default();
+    let priv_content = proxmox_sys::fs::file_read_optional_string(NOTIFICATION_PRIV_CONFIG_PATH)?
+        .unwrap_or_default();
+
+    Ok(Config::new(&content, &priv_content)?)
+}
+
+/// Save the notification config.
+pub fn save_config(config: Config) -> Result<(), Error> {
+    let (cfg, priv_cfg) = config.write()?;
+    replace_config(NOTIFICATION_CONFIG_PATH, cfg.as_bytes())?;
+    replace_secret_config(NOTIFICATION_PRIV_CONFIG_PATH, priv_cfg.as_bytes())?;
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use anyhow::Error;
+
+use proxmox_product_config::{open_api_lockfile, replace_config, ApiLockGuard};
+use proxmox_section_config::typed::{ApiSectionDataEntry, SectionConfigData};
+
+use pdm_api_types::{ConfigDigest, RoleConfigEntry};
+
+use pdm_buildcfg::configdir;
+
+use crate::ConfigVersionCache;
+
+const ROLES_CFG_FILENAME: &str = configdir!("/access/roles.cfg");
+const ROLES_CFG_LOCKFILE: &str = configdir!("/access/.roles.lock");
+
+/// Get the `roles.cfg` config file contents.
+pub fn config() -> Result<(SectionConfigData<RoleConfigEntry>, ConfigDigest), Error> {
+    let content =
+        proxmox_sys::fs::file_read_optional_string(ROLES_CFG_FILENAME)?.unwrap_or_default();
+
+    let digest = openssl::sha::sha256(content.as_bytes());
+
+    let data = RoleConfigEntry::parse_section_config(ROLES_CFG_FILENAME, &content)?;
+    Ok((data, digest.into()))
+}
+
+/// Get exclusive lock
+pub fn lock_config() -> Result<ApiLockGuard, Error> {
+    open_api_lockfile(ROLES_CFG_LOCKFILE, None, true)
+}
+
+/// Save the `roles.cfg` config and notify all processes about the changed roles.
+pub fn save_config(config: &SectionConfigData<RoleConfigEntry>) -> Result<(), Error> {
+    let raw = RoleConfigEntry::write_section_config(ROLES_CFG_FILENAME, config)?;
+    replace_config(ROLES_CFG_FILENAME, raw.as_bytes())?;
+
+    if let Some(version_cache) = ConfigVersionCache::new_log_error() {
+        version_cache.increase_roles_generation();
+    }
+
+    Ok(())
+}
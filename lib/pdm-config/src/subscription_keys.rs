@@ -0,0 +1,34 @@
+use anyhow::Error;
+
+use proxmox_product_config::{open_api_lockfile, replace_config, ApiLockGuard};
+use proxmox_section_config::typed::{ApiSectionDataEntry, SectionConfigData};
+
+use pdm_api_types::{subscription::SubscriptionKeyEntry, ConfigDigest};
+
+use pdm_buildcfg::configdir;
+
+const SUBSCRIPTION_KEYS_CFG_FILENAME: &str = configdir!("/subscription-keys.cfg");
+const SUBSCRIPTION_KEYS_CFG_LOCKFILE: &str = configdir!("/.subscription-keys.lock");
+
+/// Get the `subscription-keys.cfg` config file contents.
+pub fn config() -> Result<(SectionConfigData<SubscriptionKeyEntry>, ConfigDigest), Error> {
+    let content = proxmox_sys::fs::file_read_optional_string(SUBSCRIPTION_KEYS_CFG_FILENAME)?
+        .unwrap_or_default();
+
+    let digest = openssl::sha::sha256(content.as_bytes());
+
+    let data =
+        SubscriptionKeyEntry::parse_section_config(SUBSCRIPTION_KEYS_CFG_FILENAME, &content)?;
+    Ok((data, digest.into()))
+}
+
+/// Get exclusive lock
+pub fn lock_config() -> Result<ApiLockGuard, Error> {
+    open_api_lockfile(SUBSCRIPTION_KEYS_CFG_LOCKFILE, None, true)
+}
+
+pub fn save_config(config: &SectionConfigData<SubscriptionKeyEntry>) -> Result<(), Error> {
+    let raw = SubscriptionKeyEntry::write_section_config(SUBSCRIPTION_KEYS_CFG_FILENAME, config)?;
+    replace_config(SUBSCRIPTION_KEYS_CFG_FILENAME, raw.as_bytes())?;
+    Ok(())
+}
@@ -1,7 +1,376 @@
-pub(crate) fn init() {
-    static ACCESS_CONTROL_CONFIG: pdm_api_types::AccessControlConfig =
-        pdm_api_types::AccessControlConfig;
+//! Access control setup and guest permission helpers.
+//!
+//! Besides `/resource/{remote}/guest/{vmid}`, privileges on a guest can be granted via the ACL
+//! paths of its PVE pool, `/resource/{remote}/pool/{pool}`, and of its tags, `/tag/{tag}`. These
+//! follow the guest when it is moved to another node, and the tag ACLs even when it is migrated to
+//! another remote. The privileges of all matching paths are combined.
+//!
+//! Next to the built-in roles, user-defined roles from `roles.cfg` can be used in ACLs.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, Mutex, RwLock};
+
+use anyhow::Error;
+
+use proxmox_access_control::init::AccessControlConfig as _;
+use proxmox_access_control::CachedUserInfo;
+use proxmox_router::UserInformation;
+use proxmox_section_config::{typed, SectionConfigData};
+
+use pdm_api_types::resource::Resource;
+use pdm_api_types::{Authid, Role, RoleConfigEntry};
+use pdm_config::ConfigVersionCache;
+
+/// Maximum age of the cached resources used to look up the pool and tags of a guest.
+///
+/// The resource cache is refreshed every 15 minutes, anything older is not trusted for
+/// permission checks.
+const GUEST_MEMBERSHIP_MAX_AGE: u64 = 20 * 60;
+
+static BUILTIN_ACCESS_CONTROL_CONFIG: pdm_api_types::AccessControlConfig =
+    pdm_api_types::AccessControlConfig;
+
+pub fn init() {
+    static ACCESS_CONTROL_CONFIG: AccessControlConfig = AccessControlConfig;
 
     proxmox_access_control::init::init(&ACCESS_CONTROL_CONFIG, pdm_buildcfg::configdir!("/access"))
         .expect("failed to setup access control config");
 }
+
+/// Built-in and user-defined roles, mapping the role id to its privileges and comment.
+pub type Roles = BTreeMap<String, (u64, String)>;
+
+type RoleMap = HashMap<&'static str, (u64, &'static str)>;
+
+/// Maximum number of distinct role configurations passed to the access control code during the
+/// lifetime of a daemon.
+///
+/// Each one costs a map which can never be freed. Once the limit is reached, only the built-in
+/// roles are used until the daemon is restarted.
+const MAX_ROLE_MAPS: usize = 64;
+
+/// Get the built-in and user-defined roles.
+///
+/// The roles are reloaded whenever `roles.cfg` is saved.
+pub fn roles() -> Arc<Roles> {
+    static ROLES: RwLock<Option<(usize, Arc<Roles>)>> = RwLock::new(None);
+
+    let Some(generation) = ConfigVersionCache::new_log_error().map(|c| c.roles_generation()) else {
+        return Arc::new(builtin_roles());
+    };
+
+    if let Some((cached_generation, roles)) = &*ROLES.read().unwrap() {
+        if *cached_generation == generation {
+            return Arc::clone(roles);
+        }
+    }
+
+    let roles = match pdm_config::roles::config() {
+        Ok((config, _digest)) => Arc::new(merge_roles(config)),
+        Err(err) => {
+            log::error!("failed to load user-defined roles - {err:#}");
+            Arc::new(builtin_roles())
+        }
+    };
+    *ROLES.write().unwrap() = Some((generation, Arc::clone(&roles)));
+
+    roles
+}
+
+/// The access control config of PDM, which adds the user-defined roles to the built-in ones of
+/// [`pdm_api_types::AccessControlConfig`].
+pub struct AccessControlConfig;
+
+impl proxmox_access_control::init::AccessControlConfig for AccessControlConfig {
+    fn privileges(&self) -> &HashMap<&str, u64> {
+        BUILTIN_ACCESS_CONTROL_CONFIG.privileges()
+    }
+
+    fn roles(&self) -> &HashMap<&str, (u64, &str)> {
+        // The trait hands out plain references, so the maps passed to it must never be freed.
+        // Identical role configurations share a map and only the first [`MAX_ROLE_MAPS`] distinct
+        // ones get a map at all, which bounds the memory used for them.
+        static CURRENT: RwLock<Option<(usize, &'static RoleMap)>> = RwLock::new(None);
+        static ROLE_MAPS: Mutex<Vec<(Arc<Roles>, &'static RoleMap)>> = Mutex::new(Vec::new());
+
+        let Some(generation) = ConfigVersionCache::new_log_error().map(|c| c.roles_generation())
+        else {
+            return BUILTIN_ACCESS_CONTROL_CONFIG.roles();
+        };
+
+        if let Some((current_generation, map)) = *CURRENT.read().unwrap() {
+            if current_generation == generation {
+                return map;
+            }
+        }
+
+        let roles = roles();
+        let mut role_maps = ROLE_MAPS.lock().unwrap();
+
+        let map = match role_maps.iter().find(|(r, _)| **r == *roles) {
+            Some((_, map)) => *map,
+            None if role_maps.len() < MAX_ROLE_MAPS => {
+                let map: &'static RoleMap = Box::leak(Box::new(
+                    roles
+                        .iter()
+                        .map(|(roleid, (privs, comment))| {
+                            (intern(roleid), (*privs, intern(comment)))
+                        })
+                        .collect(),
+                ));
+                role_maps.push((roles, map));
+                map
+            }
+            None => {
+                // fail closed, so privileges removed from a role do not stay in effect
+                log::error!(
+                    "roles changed too often, ignoring user-defined roles until the daemon is \
+                    restarted"
+                );
+                BUILTIN_ACCESS_CONTROL_CONFIG.roles()
+            }
+        };
+
+        *CURRENT.write().unwrap() = Some((generation, map));
+
+        map
+    }
+
+    fn is_superuser(&self, auth_id: &Authid) -> bool {
+        BUILTIN_ACCESS_CONTROL_CONFIG.is_superuser(auth_id)
+    }
+
+    fn role_admin(&self) -> Option<&str> {
+        BUILTIN_ACCESS_CONTROL_CONFIG.role_admin()
+    }
+
+    fn init_user_config(&self, config: &mut SectionConfigData) -> Result<(), Error> {
+        BUILTIN_ACCESS_CONTROL_CONFIG.init_user_config(config)
+    }
+
+    fn acl_audit_privileges(&self) -> u64 {
+        BUILTIN_ACCESS_CONTROL_CONFIG.acl_audit_privileges()
+    }
+
+    fn acl_modify_privileges(&self) -> u64 {
+        BUILTIN_ACCESS_CONTROL_CONFIG.acl_modify_privileges()
+    }
+
+    fn check_acl_path(&self, path: &str) -> Result<(), Error> {
+        BUILTIN_ACCESS_CONTROL_CONFIG.check_acl_path(path)
+    }
+}
+
+fn builtin_roles() -> Roles {
+    BUILTIN_ACCESS_CONTROL_CONFIG
+        .roles()
+        .iter()
+        .map(|(roleid, (privs, comment))| (roleid.to_string(), (*privs, comment.to_string())))
+        .collect()
+}
+
+/// Add the user-defined roles to the built-in ones. Built-in roles cannot be overridden.
+fn merge_roles(config: typed::SectionConfigData<RoleConfigEntry>) -> Roles {
+    let mut roles = builtin_roles();
+
+    for (roleid, RoleConfigEntry::Role(role)) in config {
+        if Role::is_builtin(&roleid) || roles.contains_key(&roleid) {
+            continue;
+        }
+
+        let privs = role.privileges();
+        roles.insert(roleid, (privs, role.comment.unwrap_or_default()));
+    }
+
+    roles
+}
+
+/// Get a `'static` copy of a string, which is only allocated once per distinct string.
+///
+/// Only used for the maps of at most [`MAX_ROLE_MAPS`] role configurations.
+fn intern(value: &str) -> &'static str {
+    static STRINGS: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+
+    let mut strings = STRINGS.lock().unwrap();
+    match strings.get(value) {
+        Some(interned) => interned,
+        None => {
+            let interned: &'static str = value.to_string().leak();
+            strings.insert(interned);
+            interned
+        }
+    }
+}
+
+/// Look up the privileges on a path, for guest paths including the pool and tag ACLs.
+pub fn lookup_privs(user_info: &CachedUserInfo, auth_id: &Authid, path: &[&str]) -> u64 {
+    match *path {
+        ["resource", remote, "guest", vmid] => match vmid.parse() {
+            Ok(vmid) => lookup_guest_privs(user_info, auth_id, remote, vmid),
+            Err(_) => user_info.lookup_privs(auth_id, path),
+        },
+        _ => user_info.lookup_privs(auth_id, path),
+    }
+}
+
+/// Look up the privileges on a guest, including those granted via its pool and tags.
+///
+/// Pool and tags are taken from the resource cache, so they are not considered for guests which
+/// were not seen recently.
+pub fn lookup_guest_privs(
+    user_info: &CachedUserInfo,
+    auth_id: &Authid,
+    remote: &str,
+    vmid: u32,
+) -> u64 {
+    let privs = user_info.lookup_privs(auth_id, &["resource", remote, "guest", &vmid.to_string()]);
+
+    let Some(cached) =
+        crate::api::resources::get_cached_resources(remote, GUEST_MEMBERSHIP_MAX_AGE)
+    else {
+        return privs;
+    };
+
+    cached
+        .resources
+        .iter()
+        .find(|resource| guest_vmid(resource) == Some(vmid))
+        .map_or(privs, |guest| {
+            privs | membership_privs(user_info, auth_id, remote, guest)
+        })
+}
+
+/// Look up the privileges on a guest resource of a remote, including those granted via its pool
+/// and tags. Returns no privileges for other resource types.
+pub fn lookup_resource_privs(
+    user_info: &CachedUserInfo,
+    auth_id: &Authid,
+    remote: &str,
+    resource: &Resource,
+) -> u64 {
+    let Some(vmid) = guest_vmid(resource) else {
+        return 0;
+    };
+
+    user_info.lookup_privs(auth_id, &["resource", remote, "guest", &vmid.to_string()])
+        | membership_privs(user_info, auth_id, remote, resource)
+}
+
+/// Look up the privileges granted via the tags of a guest.
+///
+/// Unlike the pool, tags are part of the guest config and are kept on remote migration.
+pub fn lookup_guest_tag_privs(
+    user_info: &CachedUserInfo,
+    auth_id: &Authid,
+    remote: &str,
+    vmid: u32,
+) -> u64 {
+    let Some(cached) =
+        crate::api::resources::get_cached_resources(remote, GUEST_MEMBERSHIP_MAX_AGE)
+    else {
+        return 0;
+    };
+
+    cached
+        .resources
+        .iter()
+        .find(|resource| guest_vmid(resource) == Some(vmid))
+        .map_or(0, |guest| {
+            tag_privs(user_info, auth_id, guest_pool_and_tags(guest).1)
+        })
+}
+
+/// Look up the privileges on a PVE pool of a remote.
+pub fn lookup_pool_privs(
+    user_info: &CachedUserInfo,
+    auth_id: &Authid,
+    remote: &str,
+    pool: &str,
+) -> u64 {
+    user_info.lookup_privs(auth_id, &pool_acl_path(remote, pool))
+}
+
+fn guest_vmid(resource: &Resource) -> Option<u32> {
+    match resource {
+        Resource::PveQemu(guest) => Some(guest.vmid),
+        Resource::PveLxc(guest) => Some(guest.vmid),
+        _ => None,
+    }
+}
+
+fn guest_pool_and_tags(resource: &Resource) -> (&str, &[String]) {
+    match resource {
+        Resource::PveQemu(guest) => (&guest.pool, &guest.tags),
+        Resource::PveLxc(guest) => (&guest.pool, &guest.tags),
+        _ => ("", &[]),
+    }
+}
+
+fn membership_privs(
+    user_info: &CachedUserInfo,
+    auth_id: &Authid,
+    remote: &str,
+    guest: &Resource,
+) -> u64 {
+    let (pool, tags) = guest_pool_and_tags(guest);
+
+    let mut privs = tag_privs(user_info, auth_id, tags);
+
+    if !pool.is_empty() {
+        privs |= user_info.lookup_privs(auth_id, &pool_acl_path(remote, pool));
+    }
+
+    privs
+}
+
+fn tag_privs(user_info: &CachedUserInfo, auth_id: &Authid, tags: &[String]) -> u64 {
+    tags.iter().fold(0, |privs, tag| {
+        privs | user_info.lookup_privs(auth_id, &["tag", tag])
+    })
+}
+
+/// Nested PVE pools like `parent/child` map to nested ACL paths, so the ACLs of parent pools
+/// propagate to their children.
+fn pool_acl_path<'a>(remote: &'a str, pool: &'a str) -> Vec<&'a str> {
+    let mut path = vec!["resource", remote, "pool"];
+    path.extend(pool.split('/').filter(|component| !component.is_empty()));
+    path
+}
+
+/// [`UserInformation`] handed to the REST server, which takes the pool and tag ACLs into account
+/// for API methods requiring privileges on a guest path.
+pub struct PdmUserInformation(pub std::sync::Arc<CachedUserInfo>);
+
+impl UserInformation for PdmUserInformation {
+    fn is_superuser(&self, userid: &str) -> bool {
+        <CachedUserInfo as UserInformation>::is_superuser(&self.0, userid)
+    }
+
+    fn is_group_member(&self, userid: &str, group: &str) -> bool {
+        <CachedUserInfo as UserInformation>::is_group_member(&self.0, userid, group)
+    }
+
+    fn lookup_privs(&self, userid: &str, path: &[&str]) -> u64 {
+        match userid.parse::<Authid>() {
+            Ok(auth_id) => lookup_privs(&self.0, &auth_id, path),
+            Err(_) => <CachedUserInfo as UserInformation>::lookup_privs(&self.0, userid, path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_pool_path() {
+        assert_eq!(
+            pool_acl_path("pve-1", "customer-a"),
+            ["resource", "pve-1", "pool", "customer-a"]
+        );
+        assert_eq!(
+            pool_acl_path("pve-1", "customer-a/web"),
+            ["resource", "pve-1", "pool", "customer-a", "web"]
+        );
+    }
+}
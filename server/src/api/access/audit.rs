@@ -0,0 +1,73 @@
+//! Query the audit log of state-changing API calls.
+
+use anyhow::Error;
+
+use proxmox_router::{Permission, Router};
+use proxmox_schema::api;
+
+use pdm_api_types::audit::AuditLogEntry;
+use pdm_api_types::remotes::REMOTE_ID_SCHEMA;
+use pdm_api_types::{Authid, PRIV_SYS_AUDIT};
+
+use crate::audit::{self, AuditLogFilter};
+
+pub const ROUTER: Router = Router::new().get(&API_METHOD_READ_AUDIT_LOG);
+
+#[api(
+    input: {
+        properties: {
+            since: {
+                type: Integer,
+                description: "Only list calls made since this UNIX epoch.",
+                optional: true,
+            },
+            until: {
+                type: Integer,
+                description: "Only list calls made until this UNIX epoch.",
+                optional: true,
+            },
+            user: {
+                type: Authid,
+                optional: true,
+            },
+            remote: {
+                schema: REMOTE_ID_SCHEMA,
+                optional: true,
+            },
+            limit: {
+                type: Integer,
+                description: "Maximum number of entries to return, 0 for no limit.",
+                optional: true,
+                default: 500,
+                minimum: 0,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["system", "log"], PRIV_SYS_AUDIT, false),
+    },
+    returns: {
+        description: "The matching entries, newest first.",
+        type: Array,
+        items: { type: AuditLogEntry },
+    },
+)]
+/// List state-changing API calls from the audit log.
+pub async fn read_audit_log(
+    since: Option<i64>,
+    until: Option<i64>,
+    user: Option<Authid>,
+    remote: Option<String>,
+    limit: u64,
+) -> Result<Vec<AuditLogEntry>, Error> {
+    let filter = AuditLogFilter {
+        since,
+        until,
+        user,
+        remote,
+    };
+
+    let limit = (limit > 0).then_some(limit as usize);
+
+    tokio::task::spawn_blocking(move || audit::read_entries(&filter, limit)).await?
+}
@@ -13,20 +13,23 @@ use proxmox_sortable_macro::sortable;
 
 use pdm_api_types::{Authid, ACL_PATH_SCHEMA, PRIVILEGES, PRIV_ACCESS_AUDIT};
 
+mod audit;
 mod domains;
 mod openid;
+mod roles;
 mod tfa;
 mod users;
 
 #[sortable]
 const SUBDIRS: SubdirMap = &sorted!([
     ("acl", &proxmox_access_control::api::ACL_ROUTER),
+    ("audit", &audit::ROUTER),
     ("domains", &domains::ROUTER),
     (
         "permissions",
         &Router::new().get(&API_METHOD_LIST_PERMISSIONS)
     ),
-    ("roles", &proxmox_access_control::api::ROLE_ROUTER),
+    ("roles", &roles::ROUTER),
     ("tfa", &tfa::ROUTER),
     (
         "ticket",
@@ -0,0 +1,275 @@
+//! Built-in and user-defined roles.
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+
+use proxmox_access_control::acl::AclTreeNode;
+use proxmox_router::{http_bail, http_err, Permission, Router, RpcEnvironment};
+use proxmox_schema::{api, param_bail};
+
+use pdm_api_types::{
+    privs_to_priv_names, ConfigDigest, Role, RoleConfig, RoleConfigEntry, RoleListItem,
+    PRIVILEGE_LIST_SCHEMA, PRIV_ACCESS_MODIFY, ROLE_ID_SCHEMA, SINGLE_LINE_COMMENT_SCHEMA,
+};
+
+pub const ROUTER: Router = Router::new()
+    .get(&API_METHOD_LIST_ROLES)
+    .post(&API_METHOD_CREATE_ROLE)
+    .match_all("roleid", &ITEM_ROUTER);
+
+const ITEM_ROUTER: Router = Router::new()
+    .get(&API_METHOD_READ_ROLE)
+    .put(&API_METHOD_UPDATE_ROLE)
+    .delete(&API_METHOD_DELETE_ROLE);
+
+#[api(
+    returns: {
+        description: "List of built-in and user-defined roles.",
+        type: Array,
+        items: { type: RoleListItem },
+    },
+    access: {
+        permission: &Permission::Anybody,
+    },
+)]
+/// List all roles.
+pub fn list_roles(rpcenv: &mut dyn RpcEnvironment) -> Result<Vec<RoleListItem>, Error> {
+    let (config, digest) = pdm_config::roles::config()?;
+
+    rpcenv["digest"] = digest.to_hex().into();
+
+    let mut list: Vec<RoleListItem> = crate::acl::roles()
+        .iter()
+        .filter(|(roleid, _)| Role::is_builtin(roleid))
+        .map(|(roleid, (privs, comment))| RoleListItem {
+            roleid: roleid.to_string(),
+            privs: priv_names(*privs),
+            comment: Some(comment.to_string()),
+            builtin: true,
+        })
+        .collect();
+
+    list.extend(
+        config
+            .into_iter()
+            .map(|(_, RoleConfigEntry::Role(role))| RoleListItem {
+                privs: priv_names(role.privileges()),
+                roleid: role.roleid,
+                comment: role.comment,
+                builtin: false,
+            }),
+    );
+
+    Ok(list)
+}
+
+fn priv_names(privs: u64) -> Vec<String> {
+    privs_to_priv_names(privs)
+        .into_iter()
+        .map(str::to_string)
+        .collect()
+}
+
+#[api(
+    protected: true,
+    input: {
+        properties: {
+            role: {
+                type: RoleConfig,
+                flatten: true,
+            },
+            digest: {
+                type: ConfigDigest,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["access", "roles"], PRIV_ACCESS_MODIFY, false),
+    },
+)]
+/// Create a new role.
+pub fn create_role(role: RoleConfig, digest: Option<ConfigDigest>) -> Result<(), Error> {
+    let _lock = pdm_config::roles::lock_config()?;
+
+    let (mut config, config_digest) = pdm_config::roles::config()?;
+
+    config_digest.detect_modification(digest.as_ref())?;
+
+    if Role::is_builtin(&role.roleid) {
+        param_bail!("roleid", "'{}' is a built-in role", role.roleid);
+    }
+
+    if config.contains_key(&role.roleid) {
+        param_bail!("roleid", "role '{}' already exists", role.roleid);
+    }
+
+    config.insert(role.roleid.clone(), RoleConfigEntry::Role(role));
+
+    pdm_config::roles::save_config(&config)
+}
+
+#[api(
+    input: {
+        properties: {
+            roleid: { schema: ROLE_ID_SCHEMA },
+        },
+    },
+    returns: { type: RoleConfig },
+    access: {
+        permission: &Permission::Anybody,
+    },
+)]
+/// Read a user-defined role.
+pub fn read_role(roleid: String, rpcenv: &mut dyn RpcEnvironment) -> Result<RoleConfig, Error> {
+    let (config, digest) = pdm_config::roles::config()?;
+
+    rpcenv["digest"] = digest.to_hex().into();
+
+    match config.get(&roleid) {
+        Some(RoleConfigEntry::Role(role)) => Ok(role.clone()),
+        None => http_bail!(NOT_FOUND, "no such role '{roleid}'"),
+    }
+}
+
+#[api()]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// Deletable property name
+pub enum DeletableProperty {
+    /// Delete the comment.
+    Comment,
+}
+
+#[api(
+    protected: true,
+    input: {
+        properties: {
+            roleid: { schema: ROLE_ID_SCHEMA },
+            privs: {
+                schema: PRIVILEGE_LIST_SCHEMA,
+                optional: true,
+            },
+            comment: {
+                schema: SINGLE_LINE_COMMENT_SCHEMA,
+                optional: true,
+            },
+            delete: {
+                description: "List of properties to delete.",
+                type: Array,
+                optional: true,
+                items: {
+                    type: DeletableProperty,
+                }
+            },
+            digest: {
+                type: ConfigDigest,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["access", "roles"], PRIV_ACCESS_MODIFY, false),
+    },
+)]
+/// Update a user-defined role. Changed privileges apply to all existing ACL entries of the role.
+pub fn update_role(
+    roleid: String,
+    privs: Option<String>,
+    comment: Option<String>,
+    delete: Option<Vec<DeletableProperty>>,
+    digest: Option<ConfigDigest>,
+) -> Result<(), Error> {
+    let _lock = pdm_config::roles::lock_config()?;
+
+    let (mut config, config_digest) = pdm_config::roles::config()?;
+
+    config_digest.detect_modification(digest.as_ref())?;
+
+    if Role::is_builtin(&roleid) {
+        http_bail!(FORBIDDEN, "built-in role '{roleid}' cannot be modified");
+    }
+
+    let RoleConfigEntry::Role(role) = config
+        .get_mut(&roleid)
+        .ok_or_else(|| http_err!(NOT_FOUND, "no such role '{roleid}'"))?;
+
+    if let Some(delete) = delete {
+        for delete_prop in delete {
+            match delete_prop {
+                DeletableProperty::Comment => role.comment = None,
+            }
+        }
+    }
+
+    if let Some(privs) = privs {
+        role.privs = privs;
+    }
+
+    if comment.is_some() {
+        role.comment = comment;
+    }
+
+    pdm_config::roles::save_config(&config)
+}
+
+#[api(
+    protected: true,
+    input: {
+        properties: {
+            roleid: { schema: ROLE_ID_SCHEMA },
+            digest: {
+                type: ConfigDigest,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["access", "roles"], PRIV_ACCESS_MODIFY, false),
+    },
+)]
+/// Delete a user-defined role. Roles which are still used in ACL entries cannot be deleted.
+pub fn delete_role(roleid: String, digest: Option<ConfigDigest>) -> Result<(), Error> {
+    let _lock = pdm_config::roles::lock_config()?;
+
+    let (mut config, config_digest) = pdm_config::roles::config()?;
+
+    config_digest.detect_modification(digest.as_ref())?;
+
+    if Role::is_builtin(&roleid) {
+        http_bail!(FORBIDDEN, "built-in role '{roleid}' cannot be deleted");
+    }
+
+    if !config.contains_key(&roleid) {
+        http_bail!(NOT_FOUND, "no such role '{roleid}'");
+    }
+
+    let (acl_tree, _) = proxmox_access_control::acl::config()?;
+    if let Some(path) = find_role_usage(&acl_tree.root, "", &roleid) {
+        http_bail!(
+            BAD_REQUEST,
+            "role '{roleid}' is still used in an ACL entry on '{path}'"
+        );
+    }
+
+    config.remove(&roleid);
+
+    pdm_config::roles::save_config(&config)
+}
+
+/// Find the first ACL path with an entry for a role.
+fn find_role_usage(node: &AclTreeNode, path: &str, roleid: &str) -> Option<String> {
+    let in_use = node
+        .users
+        .values()
+        .chain(node.groups.values())
+        .any(|roles| roles.contains_key(roleid));
+
+    if in_use {
+        return Some(if path.is_empty() { "/" } else { path }.to_string());
+    }
+
+    node.children.iter().find_map(|(component, child)| {
+        find_role_usage(child, &format!("{path}/{component}"), roleid)
+    })
+}
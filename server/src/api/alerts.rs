@@ -0,0 +1,77 @@
+//! Alerts raised by the alert rules.
+
+use anyhow::{Context, Error};
+
+use proxmox_access_control::CachedUserInfo;
+use proxmox_router::{http_bail, Permission, Router, RpcEnvironment};
+use proxmox_schema::api;
+
+use pdm_api_types::alerts::Alert;
+use pdm_api_types::{Authid, PRIV_RESOURCE_AUDIT, VIEW_ID_SCHEMA};
+
+use crate::metric_collection::alerts;
+use crate::views;
+
+pub const ROUTER: Router = Router::new().get(&API_METHOD_LIST_ALERTS);
+
+#[api(
+    input: {
+        properties: {
+            "include-resolved": {
+                description: "Also return alerts which were resolved recently.",
+                optional: true,
+                default: false,
+            },
+            view: {
+                schema: VIEW_ID_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Anybody,
+        description: "The user needs to have at least `Resource.Audit` on one resources under `/resource`.
+        Only alerts for remotes on which the user has `Resource.Audit` on `/resource/{remote_name}`
+        are returned. If a view is given, `Resource.Audit` on `/view/{view}` is required instead.",
+    },
+    returns: {
+        description: "List of alerts.",
+        type: Array,
+        items: { type: Alert },
+    },
+)]
+/// List active alerts.
+pub fn list_alerts(
+    include_resolved: bool,
+    view: Option<String>,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<Vec<Alert>, Error> {
+    let user_info = CachedUserInfo::new()?;
+    let auth_id: Authid = rpcenv
+        .get_auth_id()
+        .context("no authid available")?
+        .parse()?;
+
+    if let Some(view) = &view {
+        user_info.check_privs(&auth_id, &["view", view], PRIV_RESOURCE_AUDIT, false)?;
+    } else if !user_info.any_privs_below(&auth_id, &["resource"], PRIV_RESOURCE_AUDIT)? {
+        http_bail!(FORBIDDEN, "user has no access to resources");
+    }
+
+    let view = views::get_optional_view(view.as_deref())?;
+
+    let alerts = alerts::get_alerts(include_resolved)?
+        .into_iter()
+        .filter(|entry| {
+            let remote = entry.alert.remote.as_str();
+            if let Some(view) = &view {
+                view.resource_matches(remote, &entry.resource)
+            } else {
+                user_info.lookup_privs(&auth_id, &["resource", remote]) & PRIV_RESOURCE_AUDIT != 0
+            }
+        })
+        .map(|entry| entry.alert)
+        .collect();
+
+    Ok(alerts)
+}
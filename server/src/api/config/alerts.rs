@@ -0,0 +1,271 @@
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+
+use proxmox_config_digest::ConfigDigest;
+use proxmox_router::{http_bail, http_err, Permission, Router, RpcEnvironment};
+use proxmox_schema::{api, param_bail};
+
+use pdm_api_types::alerts::{AlertRule, AlertRuleEntry, AlertRuleUpdater, ALERT_RULE_ID_SCHEMA};
+use pdm_api_types::{PRIV_RESOURCE_AUDIT, PRIV_RESOURCE_MODIFY};
+
+const RULE_ROUTER: Router = Router::new()
+    .put(&API_METHOD_UPDATE_RULE)
+    .delete(&API_METHOD_REMOVE_RULE)
+    .get(&API_METHOD_READ_RULE);
+
+pub const ROUTER: Router = Router::new()
+    .get(&API_METHOD_LIST_RULES)
+    .post(&API_METHOD_ADD_RULE)
+    .match_all("id", &RULE_ROUTER);
+
+fn check_metric(rule: &AlertRule) -> Result<(), Error> {
+    if !rule.metric.supports(rule.resource_type) {
+        param_bail!(
+            "metric",
+            "metric '{}' is not available for resource type '{}'",
+            rule.metric,
+            rule.resource_type,
+        );
+    }
+
+    Ok(())
+}
+
+#[api(
+    protected: true,
+    access: {
+        permission: &Permission::Privilege(&["resource"], PRIV_RESOURCE_AUDIT, false),
+    },
+    returns: {
+        description: "List of alert rules.",
+        type: Array,
+        items: { type: AlertRule },
+    },
+)]
+/// List alert rules.
+pub fn list_rules(rpcenv: &mut dyn RpcEnvironment) -> Result<Vec<AlertRule>, Error> {
+    let (config, digest) = pdm_config::alerts::config()?;
+
+    rpcenv["digest"] = digest.to_hex().into();
+
+    let rules = config
+        .into_iter()
+        .map(|(_, value)| match value {
+            AlertRuleEntry::Rule(rule) => rule,
+        })
+        .collect();
+
+    Ok(rules)
+}
+
+#[api(
+    protected: true,
+    input: {
+        properties: {
+            rule: {
+                flatten: true,
+                type: AlertRule,
+            },
+            digest: {
+                type: ConfigDigest,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource"], PRIV_RESOURCE_MODIFY, false),
+    },
+)]
+/// Add new alert rule.
+pub fn add_rule(rule: AlertRule, digest: Option<ConfigDigest>) -> Result<(), Error> {
+    let _lock = pdm_config::alerts::lock_config()?;
+
+    let (mut config, config_digest) = pdm_config::alerts::config()?;
+
+    config_digest.detect_modification(digest.as_ref())?;
+
+    check_metric(&rule)?;
+
+    let id = rule.id.clone();
+
+    if let Some(AlertRuleEntry::Rule(_)) = config.insert(id.clone(), AlertRuleEntry::Rule(rule)) {
+        param_bail!("id", "alert rule '{}' already exists.", id)
+    }
+
+    pdm_config::alerts::save_config(&config)?;
+
+    Ok(())
+}
+
+#[api()]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// Deletable property name
+pub enum DeletableProperty {
+    /// Delete the threshold.
+    Threshold,
+    /// Delete the duration.
+    Duration,
+    /// Delete the remote filter.
+    Remote,
+    /// Delete the comment.
+    Comment,
+    /// Delete the disable flag.
+    Disable,
+}
+
+#[api(
+    protected: true,
+    input: {
+        properties: {
+            id: {
+                schema: ALERT_RULE_ID_SCHEMA,
+            },
+            rule: {
+                flatten: true,
+                type: AlertRuleUpdater,
+            },
+            delete: {
+                description: "List of properties to delete.",
+                type: Array,
+                optional: true,
+                items: {
+                    type: DeletableProperty,
+                }
+            },
+            digest: {
+                type: ConfigDigest,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource"], PRIV_RESOURCE_MODIFY, false),
+    },
+)]
+/// Update alert rule.
+pub fn update_rule(
+    id: String,
+    rule: AlertRuleUpdater,
+    delete: Option<Vec<DeletableProperty>>,
+    digest: Option<ConfigDigest>,
+) -> Result<(), Error> {
+    let _lock = pdm_config::alerts::lock_config()?;
+
+    let (mut config, config_digest) = pdm_config::alerts::config()?;
+
+    config_digest.detect_modification(digest.as_ref())?;
+
+    let entry = config
+        .get_mut(&id)
+        .ok_or_else(|| http_err!(NOT_FOUND, "no such alert rule '{id}'"))?;
+
+    let AlertRuleEntry::Rule(conf) = entry;
+
+    if let Some(delete) = delete {
+        for delete_prop in delete {
+            match delete_prop {
+                DeletableProperty::Threshold => conf.threshold = None,
+                DeletableProperty::Duration => conf.duration = None,
+                DeletableProperty::Remote => conf.remote = None,
+                DeletableProperty::Comment => conf.comment = None,
+                DeletableProperty::Disable => conf.disable = None,
+            }
+        }
+    }
+
+    if let Some(metric) = rule.metric {
+        conf.metric = metric;
+    }
+
+    if rule.threshold.is_some() {
+        conf.threshold = rule.threshold;
+    }
+
+    if rule.duration.is_some() {
+        conf.duration = rule.duration;
+    }
+
+    if rule.remote.is_some() {
+        conf.remote = rule.remote;
+    }
+
+    if rule.comment.is_some() {
+        conf.comment = rule.comment;
+    }
+
+    if rule.disable.is_some() {
+        conf.disable = rule.disable;
+    }
+
+    check_metric(conf)?;
+
+    pdm_config::alerts::save_config(&config)?;
+
+    Ok(())
+}
+
+#[api(
+    protected: true,
+    input: {
+        properties: {
+            id: {
+                schema: ALERT_RULE_ID_SCHEMA,
+            },
+            digest: {
+                type: ConfigDigest,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource"], PRIV_RESOURCE_MODIFY, false),
+    },
+)]
+/// Delete the alert rule with the given id.
+pub fn remove_rule(id: String, digest: Option<ConfigDigest>) -> Result<(), Error> {
+    let _lock = pdm_config::alerts::lock_config()?;
+
+    let (mut config, config_digest) = pdm_config::alerts::config()?;
+
+    config_digest.detect_modification(digest.as_ref())?;
+
+    match config.remove(&id) {
+        Some(AlertRuleEntry::Rule(_)) => {}
+        None => http_bail!(NOT_FOUND, "alert rule '{id}' does not exist."),
+    }
+
+    pdm_config::alerts::save_config(&config)?;
+
+    Ok(())
+}
+
+#[api(
+    input: {
+        properties: {
+            id: {
+                schema: ALERT_RULE_ID_SCHEMA,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource"], PRIV_RESOURCE_AUDIT, false),
+    },
+    returns: { type: AlertRule },
+)]
+/// Get the config of a single alert rule.
+pub fn read_rule(id: String, rpcenv: &mut dyn RpcEnvironment) -> Result<AlertRule, Error> {
+    let (config, digest) = pdm_config::alerts::config()?;
+
+    rpcenv["digest"] = digest.to_hex().into();
+
+    let rule = config
+        .get(&id)
+        .ok_or_else(|| http_err!(NOT_FOUND, "no such alert rule '{id}'"))?;
+
+    let rule = match rule {
+        AlertRuleEntry::Rule(rule) => rule.clone(),
+    };
+
+    Ok(rule)
+}
@@ -0,0 +1,434 @@
+use anyhow::{Context, Error};
+use serde::{Deserialize, Serialize};
+
+use proxmox_access_control::CachedUserInfo;
+use proxmox_config_digest::ConfigDigest;
+use proxmox_router::{http_bail, http_err, Permission, Router, RpcEnvironment, SubdirMap};
+use proxmox_schema::{api, param_bail};
+use proxmox_sortable_macro::sortable;
+
+use pdm_api_types::bulk_action::GuestAction;
+use pdm_api_types::jobs::{
+    JobConfigEntry, JobType, ScheduledJob, ScheduledJobStatus, ScheduledJobUpdater, JOB_ID_SCHEMA,
+};
+use pdm_api_types::{
+    Authid, PRIV_RESOURCE_MANAGE, PRIV_RESOURCE_MIGRATE, PRIV_RESOURCE_MODIFY, PRIV_SYS_AUDIT,
+    PRIV_SYS_MODIFY, UPID_SCHEMA,
+};
+
+use crate::jobstate::{self, JobState};
+use crate::scheduled_jobs::{self, SCHEDULED_JOB_TYPE};
+
+const JOB_ROUTER: Router = Router::new()
+    .put(&API_METHOD_UPDATE_JOB)
+    .delete(&API_METHOD_REMOVE_JOB)
+    .get(&API_METHOD_READ_JOB)
+    .subdirs(JOB_SUBDIRS);
+#[sortable]
+const JOB_SUBDIRS: SubdirMap = &sorted!([("run", &Router::new().post(&API_METHOD_RUN_JOB)),]);
+
+pub const ROUTER: Router = Router::new()
+    .get(&API_METHOD_LIST_JOBS)
+    .post(&API_METHOD_ADD_JOB)
+    .match_all("id", &JOB_ROUTER);
+
+/// Check that the properties of the job fit its type.
+fn check_job(job: &ScheduledJob) -> Result<(), Error> {
+    match job.job_type {
+        JobType::GuestAction => {
+            if job.action.is_none() {
+                param_bail!("action", "guest-action jobs need an action");
+            }
+            if job.search.is_none() {
+                param_bail!("search", "guest-action jobs need a search to select guests");
+            }
+        }
+        job_type => {
+            if job.action.is_some() {
+                param_bail!("action", "'{job_type}' jobs do not take an action");
+            }
+            if job.search.is_some() {
+                param_bail!("search", "'{job_type}' jobs do not take a search");
+            }
+            if job.remotes.is_some() && !job_type.uses_remotes() {
+                param_bail!("remotes", "'{job_type}' jobs do not act on remotes");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Jobs run with full privileges, so the user needs to have the privileges for what the job
+/// does on all remotes it may act on.
+fn check_job_privs(job: &ScheduledJob, rpcenv: &dyn RpcEnvironment) -> Result<(), Error> {
+    let auth_id: Authid = rpcenv
+        .get_auth_id()
+        .context("no authid available")?
+        .parse()?;
+    let user_info = CachedUserInfo::new()?;
+
+    let privilege = match job.job_type {
+        JobType::AptRefresh => PRIV_RESOURCE_MODIFY,
+        JobType::GuestAction if job.action == Some(GuestAction::Migrate) => PRIV_RESOURCE_MIGRATE,
+        JobType::GuestAction | JobType::GarbageCollection => PRIV_RESOURCE_MANAGE,
+        JobType::Report => {
+            return user_info.check_privs(&auth_id, &["system", "status"], PRIV_SYS_AUDIT, false);
+        }
+    };
+
+    match &job.remotes {
+        Some(remotes) => {
+            for remote in remotes {
+                user_info.check_privs(&auth_id, &["resource", remote], privilege, false)?;
+            }
+            Ok(())
+        }
+        None => user_info.check_privs(&auth_id, &["resource"], privilege, false),
+    }
+}
+
+fn job_status(job: ScheduledJob) -> Result<ScheduledJobStatus, Error> {
+    let state = JobState::load(SCHEDULED_JOB_TYPE, &job.id)?;
+    let mut status = jobstate::compute_schedule_status(&state, Some(&job.schedule))?;
+
+    if !job.enabled() {
+        status.next_run = None;
+    }
+
+    Ok(ScheduledJobStatus {
+        config: job,
+        status,
+    })
+}
+
+#[api(
+    protected: true,
+    access: {
+        permission: &Permission::Privilege(&["system"], PRIV_SYS_AUDIT, false),
+    },
+    returns: {
+        description: "List of scheduled jobs with the state of their last and next run.",
+        type: Array,
+        items: { type: ScheduledJobStatus },
+    },
+)]
+/// List scheduled jobs.
+pub fn list_jobs(rpcenv: &mut dyn RpcEnvironment) -> Result<Vec<ScheduledJobStatus>, Error> {
+    let (config, digest) = pdm_config::jobs::config()?;
+
+    rpcenv["digest"] = digest.to_hex().into();
+
+    config
+        .into_iter()
+        .map(|(_, value)| match value {
+            JobConfigEntry::Job(job) => job_status(job),
+        })
+        .collect()
+}
+
+#[api(
+    protected: true,
+    input: {
+        properties: {
+            job: {
+                flatten: true,
+                type: ScheduledJob,
+            },
+            digest: {
+                type: ConfigDigest,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["system"], PRIV_SYS_MODIFY, false),
+        description: "Additionally, the privileges needed for the job's action are required, for \
+            example `Resource.Manage` on `/resource` or on all configured remotes for \
+            guest-action jobs.",
+    },
+)]
+/// Add new scheduled job.
+pub fn add_job(
+    job: ScheduledJob,
+    digest: Option<ConfigDigest>,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<(), Error> {
+    check_job(&job)?;
+    check_job_privs(&job, rpcenv)?;
+
+    let _lock = pdm_config::jobs::lock_config()?;
+
+    let (mut config, config_digest) = pdm_config::jobs::config()?;
+
+    config_digest.detect_modification(digest.as_ref())?;
+
+    let id = job.id.clone();
+
+    if let Some(JobConfigEntry::Job(_)) = config.insert(id.clone(), JobConfigEntry::Job(job)) {
+        param_bail!("id", "job '{}' already exists.", id)
+    }
+
+    pdm_config::jobs::save_config(&config)?;
+
+    if let Err(err) = jobstate::create_state_file(SCHEDULED_JOB_TYPE, &id) {
+        log::error!("could not create job state for job '{id}': {err}");
+    }
+
+    Ok(())
+}
+
+#[api()]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// Deletable property name
+pub enum DeletableProperty {
+    /// Delete the remote selection.
+    Remotes,
+    /// Delete the search.
+    Search,
+    /// Delete the action.
+    Action,
+    /// Delete the comment.
+    Comment,
+    /// Delete the disable flag.
+    Disable,
+}
+
+#[api(
+    protected: true,
+    input: {
+        properties: {
+            id: {
+                schema: JOB_ID_SCHEMA,
+            },
+            job: {
+                flatten: true,
+                type: ScheduledJobUpdater,
+            },
+            delete: {
+                description: "List of properties to delete.",
+                type: Array,
+                optional: true,
+                items: {
+                    type: DeletableProperty,
+                }
+            },
+            digest: {
+                type: ConfigDigest,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["system"], PRIV_SYS_MODIFY, false),
+        description: "Additionally, the privileges needed for the job's action are required.",
+    },
+)]
+/// Update scheduled job.
+pub fn update_job(
+    id: String,
+    job: ScheduledJobUpdater,
+    delete: Option<Vec<DeletableProperty>>,
+    digest: Option<ConfigDigest>,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<(), Error> {
+    let _lock = pdm_config::jobs::lock_config()?;
+
+    let (mut config, config_digest) = pdm_config::jobs::config()?;
+
+    config_digest.detect_modification(digest.as_ref())?;
+
+    let entry = config
+        .get_mut(&id)
+        .ok_or_else(|| http_err!(NOT_FOUND, "no such job '{id}'"))?;
+
+    let JobConfigEntry::Job(conf) = entry;
+
+    if let Some(delete) = delete {
+        for delete_prop in delete {
+            match delete_prop {
+                DeletableProperty::Remotes => conf.remotes = None,
+                DeletableProperty::Search => conf.search = None,
+                DeletableProperty::Action => conf.action = None,
+                DeletableProperty::Comment => conf.comment = None,
+                DeletableProperty::Disable => conf.disable = None,
+            }
+        }
+    }
+
+    let mut schedule_changed = false;
+    if let Some(schedule) = job.schedule {
+        schedule_changed = conf.schedule != schedule;
+        conf.schedule = schedule;
+    }
+
+    if job.remotes.is_some() {
+        conf.remotes = job.remotes;
+    }
+
+    if job.search.is_some() {
+        conf.search = job.search;
+    }
+
+    if job.action.is_some() {
+        conf.action = job.action;
+    }
+
+    if job.comment.is_some() {
+        conf.comment = job.comment;
+    }
+
+    if job.disable.is_some() {
+        conf.disable = job.disable;
+    }
+
+    check_job(conf)?;
+    check_job_privs(conf, rpcenv)?;
+
+    pdm_config::jobs::save_config(&config)?;
+
+    if schedule_changed {
+        jobstate::update_job_last_run_time(SCHEDULED_JOB_TYPE, &id)?;
+    }
+
+    Ok(())
+}
+
+#[api(
+    protected: true,
+    input: {
+        properties: {
+            id: {
+                schema: JOB_ID_SCHEMA,
+            },
+            digest: {
+                type: ConfigDigest,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["system"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Delete the scheduled job with the given id.
+pub fn remove_job(id: String, digest: Option<ConfigDigest>) -> Result<(), Error> {
+    let _lock = pdm_config::jobs::lock_config()?;
+
+    let (mut config, config_digest) = pdm_config::jobs::config()?;
+
+    config_digest.detect_modification(digest.as_ref())?;
+
+    match config.remove(&id) {
+        Some(JobConfigEntry::Job(_)) => {}
+        None => http_bail!(NOT_FOUND, "job '{id}' does not exist."),
+    }
+
+    pdm_config::jobs::save_config(&config)?;
+
+    if let Err(err) = jobstate::remove_state_file(SCHEDULED_JOB_TYPE, &id) {
+        log::error!("could not remove job state for job '{id}': {err}");
+    }
+
+    Ok(())
+}
+
+#[api(
+    input: {
+        properties: {
+            id: {
+                schema: JOB_ID_SCHEMA,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["system"], PRIV_SYS_AUDIT, false),
+    },
+    returns: { type: ScheduledJobStatus },
+)]
+/// Get the config of a single scheduled job, together with the state of its last and next run.
+pub fn read_job(id: String, rpcenv: &mut dyn RpcEnvironment) -> Result<ScheduledJobStatus, Error> {
+    let (config, digest) = pdm_config::jobs::config()?;
+
+    rpcenv["digest"] = digest.to_hex().into();
+
+    let job = config
+        .get(&id)
+        .ok_or_else(|| http_err!(NOT_FOUND, "no such job '{id}'"))?;
+
+    let job = match job {
+        JobConfigEntry::Job(job) => job.clone(),
+    };
+
+    job_status(job)
+}
+
+#[api(
+    protected: true,
+    input: {
+        properties: {
+            id: {
+                schema: JOB_ID_SCHEMA,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["system"], PRIV_SYS_MODIFY, false),
+        description: "Additionally, the privileges needed for the job's action are required.",
+    },
+    returns: { schema: UPID_SCHEMA },
+)]
+/// Run a scheduled job right away, regardless of its schedule.
+pub fn run_job(id: String, rpcenv: &mut dyn RpcEnvironment) -> Result<String, Error> {
+    let (config, _digest) = pdm_config::jobs::config()?;
+
+    let job = match config.get(&id) {
+        Some(JobConfigEntry::Job(job)) => job.clone(),
+        None => http_bail!(NOT_FOUND, "no such job '{id}'"),
+    };
+
+    check_job_privs(&job, rpcenv)?;
+
+    let auth_id: Authid = rpcenv
+        .get_auth_id()
+        .context("no authid available")?
+        .parse()?;
+
+    scheduled_jobs::run_job(job, &auth_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn job_properties_match_type() {
+        let mut job = ScheduledJob {
+            id: "stop-test".to_string(),
+            job_type: JobType::GuestAction,
+            schedule: "sat 22:00".to_string(),
+            remotes: Some(vec!["pve-test".to_string()]),
+            search: Some("tag:test".to_string()),
+            action: Some(GuestAction::Shutdown),
+            comment: None,
+            disable: None,
+        };
+        assert!(check_job(&job).is_ok());
+
+        job.search = None;
+        assert!(check_job(&job).is_err());
+
+        job.job_type = JobType::GarbageCollection;
+        assert!(check_job(&job).is_err());
+
+        job.action = None;
+        assert!(check_job(&job).is_ok());
+
+        job.job_type = JobType::Report;
+        assert!(check_job(&job).is_err());
+
+        job.remotes = None;
+        assert!(check_job(&job).is_ok());
+    }
+}
@@ -0,0 +1,186 @@
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+
+use proxmox_config_digest::ConfigDigest;
+use proxmox_router::{http_err, Permission, Router, RpcEnvironment};
+use proxmox_schema::api;
+
+use pdm_api_types::metric_server::{
+    InfluxDbUdp, InfluxDbUdpUpdater, MetricServerEntry, METRIC_SERVER_ID_SCHEMA,
+};
+use pdm_api_types::{PRIV_SYS_AUDIT, PRIV_SYS_MODIFY};
+
+pub const ROUTER: Router = Router::new()
+    .post(&API_METHOD_ADD_SERVER)
+    .match_all("name", &ITEM_ROUTER);
+
+const ITEM_ROUTER: Router = Router::new()
+    .get(&API_METHOD_GET_SERVER)
+    .put(&API_METHOD_UPDATE_SERVER)
+    .delete(&API_METHOD_REMOVE_SERVER);
+
+const SECTION_TYPE: &str = "influxdb-udp";
+
+#[api(
+    protected: true,
+    input: {
+        properties: {
+            name: {
+                schema: METRIC_SERVER_ID_SCHEMA,
+            },
+        },
+    },
+    returns: { type: InfluxDbUdp },
+    access: {
+        permission: &Permission::Privilege(&["system", "metrics"], PRIV_SYS_AUDIT, false),
+    },
+)]
+/// Get an InfluxDB UDP metric server.
+pub fn get_server(name: String, rpcenv: &mut dyn RpcEnvironment) -> Result<InfluxDbUdp, Error> {
+    let (config, digest) = pdm_config::metric_server::config()?;
+
+    rpcenv["digest"] = digest.to_hex().into();
+
+    match config.get(&name) {
+        Some(MetricServerEntry::InfluxDbUdp(server)) => Ok(server.clone()),
+        _ => Err(http_err!(
+            NOT_FOUND,
+            "no such {SECTION_TYPE} metric server '{name}'"
+        )),
+    }
+}
+
+#[api(
+    protected: true,
+    input: {
+        properties: {
+            server: {
+                type: InfluxDbUdp,
+                flatten: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["system", "metrics"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Add a new InfluxDB UDP metric server.
+pub fn add_server(server: InfluxDbUdp) -> Result<(), Error> {
+    super::add_entry(MetricServerEntry::InfluxDbUdp(server))
+}
+
+#[api()]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// Deletable property name
+pub enum DeletableProperty {
+    /// Delete the enable flag.
+    Enable,
+    /// Delete the MTU.
+    Mtu,
+    /// Delete the comment.
+    Comment,
+}
+
+#[api(
+    protected: true,
+    input: {
+        properties: {
+            name: {
+                schema: METRIC_SERVER_ID_SCHEMA,
+            },
+            updater: {
+                type: InfluxDbUdpUpdater,
+                flatten: true,
+            },
+            delete: {
+                description: "List of properties to delete.",
+                type: Array,
+                optional: true,
+                items: {
+                    type: DeletableProperty,
+                }
+            },
+            digest: {
+                type: ConfigDigest,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["system", "metrics"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Update an InfluxDB UDP metric server.
+pub fn update_server(
+    name: String,
+    updater: InfluxDbUdpUpdater,
+    delete: Option<Vec<DeletableProperty>>,
+    digest: Option<ConfigDigest>,
+) -> Result<(), Error> {
+    let _lock = pdm_config::metric_server::lock_config()?;
+
+    let (mut config, config_digest) = pdm_config::metric_server::config()?;
+
+    config_digest.detect_modification(digest.as_ref())?;
+
+    let server = match config.get_mut(&name) {
+        Some(MetricServerEntry::InfluxDbUdp(server)) => server,
+        _ => {
+            return Err(http_err!(
+                NOT_FOUND,
+                "no such {SECTION_TYPE} metric server '{name}'"
+            ))
+        }
+    };
+
+    if let Some(delete) = delete {
+        for delete_prop in delete {
+            match delete_prop {
+                DeletableProperty::Enable => server.enable = true,
+                DeletableProperty::Mtu => server.mtu = None,
+                DeletableProperty::Comment => server.comment = None,
+            }
+        }
+    }
+
+    if let Some(enable) = updater.enable {
+        server.enable = enable;
+    }
+
+    if let Some(host) = updater.host {
+        server.host = host;
+    }
+
+    if updater.mtu.is_some() {
+        server.mtu = updater.mtu;
+    }
+
+    if updater.comment.is_some() {
+        server.comment = updater.comment;
+    }
+
+    pdm_config::metric_server::save_config(&config)
+}
+
+#[api(
+    protected: true,
+    input: {
+        properties: {
+            name: {
+                schema: METRIC_SERVER_ID_SCHEMA,
+            },
+            digest: {
+                type: ConfigDigest,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["system", "metrics"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Remove an InfluxDB UDP metric server.
+pub fn remove_server(name: String, digest: Option<ConfigDigest>) -> Result<(), Error> {
+    super::remove_entry(&name, SECTION_TYPE, digest)
+}
@@ -0,0 +1,86 @@
+//! Metric server configuration.
+
+use anyhow::Error;
+
+use proxmox_config_digest::ConfigDigest;
+use proxmox_router::{http_bail, Permission, Router, RpcEnvironment, SubdirMap};
+use proxmox_schema::api;
+use proxmox_section_config::typed::ApiSectionDataEntry;
+use proxmox_sortable_macro::sortable;
+
+use pdm_api_types::metric_server::{MetricServerEntry, MetricServerInfo};
+use pdm_api_types::PRIV_SYS_AUDIT;
+
+mod graphite;
+mod influxdb_http;
+mod influxdb_udp;
+
+#[sortable]
+const SUBDIRS: SubdirMap = &sorted!([
+    ("graphite", &graphite::ROUTER),
+    ("influxdb-http", &influxdb_http::ROUTER),
+    ("influxdb-udp", &influxdb_udp::ROUTER),
+]);
+
+pub const ROUTER: Router = Router::new()
+    .get(&API_METHOD_LIST_METRIC_SERVERS)
+    .subdirs(SUBDIRS);
+
+#[api(
+    protected: true,
+    returns: {
+        description: "List of configured metric servers.",
+        type: Array,
+        items: { type: MetricServerInfo },
+    },
+    access: {
+        permission: &Permission::Privilege(&["system", "metrics"], PRIV_SYS_AUDIT, false),
+    },
+)]
+/// List all configured metric servers.
+pub fn list_metric_servers(
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<Vec<MetricServerInfo>, Error> {
+    let (config, digest) = pdm_config::metric_server::config()?;
+
+    rpcenv["digest"] = digest.to_hex().into();
+
+    Ok(config.values().map(MetricServerInfo::from).collect())
+}
+
+/// Remove a metric server entry, making sure it has the expected type.
+fn remove_entry(name: &str, section_type: &str, digest: Option<ConfigDigest>) -> Result<(), Error> {
+    let _lock = pdm_config::metric_server::lock_config()?;
+
+    let (mut config, config_digest) = pdm_config::metric_server::config()?;
+
+    config_digest.detect_modification(digest.as_ref())?;
+
+    match config.get(name) {
+        Some(entry) if entry.section_type() == section_type => {}
+        _ => http_bail!(
+            NOT_FOUND,
+            "{section_type} metric server '{name}' does not exist."
+        ),
+    }
+
+    config.remove(name);
+
+    pdm_config::metric_server::save_config(&config)
+}
+
+/// Add a new metric server entry.
+fn add_entry(entry: MetricServerEntry) -> Result<(), Error> {
+    let _lock = pdm_config::metric_server::lock_config()?;
+
+    let (mut config, _) = pdm_config::metric_server::config()?;
+
+    let name = entry.name().to_string();
+    if config.contains_key(&name) {
+        http_bail!(BAD_REQUEST, "metric server '{name}' already exists.");
+    }
+
+    config.insert(name, entry);
+
+    pdm_config::metric_server::save_config(&config)
+}
@@ -0,0 +1,168 @@
+//! Notification target and matcher configuration.
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+
+use proxmox_router::{list_subdirs_api_method, Permission, Router, SubdirMap};
+use proxmox_schema::api;
+use proxmox_sortable_macro::sortable;
+
+use pdm_api_types::PRIV_SYS_AUDIT;
+
+mod gotify;
+mod matchers;
+mod sendmail;
+mod smtp;
+mod targets;
+mod webhook;
+
+#[sortable]
+const SUBDIRS: SubdirMap = &sorted!([
+    ("endpoints", &ENDPOINT_ROUTER),
+    ("matcher-fields", &FIELD_ROUTER),
+    ("matcher-field-values", &VALUE_ROUTER),
+    ("matchers", &matchers::ROUTER),
+    ("targets", &targets::ROUTER),
+]);
+
+pub const ROUTER: Router = Router::new()
+    .get(&list_subdirs_api_method!(SUBDIRS))
+    .subdirs(SUBDIRS);
+
+#[sortable]
+const ENDPOINT_SUBDIRS: SubdirMap = &sorted!([
+    ("gotify", &gotify::ROUTER),
+    ("sendmail", &sendmail::ROUTER),
+    ("smtp", &smtp::ROUTER),
+    ("webhook", &webhook::ROUTER),
+]);
+
+const ENDPOINT_ROUTER: Router = Router::new()
+    .get(&list_subdirs_api_method!(ENDPOINT_SUBDIRS))
+    .subdirs(ENDPOINT_SUBDIRS);
+
+/// Values of the `type` metadata field of notifications sent by PDM.
+const NOTIFICATION_TYPES: &[(&str, &str)] = &[
+    ("alert", "Alert rule fired or resolved"),
+    ("metric-collection-failed", "Metric collection failed"),
+    ("package-updates", "Updates available"),
+    ("remote-package-updates", "Updates available on a remote"),
+    ("remote-task-failed", "Remote task failed"),
+    ("subscription-expiring", "Subscription expires soon"),
+];
+
+const FIELD_ROUTER: Router = Router::new().get(&API_METHOD_GET_FIELDS);
+const VALUE_ROUTER: Router = Router::new().get(&API_METHOD_GET_VALUES);
+
+#[api]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// A matchable metadata field.
+pub struct MatchableField {
+    /// Name of the field.
+    name: String,
+}
+
+#[api]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// A matchable metadata field value.
+pub struct MatchableValue {
+    /// Field this value belongs to.
+    field: String,
+    /// Notification metadata value known by the system.
+    value: String,
+    /// Additional comment for this value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+}
+
+#[api(
+    protected: true,
+    input: {
+        properties: {},
+    },
+    returns: {
+        description: "List of known metadata fields.",
+        type: Array,
+        items: { type: MatchableField },
+    },
+    access: {
+        permission: &Permission::Privilege(&["system", "notifications"], PRIV_SYS_AUDIT, false),
+    },
+)]
+/// Get all known metadata fields.
+pub fn get_fields() -> Result<Vec<MatchableField>, Error> {
+    let fields = [
+        "alert-rule",
+        "hostname",
+        "remote",
+        "remote-type",
+        "type",
+        "worker-type",
+    ]
+    .into_iter()
+    .map(|name| MatchableField { name: name.into() })
+    .collect();
+
+    Ok(fields)
+}
+
+#[api(
+    protected: true,
+    input: {
+        properties: {},
+    },
+    returns: {
+        description: "List of known metadata field values.",
+        type: Array,
+        items: { type: MatchableValue },
+    },
+    access: {
+        permission: &Permission::Privilege(&["system", "notifications"], PRIV_SYS_AUDIT, false),
+    },
+)]
+/// List all known, matchable metadata field values.
+pub fn get_values() -> Result<Vec<MatchableValue>, Error> {
+    let mut values = vec![MatchableValue {
+        field: "hostname".into(),
+        value: proxmox_sys::nodename().into(),
+        comment: None,
+    }];
+
+    for (value, comment) in NOTIFICATION_TYPES {
+        values.push(MatchableValue {
+            field: "type".into(),
+            value: value.to_string(),
+            comment: Some(comment.to_string()),
+        });
+    }
+
+    for ty in ["pbs", "pve"] {
+        values.push(MatchableValue {
+            field: "remote-type".into(),
+            value: ty.into(),
+            comment: None,
+        });
+    }
+
+    let (rules, _digest) = pdm_config::alerts::config()?;
+    for rule in rules.keys() {
+        values.push(MatchableValue {
+            field: "alert-rule".into(),
+            value: rule.clone(),
+            comment: None,
+        });
+    }
+
+    let (remotes, _digest) = pdm_config::remotes::config()?;
+    for remote in remotes.keys() {
+        values.push(MatchableValue {
+            field: "remote".into(),
+            value: remote.clone(),
+            comment: None,
+        });
+    }
+
+    Ok(values)
+}
@@ -0,0 +1,68 @@
+use anyhow::Error;
+use serde_json::Value;
+
+use proxmox_notify::api::Target;
+use proxmox_notify::schema::ENTITY_NAME_SCHEMA;
+use proxmox_router::{list_subdirs_api_method, Permission, Router, RpcEnvironment, SubdirMap};
+use proxmox_schema::api;
+use proxmox_sortable_macro::sortable;
+
+use pdm_api_types::{PRIV_SYS_AUDIT, PRIV_SYS_MODIFY};
+
+pub const ROUTER: Router = Router::new()
+    .get(&API_METHOD_LIST_TARGETS)
+    .match_all("name", &ITEM_ROUTER);
+
+#[sortable]
+const ITEM_SUBDIRS: SubdirMap = &sorted!([("test", &TEST_ROUTER)]);
+
+const ITEM_ROUTER: Router = Router::new()
+    .get(&list_subdirs_api_method!(ITEM_SUBDIRS))
+    .subdirs(ITEM_SUBDIRS);
+
+const TEST_ROUTER: Router = Router::new().post(&API_METHOD_TEST_TARGET);
+
+#[api(
+    protected: true,
+    input: {
+        properties: {},
+    },
+    returns: {
+        description: "List of all notification targets.",
+        type: Array,
+        items: { type: Target },
+    },
+    access: {
+        permission: &Permission::Privilege(&["system", "notifications"], PRIV_SYS_AUDIT, false),
+    },
+)]
+/// List all notification targets.
+pub fn list_targets(_param: Value, _rpcenv: &mut dyn RpcEnvironment) -> Result<Vec<Target>, Error> {
+    let config = pdm_config::notifications::config()?;
+
+    let targets = proxmox_notify::api::get_targets(&config)?;
+
+    Ok(targets)
+}
+
+#[api(
+    protected: true,
+    input: {
+        properties: {
+            name: {
+                schema: ENTITY_NAME_SCHEMA,
+            }
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["system", "notifications"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Send a test notification to a target.
+pub fn test_target(name: String) -> Result<(), Error> {
+    let config = pdm_config::notifications::config()?;
+
+    proxmox_notify::api::common::test_target(&config, &name)?;
+
+    Ok(())
+}
@@ -0,0 +1,501 @@
+//! OpenMetrics endpoint exposing the state aggregated from all remotes.
+//!
+//! The endpoint is mounted at `/metrics` by the API daemon, so that it can be scraped by
+//! Prometheus compatible monitoring systems without any further configuration.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+
+use anyhow::Error;
+use http::request::Parts;
+use http::{header, Response, StatusCode};
+use serde_json::Value;
+
+use proxmox_access_control::CachedUserInfo;
+use proxmox_http::Body;
+use proxmox_rest_server::AuthError;
+use proxmox_router::{
+    http_bail, ApiHandler, ApiMethod, ApiResponseFuture, Permission, Router, RpcEnvironment,
+};
+use proxmox_schema::ObjectSchema;
+
+use pdm_api_types::remotes::RemoteType;
+use pdm_api_types::resource::Resource;
+use pdm_api_types::{Authid, TaskStateType, PRIV_RESOURCE_AUDIT};
+
+use crate::remote_tasks::task_cache::GetTasks;
+use crate::{metric_collection, remote_tasks, remote_updates};
+
+/// Content type of the OpenMetrics text format.
+const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+pub const ROUTER: Router = Router::new().get(&API_METHOD_GET_METRICS);
+
+pub const API_METHOD_GET_METRICS: ApiMethod = ApiMethod::new(
+    &ApiHandler::AsyncHttp(&get_metrics),
+    &ObjectSchema::new(
+        "Get the state of all remotes in the OpenMetrics text format.",
+        &[],
+    ),
+)
+.access(
+    Some(
+        "Anyone can access this if 'public-metrics' is set in the node configuration. Otherwise \
+        a valid ticket or API token is required and only remotes on which it has \
+        `Resource.Audit` on `/resource/{remote}` are included.",
+    ),
+    &Permission::World,
+);
+
+fn get_metrics(
+    parts: Parts,
+    _req_body: hyper::body::Incoming,
+    _param: Value,
+    _info: &ApiMethod,
+    _rpcenv: Box<dyn RpcEnvironment>,
+) -> ApiResponseFuture {
+    Box::pin(async move {
+        let (node_config, _) = pdm_config::node::config()?;
+
+        let access = if node_config.public_metrics.unwrap_or(false) {
+            None
+        } else {
+            Some(authenticate(&parts).await?)
+        };
+
+        let (remotes, _) = pdm_config::remotes::config()?;
+        let remotes: HashMap<String, RemoteType> = remotes
+            .into_iter()
+            .filter(|(name, _)| match &access {
+                Some((user_info, auth_id)) => {
+                    user_info.lookup_privs(auth_id, &["resource", name]) & PRIV_RESOURCE_AUDIT != 0
+                }
+                None => true,
+            })
+            .map(|(name, remote)| (name, remote.ty))
+            .collect();
+
+        let remote_names: HashSet<String> = remotes.keys().cloned().collect();
+        let task_counts = tokio::task::spawn_blocking(move || count_tasks(&remote_names)).await??;
+        let updates =
+            tokio::task::spawn_blocking(remote_updates::get_available_updates_summary).await??;
+        let collection_status = metric_collection::get_status()?;
+
+        let mut metrics = OpenMetrics::default();
+
+        let mut remote_names: Vec<&String> = remotes.keys().collect();
+        remote_names.sort();
+
+        for remote in remote_names {
+            let remote_type = remotes[remote];
+
+            if let Some(cached) =
+                crate::api::resources::get_cached_resources(remote, i64::MAX as u64)
+            {
+                metrics.gauge(
+                    "pdm_remote_resources_timestamp_seconds",
+                    "Time the cached resources of the remote were last updated.",
+                    &[("remote", remote)],
+                    cached.timestamp as f64,
+                );
+
+                for resource in &cached.resources {
+                    add_resource_metrics(&mut metrics, remote, remote_type, resource);
+                }
+            }
+
+            if let Some(status) = collection_status.iter().find(|s| &s.remote == remote) {
+                metrics.gauge(
+                    "pdm_remote_metric_collection_error",
+                    "Whether the last metric collection of the remote failed.",
+                    &[("remote", remote)],
+                    status.error.is_some() as u8 as f64,
+                );
+
+                if let Some(last_collection) = status.last_collection {
+                    metrics.gauge(
+                        "pdm_remote_metric_collection_last_success_timestamp_seconds",
+                        "Time of the last successful metric collection of the remote.",
+                        &[("remote", remote)],
+                        last_collection as f64,
+                    );
+                }
+            }
+
+            if let Some(counts) = task_counts.get(remote) {
+                for (state, count) in counts {
+                    metrics.gauge(
+                        "pdm_remote_tasks",
+                        "Number of cached tasks of the remote by state.",
+                        &[("remote", remote), ("state", state)],
+                        *count as f64,
+                    );
+                }
+            }
+
+            if let Some(summary) = updates.remotes.get(remote) {
+                let mut nodes: Vec<_> = summary.nodes.iter().collect();
+                nodes.sort_by(|a, b| a.0.cmp(b.0));
+
+                for (node, node_summary) in nodes {
+                    metrics.gauge(
+                        "pdm_node_updates_available",
+                        "Number of available package updates of the node.",
+                        &[("remote", remote), ("node", node)],
+                        node_summary.number_of_updates as f64,
+                    );
+                    metrics.gauge(
+                        "pdm_node_updates_last_refresh_timestamp_seconds",
+                        "Time the list of available updates of the node was last refreshed.",
+                        &[("remote", remote), ("node", node)],
+                        node_summary.last_refresh as f64,
+                    );
+                }
+            }
+        }
+
+        Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, OPENMETRICS_CONTENT_TYPE)
+            .body(Body::from(metrics.render()))
+            .unwrap())
+    })
+}
+
+/// Authenticate the request with the ticket or API token it carries.
+async fn authenticate(parts: &Parts) -> Result<(CachedUserInfo, Authid), Error> {
+    match crate::auth::check_auth(&parts.headers, &parts.method).await {
+        Ok((auth_id, _)) => Ok((CachedUserInfo::new()?, auth_id.parse()?)),
+        Err(AuthError::NoData) => http_bail!(UNAUTHORIZED, "authentication required"),
+        Err(AuthError::Generic(err)) => {
+            http_bail!(UNAUTHORIZED, "authentication failed - {err}")
+        }
+    }
+}
+
+/// Count the tasks in the task cache per remote and state.
+///
+/// Tasks which did not finish yet are counted as `running`.
+fn count_tasks(
+    remotes: &HashSet<String>,
+) -> Result<HashMap<String, BTreeMap<&'static str, u64>>, Error> {
+    let cache = remote_tasks::get_cache()?.read()?;
+
+    let mut counts: HashMap<String, BTreeMap<&'static str, u64>> = HashMap::new();
+
+    for task in cache.get_tasks(GetTasks::All)? {
+        let remote = task.upid.remote();
+        if !remotes.contains(remote) {
+            continue;
+        }
+
+        let state = match (task.endtime, task.status.as_deref()) {
+            (None, _) => "running",
+            (Some(_), status) => match TaskStateType::new_from_str(status.unwrap_or_default()) {
+                TaskStateType::OK => "ok",
+                TaskStateType::Warning => "warning",
+                TaskStateType::Error => "error",
+                TaskStateType::Unknown => "unknown",
+            },
+        };
+
+        *counts
+            .entry(remote.to_string())
+            .or_default()
+            .entry(state)
+            .or_default() += 1;
+    }
+
+    Ok(counts)
+}
+
+fn add_resource_metrics(
+    metrics: &mut OpenMetrics,
+    remote: &str,
+    remote_type: RemoteType,
+    resource: &Resource,
+) {
+    let id = resource.id();
+    let name = resource.name();
+    let ty = match resource {
+        Resource::PveStorage(_) => "storage",
+        Resource::PveQemu(_) => "qemu",
+        Resource::PveLxc(_) => "lxc",
+        Resource::PveNode(_) | Resource::PbsNode(_) => "node",
+        Resource::PbsDatastore(_) => "datastore",
+        // network resources do not have any usage values
+        Resource::PveNetwork(_) => return,
+    };
+    let remote_type = remote_type.to_string();
+
+    let labels = [
+        ("remote", remote),
+        ("remote_type", remote_type.as_str()),
+        ("id", id.as_str()),
+        ("type", ty),
+        ("name", name),
+    ];
+
+    let (up, cpu, maxcpu, mem, maxmem, disk, maxdisk, uptime) = match resource {
+        Resource::PveQemu(r) => (
+            Some(r.status == "running"),
+            Some(r.cpu),
+            Some(r.maxcpu),
+            Some(r.mem),
+            Some(r.maxmem),
+            Some(r.disk),
+            Some(r.maxdisk),
+            Some(r.uptime),
+        ),
+        Resource::PveLxc(r) => (
+            Some(r.status == "running"),
+            Some(r.cpu),
+            Some(r.maxcpu),
+            Some(r.mem),
+            Some(r.maxmem),
+            Some(r.disk),
+            Some(r.maxdisk),
+            Some(r.uptime),
+        ),
+        Resource::PveNode(r) => (
+            Some(r.status == "online"),
+            Some(r.cpu),
+            Some(r.maxcpu),
+            Some(r.mem),
+            Some(r.maxmem),
+            None,
+            None,
+            Some(r.uptime),
+        ),
+        Resource::PbsNode(r) => (
+            None,
+            Some(r.cpu),
+            Some(r.maxcpu),
+            Some(r.mem),
+            Some(r.maxmem),
+            None,
+            None,
+            Some(r.uptime),
+        ),
+        Resource::PveStorage(r) => (
+            Some(r.status == "available"),
+            None,
+            None,
+            None,
+            None,
+            Some(r.disk),
+            Some(r.maxdisk),
+            None,
+        ),
+        Resource::PbsDatastore(r) => (
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(r.disk),
+            Some(r.maxdisk),
+            None,
+        ),
+        Resource::PveNetwork(_) => return,
+    };
+
+    if let Some(up) = up {
+        metrics.gauge(
+            "pdm_resource_up",
+            "Whether the resource is running, online or available.",
+            &labels,
+            up as u8 as f64,
+        );
+    }
+    if let Some(cpu) = cpu {
+        metrics.gauge(
+            "pdm_resource_cpu_usage_ratio",
+            "CPU usage of the resource in relation to its CPUs.",
+            &labels,
+            cpu,
+        );
+    }
+    if let Some(maxcpu) = maxcpu {
+        metrics.gauge(
+            "pdm_resource_cpus",
+            "Number of CPUs of the resource.",
+            &labels,
+            maxcpu,
+        );
+    }
+    if let Some(mem) = mem {
+        metrics.gauge(
+            "pdm_resource_memory_used_bytes",
+            "Used memory of the resource.",
+            &labels,
+            mem as f64,
+        );
+    }
+    if let Some(maxmem) = maxmem {
+        metrics.gauge(
+            "pdm_resource_memory_total_bytes",
+            "Total memory of the resource.",
+            &labels,
+            maxmem as f64,
+        );
+    }
+    if let Some(disk) = disk {
+        metrics.gauge(
+            "pdm_resource_disk_used_bytes",
+            "Used disk space of the resource.",
+            &labels,
+            disk as f64,
+        );
+    }
+    if let Some(maxdisk) = maxdisk {
+        metrics.gauge(
+            "pdm_resource_disk_total_bytes",
+            "Total disk space of the resource.",
+            &labels,
+            maxdisk as f64,
+        );
+    }
+    if let Some(uptime) = uptime {
+        metrics.gauge(
+            "pdm_resource_uptime_seconds",
+            "Uptime of the resource.",
+            &labels,
+            uptime as f64,
+        );
+    }
+}
+
+/// A single metric family with all its samples.
+struct MetricFamily {
+    help: &'static str,
+    samples: Vec<String>,
+}
+
+/// Collects samples and renders them in the OpenMetrics text format.
+///
+/// The format requires all samples of a metric family to be grouped together, so the samples
+/// are kept per family until the output is rendered.
+#[derive(Default)]
+struct OpenMetrics {
+    families: BTreeMap<&'static str, MetricFamily>,
+}
+
+impl OpenMetrics {
+    /// Add a sample of a gauge.
+    fn gauge(
+        &mut self,
+        name: &'static str,
+        help: &'static str,
+        labels: &[(&str, &str)],
+        value: f64,
+    ) {
+        let mut sample = name.to_string();
+
+        if !labels.is_empty() {
+            sample.push('{');
+            for (i, (label, label_value)) in labels.iter().enumerate() {
+                if i > 0 {
+                    sample.push(',');
+                }
+                let _ = write!(sample, "{label}=\"{}\"", escape_label_value(label_value));
+            }
+            sample.push('}');
+        }
+
+        let _ = write!(sample, " {}", format_value(value));
+
+        self.families
+            .entry(name)
+            .or_insert_with(|| MetricFamily {
+                help,
+                samples: Vec::new(),
+            })
+            .samples
+            .push(sample);
+    }
+
+    /// Render all metric families, terminated by the mandatory `# EOF` line.
+    fn render(&self) -> String {
+        let mut output = String::new();
+
+        for (name, family) in &self.families {
+            let _ = writeln!(output, "# TYPE {name} gauge");
+            let _ = writeln!(output, "# HELP {name} {}", family.help);
+            for sample in &family.samples {
+                output.push_str(sample);
+                output.push('\n');
+            }
+        }
+
+        output.push_str("# EOF\n");
+        output
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_groups_families() {
+        let mut metrics = OpenMetrics::default();
+
+        metrics.gauge("b_metric", "Metric B.", &[("remote", "one")], 1.0);
+        metrics.gauge("a_metric", "Metric A.", &[], 0.5);
+        metrics.gauge("b_metric", "Metric B.", &[("remote", "two")], 2.0);
+
+        assert_eq!(
+            metrics.render(),
+            "# TYPE a_metric gauge\n\
+            # HELP a_metric Metric A.\n\
+            a_metric 0.5\n\
+            # TYPE b_metric gauge\n\
+            # HELP b_metric Metric B.\n\
+            b_metric{remote=\"one\"} 1\n\
+            b_metric{remote=\"two\"} 2\n\
+            # EOF\n"
+        );
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        let mut metrics = OpenMetrics::default();
+
+        metrics.gauge(
+            "metric",
+            "Metric.",
+            &[("name", "a \"quoted\" \\ name\n")],
+            f64::NAN,
+        );
+
+        assert!(metrics
+            .render()
+            .contains("metric{name=\"a \\\"quoted\\\" \\\\ name\\n\"} NaN\n"));
+    }
+}
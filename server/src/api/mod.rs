@@ -9,11 +9,14 @@ use proxmox_schema::api;
 use proxmox_sortable_macro::sortable;
 
 pub mod access;
+pub mod alerts;
 pub mod config;
 pub mod metric_collection;
+pub mod metrics;
 pub mod nodes;
 pub mod pbs;
 pub mod pve;
+pub mod remote_certificates;
 pub mod remote_shell;
 pub mod remote_tasks;
 pub mod remote_updates;
@@ -21,10 +24,12 @@ pub mod remotes;
 pub mod resources;
 mod rrd_common;
 pub mod sdn;
+pub mod subscriptions;
 
 #[sortable]
 const SUBDIRS: SubdirMap = &sorted!([
     ("access", &access::ROUTER),
+    ("alerts", &alerts::ROUTER),
     ("config", &config::ROUTER),
     ("ping", &Router::new().get(&API_METHOD_PING)),
     ("pve", &pve::ROUTER),
@@ -33,6 +38,7 @@ const SUBDIRS: SubdirMap = &sorted!([
     ("resources", &resources::ROUTER),
     ("nodes", &nodes::ROUTER),
     ("sdn", &sdn::ROUTER),
+    ("subscriptions", &subscriptions::ROUTER),
     ("version", &Router::new().get(&API_METHOD_VERSION)),
 ]);
 
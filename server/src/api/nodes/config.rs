@@ -50,6 +50,8 @@ pub enum DeletableProperty {
     CiphersTls1_2,
     /// Delete the default-lang property.
     DefaultLang,
+    /// Delete the public-metrics property.
+    PublicMetrics,
 }
 
 #[api(
@@ -108,6 +110,9 @@ pub fn update_node_config(
                 DeletableProperty::DefaultLang => {
                     config.default_lang = None;
                 }
+                DeletableProperty::PublicMetrics => {
+                    config.public_metrics = None;
+                }
             }
         }
     }
@@ -127,6 +132,9 @@ pub fn update_node_config(
     if update.default_lang.is_some() {
         config.default_lang = update.default_lang;
     }
+    if update.public_metrics.is_some() {
+        config.public_metrics = update.public_metrics;
+    }
 
     pdm_config::node::save_config(&config)?;
 
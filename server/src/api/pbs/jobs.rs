@@ -0,0 +1,295 @@
+//! Garbage collection, prune, sync and verification jobs of PBS datastores.
+
+use anyhow::Error;
+
+use proxmox_router::{http_bail, list_subdirs_api_method, Permission, Router, SubdirMap};
+use proxmox_schema::api;
+use proxmox_sortable_macro::sortable;
+
+use pbs_api_types::{DATASTORE_SCHEMA, JOB_ID_SCHEMA};
+
+use pdm_api_types::remotes::REMOTE_ID_SCHEMA;
+use pdm_api_types::{RemoteUpid, PRIV_RESOURCE_AUDIT, PRIV_RESOURCE_MANAGE};
+
+use crate::pbs_client::{self, VerifyDatastoreParams};
+
+use super::new_remote_upid;
+
+pub const GC_ROUTER: Router = Router::new()
+    .get(&API_METHOD_GET_GC_STATUS)
+    .post(&API_METHOD_START_GARBAGE_COLLECTION);
+
+pub const VERIFY_ROUTER: Router = Router::new().post(&API_METHOD_START_VERIFICATION);
+
+pub const PRUNE_JOBS_ROUTER: Router = Router::new()
+    .get(&API_METHOD_LIST_PRUNE_JOBS)
+    .match_all("id", &PRUNE_JOB_ROUTER);
+
+const PRUNE_JOB_ROUTER: Router = Router::new()
+    .get(&list_subdirs_api_method!(PRUNE_JOB_SUBDIRS))
+    .subdirs(PRUNE_JOB_SUBDIRS);
+#[sortable]
+const PRUNE_JOB_SUBDIRS: SubdirMap =
+    &sorted!([("run", &Router::new().post(&API_METHOD_RUN_PRUNE_JOB)),]);
+
+pub const SYNC_JOBS_ROUTER: Router = Router::new()
+    .get(&API_METHOD_LIST_SYNC_JOBS)
+    .match_all("id", &SYNC_JOB_ROUTER);
+
+const SYNC_JOB_ROUTER: Router = Router::new()
+    .get(&list_subdirs_api_method!(SYNC_JOB_SUBDIRS))
+    .subdirs(SYNC_JOB_SUBDIRS);
+#[sortable]
+const SYNC_JOB_SUBDIRS: SubdirMap =
+    &sorted!([("run", &Router::new().post(&API_METHOD_RUN_SYNC_JOB)),]);
+
+pub const VERIFICATION_JOBS_ROUTER: Router = Router::new()
+    .get(&API_METHOD_LIST_VERIFICATION_JOBS)
+    .match_all("id", &VERIFICATION_JOB_ROUTER);
+
+const VERIFICATION_JOB_ROUTER: Router = Router::new()
+    .get(&list_subdirs_api_method!(VERIFICATION_JOB_SUBDIRS))
+    .subdirs(VERIFICATION_JOB_SUBDIRS);
+#[sortable]
+const VERIFICATION_JOB_SUBDIRS: SubdirMap =
+    &sorted!([("run", &Router::new().post(&API_METHOD_RUN_VERIFICATION_JOB)),]);
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            datastore: { schema: DATASTORE_SCHEMA },
+        },
+    },
+    returns: {
+        type: Array,
+        description: "Garbage collection job status of the datastore.",
+        items: { type: pbs_api_types::GarbageCollectionJobStatus },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}", "datastore", "{datastore}"], PRIV_RESOURCE_AUDIT, false),
+    },
+)]
+/// Get the garbage collection schedule and the status of the last run of a datastore.
+async fn get_gc_status(
+    remote: String,
+    datastore: String,
+) -> Result<Vec<pbs_api_types::GarbageCollectionJobStatus>, Error> {
+    Ok(pbs_client::connect_to_remote_by_id(&remote)?
+        .list_gc_jobs(Some(&datastore))
+        .await?)
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            datastore: { schema: DATASTORE_SCHEMA },
+        },
+    },
+    returns: { type: RemoteUpid },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}", "datastore", "{datastore}"], PRIV_RESOURCE_MANAGE, false),
+    },
+)]
+/// Start a garbage collection on a datastore.
+async fn start_garbage_collection(remote: String, datastore: String) -> Result<RemoteUpid, Error> {
+    let upid = pbs_client::connect_to_remote_by_id(&remote)?
+        .start_garbage_collection(&datastore)
+        .await?;
+
+    new_remote_upid(remote, upid).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            datastore: { schema: DATASTORE_SCHEMA },
+            params: {
+                type: VerifyDatastoreParams,
+                flatten: true,
+            },
+        },
+    },
+    returns: { type: RemoteUpid },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}", "datastore", "{datastore}"], PRIV_RESOURCE_MANAGE, false),
+    },
+)]
+/// Verify the backups of a datastore.
+async fn start_verification(
+    remote: String,
+    datastore: String,
+    params: VerifyDatastoreParams,
+) -> Result<RemoteUpid, Error> {
+    let upid = pbs_client::connect_to_remote_by_id(&remote)?
+        .start_verification(&datastore, params)
+        .await?;
+
+    new_remote_upid(remote, upid).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            datastore: { schema: DATASTORE_SCHEMA },
+        },
+    },
+    returns: {
+        type: Array,
+        description: "Prune jobs of the datastore.",
+        items: { type: pbs_api_types::PruneJobStatus },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}", "datastore", "{datastore}"], PRIV_RESOURCE_AUDIT, false),
+    },
+)]
+/// List the prune jobs of a datastore.
+async fn list_prune_jobs(
+    remote: String,
+    datastore: String,
+) -> Result<Vec<pbs_api_types::PruneJobStatus>, Error> {
+    Ok(pbs_client::connect_to_remote_by_id(&remote)?
+        .list_prune_jobs(Some(&datastore))
+        .await?)
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            datastore: { schema: DATASTORE_SCHEMA },
+            id: { schema: JOB_ID_SCHEMA },
+        },
+    },
+    returns: { type: RemoteUpid },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}", "datastore", "{datastore}"], PRIV_RESOURCE_MANAGE, false),
+    },
+)]
+/// Run a prune job of a datastore now.
+async fn run_prune_job(remote: String, datastore: String, id: String) -> Result<RemoteUpid, Error> {
+    let client = pbs_client::connect_to_remote_by_id(&remote)?;
+
+    // the permission check is done on the datastore, so the job must belong to it
+    let jobs = client.list_prune_jobs(Some(&datastore)).await?;
+    if !jobs.iter().any(|job| job.config.id == id) {
+        http_bail!(NOT_FOUND, "no prune job '{id}' on datastore '{datastore}'");
+    }
+
+    let upid = client.run_prune_job(&id).await?;
+    new_remote_upid(remote, upid).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            datastore: { schema: DATASTORE_SCHEMA },
+        },
+    },
+    returns: {
+        type: Array,
+        description: "Sync jobs of the datastore.",
+        items: { type: pbs_api_types::SyncJobStatus },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}", "datastore", "{datastore}"], PRIV_RESOURCE_AUDIT, false),
+    },
+)]
+/// List the sync jobs of a datastore.
+async fn list_sync_jobs(
+    remote: String,
+    datastore: String,
+) -> Result<Vec<pbs_api_types::SyncJobStatus>, Error> {
+    Ok(pbs_client::connect_to_remote_by_id(&remote)?
+        .list_sync_jobs(Some(&datastore))
+        .await?)
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            datastore: { schema: DATASTORE_SCHEMA },
+            id: { schema: JOB_ID_SCHEMA },
+        },
+    },
+    returns: { type: RemoteUpid },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}", "datastore", "{datastore}"], PRIV_RESOURCE_MANAGE, false),
+    },
+)]
+/// Run a sync job of a datastore now.
+async fn run_sync_job(remote: String, datastore: String, id: String) -> Result<RemoteUpid, Error> {
+    let client = pbs_client::connect_to_remote_by_id(&remote)?;
+
+    let jobs = client.list_sync_jobs(Some(&datastore)).await?;
+    if !jobs.iter().any(|job| job.config.id == id) {
+        http_bail!(NOT_FOUND, "no sync job '{id}' on datastore '{datastore}'");
+    }
+
+    let upid = client.run_sync_job(&id).await?;
+    new_remote_upid(remote, upid).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            datastore: { schema: DATASTORE_SCHEMA },
+        },
+    },
+    returns: {
+        type: Array,
+        description: "Verification jobs of the datastore.",
+        items: { type: pbs_api_types::VerificationJobStatus },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}", "datastore", "{datastore}"], PRIV_RESOURCE_AUDIT, false),
+    },
+)]
+/// List the verification jobs of a datastore.
+async fn list_verification_jobs(
+    remote: String,
+    datastore: String,
+) -> Result<Vec<pbs_api_types::VerificationJobStatus>, Error> {
+    Ok(pbs_client::connect_to_remote_by_id(&remote)?
+        .list_verification_jobs(Some(&datastore))
+        .await?)
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            datastore: { schema: DATASTORE_SCHEMA },
+            id: { schema: JOB_ID_SCHEMA },
+        },
+    },
+    returns: { type: RemoteUpid },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}", "datastore", "{datastore}"], PRIV_RESOURCE_MANAGE, false),
+    },
+)]
+/// Run a verification job of a datastore now.
+async fn run_verification_job(
+    remote: String,
+    datastore: String,
+    id: String,
+) -> Result<RemoteUpid, Error> {
+    let client = pbs_client::connect_to_remote_by_id(&remote)?;
+
+    let jobs = client.list_verification_jobs(Some(&datastore)).await?;
+    if !jobs.iter().any(|job| job.config.id == id) {
+        http_bail!(
+            NOT_FOUND,
+            "no verification job '{id}' on datastore '{datastore}'"
+        );
+    }
+
+    let upid = client.run_verification_job(&id).await?;
+    new_remote_upid(remote, upid).await
+}
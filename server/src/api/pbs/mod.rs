@@ -20,6 +20,7 @@ use crate::{
 
 use crate::remote_tasks;
 
+mod jobs;
 mod node;
 mod rrddata;
 pub mod tasks;
@@ -68,12 +69,17 @@ const DATASTORE_ITEM_ROUTER: Router = Router::new()
 
 #[sortable]
 const DATASTORE_ITEM_SUBDIRS: SubdirMap = &sorted!([
+    ("gc", &jobs::GC_ROUTER),
+    ("prune-jobs", &jobs::PRUNE_JOBS_ROUTER),
     ("rrddata", &rrddata::PBS_DATASTORE_RRD_ROUTER),
     (
         "namespaces",
         &Router::new().get(&API_METHOD_LIST_NAMESPACES)
     ),
     ("snapshots", &Router::new().get(&API_METHOD_LIST_SNAPSHOTS)),
+    ("sync-jobs", &jobs::SYNC_JOBS_ROUTER),
+    ("verification-jobs", &jobs::VERIFICATION_JOBS_ROUTER),
+    ("verify", &jobs::VERIFY_ROUTER),
 ]);
 
 // converts a remote + pbs_api_types::UPID into a RemoteUpid and starts tracking it
@@ -96,10 +102,11 @@ pub async fn new_remote_upid(
 )]
 /// Return the list of PBS remotes
 fn list_remotes() -> Result<Vec<RemoteListEntry>, Error> {
+    let now = proxmox_time::epoch_i64();
     Ok(super::remotes::RemoteIterator::new()?
         .remote_type(RemoteType::Pbs)
-        .into_names()
-        .map(|name| RemoteListEntry { remote: name })
+        .into_remotes()
+        .map(|remote| RemoteListEntry::new(&remote, now))
         .collect())
 }
 
@@ -275,6 +282,16 @@ pub async fn scan_remote_pbs(
         authid: authid.clone(),
         token,
         web_url: None,
+        token_created: None,
+        token_expire: None,
+        sync_nodes: None,
+        http_proxy: None,
+        connect_timeout: None,
+        request_timeout: None,
+        max_connections: None,
+        maintenance: None,
+        maintenance_nodes: None,
+        maintenance_end: None,
     };
 
     let _client = connect_or_login(&remote)
@@ -327,6 +344,16 @@ pub async fn list_realm_remote_pbs(
         authid: "root@pam".parse()?,
         token: String::new(),
         web_url: None,
+        token_created: None,
+        token_expire: None,
+        sync_nodes: None,
+        http_proxy: None,
+        connect_timeout: None,
+        request_timeout: None,
+        max_connections: None,
+        maintenance: None,
+        maintenance_nodes: None,
+        maintenance_end: None,
     };
 
     let client = connection::make_pbs_client(&remote)?;
@@ -0,0 +1,474 @@
+//! Run an action on many guests across all PVE remotes at once.
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Context, Error};
+
+use proxmox_access_control::CachedUserInfo;
+use proxmox_rest_server::WorkerTask;
+use proxmox_router::{Permission, Router, RpcEnvironment};
+use proxmox_schema::{api, param_bail};
+
+use pdm_api_types::bulk_action::{BulkActionGuest, BulkActionResult, GuestAction};
+use pdm_api_types::remotes::{Remote, RemoteType};
+use pdm_api_types::resource::{GuestType, Resource};
+use pdm_api_types::{
+    Authid, NODE_SCHEMA, PRIV_RESOURCE_MANAGE, PRIV_RESOURCE_MIGRATE, VIEW_ID_SCHEMA,
+};
+
+use crate::api::resources::{get_resources_impl, RemoteWithResources};
+use crate::connection;
+use crate::parallel_fetcher::ParallelFetcher;
+
+use super::{check_guest_permissions, new_remote_upid, wait_for_remote_task};
+
+pub const ROUTER: Router = Router::new().post(&API_METHOD_BULK_GUEST_ACTION);
+
+/// Maximum age of the cached resources used to select the guests.
+const RESOURCE_MAX_AGE: u64 = 10;
+
+/// A guest selected for a bulk action.
+struct PlannedGuest {
+    remote: Remote,
+    vmid: u32,
+    guest_type: GuestType,
+    running: bool,
+    guest: BulkActionGuest,
+}
+
+#[api(
+    input: {
+        properties: {
+            action: {
+                type: GuestAction,
+            },
+            search: {
+                description: "Search term to select the guests, uses the same syntax as the resource list.",
+                optional: true,
+            },
+            view: {
+                schema: VIEW_ID_SCHEMA,
+                optional: true,
+            },
+            target: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            "dry-run": {
+                description: "Only list the guests which would be affected.",
+                optional: true,
+                default: false,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Anybody,
+        description: "The action is only run on guests on which the user has `Resource.Manage` \
+            (`Resource.Migrate` for migrations) on `/resource/{remote}/guest/{vmid}`.",
+    },
+    returns: { type: BulkActionResult },
+)]
+/// Run an action on all guests matching a search or view, across all PVE remotes.
+///
+/// Guests for which the action does not make sense, for example starting an already running
+/// guest, as well as templates are skipped.
+///
+/// `target` can only be set for migrations. If it is not set, each guest is migrated to the
+/// online node with the lowest memory usage of its cluster.
+pub async fn bulk_guest_action(
+    action: GuestAction,
+    search: Option<String>,
+    view: Option<String>,
+    target: Option<String>,
+    dry_run: bool,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<BulkActionResult, Error> {
+    let auth_id: Authid = rpcenv
+        .get_auth_id()
+        .context("no authid available")?
+        .parse()?;
+
+    if search.is_none() && view.is_none() {
+        param_bail!(
+            "search",
+            "either a search or a view is required to select guests"
+        );
+    }
+
+    if target.is_some() && action != GuestAction::Migrate {
+        param_bail!("target", "a target can only be set for migrations");
+    }
+
+    let remotes = get_resources_impl(
+        RESOURCE_MAX_AGE,
+        search,
+        None,
+        view.as_deref(),
+        Some(rpcenv),
+    )
+    .await?;
+
+    let user_info = CachedUserInfo::new()?;
+    let guests = plan_guests(remotes, action, target.as_deref(), |remote, vmid| {
+        let privilege = match action {
+            GuestAction::Migrate => PRIV_RESOURCE_MIGRATE,
+            _ => PRIV_RESOURCE_MANAGE,
+        };
+        check_guest_permissions(&auth_id, &user_info, remote, privilege, vmid)
+    });
+
+    let result_guests = guests.iter().map(|planned| planned.guest.clone()).collect();
+
+    if dry_run || guests.is_empty() {
+        return Ok(BulkActionResult {
+            guests: result_guests,
+            upid: None,
+        });
+    }
+
+    let upid = WorkerTask::spawn(
+        "bulk-guest-action",
+        Some(action.to_string()),
+        auth_id.to_string(),
+        true,
+        move |_worker| async move { run_bulk_action(action, guests).await },
+    )?;
+
+    Ok(BulkActionResult {
+        guests: result_guests,
+        upid: Some(upid),
+    })
+}
+
+/// Run an action on all guests matching a search, as done by scheduled jobs.
+///
+/// No permissions are checked here, they need to be checked when the job is configured. If
+/// `remotes` is set, only guests on these remotes are considered.
+pub(crate) async fn run_scheduled_action(
+    action: GuestAction,
+    search: String,
+    remotes: Option<&[String]>,
+) -> Result<(), Error> {
+    let mut resources =
+        get_resources_impl(RESOURCE_MAX_AGE, Some(search), None, None, None).await?;
+
+    if let Some(remotes) = remotes {
+        resources.retain(|remote| remotes.contains(&remote.remote_name));
+    }
+
+    let guests = plan_guests(resources, action, None, |_, _| true);
+
+    if guests.is_empty() {
+        log::info!("no guests to {action}");
+        return Ok(());
+    }
+
+    run_bulk_action(action, guests).await
+}
+
+/// Select the guests the action should be run on.
+fn plan_guests(
+    remotes: Vec<RemoteWithResources>,
+    action: GuestAction,
+    target: Option<&str>,
+    check_privs: impl Fn(&str, u32) -> bool,
+) -> Vec<PlannedGuest> {
+    let mut guests = Vec::new();
+
+    for remote in remotes {
+        if remote.remote.ty != RemoteType::Pve {
+            continue;
+        }
+
+        let mut node_memory: HashMap<String, (u64, u64)> = remote
+            .resources
+            .iter()
+            .filter_map(|resource| match resource {
+                Resource::PveNode(node) if node.status == "online" => {
+                    Some((node.node.clone(), (node.mem, node.maxmem)))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let now = proxmox_time::epoch_i64();
+        let maintenance = nodes_in_maintenance(&remote.remote, now);
+
+        for resource in &remote.resources {
+            let (guest_type, vmid, name, node, status, template, maxmem) = match resource {
+                Resource::PveQemu(qemu) => (
+                    GuestType::Qemu,
+                    qemu.vmid,
+                    &qemu.name,
+                    &qemu.node,
+                    &qemu.status,
+                    qemu.template,
+                    qemu.maxmem,
+                ),
+                Resource::PveLxc(lxc) => (
+                    GuestType::Lxc,
+                    lxc.vmid,
+                    &lxc.name,
+                    &lxc.node,
+                    &lxc.status,
+                    lxc.template,
+                    lxc.maxmem,
+                ),
+                _ => continue,
+            };
+
+            if template || !check_privs(&remote.remote_name, vmid) {
+                continue;
+            }
+
+            let running = status == "running";
+
+            let target = match action {
+                GuestAction::Start if running => continue,
+                GuestAction::Stop | GuestAction::Shutdown if !running => continue,
+                GuestAction::Migrate => match target {
+                    Some(target)
+                        if target == node
+                            || !node_memory.contains_key(target)
+                            || maintenance.contains(&target) =>
+                    {
+                        continue
+                    }
+                    Some(target) => Some(target.to_string()),
+                    None => {
+                        match pick_migration_target(&mut node_memory, node, maxmem, &maintenance) {
+                            Some(target) => Some(target),
+                            None => continue,
+                        }
+                    }
+                },
+                _ => None,
+            };
+
+            guests.push(PlannedGuest {
+                remote: remote.remote.clone(),
+                vmid,
+                guest_type,
+                running,
+                guest: BulkActionGuest {
+                    remote: remote.remote_name.clone(),
+                    id: resource.id(),
+                    name: name.clone(),
+                    node: node.clone(),
+                    target,
+                },
+            });
+        }
+    }
+
+    guests
+}
+
+/// The cluster nodes of a remote which are in maintenance mode at `now`.
+pub(super) fn nodes_in_maintenance(remote: &Remote, now: i64) -> Vec<&str> {
+    remote
+        .nodes_in_maintenance()
+        .filter(|node| remote.node_in_maintenance(node, now))
+        .collect()
+}
+
+/// Pick the node with the lowest memory usage, other than `source` and the nodes in
+/// `maintenance`.
+///
+/// The memory of the guest is accounted to the chosen node, so that migrating many guests
+/// spreads them over all nodes instead of moving all of them to the same one.
+pub(super) fn pick_migration_target(
+    node_memory: &mut HashMap<String, (u64, u64)>,
+    source: &str,
+    guest_memory: u64,
+    maintenance: &[&str],
+) -> Option<String> {
+    let (node, (mem, _)) = node_memory
+        .iter_mut()
+        .filter(|(node, (_, maxmem))| {
+            node.as_str() != source && *maxmem > 0 && !maintenance.contains(&node.as_str())
+        })
+        .min_by(|(a_node, (a_mem, a_max)), (b_node, (b_mem, b_max))| {
+            let a = *a_mem as f64 / *a_max as f64;
+            let b = *b_mem as f64 / *b_max as f64;
+            a.total_cmp(&b).then_with(|| a_node.cmp(b_node))
+        })?;
+
+    *mem += guest_memory;
+
+    Some(node.clone())
+}
+
+async fn run_bulk_action(action: GuestAction, guests: Vec<PlannedGuest>) -> Result<(), Error> {
+    let total = guests.len();
+    log::info!("running {action} on {total} guests");
+
+    let results = ParallelFetcher::new(action)
+        .do_for_all_remote_items(
+            guests
+                .into_iter()
+                .map(|planned| (planned.remote.clone(), planned)),
+            run_planned_action,
+        )
+        .await;
+
+    let failed = results.iter().filter(|result| result.is_err()).count();
+
+    if failed > 0 {
+        bail!("{action} failed for {failed} of {total} guests");
+    }
+
+    Ok(())
+}
+
+/// Run the action on a planned guest and log the outcome.
+async fn run_planned_action(
+    action: GuestAction,
+    _remote: Remote,
+    planned: PlannedGuest,
+) -> Result<(), Error> {
+    let label = format!(
+        "{}/{} ({})",
+        planned.guest.remote, planned.guest.id, planned.guest.name
+    );
+
+    match run_guest_action(action, &planned, &label).await {
+        Ok(()) => {
+            log::info!("{label}: {action} finished successfully");
+            Ok(())
+        }
+        Err(err) => {
+            log::error!("{label}: {action} failed - {err:#}");
+            Err(err)
+        }
+    }
+}
+
+/// Run the action on a single guest and wait for the resulting task to finish.
+async fn run_guest_action(
+    action: GuestAction,
+    planned: &PlannedGuest,
+    label: &str,
+) -> Result<(), Error> {
+    let client = connection::make_pve_client(&planned.remote)?;
+    let node = planned.guest.node.as_str();
+    let vmid = planned.vmid;
+
+    let upid = match (action, planned.guest_type) {
+        (GuestAction::Start, GuestType::Qemu) => {
+            client
+                .start_qemu_async(node, vmid, Default::default())
+                .await?
+        }
+        (GuestAction::Start, GuestType::Lxc) => {
+            client
+                .start_lxc_async(node, vmid, Default::default())
+                .await?
+        }
+        (GuestAction::Stop, GuestType::Qemu) => {
+            client
+                .stop_qemu_async(node, vmid, Default::default())
+                .await?
+        }
+        (GuestAction::Stop, GuestType::Lxc) => {
+            client
+                .stop_lxc_async(node, vmid, Default::default())
+                .await?
+        }
+        (GuestAction::Shutdown, GuestType::Qemu) => {
+            client
+                .shutdown_qemu_async(node, vmid, Default::default())
+                .await?
+        }
+        (GuestAction::Shutdown, GuestType::Lxc) => {
+            client
+                .shutdown_lxc_async(node, vmid, Default::default())
+                .await?
+        }
+        (GuestAction::Migrate, guest_type) => {
+            let target = planned
+                .guest
+                .target
+                .clone()
+                .context("no migration target")?;
+
+            log::info!("{label}: migrating from node {node} to node {target}");
+
+            match guest_type {
+                GuestType::Qemu => {
+                    let params = pve_api_types::MigrateQemu {
+                        bwlimit: None,
+                        force: None,
+                        migration_network: None,
+                        migration_type: None,
+                        online: Some(planned.running),
+                        target,
+                        targetstorage: None,
+                        with_local_disks: None,
+                        with_conntrack_state: None,
+                    };
+                    client.migrate_qemu(node, vmid, params).await?
+                }
+                GuestType::Lxc => {
+                    let params = pve_api_types::MigrateLxc {
+                        bwlimit: None,
+                        online: None,
+                        restart: Some(planned.running),
+                        target,
+                        target_storage: None,
+                        timeout: None,
+                    };
+                    client.migrate_lxc(node, vmid, params).await?
+                }
+            }
+        }
+    };
+
+    let upid = new_remote_upid(planned.remote.id.clone(), upid).await?;
+    log::info!("{label}: started task {upid}");
+
+    wait_for_remote_task(&client, node, &upid).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migration_targets_are_spread() {
+        let mut nodes = HashMap::from([
+            ("pve1".to_string(), (4, 16)),
+            ("pve2".to_string(), (8, 16)),
+            ("pve3".to_string(), (2, 16)),
+        ]);
+
+        // never migrate to the source node
+        assert_eq!(
+            pick_migration_target(&mut nodes, "pve3", 4, &[]).as_deref(),
+            Some("pve1")
+        );
+        // pve1 is now at 8/16, so pve3 has the lowest usage
+        assert_eq!(
+            pick_migration_target(&mut nodes, "pve2", 4, &[]).as_deref(),
+            Some("pve3")
+        );
+        // pve1 and pve3 are at 8/16 and 6/16 now
+        assert_eq!(
+            pick_migration_target(&mut nodes, "pve1", 4, &[]).as_deref(),
+            Some("pve3")
+        );
+
+        let mut single = HashMap::from([("pve1".to_string(), (4, 16))]);
+        assert_eq!(pick_migration_target(&mut single, "pve1", 4, &[]), None);
+
+        // nodes in maintenance are never picked
+        assert_eq!(
+            pick_migration_target(&mut nodes, "pve1", 4, &["pve3"]).as_deref(),
+            Some("pve2")
+        );
+        assert_eq!(
+            pick_migration_target(&mut nodes, "pve1", 4, &["pve2", "pve3"]),
+            None
+        );
+    }
+}
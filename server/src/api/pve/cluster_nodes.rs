@@ -0,0 +1,332 @@
+//! Keep the node list of PVE remotes in sync with the cluster membership.
+//!
+//! The remote node mapping task records the cluster members reported by a quorate node in the
+//! [`RemoteMappingCache`]. Nodes which joined the cluster, but are missing in the node list of the
+//! remote, and configured nodes which left the cluster are offered to be added or removed. With
+//! `sync-nodes` enabled on the remote, this is done automatically.
+
+use std::net::IpAddr;
+
+use anyhow::{bail, format_err, Error};
+
+use proxmox_router::{Permission, Router};
+use proxmox_schema::api;
+use proxmox_schema::property_string::PropertyString;
+
+use pdm_api_types::remotes::{
+    NodeMembershipChange, NodeMembershipEntry, NodeUrl, Remote, RemoteType, TlsProbeOutcome,
+    REMOTE_ID_SCHEMA,
+};
+use pdm_api_types::{PRIV_RESOURCE_AUDIT, PRIV_RESOURCE_MODIFY};
+
+use crate::connection::probe_tls_connection;
+use crate::remote_cache::{RemoteMapping, RemoteMappingCache};
+
+use super::{connect, get_remote};
+
+pub const ROUTER: Router = Router::new()
+    .get(&API_METHOD_LIST_CLUSTER_NODE_CHANGES)
+    .post(&API_METHOD_SYNC_CLUSTER_NODES);
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+        },
+    },
+    returns: {
+        type: Array,
+        description: "Nodes which joined or left the cluster.",
+        items: { type: NodeMembershipEntry },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_RESOURCE_AUDIT, false),
+    },
+)]
+/// List the nodes which joined or left the cluster, but are not yet reflected in the node list of
+/// the remote.
+pub fn list_cluster_node_changes(remote: String) -> Result<Vec<NodeMembershipEntry>, Error> {
+    let (remotes, _) = pdm_config::remotes::config()?;
+    let remote = get_remote(&remotes, &remote)?;
+
+    Ok(pending_changes(remote))
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+        },
+    },
+    returns: {
+        type: Array,
+        description: "The applied changes.",
+        items: { type: NodeMembershipEntry },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_RESOURCE_MODIFY, false),
+    },
+)]
+/// Add the nodes which joined and remove the nodes which left the cluster from the node list of
+/// the remote.
+pub async fn sync_cluster_nodes(remote: String) -> Result<Vec<NodeMembershipEntry>, Error> {
+    let (remotes, _) = pdm_config::remotes::config()?;
+    let config = get_remote(&remotes, &remote)?;
+
+    let changes = pending_changes(config);
+    if changes.is_empty() {
+        return Ok(changes);
+    }
+
+    let mut applied = Vec::new();
+    let mut new_nodes = Vec::new();
+
+    if changes
+        .iter()
+        .any(|change| change.change == NodeMembershipChange::Joined)
+    {
+        let cluster_nodes = connect(config)?.list_nodes().await?;
+
+        for change in &changes {
+            if change.change != NodeMembershipChange::Joined {
+                continue;
+            }
+
+            let expected = cluster_nodes
+                .iter()
+                .find(|node| node.node == change.node)
+                .and_then(|node| node.ssl_fingerprint.as_deref());
+
+            match verified_fingerprint(&change.hostname, expected).await {
+                Ok(fingerprint) => {
+                    new_nodes.push(PropertyString::new(NodeUrl {
+                        hostname: change.hostname.clone(),
+                        fingerprint,
+                    }));
+                    applied.push(change.clone());
+                }
+                Err(err) => {
+                    log::warn!(
+                        "not adding node {:?} to remote {remote:?} - {err}",
+                        change.node
+                    );
+                }
+            }
+        }
+    }
+
+    let _lock = pdm_config::remotes::lock_config()?;
+    let (mut remotes, _) = pdm_config::remotes::config()?;
+    let entry = remotes
+        .get_mut(&remote)
+        .ok_or_else(|| format_err!("no such remote {remote:?}"))?;
+
+    for node in new_nodes {
+        if !entry.nodes.iter().any(|n| n.hostname == node.hostname) {
+            entry.nodes.push(node);
+        }
+    }
+
+    for change in &changes {
+        if change.change != NodeMembershipChange::Left {
+            continue;
+        }
+
+        if entry.nodes.len() <= 1 {
+            log::warn!(
+                "not removing node {:?} from remote {remote:?} - it is the last node",
+                change.node
+            );
+            continue;
+        }
+
+        entry.nodes.retain(|node| node.hostname != change.hostname);
+        applied.push(change.clone());
+    }
+
+    if !applied.is_empty() {
+        pdm_config::remotes::save_config(remotes)?;
+    }
+
+    Ok(applied)
+}
+
+/// Probe the TLS certificate of a new node and check it against the fingerprint reported by the
+/// cluster. Returns the fingerprint to store, if the certificate is not trusted anyway.
+async fn verified_fingerprint(
+    hostname: &str,
+    expected: Option<&str>,
+) -> Result<Option<String>, Error> {
+    match probe_tls_connection(RemoteType::Pve, hostname.to_string(), None).await? {
+        TlsProbeOutcome::TrustedCertificate => Ok(None),
+        TlsProbeOutcome::UntrustedCertificate(cert) => match (cert.fingerprint, expected) {
+            (Some(fingerprint), Some(expected)) if fingerprint.eq_ignore_ascii_case(expected) => {
+                Ok(Some(fingerprint))
+            }
+            _ => bail!("certificate does not match the fingerprint reported by the cluster"),
+        },
+    }
+}
+
+/// The membership changes of a remote, based on the current remote mapping cache.
+fn pending_changes(remote: &Remote) -> Vec<NodeMembershipEntry> {
+    let cache = RemoteMappingCache::get();
+    match cache.remotes.get(&remote.id) {
+        Some(mapping) => membership_changes(remote, mapping),
+        None => Vec::new(),
+    }
+}
+
+/// Compare the node list of a remote to the cluster members last seen.
+///
+/// Configured nodes whose name is not known yet are never considered to have left.
+fn membership_changes(remote: &Remote, mapping: &RemoteMapping) -> Vec<NodeMembershipEntry> {
+    let Some(members) = &mapping.cluster_members else {
+        return Vec::new();
+    };
+
+    let mut changes = Vec::new();
+
+    for member in members {
+        let hostname = match member.ip.as_deref().map(str::parse::<IpAddr>) {
+            Some(Ok(IpAddr::V6(ip))) => format!("[{ip}]"),
+            Some(Ok(IpAddr::V4(ip))) => ip.to_string(),
+            _ => member.name.clone(),
+        };
+
+        let configured = mapping.node_to_host.contains_key(&member.name)
+            || remote.nodes.iter().any(|node| node.hostname == hostname);
+
+        if !configured {
+            changes.push(NodeMembershipEntry {
+                change: NodeMembershipChange::Joined,
+                node: member.name.clone(),
+                hostname,
+            });
+        }
+    }
+
+    for node in &remote.nodes {
+        let Some(name) = mapping
+            .hosts
+            .get(&node.hostname)
+            .and_then(|info| info.node_name())
+        else {
+            continue;
+        };
+
+        if !members.iter().any(|member| member.name == name) {
+            changes.push(NodeMembershipEntry {
+                change: NodeMembershipChange::Left,
+                node: name.to_string(),
+                hostname: node.hostname.clone(),
+            });
+        }
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use proxmox_schema::property_string::PropertyString;
+
+    use pdm_api_types::remotes::{
+        NodeMembershipChange, NodeMembershipEntry, NodeUrl, Remote, RemoteType,
+    };
+
+    use crate::remote_cache::{ClusterMember, HostInfo, RemoteMapping};
+
+    use super::membership_changes;
+
+    fn remote(hosts: &[&str]) -> Remote {
+        Remote {
+            ty: RemoteType::Pve,
+            id: "pve".to_string(),
+            nodes: hosts
+                .iter()
+                .map(|host| {
+                    PropertyString::new(NodeUrl {
+                        hostname: host.to_string(),
+                        fingerprint: None,
+                    })
+                })
+                .collect(),
+            authid: "root@pam".parse().unwrap(),
+            token: String::new(),
+            web_url: None,
+            token_created: None,
+            token_expire: None,
+            sync_nodes: None,
+            http_proxy: None,
+            connect_timeout: None,
+            request_timeout: None,
+            max_connections: None,
+            maintenance: None,
+            maintenance_nodes: None,
+            maintenance_end: None,
+        }
+    }
+
+    fn mapping(hosts: &[(&str, Option<&str>)], members: &[(&str, &str)]) -> RemoteMapping {
+        let mut mapping = RemoteMapping::new(RemoteType::Pve);
+        for (host, name) in hosts {
+            mapping
+                .hosts
+                .insert(host.to_string(), HostInfo::new(host.to_string()));
+            mapping.set_node_name(host, name.map(str::to_string));
+        }
+        mapping.cluster_members = Some(
+            members
+                .iter()
+                .map(|(name, ip)| ClusterMember {
+                    name: name.to_string(),
+                    ip: Some(ip.to_string()),
+                })
+                .collect(),
+        );
+        mapping
+    }
+
+    #[test]
+    fn joined_and_left_nodes() {
+        let remote = remote(&["10.0.0.1", "10.0.0.2", "10.0.0.3"]);
+        let mapping = mapping(
+            &[
+                ("10.0.0.1", Some("pve1")),
+                ("10.0.0.2", Some("pve2")),
+                ("10.0.0.3", None),
+            ],
+            &[
+                ("pve1", "10.0.0.1"),
+                ("pve3", "10.0.0.3"),
+                ("pve4", "fd00::4"),
+            ],
+        );
+
+        assert_eq!(
+            membership_changes(&remote, &mapping),
+            vec![
+                NodeMembershipEntry {
+                    change: NodeMembershipChange::Joined,
+                    node: "pve4".to_string(),
+                    hostname: "[fd00::4]".to_string(),
+                },
+                NodeMembershipEntry {
+                    change: NodeMembershipChange::Left,
+                    node: "pve2".to_string(),
+                    hostname: "10.0.0.2".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_membership() {
+        let remote = remote(&["10.0.0.1"]);
+        let mut mapping = mapping(&[("10.0.0.1", Some("pve1"))], &[]);
+        mapping.cluster_members = None;
+
+        assert!(membership_changes(&remote, &mapping).is_empty());
+    }
+}
@@ -16,7 +16,7 @@ use pdm_api_types::remotes::{Remote, REMOTE_ID_SCHEMA};
 use pdm_api_types::{NODE_SCHEMA, VMID_SCHEMA};
 use pdm_api_types::{PRIV_RESOURCE_AUDIT, PRIV_RESOURCE_MODIFY, PRIV_SYS_MODIFY};
 
-use super::{connect_to_remote_by_id, find_node_for_vm};
+use super::{connect_to_remote_by_id, find_node_for_vm, firewall_config, firewall_drift};
 use crate::connection::PveClient;
 use crate::parallel_fetcher::ParallelFetcher;
 
@@ -42,12 +42,20 @@ pub const QEMU_FW_ROUTER: Router = Router::new()
 
 // pve
 #[sortable]
-const PVE_FW_SUBDIRS: SubdirMap = &sorted!([("status", &PVE_STATUS_ROUTER),]);
+const PVE_FW_SUBDIRS: SubdirMap = &sorted!([
+    ("baseline", &firewall_drift::BASELINE_ROUTER),
+    ("drift", &firewall_drift::DRIFT_ROUTER),
+    ("status", &PVE_STATUS_ROUTER),
+]);
 
 // cluster
 #[sortable]
 const CLUSTER_FW_SUBDIRS: SubdirMap = &sorted!([
+    ("aliases", &firewall_config::ALIASES_ROUTER),
+    ("groups", &firewall_config::GROUPS_ROUTER),
+    ("ipset", &firewall_config::IPSETS_ROUTER),
     ("options", &CLUSTER_OPTIONS_ROUTER),
+    ("push", &firewall_config::PUSH_ROUTER),
     ("rules", &CLUSTER_RULES_ROUTER),
     ("status", &CLUSTER_STATUS_ROUTER),
 ]);
@@ -89,10 +97,22 @@ const QEMU_OPTIONS_ROUTER: Router = Router::new()
     .put(&API_METHOD_UPDATE_QEMU_FIREWALL_OPTIONS);
 
 // /rules
-const CLUSTER_RULES_ROUTER: Router = Router::new().get(&API_METHOD_CLUSTER_FIREWALL_RULES);
-const NODE_RULES_ROUTER: Router = Router::new().get(&API_METHOD_NODE_FIREWALL_RULES);
-const LXC_RULES_ROUTER: Router = Router::new().get(&API_METHOD_LXC_FIREWALL_RULES);
-const QEMU_RULES_ROUTER: Router = Router::new().get(&API_METHOD_QEMU_FIREWALL_RULES);
+const CLUSTER_RULES_ROUTER: Router = Router::new()
+    .get(&API_METHOD_CLUSTER_FIREWALL_RULES)
+    .post(&firewall_config::API_METHOD_CREATE_CLUSTER_FIREWALL_RULE)
+    .match_all("pos", &firewall_config::CLUSTER_RULE_ROUTER);
+const NODE_RULES_ROUTER: Router = Router::new()
+    .get(&API_METHOD_NODE_FIREWALL_RULES)
+    .post(&firewall_config::API_METHOD_CREATE_NODE_FIREWALL_RULE)
+    .match_all("pos", &firewall_config::NODE_RULE_ROUTER);
+const LXC_RULES_ROUTER: Router = Router::new()
+    .get(&API_METHOD_LXC_FIREWALL_RULES)
+    .post(&firewall_config::API_METHOD_CREATE_LXC_FIREWALL_RULE)
+    .match_all("pos", &firewall_config::LXC_RULE_ROUTER);
+const QEMU_RULES_ROUTER: Router = Router::new()
+    .get(&API_METHOD_QEMU_FIREWALL_RULES)
+    .post(&firewall_config::API_METHOD_CREATE_QEMU_FIREWALL_RULE)
+    .match_all("pos", &firewall_config::QEMU_RULE_ROUTER);
 
 // /status
 const PVE_STATUS_ROUTER: Router = Router::new().get(&API_METHOD_PVE_FIREWALL_STATUS);
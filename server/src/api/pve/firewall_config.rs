@@ -0,0 +1,1788 @@
+//! Editing of firewall rules, aliases, IP sets and security groups of PVE remotes.
+//!
+//! The corresponding PVE API endpoints are not part of the generated client, so the requests are
+//! issued via the raw client. All modifications accept the `digest` of the object they are based
+//! on, which makes PVE reject them if the object was changed in the meantime.
+
+use std::collections::HashMap;
+
+use anyhow::{bail, format_err, Context, Error};
+use http::Method;
+use percent_encoding::{percent_encode, NON_ALPHANUMERIC};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+use proxmox_access_control::CachedUserInfo;
+use proxmox_client::{ApiPathBuilder, Client, HttpApiClient};
+use proxmox_rest_server::WorkerTask;
+use proxmox_router::{http_bail, Permission, Router, RpcEnvironment};
+use proxmox_schema::{api, param_bail};
+
+use pdm_api_types::firewall::{
+    ClusterFirewallConfig, FirewallAlias, FirewallIpSet, FirewallIpSetConfig, FirewallIpSetEntry,
+    FirewallOption, FirewallPushKind, FirewallRuleParams, FirewallSecurityGroup,
+    FirewallSecurityGroupConfig, GuestKind, FIREWALL_ALIAS_NAME_SCHEMA, FIREWALL_CIDR_SCHEMA,
+    FIREWALL_GROUP_NAME_SCHEMA, FIREWALL_IPSET_NAME_SCHEMA, FIREWALL_RULE_DELETABLE_SCHEMA,
+    FIREWALL_RULE_POS_SCHEMA,
+};
+use pdm_api_types::guest_config::PVE_CONFIG_DIGEST_SCHEMA;
+use pdm_api_types::remotes::REMOTE_ID_SCHEMA;
+use pdm_api_types::{
+    Authid, NODE_SCHEMA, PRIV_RESOURCE_AUDIT, PRIV_RESOURCE_MODIFY, PRIV_SYS_MODIFY, UPID_SCHEMA,
+    VMID_SCHEMA,
+};
+
+use crate::connection;
+
+use super::{connect_to_remote_by_id, find_node_for_vm, get_remote};
+
+// /rules/{pos}
+pub const CLUSTER_RULE_ROUTER: Router = Router::new()
+    .put(&API_METHOD_UPDATE_CLUSTER_FIREWALL_RULE)
+    .delete(&API_METHOD_DELETE_CLUSTER_FIREWALL_RULE);
+pub const NODE_RULE_ROUTER: Router = Router::new()
+    .put(&API_METHOD_UPDATE_NODE_FIREWALL_RULE)
+    .delete(&API_METHOD_DELETE_NODE_FIREWALL_RULE);
+pub const LXC_RULE_ROUTER: Router = Router::new()
+    .put(&API_METHOD_UPDATE_LXC_FIREWALL_RULE)
+    .delete(&API_METHOD_DELETE_LXC_FIREWALL_RULE);
+pub const QEMU_RULE_ROUTER: Router = Router::new()
+    .put(&API_METHOD_UPDATE_QEMU_FIREWALL_RULE)
+    .delete(&API_METHOD_DELETE_QEMU_FIREWALL_RULE);
+
+// /aliases
+pub const ALIASES_ROUTER: Router = Router::new()
+    .get(&API_METHOD_LIST_FIREWALL_ALIASES)
+    .post(&API_METHOD_CREATE_FIREWALL_ALIAS)
+    .match_all("name", &ALIAS_ROUTER);
+const ALIAS_ROUTER: Router = Router::new()
+    .put(&API_METHOD_UPDATE_FIREWALL_ALIAS)
+    .delete(&API_METHOD_DELETE_FIREWALL_ALIAS);
+
+// /ipset
+pub const IPSETS_ROUTER: Router = Router::new()
+    .get(&API_METHOD_LIST_FIREWALL_IPSETS)
+    .post(&API_METHOD_CREATE_FIREWALL_IPSET)
+    .match_all("name", &IPSET_ROUTER);
+const IPSET_ROUTER: Router = Router::new()
+    .get(&API_METHOD_LIST_FIREWALL_IPSET_ENTRIES)
+    .post(&API_METHOD_ADD_FIREWALL_IPSET_ENTRY)
+    .delete(&API_METHOD_DELETE_FIREWALL_IPSET)
+    .match_all("cidr", &IPSET_ENTRY_ROUTER);
+const IPSET_ENTRY_ROUTER: Router = Router::new()
+    .put(&API_METHOD_UPDATE_FIREWALL_IPSET_ENTRY)
+    .delete(&API_METHOD_REMOVE_FIREWALL_IPSET_ENTRY);
+
+// /groups
+pub const GROUPS_ROUTER: Router = Router::new()
+    .get(&API_METHOD_LIST_SECURITY_GROUPS)
+    .post(&API_METHOD_CREATE_SECURITY_GROUP)
+    .match_all("group", &GROUP_ROUTER);
+const GROUP_ROUTER: Router = Router::new()
+    .get(&API_METHOD_SECURITY_GROUP_RULES)
+    .post(&API_METHOD_CREATE_SECURITY_GROUP_RULE)
+    .delete(&API_METHOD_DELETE_SECURITY_GROUP)
+    .match_all("pos", &GROUP_RULE_ROUTER);
+const GROUP_RULE_ROUTER: Router = Router::new()
+    .put(&API_METHOD_UPDATE_SECURITY_GROUP_RULE)
+    .delete(&API_METHOD_DELETE_SECURITY_GROUP_RULE);
+
+// /push
+pub const PUSH_ROUTER: Router = Router::new().post(&API_METHOD_PUSH_FIREWALL_OBJECT);
+
+/// Rule properties which are copied when pushing a security group.
+const RULE_PROPERTIES: &[&str] = &[
+    "type",
+    "action",
+    "enable",
+    "iface",
+    "source",
+    "dest",
+    "proto",
+    "dport",
+    "sport",
+    "macro",
+    "icmp-type",
+    "log",
+    "comment",
+];
+
+fn encode(component: &str) -> String {
+    percent_encode(component.as_bytes(), NON_ALPHANUMERIC).to_string()
+}
+
+fn guest_rules_path(kind: GuestKind, node: &str, vmid: u32) -> String {
+    format!(
+        "/nodes/{node}/{kind}/{vmid}/firewall/rules",
+        kind = kind.as_str()
+    )
+}
+
+fn group_path(group: &str) -> String {
+    format!("/cluster/firewall/groups/{}", encode(group))
+}
+
+fn ipset_path(name: &str) -> String {
+    format!("/cluster/firewall/ipset/{}", encode(name))
+}
+
+fn raw_client(remote: &str) -> Result<Box<Client>, Error> {
+    let (remotes, _) = pdm_config::remotes::config()?;
+    connection::make_raw_client(get_remote(&remotes, remote)?)
+}
+
+async fn get_json<T: DeserializeOwned>(client: &Client, path: &str) -> Result<T, Error> {
+    Ok(client
+        .get(&format!("/api2/extjs{path}"))
+        .await?
+        .expect_json()?
+        .data)
+}
+
+async fn send(
+    client: &Client,
+    method: Method,
+    path: &str,
+    params: Option<Value>,
+) -> Result<(), Error> {
+    client
+        .request(method, &format!("/api2/extjs{path}"), params)
+        .await?
+        .nodata()?;
+    Ok(())
+}
+
+/// Delete requests take their parameters in the query string.
+async fn send_delete(client: &Client, path: &str, digest: Option<String>) -> Result<(), Error> {
+    let path = ApiPathBuilder::new(format!("/api2/extjs{path}"))
+        .maybe_arg("digest", &digest)
+        .build();
+    client.delete(&path).await?.nodata()?;
+    Ok(())
+}
+
+/// PVE expects the `enable` flag of rules as integer.
+fn rule_params(mut rule: FirewallRuleParams) -> Result<Value, Error> {
+    let enable = rule.enable.take();
+    let mut params = serde_json::to_value(rule)?;
+    if let Some(enable) = enable {
+        params["enable"] = u8::from(enable).into();
+    }
+    Ok(params)
+}
+
+async fn create_rule(
+    remote: &str,
+    rules_path: &str,
+    rule: FirewallRuleParams,
+    pos: Option<u64>,
+    digest: Option<String>,
+) -> Result<(), Error> {
+    if rule.ty.is_none() {
+        param_bail!("type", "the rule type is required");
+    }
+    if rule.action.is_none() {
+        param_bail!("action", "the rule action is required");
+    }
+
+    let mut params = rule_params(rule)?;
+    if let Some(pos) = pos {
+        params["pos"] = pos.into();
+    }
+    if let Some(digest) = digest {
+        params["digest"] = digest.into();
+    }
+
+    send(
+        &*raw_client(remote)?,
+        Method::POST,
+        rules_path,
+        Some(params),
+    )
+    .await
+}
+
+async fn update_rule(
+    remote: &str,
+    rule_path: &str,
+    rule: FirewallRuleParams,
+    moveto: Option<u64>,
+    delete: Option<Vec<String>>,
+    digest: Option<String>,
+) -> Result<(), Error> {
+    let mut params = rule_params(rule)?;
+
+    let delete = delete.unwrap_or_default();
+    for key in &delete {
+        if params.get(key).is_some() {
+            param_bail!("delete", "cannot set and delete '{key}' at the same time");
+        }
+    }
+    if !delete.is_empty() {
+        params["delete"] = delete.join(",").into();
+    }
+    if let Some(moveto) = moveto {
+        params["moveto"] = moveto.into();
+    }
+
+    let has_changes = params.as_object().is_some_and(|params| !params.is_empty());
+    if !has_changes {
+        bail!("no rule changes requested");
+    }
+
+    if let Some(digest) = digest {
+        params["digest"] = digest.into();
+    }
+
+    send(&*raw_client(remote)?, Method::PUT, rule_path, Some(params)).await
+}
+
+async fn delete_rule(remote: &str, rule_path: &str, digest: Option<String>) -> Result<(), Error> {
+    send_delete(&*raw_client(remote)?, rule_path, digest).await
+}
+
+async fn guest_rules(
+    remote: &str,
+    node: Option<String>,
+    vmid: u32,
+    kind: GuestKind,
+) -> Result<String, Error> {
+    let pve = connect_to_remote_by_id(remote)?;
+    let node = find_node_for_vm(node, vmid, pve.as_ref()).await?;
+    Ok(guest_rules_path(kind, &node, vmid))
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            rule: {
+                type: FirewallRuleParams,
+                flatten: true,
+            },
+            pos: {
+                schema: FIREWALL_RULE_POS_SCHEMA,
+                optional: true,
+            },
+            digest: {
+                schema: PVE_CONFIG_DIGEST_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Create a cluster firewall rule.
+pub async fn create_cluster_firewall_rule(
+    remote: String,
+    rule: FirewallRuleParams,
+    pos: Option<u64>,
+    digest: Option<String>,
+) -> Result<(), Error> {
+    create_rule(&remote, "/cluster/firewall/rules", rule, pos, digest).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            pos: { schema: FIREWALL_RULE_POS_SCHEMA },
+            rule: {
+                type: FirewallRuleParams,
+                flatten: true,
+            },
+            moveto: {
+                schema: FIREWALL_RULE_POS_SCHEMA,
+                optional: true,
+            },
+            delete: {
+                type: Array,
+                optional: true,
+                items: { schema: FIREWALL_RULE_DELETABLE_SCHEMA },
+            },
+            digest: {
+                schema: PVE_CONFIG_DIGEST_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Update or move a cluster firewall rule.
+pub async fn update_cluster_firewall_rule(
+    remote: String,
+    pos: u64,
+    rule: FirewallRuleParams,
+    moveto: Option<u64>,
+    delete: Option<Vec<String>>,
+    digest: Option<String>,
+) -> Result<(), Error> {
+    let path = format!("/cluster/firewall/rules/{pos}");
+    update_rule(&remote, &path, rule, moveto, delete, digest).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            pos: { schema: FIREWALL_RULE_POS_SCHEMA },
+            digest: {
+                schema: PVE_CONFIG_DIGEST_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Delete a cluster firewall rule.
+pub async fn delete_cluster_firewall_rule(
+    remote: String,
+    pos: u64,
+    digest: Option<String>,
+) -> Result<(), Error> {
+    delete_rule(&remote, &format!("/cluster/firewall/rules/{pos}"), digest).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: { schema: NODE_SCHEMA },
+            rule: {
+                type: FirewallRuleParams,
+                flatten: true,
+            },
+            pos: {
+                schema: FIREWALL_RULE_POS_SCHEMA,
+                optional: true,
+            },
+            digest: {
+                schema: PVE_CONFIG_DIGEST_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_RESOURCE_MODIFY, false),
+    },
+)]
+/// Create a node firewall rule.
+pub async fn create_node_firewall_rule(
+    remote: String,
+    node: String,
+    rule: FirewallRuleParams,
+    pos: Option<u64>,
+    digest: Option<String>,
+) -> Result<(), Error> {
+    let path = format!("/nodes/{node}/firewall/rules");
+    create_rule(&remote, &path, rule, pos, digest).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: { schema: NODE_SCHEMA },
+            pos: { schema: FIREWALL_RULE_POS_SCHEMA },
+            rule: {
+                type: FirewallRuleParams,
+                flatten: true,
+            },
+            moveto: {
+                schema: FIREWALL_RULE_POS_SCHEMA,
+                optional: true,
+            },
+            delete: {
+                type: Array,
+                optional: true,
+                items: { schema: FIREWALL_RULE_DELETABLE_SCHEMA },
+            },
+            digest: {
+                schema: PVE_CONFIG_DIGEST_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_RESOURCE_MODIFY, false),
+    },
+)]
+/// Update or move a node firewall rule.
+pub async fn update_node_firewall_rule(
+    remote: String,
+    node: String,
+    pos: u64,
+    rule: FirewallRuleParams,
+    moveto: Option<u64>,
+    delete: Option<Vec<String>>,
+    digest: Option<String>,
+) -> Result<(), Error> {
+    let path = format!("/nodes/{node}/firewall/rules/{pos}");
+    update_rule(&remote, &path, rule, moveto, delete, digest).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: { schema: NODE_SCHEMA },
+            pos: { schema: FIREWALL_RULE_POS_SCHEMA },
+            digest: {
+                schema: PVE_CONFIG_DIGEST_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_RESOURCE_MODIFY, false),
+    },
+)]
+/// Delete a node firewall rule.
+pub async fn delete_node_firewall_rule(
+    remote: String,
+    node: String,
+    pos: u64,
+    digest: Option<String>,
+) -> Result<(), Error> {
+    let path = format!("/nodes/{node}/firewall/rules/{pos}");
+    delete_rule(&remote, &path, digest).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            rule: {
+                type: FirewallRuleParams,
+                flatten: true,
+            },
+            pos: {
+                schema: FIREWALL_RULE_POS_SCHEMA,
+                optional: true,
+            },
+            digest: {
+                schema: PVE_CONFIG_DIGEST_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}", "guest", "{vmid}"], PRIV_RESOURCE_MODIFY, false),
+    },
+)]
+/// Create a LXC firewall rule.
+pub async fn create_lxc_firewall_rule(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    rule: FirewallRuleParams,
+    pos: Option<u64>,
+    digest: Option<String>,
+) -> Result<(), Error> {
+    let path = guest_rules(&remote, node, vmid, GuestKind::Lxc).await?;
+    create_rule(&remote, &path, rule, pos, digest).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            pos: { schema: FIREWALL_RULE_POS_SCHEMA },
+            rule: {
+                type: FirewallRuleParams,
+                flatten: true,
+            },
+            moveto: {
+                schema: FIREWALL_RULE_POS_SCHEMA,
+                optional: true,
+            },
+            delete: {
+                type: Array,
+                optional: true,
+                items: { schema: FIREWALL_RULE_DELETABLE_SCHEMA },
+            },
+            digest: {
+                schema: PVE_CONFIG_DIGEST_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}", "guest", "{vmid}"], PRIV_RESOURCE_MODIFY, false),
+    },
+)]
+/// Update or move a LXC firewall rule.
+#[allow(clippy::too_many_arguments)]
+pub async fn update_lxc_firewall_rule(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    pos: u64,
+    rule: FirewallRuleParams,
+    moveto: Option<u64>,
+    delete: Option<Vec<String>>,
+    digest: Option<String>,
+) -> Result<(), Error> {
+    let path = guest_rules(&remote, node, vmid, GuestKind::Lxc).await?;
+    update_rule(
+        &remote,
+        &format!("{path}/{pos}"),
+        rule,
+        moveto,
+        delete,
+        digest,
+    )
+    .await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            pos: { schema: FIREWALL_RULE_POS_SCHEMA },
+            digest: {
+                schema: PVE_CONFIG_DIGEST_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}", "guest", "{vmid}"], PRIV_RESOURCE_MODIFY, false),
+    },
+)]
+/// Delete a LXC firewall rule.
+pub async fn delete_lxc_firewall_rule(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    pos: u64,
+    digest: Option<String>,
+) -> Result<(), Error> {
+    let path = guest_rules(&remote, node, vmid, GuestKind::Lxc).await?;
+    delete_rule(&remote, &format!("{path}/{pos}"), digest).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            rule: {
+                type: FirewallRuleParams,
+                flatten: true,
+            },
+            pos: {
+                schema: FIREWALL_RULE_POS_SCHEMA,
+                optional: true,
+            },
+            digest: {
+                schema: PVE_CONFIG_DIGEST_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}", "guest", "{vmid}"], PRIV_RESOURCE_MODIFY, false),
+    },
+)]
+/// Create a QEMU firewall rule.
+pub async fn create_qemu_firewall_rule(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    rule: FirewallRuleParams,
+    pos: Option<u64>,
+    digest: Option<String>,
+) -> Result<(), Error> {
+    let path = guest_rules(&remote, node, vmid, GuestKind::Qemu).await?;
+    create_rule(&remote, &path, rule, pos, digest).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            pos: { schema: FIREWALL_RULE_POS_SCHEMA },
+            rule: {
+                type: FirewallRuleParams,
+                flatten: true,
+            },
+            moveto: {
+                schema: FIREWALL_RULE_POS_SCHEMA,
+                optional: true,
+            },
+            delete: {
+                type: Array,
+                optional: true,
+                items: { schema: FIREWALL_RULE_DELETABLE_SCHEMA },
+            },
+            digest: {
+                schema: PVE_CONFIG_DIGEST_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}", "guest", "{vmid}"], PRIV_RESOURCE_MODIFY, false),
+    },
+)]
+/// Update or move a QEMU firewall rule.
+#[allow(clippy::too_many_arguments)]
+pub async fn update_qemu_firewall_rule(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    pos: u64,
+    rule: FirewallRuleParams,
+    moveto: Option<u64>,
+    delete: Option<Vec<String>>,
+    digest: Option<String>,
+) -> Result<(), Error> {
+    let path = guest_rules(&remote, node, vmid, GuestKind::Qemu).await?;
+    update_rule(
+        &remote,
+        &format!("{path}/{pos}"),
+        rule,
+        moveto,
+        delete,
+        digest,
+    )
+    .await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            pos: { schema: FIREWALL_RULE_POS_SCHEMA },
+            digest: {
+                schema: PVE_CONFIG_DIGEST_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}", "guest", "{vmid}"], PRIV_RESOURCE_MODIFY, false),
+    },
+)]
+/// Delete a QEMU firewall rule.
+pub async fn delete_qemu_firewall_rule(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    pos: u64,
+    digest: Option<String>,
+) -> Result<(), Error> {
+    let path = guest_rules(&remote, node, vmid, GuestKind::Qemu).await?;
+    delete_rule(&remote, &format!("{path}/{pos}"), digest).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+        },
+    },
+    returns: {
+        type: Array,
+        description: "List of cluster firewall aliases.",
+        items: { type: FirewallAlias },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_RESOURCE_AUDIT, false),
+    },
+)]
+/// List the cluster firewall aliases.
+pub async fn list_firewall_aliases(remote: String) -> Result<Vec<FirewallAlias>, Error> {
+    get_json(&*raw_client(&remote)?, "/cluster/firewall/aliases").await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            name: { schema: FIREWALL_ALIAS_NAME_SCHEMA },
+            cidr: { schema: FIREWALL_CIDR_SCHEMA },
+            comment: {
+                type: String,
+                description: "Descriptive comment.",
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Create a cluster firewall alias.
+pub async fn create_firewall_alias(
+    remote: String,
+    name: String,
+    cidr: String,
+    comment: Option<String>,
+) -> Result<(), Error> {
+    let params = json!({ "name": name, "cidr": cidr, "comment": comment });
+    send(
+        &*raw_client(&remote)?,
+        Method::POST,
+        "/cluster/firewall/aliases",
+        Some(strip_nulls(params)),
+    )
+    .await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            name: { schema: FIREWALL_ALIAS_NAME_SCHEMA },
+            cidr: { schema: FIREWALL_CIDR_SCHEMA },
+            comment: {
+                type: String,
+                description: "Descriptive comment.",
+                optional: true,
+            },
+            rename: {
+                schema: FIREWALL_ALIAS_NAME_SCHEMA,
+                optional: true,
+            },
+            digest: {
+                schema: PVE_CONFIG_DIGEST_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Update or rename a cluster firewall alias.
+pub async fn update_firewall_alias(
+    remote: String,
+    name: String,
+    cidr: String,
+    comment: Option<String>,
+    rename: Option<String>,
+    digest: Option<String>,
+) -> Result<(), Error> {
+    let mut params = json!({ "cidr": cidr, "comment": comment.unwrap_or_default() });
+    if let Some(rename) = rename {
+        params["rename"] = rename.into();
+    }
+    if let Some(digest) = digest {
+        params["digest"] = digest.into();
+    }
+    let path = format!("/cluster/firewall/aliases/{}", encode(&name));
+    send(&*raw_client(&remote)?, Method::PUT, &path, Some(params)).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            name: { schema: FIREWALL_ALIAS_NAME_SCHEMA },
+            digest: {
+                schema: PVE_CONFIG_DIGEST_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Delete a cluster firewall alias.
+pub async fn delete_firewall_alias(
+    remote: String,
+    name: String,
+    digest: Option<String>,
+) -> Result<(), Error> {
+    let path = format!("/cluster/firewall/aliases/{}", encode(&name));
+    send_delete(&*raw_client(&remote)?, &path, digest).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+        },
+    },
+    returns: {
+        type: Array,
+        description: "List of cluster firewall IP sets.",
+        items: { type: FirewallIpSet },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_RESOURCE_AUDIT, false),
+    },
+)]
+/// List the cluster firewall IP sets.
+pub async fn list_firewall_ipsets(remote: String) -> Result<Vec<FirewallIpSet>, Error> {
+    get_json(&*raw_client(&remote)?, "/cluster/firewall/ipset").await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            name: { schema: FIREWALL_IPSET_NAME_SCHEMA },
+            comment: {
+                type: String,
+                description: "Descriptive comment.",
+                optional: true,
+            },
+            rename: {
+                schema: FIREWALL_IPSET_NAME_SCHEMA,
+                optional: true,
+            },
+            digest: {
+                schema: PVE_CONFIG_DIGEST_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Create a cluster firewall IP set.
+///
+/// If `rename` is set, the existing IP set `rename` is renamed to `name` and its comment updated
+/// instead.
+pub async fn create_firewall_ipset(
+    remote: String,
+    name: String,
+    comment: Option<String>,
+    rename: Option<String>,
+    digest: Option<String>,
+) -> Result<(), Error> {
+    let params = json!({
+        "name": name,
+        "comment": comment,
+        "rename": rename,
+        "digest": digest,
+    });
+    send(
+        &*raw_client(&remote)?,
+        Method::POST,
+        "/cluster/firewall/ipset",
+        Some(strip_nulls(params)),
+    )
+    .await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            name: { schema: FIREWALL_IPSET_NAME_SCHEMA },
+        },
+    },
+    returns: {
+        type: Array,
+        description: "Entries of the IP set.",
+        items: { type: FirewallIpSetEntry },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_RESOURCE_AUDIT, false),
+    },
+)]
+/// List the entries of a cluster firewall IP set.
+pub async fn list_firewall_ipset_entries(
+    remote: String,
+    name: String,
+) -> Result<Vec<FirewallIpSetEntry>, Error> {
+    ipset_entries(&*raw_client(&remote)?, &name).await
+}
+
+async fn ipset_entries(client: &Client, name: &str) -> Result<Vec<FirewallIpSetEntry>, Error> {
+    let entries: Vec<Value> = get_json(client, &ipset_path(name)).await?;
+    entries.into_iter().map(parse_ipset_entry).collect()
+}
+
+/// PVE reports `nomatch` as perl boolean.
+fn parse_ipset_entry(mut entry: Value) -> Result<FirewallIpSetEntry, Error> {
+    let nomatch = match entry.get("nomatch") {
+        Some(Value::Bool(nomatch)) => *nomatch,
+        Some(Value::Number(nomatch)) => nomatch.as_u64().unwrap_or(0) != 0,
+        Some(Value::String(nomatch)) => nomatch == "1",
+        _ => false,
+    };
+    entry["nomatch"] = nomatch.into();
+    Ok(serde_json::from_value(entry)?)
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            name: { schema: FIREWALL_IPSET_NAME_SCHEMA },
+            cidr: { schema: FIREWALL_CIDR_SCHEMA },
+            comment: {
+                type: String,
+                description: "Descriptive comment.",
+                optional: true,
+            },
+            nomatch: {
+                type: bool,
+                description: "Exclude the address from the IP set.",
+                optional: true,
+                default: false,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Add an entry to a cluster firewall IP set.
+pub async fn add_firewall_ipset_entry(
+    remote: String,
+    name: String,
+    cidr: String,
+    comment: Option<String>,
+    nomatch: bool,
+) -> Result<(), Error> {
+    add_ipset_entry(
+        &*raw_client(&remote)?,
+        &name,
+        &FirewallIpSetEntry {
+            cidr,
+            comment,
+            nomatch,
+            digest: None,
+        },
+    )
+    .await
+}
+
+async fn add_ipset_entry(
+    client: &Client,
+    name: &str,
+    entry: &FirewallIpSetEntry,
+) -> Result<(), Error> {
+    let params = json!({
+        "cidr": entry.cidr,
+        "comment": entry.comment,
+        "nomatch": u8::from(entry.nomatch),
+    });
+    send(
+        client,
+        Method::POST,
+        &ipset_path(name),
+        Some(strip_nulls(params)),
+    )
+    .await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            name: { schema: FIREWALL_IPSET_NAME_SCHEMA },
+            cidr: { schema: FIREWALL_CIDR_SCHEMA },
+            comment: {
+                type: String,
+                description: "Descriptive comment.",
+                optional: true,
+            },
+            nomatch: {
+                type: bool,
+                description: "Exclude the address from the IP set.",
+                optional: true,
+                default: false,
+            },
+            digest: {
+                schema: PVE_CONFIG_DIGEST_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Update an entry of a cluster firewall IP set.
+pub async fn update_firewall_ipset_entry(
+    remote: String,
+    name: String,
+    cidr: String,
+    comment: Option<String>,
+    nomatch: bool,
+    digest: Option<String>,
+) -> Result<(), Error> {
+    update_ipset_entry(
+        &*raw_client(&remote)?,
+        &name,
+        &FirewallIpSetEntry {
+            cidr,
+            comment,
+            nomatch,
+            digest,
+        },
+    )
+    .await
+}
+
+async fn update_ipset_entry(
+    client: &Client,
+    name: &str,
+    entry: &FirewallIpSetEntry,
+) -> Result<(), Error> {
+    let params = json!({
+        "comment": entry.comment.as_deref().unwrap_or_default(),
+        "nomatch": u8::from(entry.nomatch),
+        "digest": entry.digest,
+    });
+    let path = format!("{}/{}", ipset_path(name), encode(&entry.cidr));
+    send(client, Method::PUT, &path, Some(strip_nulls(params))).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            name: { schema: FIREWALL_IPSET_NAME_SCHEMA },
+            cidr: { schema: FIREWALL_CIDR_SCHEMA },
+            digest: {
+                schema: PVE_CONFIG_DIGEST_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Remove an entry from a cluster firewall IP set.
+pub async fn remove_firewall_ipset_entry(
+    remote: String,
+    name: String,
+    cidr: String,
+    digest: Option<String>,
+) -> Result<(), Error> {
+    let path = format!("{}/{}", ipset_path(&name), encode(&cidr));
+    send_delete(&*raw_client(&remote)?, &path, digest).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            name: { schema: FIREWALL_IPSET_NAME_SCHEMA },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Delete a cluster firewall IP set, it must not contain any entries.
+pub async fn delete_firewall_ipset(remote: String, name: String) -> Result<(), Error> {
+    send_delete(&*raw_client(&remote)?, &ipset_path(&name), None).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+        },
+    },
+    returns: {
+        type: Array,
+        description: "List of security groups.",
+        items: { type: FirewallSecurityGroup },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_RESOURCE_AUDIT, false),
+    },
+)]
+/// List the firewall security groups.
+pub async fn list_security_groups(remote: String) -> Result<Vec<FirewallSecurityGroup>, Error> {
+    get_json(&*raw_client(&remote)?, "/cluster/firewall/groups").await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            group: { schema: FIREWALL_GROUP_NAME_SCHEMA },
+            comment: {
+                type: String,
+                description: "Descriptive comment.",
+                optional: true,
+            },
+            rename: {
+                schema: FIREWALL_GROUP_NAME_SCHEMA,
+                optional: true,
+            },
+            digest: {
+                schema: PVE_CONFIG_DIGEST_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Create a firewall security group.
+///
+/// If `rename` is set, the existing security group `rename` is renamed to `group` and its comment
+/// updated instead.
+pub async fn create_security_group(
+    remote: String,
+    group: String,
+    comment: Option<String>,
+    rename: Option<String>,
+    digest: Option<String>,
+) -> Result<(), Error> {
+    let params = json!({
+        "group": group,
+        "comment": comment,
+        "rename": rename,
+        "digest": digest,
+    });
+    send(
+        &*raw_client(&remote)?,
+        Method::POST,
+        "/cluster/firewall/groups",
+        Some(strip_nulls(params)),
+    )
+    .await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            group: { schema: FIREWALL_GROUP_NAME_SCHEMA },
+        },
+    },
+    returns: {
+        type: Array,
+        description: "Rules of the security group.",
+        items: { type: pve_api_types::ListFirewallRules },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_RESOURCE_AUDIT, false),
+    },
+)]
+/// List the rules of a firewall security group.
+pub async fn security_group_rules(
+    remote: String,
+    group: String,
+) -> Result<Vec<pve_api_types::ListFirewallRules>, Error> {
+    get_json(&*raw_client(&remote)?, &group_path(&group)).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            group: { schema: FIREWALL_GROUP_NAME_SCHEMA },
+            rule: {
+                type: FirewallRuleParams,
+                flatten: true,
+            },
+            pos: {
+                schema: FIREWALL_RULE_POS_SCHEMA,
+                optional: true,
+            },
+            digest: {
+                schema: PVE_CONFIG_DIGEST_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Create a rule in a firewall security group.
+pub async fn create_security_group_rule(
+    remote: String,
+    group: String,
+    rule: FirewallRuleParams,
+    pos: Option<u64>,
+    digest: Option<String>,
+) -> Result<(), Error> {
+    create_rule(&remote, &group_path(&group), rule, pos, digest).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            group: { schema: FIREWALL_GROUP_NAME_SCHEMA },
+            pos: { schema: FIREWALL_RULE_POS_SCHEMA },
+            rule: {
+                type: FirewallRuleParams,
+                flatten: true,
+            },
+            moveto: {
+                schema: FIREWALL_RULE_POS_SCHEMA,
+                optional: true,
+            },
+            delete: {
+                type: Array,
+                optional: true,
+                items: { schema: FIREWALL_RULE_DELETABLE_SCHEMA },
+            },
+            digest: {
+                schema: PVE_CONFIG_DIGEST_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Update or move a rule of a firewall security group.
+pub async fn update_security_group_rule(
+    remote: String,
+    group: String,
+    pos: u64,
+    rule: FirewallRuleParams,
+    moveto: Option<u64>,
+    delete: Option<Vec<String>>,
+    digest: Option<String>,
+) -> Result<(), Error> {
+    let path = format!("{}/{pos}", group_path(&group));
+    update_rule(&remote, &path, rule, moveto, delete, digest).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            group: { schema: FIREWALL_GROUP_NAME_SCHEMA },
+            pos: { schema: FIREWALL_RULE_POS_SCHEMA },
+            digest: {
+                schema: PVE_CONFIG_DIGEST_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Delete a rule of a firewall security group.
+pub async fn delete_security_group_rule(
+    remote: String,
+    group: String,
+    pos: u64,
+    digest: Option<String>,
+) -> Result<(), Error> {
+    let path = format!("{}/{pos}", group_path(&group));
+    delete_rule(&remote, &path, digest).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            group: { schema: FIREWALL_GROUP_NAME_SCHEMA },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Delete a firewall security group, it must not contain any rules.
+pub async fn delete_security_group(remote: String, group: String) -> Result<(), Error> {
+    send_delete(&*raw_client(&remote)?, &group_path(&group), None).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            kind: { type: FirewallPushKind },
+            name: {
+                type: String,
+                description: "Name of the security group or IP set.",
+            },
+            targets: {
+                type: Array,
+                description: "The remotes to push the object to.",
+                items: { schema: REMOTE_ID_SCHEMA },
+            },
+        },
+    },
+    returns: { schema: UPID_SCHEMA },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_RESOURCE_AUDIT, false),
+        description: "Additionally requires `Sys.Modify` on `/resource/{target}` for every target.",
+    },
+)]
+/// Copy a security group or IP set of a remote to other remotes.
+///
+/// Missing objects are created on the targets, existing ones are changed to match the source.
+/// Rules and entries of the targets which do not exist on the source are removed.
+pub async fn push_firewall_object(
+    remote: String,
+    kind: FirewallPushKind,
+    name: String,
+    targets: Vec<String>,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<String, Error> {
+    let auth_id: Authid = rpcenv
+        .get_auth_id()
+        .context("no authid available")?
+        .parse()?;
+    let user_info = CachedUserInfo::new()?;
+
+    if targets.is_empty() {
+        param_bail!("targets", "no target remotes given");
+    }
+
+    let (remotes, _) = pdm_config::remotes::config()?;
+    for target in &targets {
+        if *target == remote {
+            param_bail!("targets", "cannot push to the source remote '{target}'");
+        }
+        get_remote(&remotes, target)?;
+        if user_info.lookup_privs(&auth_id, &["resource", target]) & PRIV_SYS_MODIFY == 0 {
+            http_bail!(FORBIDDEN, "missing permissions on remote '{target}'");
+        }
+    }
+
+    let source = fetch_push_source(&*raw_client(&remote)?, kind, &name).await?;
+
+    let upid = WorkerTask::spawn(
+        "firewall-push",
+        Some(format!("{remote}:{kind}:{name}")),
+        auth_id.to_string(),
+        true,
+        move |_worker| async move {
+            let mut failed = 0;
+            for target in targets {
+                match push_to_target(&target, &name, &source).await {
+                    Ok(()) => log::info!("{target}: {kind} '{name}' is up to date"),
+                    Err(err) => {
+                        log::error!("{target}: failed to push {kind} '{name}' - {err:#}");
+                        failed += 1;
+                    }
+                }
+            }
+            if failed > 0 {
+                bail!("failed to push to {failed} remote(s)");
+            }
+            Ok(())
+        },
+    )?;
+
+    Ok(upid)
+}
+
+/// A security group or IP set which is pushed to other remotes.
+enum PushSource {
+    SecurityGroup {
+        comment: Option<String>,
+        rules: Vec<Value>,
+    },
+    IpSet {
+        comment: Option<String>,
+        entries: Vec<FirewallIpSetEntry>,
+    },
+}
+
+async fn fetch_push_source(
+    client: &Client,
+    kind: FirewallPushKind,
+    name: &str,
+) -> Result<PushSource, Error> {
+    match kind {
+        FirewallPushKind::SecurityGroup => {
+            let groups: Vec<FirewallSecurityGroup> =
+                get_json(client, "/cluster/firewall/groups").await?;
+            let Some(group) = groups.into_iter().find(|group| group.group == name) else {
+                http_bail!(NOT_FOUND, "no such security group '{name}'");
+            };
+            Ok(PushSource::SecurityGroup {
+                comment: group.comment,
+                rules: group_rules(client, name).await?,
+            })
+        }
+        FirewallPushKind::Ipset => {
+            let ipsets: Vec<FirewallIpSet> = get_json(client, "/cluster/firewall/ipset").await?;
+            let Some(ipset) = ipsets.into_iter().find(|ipset| ipset.name == name) else {
+                http_bail!(NOT_FOUND, "no such IP set '{name}'");
+            };
+            let mut entries = ipset_entries(client, name).await?;
+            entries.iter_mut().for_each(|entry| entry.digest = None);
+            Ok(PushSource::IpSet {
+                comment: ipset.comment,
+                entries,
+            })
+        }
+    }
+}
+
+/// Get the rules of a security group, reduced to the properties used to create them.
+async fn group_rules(client: &Client, group: &str) -> Result<Vec<Value>, Error> {
+    let rules: Vec<Value> = get_json(client, &group_path(group)).await?;
+    Ok(rules.into_iter().map(normalize_rule).collect())
+}
+
+fn normalize_rule(rule: Value) -> Value {
+    let Value::Object(mut rule) = rule else {
+        return rule;
+    };
+    rule.retain(|key, _| RULE_PROPERTIES.contains(&key.as_str()));
+    Value::Object(rule)
+}
+
+/// Parse a rule as returned by PVE, which reports the `enable` flag as integer and omits it for
+/// disabled rules.
+fn parse_rule(rule: Value) -> Result<FirewallRuleParams, Error> {
+    let mut rule = normalize_rule(rule);
+    let enable = rule
+        .get("enable")
+        .and_then(Value::as_u64)
+        .is_some_and(|enable| enable != 0);
+    rule["enable"] = enable.into();
+    Ok(serde_json::from_value(rule)?)
+}
+
+/// Fetch the cluster level firewall configuration of a PVE remote.
+pub(super) async fn fetch_cluster_firewall_config(
+    client: &Client,
+) -> Result<ClusterFirewallConfig, Error> {
+    let options: serde_json::Map<String, Value> =
+        get_json(client, "/cluster/firewall/options").await?;
+    let mut options: Vec<FirewallOption> = options
+        .into_iter()
+        .filter(|(key, _)| key != "digest")
+        .map(|(key, value)| {
+            let value = match value {
+                Value::String(value) => value,
+                Value::Bool(value) => u8::from(value).to_string(),
+                other => other.to_string(),
+            };
+            FirewallOption { key, value }
+        })
+        .collect();
+    options.sort_by(|a, b| a.key.cmp(&b.key));
+
+    let rules: Vec<Value> = get_json(client, "/cluster/firewall/rules").await?;
+    let rules = rules
+        .into_iter()
+        .map(parse_rule)
+        .collect::<Result<_, Error>>()?;
+
+    let mut ipsets = Vec::new();
+    let list: Vec<FirewallIpSet> = get_json(client, "/cluster/firewall/ipset").await?;
+    for ipset in list {
+        let mut entries = ipset_entries(client, &ipset.name).await?;
+        entries.iter_mut().for_each(|entry| entry.digest = None);
+        entries.sort_by(|a, b| a.cidr.cmp(&b.cidr));
+        ipsets.push(FirewallIpSetConfig {
+            name: ipset.name,
+            comment: ipset.comment,
+            entries,
+        });
+    }
+    ipsets.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut groups = Vec::new();
+    let list: Vec<FirewallSecurityGroup> = get_json(client, "/cluster/firewall/groups").await?;
+    for group in list {
+        let rules: Vec<Value> = get_json(client, &group_path(&group.group)).await?;
+        groups.push(FirewallSecurityGroupConfig {
+            group: group.group,
+            comment: group.comment,
+            rules: rules
+                .into_iter()
+                .map(parse_rule)
+                .collect::<Result<_, Error>>()?,
+        });
+    }
+    groups.sort_by(|a, b| a.group.cmp(&b.group));
+
+    Ok(ClusterFirewallConfig {
+        options,
+        rules,
+        ipsets,
+        groups,
+    })
+}
+
+async fn push_to_target(target: &str, name: &str, source: &PushSource) -> Result<(), Error> {
+    let client = raw_client(target)?;
+    let client = &*client;
+
+    match source {
+        PushSource::SecurityGroup { comment, rules } => {
+            let groups: Vec<FirewallSecurityGroup> =
+                get_json(client, "/cluster/firewall/groups").await?;
+            let existing = groups.into_iter().find(|group| group.group == name);
+
+            match &existing {
+                None => {
+                    log::info!("{target}: creating security group '{name}'");
+                    let params = json!({ "group": name, "comment": comment });
+                    send(
+                        client,
+                        Method::POST,
+                        "/cluster/firewall/groups",
+                        Some(strip_nulls(params)),
+                    )
+                    .await?;
+                }
+                Some(group) if group.comment != *comment => {
+                    let params = json!({
+                        "group": name,
+                        "rename": name,
+                        "comment": comment.as_deref().unwrap_or_default(),
+                    });
+                    send(
+                        client,
+                        Method::POST,
+                        "/cluster/firewall/groups",
+                        Some(params),
+                    )
+                    .await?;
+                }
+                Some(_) => {}
+            }
+
+            let target_rules = match existing {
+                Some(_) => group_rules(client, name).await?,
+                None => Vec::new(),
+            };
+            if target_rules == *rules {
+                return Ok(());
+            }
+
+            log::info!("{target}: replacing {} rule(s)", target_rules.len());
+            let path = group_path(name);
+            for pos in (0..target_rules.len()).rev() {
+                send_delete(client, &format!("{path}/{pos}"), None).await?;
+            }
+            for (pos, rule) in rules.iter().enumerate() {
+                let mut params = rule.clone();
+                params["pos"] = pos.into();
+                send(client, Method::POST, &path, Some(params))
+                    .await
+                    .map_err(|err| format_err!("failed to create rule {pos} - {err}"))?;
+            }
+        }
+        PushSource::IpSet { comment, entries } => {
+            let ipsets: Vec<FirewallIpSet> = get_json(client, "/cluster/firewall/ipset").await?;
+            let existing = ipsets.into_iter().find(|ipset| ipset.name == name);
+
+            match &existing {
+                None => {
+                    log::info!("{target}: creating IP set '{name}'");
+                    let params = json!({ "name": name, "comment": comment });
+                    send(
+                        client,
+                        Method::POST,
+                        "/cluster/firewall/ipset",
+                        Some(strip_nulls(params)),
+                    )
+                    .await?;
+                }
+                Some(ipset) if ipset.comment != *comment => {
+                    let params = json!({
+                        "name": name,
+                        "rename": name,
+                        "comment": comment.as_deref().unwrap_or_default(),
+                    });
+                    send(
+                        client,
+                        Method::POST,
+                        "/cluster/firewall/ipset",
+                        Some(params),
+                    )
+                    .await?;
+                }
+                Some(_) => {}
+            }
+
+            let target_entries = match existing {
+                Some(_) => ipset_entries(client, name).await?,
+                None => Vec::new(),
+            };
+
+            let diff = diff_ipset_entries(entries, target_entries);
+            for cidr in diff.remove {
+                log::info!("{target}: removing {cidr}");
+                let path = format!("{}/{}", ipset_path(name), encode(&cidr));
+                send_delete(client, &path, None).await?;
+            }
+            for entry in diff.update {
+                log::info!("{target}: updating {}", entry.cidr);
+                update_ipset_entry(client, name, &entry).await?;
+            }
+            for entry in diff.add {
+                log::info!("{target}: adding {}", entry.cidr);
+                add_ipset_entry(client, name, &entry).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Changes needed to turn the entries of an IP set into the ones of the source.
+#[derive(Debug, Default, PartialEq)]
+struct IpSetDiff {
+    add: Vec<FirewallIpSetEntry>,
+    update: Vec<FirewallIpSetEntry>,
+    remove: Vec<String>,
+}
+
+fn diff_ipset_entries(source: &[FirewallIpSetEntry], target: Vec<FirewallIpSetEntry>) -> IpSetDiff {
+    let mut target: HashMap<String, FirewallIpSetEntry> = target
+        .into_iter()
+        .map(|entry| (entry.cidr.clone(), entry))
+        .collect();
+
+    let mut diff = IpSetDiff::default();
+
+    for entry in source {
+        match target.remove(&entry.cidr) {
+            None => diff.add.push(entry.clone()),
+            Some(existing) => {
+                if existing.comment != entry.comment || existing.nomatch != entry.nomatch {
+                    diff.update.push(entry.clone());
+                }
+            }
+        }
+    }
+
+    diff.remove = target.into_keys().collect();
+    diff.remove.sort();
+
+    diff
+}
+
+/// Drop unset parameters, PVE rejects `null` values.
+fn strip_nulls(mut params: Value) -> Value {
+    if let Value::Object(map) = &mut params {
+        map.retain(|_, value| !value.is_null());
+    }
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(cidr: &str, comment: Option<&str>, nomatch: bool) -> FirewallIpSetEntry {
+        FirewallIpSetEntry {
+            cidr: cidr.into(),
+            comment: comment.map(str::to_string),
+            nomatch,
+            digest: None,
+        }
+    }
+
+    #[test]
+    fn ipset_diff() {
+        let source = [
+            entry("10.0.0.0/8", Some("internal"), false),
+            entry("10.1.0.0/16", None, true),
+            entry("192.168.0.0/24", None, false),
+        ];
+        let target = vec![
+            entry("10.0.0.0/8", Some("old"), false),
+            entry("192.168.0.0/24", None, false),
+            entry("172.16.0.0/12", None, false),
+        ];
+
+        let diff = diff_ipset_entries(&source, target);
+
+        assert_eq!(diff.add, [entry("10.1.0.0/16", None, true)]);
+        assert_eq!(diff.update, [entry("10.0.0.0/8", Some("internal"), false)]);
+        assert_eq!(diff.remove, ["172.16.0.0/12"]);
+    }
+
+    #[test]
+    fn ipset_diff_identical() {
+        let source = [entry("10.0.0.0/8", None, false)];
+        let diff = diff_ipset_entries(&source, source.to_vec());
+        assert_eq!(diff, IpSetDiff::default());
+    }
+
+    #[test]
+    fn rule_normalization() {
+        let rule = json!({
+            "pos": 3,
+            "digest": "0123",
+            "ipversion": 4,
+            "type": "in",
+            "action": "ACCEPT",
+            "enable": 1,
+            "dport": "22",
+        });
+        assert_eq!(
+            normalize_rule(rule),
+            json!({ "type": "in", "action": "ACCEPT", "enable": 1, "dport": "22" }),
+        );
+    }
+
+    #[test]
+    fn parses_rules() {
+        let rule = parse_rule(json!({
+            "pos": 0,
+            "type": "in",
+            "action": "ACCEPT",
+            "macro": "SSH",
+            "log": "nolog",
+        }))
+        .unwrap();
+        assert_eq!(rule.enable, Some(false));
+        assert_eq!(rule.r#macro.as_deref(), Some("SSH"));
+
+        let rule = parse_rule(json!({ "type": "out", "action": "DROP", "enable": 1 })).unwrap();
+        assert_eq!(rule.enable, Some(true));
+    }
+
+    #[test]
+    fn parses_perl_booleans() {
+        let entry = parse_ipset_entry(json!({ "cidr": "10.0.0.1", "nomatch": 1 })).unwrap();
+        assert!(entry.nomatch);
+        let entry = parse_ipset_entry(json!({ "cidr": "10.0.0.1" })).unwrap();
+        assert!(!entry.nomatch);
+    }
+}
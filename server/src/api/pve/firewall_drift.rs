@@ -0,0 +1,668 @@
+//! Detect cluster firewall configurations of PVE remotes which drifted from a reference.
+//!
+//! The reference is either the current configuration of another remote or a baseline document
+//! stored on the Datacenter Manager.
+
+use std::collections::{BTreeMap, HashSet};
+
+use anyhow::{Context, Error};
+
+use proxmox_access_control::CachedUserInfo;
+use proxmox_router::{http_bail, Permission, Router, RpcEnvironment};
+use proxmox_schema::{api, param_bail};
+
+use pdm_api_types::firewall::{
+    ClusterFirewallConfig, FirewallBaseline, FirewallDrift, FirewallDriftKind, FirewallDriftObject,
+    FirewallDriftReport, FirewallIpSetConfig, FirewallIpSetEntry, FirewallRuleParams,
+    FirewallRuleType, FirewallSecurityGroupConfig, RemoteFirewallDrift,
+};
+use pdm_api_types::remotes::{Remote, RemoteType, REMOTE_ID_SCHEMA};
+use pdm_api_types::{Authid, ConfigDigest, PRIV_RESOURCE_AUDIT, PRIV_RESOURCE_MODIFY};
+
+use crate::api::remotes::RemoteIterator;
+use crate::connection;
+use crate::parallel_fetcher::ParallelFetcher;
+
+use super::firewall_config::fetch_cluster_firewall_config;
+use super::get_remote;
+
+pub const DRIFT_ROUTER: Router = Router::new().get(&API_METHOD_FIREWALL_DRIFT);
+
+pub const BASELINE_ROUTER: Router = Router::new()
+    .get(&API_METHOD_GET_FIREWALL_BASELINE)
+    .put(&API_METHOD_SET_FIREWALL_BASELINE)
+    .delete(&API_METHOD_DELETE_FIREWALL_BASELINE);
+
+async fn fetch_remote_config(
+    _context: (),
+    remote: Remote,
+    _node: String,
+) -> Result<ClusterFirewallConfig, Error> {
+    let client = connection::make_raw_client(&remote)?;
+    fetch_cluster_firewall_config(&client).await
+}
+
+async fn remote_config(remote: &str) -> Result<ClusterFirewallConfig, Error> {
+    let (remotes, _) = pdm_config::remotes::config()?;
+    let client = connection::make_raw_client(get_remote(&remotes, remote)?)?;
+    fetch_cluster_firewall_config(&client).await
+}
+
+#[api(
+    input: {
+        properties: {
+            reference: {
+                schema: REMOTE_ID_SCHEMA,
+                optional: true,
+            },
+            remotes: {
+                type: Array,
+                description: "The remotes to compare, defaults to all PVE remotes.",
+                optional: true,
+                items: { schema: REMOTE_ID_SCHEMA },
+            },
+        },
+    },
+    returns: { type: FirewallDriftReport },
+    access: {
+        permission: &Permission::Anybody,
+        description: "Requires `Resource.Audit` on the reference remote and the compared remotes. \
+            If no remotes are given, only those with `Resource.Audit` are compared.",
+    },
+)]
+/// Compare the cluster firewall configuration of PVE remotes to a reference.
+///
+/// If no `reference` remote is given, the remotes are compared to the stored baseline.
+pub async fn firewall_drift(
+    reference: Option<String>,
+    remotes: Option<Vec<String>>,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<FirewallDriftReport, Error> {
+    let auth_id: Authid = rpcenv
+        .get_auth_id()
+        .context("no authid available")?
+        .parse()?;
+    let user_info = CachedUserInfo::new()?;
+
+    let (remote_config_data, _) = pdm_config::remotes::config()?;
+
+    let check_audit = |remote: &str| -> Result<(), Error> {
+        if user_info.lookup_privs(&auth_id, &["resource", remote]) & PRIV_RESOURCE_AUDIT == 0 {
+            http_bail!(FORBIDDEN, "missing permissions on remote '{remote}'");
+        }
+        let remote = get_remote(&remote_config_data, remote)?;
+        if remote.ty != RemoteType::Pve {
+            param_bail!("remote", "remote '{}' is not a PVE remote", remote.id);
+        }
+        Ok(())
+    };
+
+    let (reference_config, baseline_time) = match &reference {
+        Some(reference) => {
+            check_audit(reference)?;
+            let config = remote_config(reference)
+                .await
+                .with_context(|| format!("failed to query reference remote '{reference}'"))?;
+            (config, None)
+        }
+        None => match pdm_config::firewall_baseline::config()?.0 {
+            Some(baseline) => (baseline.config, Some(baseline.time)),
+            None => http_bail!(
+                NOT_FOUND,
+                "no firewall baseline stored and no reference remote given"
+            ),
+        },
+    };
+
+    let mut iter = RemoteIterator::new()?.remote_type(RemoteType::Pve);
+    match &remotes {
+        Some(remotes) => {
+            for remote in remotes {
+                check_audit(remote)?;
+            }
+            let names: HashSet<&str> = remotes.iter().map(String::as_str).collect();
+            iter = iter.name_filter(&names);
+        }
+        None => iter = iter.any_privs(&user_info, &auth_id, PRIV_RESOURCE_AUDIT),
+    }
+
+    let targets = iter
+        .into_remotes()
+        .filter(|remote| Some(&remote.id) != reference.as_ref());
+
+    let response = ParallelFetcher::new(())
+        .do_for_all_remotes(targets, fetch_remote_config)
+        .await;
+
+    let mut drift = Vec::new();
+    for remote_response in response {
+        let remote = remote_response.remote().to_string();
+        match remote_response.into_data() {
+            Ok(config) => drift.push(RemoteFirewallDrift {
+                remote,
+                differences: compare_config(&reference_config, &config),
+                error: None,
+            }),
+            Err(err) => {
+                log::error!(
+                    "failed to query firewall configuration of remote '{remote}' - {err:#}"
+                );
+                drift.push(RemoteFirewallDrift {
+                    remote,
+                    differences: Vec::new(),
+                    error: Some(err.to_string()),
+                });
+            }
+        }
+    }
+    drift.sort_by(|a, b| a.remote.cmp(&b.remote));
+
+    Ok(FirewallDriftReport {
+        reference,
+        baseline_time,
+        remotes: drift,
+    })
+}
+
+#[api(
+    returns: { type: FirewallBaseline },
+    access: {
+        permission: &Permission::Privilege(&["resource"], PRIV_RESOURCE_AUDIT, false),
+    },
+)]
+/// Get the stored firewall baseline.
+pub fn get_firewall_baseline(rpcenv: &mut dyn RpcEnvironment) -> Result<FirewallBaseline, Error> {
+    let (baseline, digest) = pdm_config::firewall_baseline::config()?;
+
+    rpcenv["digest"] = digest.to_hex().into();
+
+    match baseline {
+        Some(baseline) => Ok(baseline),
+        None => http_bail!(NOT_FOUND, "no firewall baseline stored"),
+    }
+}
+
+#[api(
+    protected: true,
+    input: {
+        properties: {
+            remote: {
+                schema: REMOTE_ID_SCHEMA,
+                optional: true,
+            },
+            config: {
+                type: ClusterFirewallConfig,
+                optional: true,
+            },
+            digest: {
+                type: ConfigDigest,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource"], PRIV_RESOURCE_MODIFY, false),
+        description: "Taking the baseline from a remote additionally requires `Resource.Audit` on \
+            the remote.",
+    },
+)]
+/// Store the firewall baseline.
+///
+/// Either the current cluster firewall configuration of `remote` is stored, or the given
+/// `config`.
+pub async fn set_firewall_baseline(
+    remote: Option<String>,
+    config: Option<ClusterFirewallConfig>,
+    digest: Option<ConfigDigest>,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<(), Error> {
+    let config = match (&remote, config) {
+        (Some(remote), None) => {
+            let auth_id: Authid = rpcenv
+                .get_auth_id()
+                .context("no authid available")?
+                .parse()?;
+            let user_info = CachedUserInfo::new()?;
+            user_info.check_privs(&auth_id, &["resource", remote], PRIV_RESOURCE_AUDIT, false)?;
+
+            remote_config(remote).await?
+        }
+        (None, Some(config)) => config,
+        _ => param_bail!("remote", "exactly one of 'remote' and 'config' must be set"),
+    };
+
+    let _lock = pdm_config::firewall_baseline::lock_config()?;
+
+    let (_, config_digest) = pdm_config::firewall_baseline::config()?;
+    config_digest.detect_modification(digest.as_ref())?;
+
+    pdm_config::firewall_baseline::save_config(&FirewallBaseline {
+        remote,
+        time: proxmox_time::epoch_i64(),
+        config,
+    })
+}
+
+#[api(
+    protected: true,
+    input: {
+        properties: {
+            digest: {
+                type: ConfigDigest,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource"], PRIV_RESOURCE_MODIFY, false),
+    },
+)]
+/// Remove the stored firewall baseline.
+pub fn delete_firewall_baseline(digest: Option<ConfigDigest>) -> Result<(), Error> {
+    let _lock = pdm_config::firewall_baseline::lock_config()?;
+
+    let (_, config_digest) = pdm_config::firewall_baseline::config()?;
+    config_digest.detect_modification(digest.as_ref())?;
+
+    pdm_config::firewall_baseline::remove_config()
+}
+
+/// Collect the differences of `actual` to the `reference` configuration.
+fn compare_config(
+    reference: &ClusterFirewallConfig,
+    actual: &ClusterFirewallConfig,
+) -> Vec<FirewallDrift> {
+    let mut drift = Vec::new();
+
+    let options = |config: &ClusterFirewallConfig| -> BTreeMap<String, String> {
+        config
+            .options
+            .iter()
+            .map(|option| (option.key.clone(), option.value.clone()))
+            .collect()
+    };
+    compare_map(
+        &mut drift,
+        FirewallDriftObject::Option,
+        options(reference),
+        options(actual),
+        |key| key.to_string(),
+        |value| value.clone(),
+    );
+
+    compare_rules(
+        &mut drift,
+        FirewallDriftObject::Rule,
+        "",
+        &reference.rules,
+        &actual.rules,
+    );
+
+    let ipsets = |config: &ClusterFirewallConfig| -> BTreeMap<String, FirewallIpSetConfig> {
+        config
+            .ipsets
+            .iter()
+            .map(|ipset| (ipset.name.clone(), ipset.clone()))
+            .collect()
+    };
+    let (reference_ipsets, mut actual_ipsets) = (ipsets(reference), ipsets(actual));
+    for (name, expected) in reference_ipsets {
+        let Some(ipset) = actual_ipsets.remove(&name) else {
+            drift.push(missing(
+                FirewallDriftObject::Ipset,
+                name,
+                render_comment(&expected.comment),
+            ));
+            continue;
+        };
+        if ipset.comment != expected.comment {
+            drift.push(changed(
+                FirewallDriftObject::Ipset,
+                name.clone(),
+                render_comment(&expected.comment),
+                render_comment(&ipset.comment),
+            ));
+        }
+        let entries = |entries: Vec<FirewallIpSetEntry>| -> BTreeMap<String, FirewallIpSetEntry> {
+            entries
+                .into_iter()
+                .map(|entry| (entry.cidr.clone(), entry))
+                .collect()
+        };
+        compare_map(
+            &mut drift,
+            FirewallDriftObject::IpsetEntry,
+            entries(expected.entries),
+            entries(ipset.entries),
+            |cidr| format!("{name}/{cidr}"),
+            render_ipset_entry,
+        );
+    }
+    for (name, ipset) in actual_ipsets {
+        drift.push(unexpected(
+            FirewallDriftObject::Ipset,
+            name,
+            render_comment(&ipset.comment),
+        ));
+    }
+
+    let groups = |config: &ClusterFirewallConfig| -> BTreeMap<String, FirewallSecurityGroupConfig> {
+        config
+            .groups
+            .iter()
+            .map(|group| (group.group.clone(), group.clone()))
+            .collect()
+    };
+    let (reference_groups, mut actual_groups) = (groups(reference), groups(actual));
+    for (name, expected) in reference_groups {
+        let Some(group) = actual_groups.remove(&name) else {
+            drift.push(missing(
+                FirewallDriftObject::Group,
+                name,
+                render_comment(&expected.comment),
+            ));
+            continue;
+        };
+        if group.comment != expected.comment {
+            drift.push(changed(
+                FirewallDriftObject::Group,
+                name.clone(),
+                render_comment(&expected.comment),
+                render_comment(&group.comment),
+            ));
+        }
+        compare_rules(
+            &mut drift,
+            FirewallDriftObject::GroupRule,
+            &format!("{name}/"),
+            &expected.rules,
+            &group.rules,
+        );
+    }
+    for (name, group) in actual_groups {
+        drift.push(unexpected(
+            FirewallDriftObject::Group,
+            name,
+            render_comment(&group.comment),
+        ));
+    }
+
+    drift
+}
+
+fn compare_map<T: PartialEq>(
+    drift: &mut Vec<FirewallDrift>,
+    object: FirewallDriftObject,
+    reference: BTreeMap<String, T>,
+    mut actual: BTreeMap<String, T>,
+    id: impl Fn(&str) -> String,
+    render: impl Fn(&T) -> String,
+) {
+    for (key, expected) in reference {
+        match actual.remove(&key) {
+            None => drift.push(missing(object, id(&key), render(&expected))),
+            Some(value) if value != expected => {
+                drift.push(changed(object, id(&key), render(&expected), render(&value)))
+            }
+            Some(_) => {}
+        }
+    }
+    for (key, value) in actual {
+        drift.push(unexpected(object, id(&key), render(&value)));
+    }
+}
+
+/// Rules are compared by their position, as their order matters.
+fn compare_rules(
+    drift: &mut Vec<FirewallDrift>,
+    object: FirewallDriftObject,
+    prefix: &str,
+    reference: &[FirewallRuleParams],
+    actual: &[FirewallRuleParams],
+) {
+    for pos in 0..reference.len().max(actual.len()) {
+        let id = format!("{prefix}{pos}");
+        match (reference.get(pos), actual.get(pos)) {
+            (Some(expected), None) => drift.push(missing(object, id, render_rule(expected))),
+            (None, Some(rule)) => drift.push(unexpected(object, id, render_rule(rule))),
+            (Some(expected), Some(rule)) if expected != rule => drift.push(changed(
+                object,
+                id,
+                render_rule(expected),
+                render_rule(rule),
+            )),
+            _ => {}
+        }
+    }
+}
+
+fn missing(object: FirewallDriftObject, id: String, expected: String) -> FirewallDrift {
+    FirewallDrift {
+        object,
+        id,
+        kind: FirewallDriftKind::Missing,
+        expected: Some(expected),
+        actual: None,
+    }
+}
+
+fn unexpected(object: FirewallDriftObject, id: String, actual: String) -> FirewallDrift {
+    FirewallDrift {
+        object,
+        id,
+        kind: FirewallDriftKind::Unexpected,
+        expected: None,
+        actual: Some(actual),
+    }
+}
+
+fn changed(
+    object: FirewallDriftObject,
+    id: String,
+    expected: String,
+    actual: String,
+) -> FirewallDrift {
+    FirewallDrift {
+        object,
+        id,
+        kind: FirewallDriftKind::Changed,
+        expected: Some(expected),
+        actual: Some(actual),
+    }
+}
+
+fn render_comment(comment: &Option<String>) -> String {
+    comment.clone().unwrap_or_default()
+}
+
+fn render_ipset_entry(entry: &FirewallIpSetEntry) -> String {
+    let mut out = String::new();
+    if entry.nomatch {
+        out.push('!');
+    }
+    out.push_str(&entry.cidr);
+    if let Some(comment) = &entry.comment {
+        out.push_str(" # ");
+        out.push_str(comment);
+    }
+    out
+}
+
+/// Render a rule in the syntax of the PVE firewall configuration files.
+fn render_rule(rule: &FirewallRuleParams) -> String {
+    let mut out = String::new();
+    if rule.enable != Some(true) {
+        out.push('|');
+    }
+
+    let ty = match rule.ty {
+        Some(FirewallRuleType::In) => "IN",
+        Some(FirewallRuleType::Out) => "OUT",
+        Some(FirewallRuleType::Forward) => "FORWARD",
+        Some(FirewallRuleType::Group) => "GROUP",
+        None => "?",
+    };
+    out.push_str(ty);
+
+    let action = rule.action.as_deref().unwrap_or_default();
+    match &rule.r#macro {
+        Some(name) => out.push_str(&format!(" {name}({action})")),
+        None => out.push_str(&format!(" {action}")),
+    }
+
+    let options = [
+        ("-i", &rule.iface),
+        ("-source", &rule.source),
+        ("-dest", &rule.dest),
+        ("-p", &rule.proto),
+        ("-dport", &rule.dport),
+        ("-sport", &rule.sport),
+        ("-icmp-type", &rule.icmp_type),
+    ];
+    for (flag, value) in options {
+        if let Some(value) = value {
+            out.push_str(&format!(" {flag} {value}"));
+        }
+    }
+    if let Some(log) = rule.log {
+        out.push_str(&format!(" -log {log}"));
+    }
+    if let Some(comment) = &rule.comment {
+        out.push_str(&format!(" # {comment}"));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use pdm_api_types::firewall::{FirewallLogLevel, FirewallOption};
+
+    use super::*;
+
+    fn rule(ty: FirewallRuleType, action: &str, dport: Option<&str>) -> FirewallRuleParams {
+        FirewallRuleParams {
+            ty: Some(ty),
+            action: Some(action.into()),
+            enable: Some(true),
+            dport: dport.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn entry(cidr: &str) -> FirewallIpSetEntry {
+        FirewallIpSetEntry {
+            cidr: cidr.into(),
+            comment: None,
+            nomatch: false,
+            digest: None,
+        }
+    }
+
+    fn config() -> ClusterFirewallConfig {
+        ClusterFirewallConfig {
+            options: vec![
+                FirewallOption {
+                    key: "enable".into(),
+                    value: "1".into(),
+                },
+                FirewallOption {
+                    key: "policy_in".into(),
+                    value: "DROP".into(),
+                },
+            ],
+            rules: vec![
+                rule(FirewallRuleType::In, "ACCEPT", Some("22")),
+                rule(FirewallRuleType::In, "ACCEPT", Some("8006")),
+            ],
+            ipsets: vec![FirewallIpSetConfig {
+                name: "management".into(),
+                comment: None,
+                entries: vec![entry("10.0.0.0/24"), entry("10.0.1.0/24")],
+            }],
+            groups: vec![FirewallSecurityGroupConfig {
+                group: "web".into(),
+                comment: Some("web servers".into()),
+                rules: vec![rule(FirewallRuleType::In, "ACCEPT", Some("443"))],
+            }],
+        }
+    }
+
+    #[test]
+    fn identical_config_has_no_drift() {
+        assert!(compare_config(&config(), &config()).is_empty());
+    }
+
+    #[test]
+    fn detects_drift() {
+        let reference = config();
+        let mut actual = config();
+
+        actual.options[1].value = "ACCEPT".into();
+        actual.rules.pop();
+        actual.ipsets[0].entries.remove(0);
+        actual.ipsets[0].entries.push(entry("192.168.0.0/24"));
+        actual.groups[0].rules[0].dport = Some("80".into());
+        actual.groups.push(FirewallSecurityGroupConfig {
+            group: "extra".into(),
+            comment: None,
+            rules: Vec::new(),
+        });
+
+        let drift = compare_config(&reference, &actual);
+        let summary: Vec<(FirewallDriftObject, &str, FirewallDriftKind)> = drift
+            .iter()
+            .map(|drift| (drift.object, drift.id.as_str(), drift.kind))
+            .collect();
+
+        assert_eq!(
+            summary,
+            [
+                (
+                    FirewallDriftObject::Option,
+                    "policy_in",
+                    FirewallDriftKind::Changed
+                ),
+                (FirewallDriftObject::Rule, "1", FirewallDriftKind::Missing),
+                (
+                    FirewallDriftObject::IpsetEntry,
+                    "management/10.0.0.0/24",
+                    FirewallDriftKind::Missing
+                ),
+                (
+                    FirewallDriftObject::IpsetEntry,
+                    "management/192.168.0.0/24",
+                    FirewallDriftKind::Unexpected
+                ),
+                (
+                    FirewallDriftObject::GroupRule,
+                    "web/0",
+                    FirewallDriftKind::Changed
+                ),
+                (
+                    FirewallDriftObject::Group,
+                    "extra",
+                    FirewallDriftKind::Unexpected
+                ),
+            ]
+        );
+        assert_eq!(drift[0].expected.as_deref(), Some("DROP"));
+        assert_eq!(drift[0].actual.as_deref(), Some("ACCEPT"));
+    }
+
+    #[test]
+    fn renders_rules() {
+        let mut rule = rule(FirewallRuleType::In, "ACCEPT", None);
+        rule.r#macro = Some("SSH".into());
+        rule.source = Some("+management".into());
+        rule.log = Some(FirewallLogLevel::Nolog);
+        rule.comment = Some("admin access".into());
+        assert_eq!(
+            render_rule(&rule),
+            "IN SSH(ACCEPT) -source +management -log nolog # admin access"
+        );
+
+        rule.enable = Some(false);
+        assert!(render_rule(&rule).starts_with("|IN "));
+    }
+}
@@ -0,0 +1,306 @@
+//! Guest lifecycle operations shared between qemu VMs and containers.
+//!
+//! The corresponding PVE API endpoints are not part of the generated client, so the requests are
+//! issued directly via [`start_remote_task`] or the raw client.
+
+use std::collections::BTreeSet;
+
+use anyhow::{bail, Context, Error};
+use http::Method;
+use serde_json::{json, Value};
+
+use proxmox_access_control::CachedUserInfo;
+use proxmox_client::{ApiPathBuilder, HttpApiClient};
+use proxmox_router::{http_bail, RpcEnvironment};
+use proxmox_schema::param_bail;
+
+use pdm_api_types::guest_config::GuestConfigUpdate;
+use pdm_api_types::resource::GuestType;
+use pdm_api_types::{
+    Authid, GuestBackupMode, RemoteUpid, PRIV_RESOURCE_CREATE, PRIV_RESOURCE_MODIFY,
+};
+
+use crate::connection;
+
+use super::{connect_to_remote_by_id, find_node_for_vm, get_remote, start_remote_task};
+
+fn guest_path(guest_type: GuestType, node: &str, vmid: u32) -> String {
+    let guest_type = match guest_type {
+        GuestType::Qemu => "qemu",
+        GuestType::Lxc => "lxc",
+    };
+    format!("/nodes/{node}/{guest_type}/{vmid}")
+}
+
+async fn resolve_node(remote: &str, node: Option<String>, vmid: u32) -> Result<String, Error> {
+    let pve = connect_to_remote_by_id(remote)?;
+    find_node_for_vm(node, vmid, pve.as_ref()).await
+}
+
+/// Clone a guest, `params` are passed on to PVE as they are.
+///
+/// Requires `Resource.Create` on the guest path of the new VMID, and on the path of `pool` if
+/// the clone is added to one.
+#[allow(clippy::too_many_arguments)]
+pub(super) async fn clone_guest(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    guest_type: GuestType,
+    newid: u32,
+    pool: Option<String>,
+    mut params: Value,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<RemoteUpid, Error> {
+    let auth_id: Authid = rpcenv
+        .get_auth_id()
+        .context("no authid available")?
+        .parse()?;
+
+    let user_info = CachedUserInfo::new()?;
+
+    let privs = user_info.lookup_privs(
+        &auth_id,
+        &["resource", &remote, "guest", &newid.to_string()],
+    );
+    if privs & PRIV_RESOURCE_CREATE == 0 {
+        http_bail!(
+            FORBIDDEN,
+            "missing PRIV_RESOURCE_CREATE on new vmid {newid}"
+        );
+    }
+
+    if let Some(pool) = pool {
+        let privs = crate::acl::lookup_pool_privs(&user_info, &auth_id, &remote, &pool);
+        if privs & PRIV_RESOURCE_CREATE == 0 {
+            http_bail!(FORBIDDEN, "missing PRIV_RESOURCE_CREATE on pool {pool}");
+        }
+        params["pool"] = pool.into();
+    }
+
+    let node = resolve_node(&remote, node, vmid).await?;
+
+    params["newid"] = newid.into();
+
+    let path = format!("{}/clone", guest_path(guest_type, &node, vmid));
+    start_remote_task(&remote, Method::POST, &path, Some(params)).await
+}
+
+/// Destroy a guest and all of its disks.
+pub(super) async fn destroy_guest(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    guest_type: GuestType,
+    purge: bool,
+    destroy_unreferenced_disks: bool,
+) -> Result<RemoteUpid, Error> {
+    let node = resolve_node(&remote, node, vmid).await?;
+
+    let path = ApiPathBuilder::new(guest_path(guest_type, &node, vmid))
+        .arg("purge", purge)
+        .arg("destroy-unreferenced-disks", destroy_unreferenced_disks)
+        .build();
+    start_remote_task(&remote, Method::DELETE, &path, None::<()>).await
+}
+
+/// Create a snapshot of a guest, `vmstate` is only supported for qemu VMs.
+pub(super) async fn create_snapshot(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    guest_type: GuestType,
+    snapname: String,
+    description: Option<String>,
+    vmstate: Option<bool>,
+) -> Result<RemoteUpid, Error> {
+    let node = resolve_node(&remote, node, vmid).await?;
+
+    let mut params = json!({ "snapname": snapname });
+    if let Some(description) = description {
+        params["description"] = description.into();
+    }
+    if let Some(vmstate) = vmstate {
+        params["vmstate"] = vmstate.into();
+    }
+
+    let path = format!("{}/snapshot", guest_path(guest_type, &node, vmid));
+    start_remote_task(&remote, Method::POST, &path, Some(params)).await
+}
+
+/// Roll a guest back to a snapshot.
+pub(super) async fn rollback_snapshot(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    guest_type: GuestType,
+    snapname: String,
+    start: bool,
+) -> Result<RemoteUpid, Error> {
+    let node = resolve_node(&remote, node, vmid).await?;
+
+    let path = format!(
+        "{}/snapshot/{snapname}/rollback",
+        guest_path(guest_type, &node, vmid)
+    );
+    start_remote_task(
+        &remote,
+        Method::POST,
+        &path,
+        Some(json!({ "start": start })),
+    )
+    .await
+}
+
+/// Delete a snapshot of a guest.
+pub(super) async fn delete_snapshot(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    guest_type: GuestType,
+    snapname: String,
+    force: bool,
+) -> Result<RemoteUpid, Error> {
+    let node = resolve_node(&remote, node, vmid).await?;
+
+    let path = ApiPathBuilder::new(format!(
+        "{}/snapshot/{snapname}",
+        guest_path(guest_type, &node, vmid)
+    ))
+    .arg("force", force)
+    .build();
+    start_remote_task(&remote, Method::DELETE, &path, None::<()>).await
+}
+
+/// Back up a single guest to a storage of its cluster.
+pub(super) async fn backup_guest(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    storage: String,
+    mode: GuestBackupMode,
+    notes_template: Option<String>,
+    protected: bool,
+) -> Result<RemoteUpid, Error> {
+    let node = resolve_node(&remote, node, vmid).await?;
+
+    let mut params = json!({
+        "vmid": vmid.to_string(),
+        "storage": storage,
+        "mode": mode,
+        "protected": protected,
+    });
+    if let Some(notes_template) = notes_template {
+        params["notes-template"] = notes_template.into();
+    }
+
+    let path = format!("/nodes/{node}/vzdump");
+    start_remote_task(&remote, Method::POST, &path, Some(params)).await
+}
+
+/// Update the configuration of a guest.
+///
+/// The digest is passed on as it is, so PVE rejects the update if the configuration was changed
+/// in the meantime.
+///
+/// Since tags can grant privileges on the guest, adding or removing a tag requires
+/// `Resource.Modify` on the path of the tag.
+pub(super) async fn update_config(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    guest_type: GuestType,
+    mut update: GuestConfigUpdate,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<(), Error> {
+    let auth_id: Authid = rpcenv
+        .get_auth_id()
+        .context("no authid available")?
+        .parse()?;
+
+    match guest_type {
+        GuestType::Qemu => {
+            if update.swap.is_some() {
+                param_bail!("swap", "only supported for containers");
+            }
+        }
+        GuestType::Lxc => {
+            if update.sockets.is_some() {
+                param_bail!("sockets", "only supported for VMs");
+            }
+            let delete_boot = update.delete.iter().flatten().any(|key| key == "boot");
+            if update.boot.is_some() || delete_boot {
+                param_bail!("boot", "only supported for VMs");
+            }
+        }
+    }
+
+    let net = update.net.take().unwrap_or_default();
+    let delete = update.delete.take().unwrap_or_default();
+
+    let mut params = serde_json::to_value(&update)?;
+
+    for device in net {
+        // the schema ensures the '=' is there
+        let (key, config) = device.split_once('=').unwrap_or((&device, ""));
+        if delete.iter().any(|deleted| deleted == key) {
+            param_bail!("net", "cannot set and delete '{key}' at the same time");
+        }
+        params[key] = config.into();
+    }
+
+    if !delete.is_empty() {
+        params["delete"] = delete.join(",").into();
+    }
+
+    let has_changes = params
+        .as_object()
+        .is_some_and(|params| params.keys().any(|key| key != "digest"));
+    if !has_changes {
+        bail!("no configuration changes requested");
+    }
+
+    let node = resolve_node(&remote, node, vmid).await?;
+
+    let (remotes, _) = pdm_config::remotes::config()?;
+    let client = connection::make_raw_client(get_remote(&remotes, &remote)?)?;
+
+    let path = format!("/api2/extjs{}/config", guest_path(guest_type, &node, vmid));
+
+    let delete_tags = delete.iter().any(|key| key == "tags");
+    if update.tags.is_some() || delete_tags {
+        let current: Value = client.get(&path).await?.expect_json()?.data;
+
+        let old_tags = split_tags(current["tags"].as_str().unwrap_or_default());
+        let new_tags = match &update.tags {
+            Some(tags) if !delete_tags => split_tags(tags),
+            _ => BTreeSet::new(),
+        };
+
+        let user_info = CachedUserInfo::new()?;
+        for tag in old_tags.symmetric_difference(&new_tags) {
+            if user_info.lookup_privs(&auth_id, &["tag", *tag]) & PRIV_RESOURCE_MODIFY == 0 {
+                http_bail!(FORBIDDEN, "missing PRIV_RESOURCE_MODIFY on tag '{tag}'");
+            }
+        }
+
+        // Make sure the tags were not changed since they were checked.
+        if update.digest.is_none() {
+            params["digest"] = current["digest"].clone();
+        }
+    }
+
+    client
+        .request(Method::PUT, &path, Some(params))
+        .await?
+        .nodata()?;
+
+    Ok(())
+}
+
+/// Split a PVE tag list, which may be separated by semicolons, commas or spaces.
+fn split_tags(tags: &str) -> BTreeSet<&str> {
+    tags.split([';', ',', ' '])
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
@@ -0,0 +1,416 @@
+//! Guest placement recommendations to even out the load of PVE nodes.
+
+use std::collections::HashSet;
+
+use anyhow::{bail, format_err, Context, Error};
+
+use proxmox_access_control::CachedUserInfo;
+use proxmox_rest_server::WorkerTask;
+use proxmox_router::{http_bail, Permission, Router, RpcEnvironment};
+use proxmox_rrd_api_types::RrdTimeframe;
+use proxmox_schema::api;
+
+use pdm_api_types::load_balancing::{MigrationKind, MigrationPlan, ProposedMigration};
+use pdm_api_types::remotes::{Remote, RemoteType};
+use pdm_api_types::resource::Resource;
+use pdm_api_types::{
+    Authid, PRIV_RESOURCE_AUDIT, PRIV_RESOURCE_DELETE, PRIV_RESOURCE_MIGRATE, UPID_SCHEMA,
+    VIEW_ID_SCHEMA,
+};
+
+use crate::connection;
+use crate::metric_collection::load_balancing;
+use crate::remote_cache::RemoteMappingCache;
+use crate::views;
+
+use super::{
+    build_migration_endpoint, check_guest_permissions, get_remote, new_remote_upid,
+    select_migration_target_node, wait_for_remote_task,
+};
+
+pub const ROUTER: Router = Router::new()
+    .get(&API_METHOD_GET_MIGRATION_PLAN)
+    .post(&API_METHOD_BALANCE_LOAD);
+
+#[api(
+    input: {
+        properties: {
+            timeframe: {
+                type: RrdTimeframe,
+                optional: true,
+            },
+            view: {
+                schema: VIEW_ID_SCHEMA,
+                optional: true,
+            },
+            "allow-remote-migration": {
+                description: "Also propose migrations to nodes of other remotes.",
+                optional: true,
+                default: false,
+            },
+            "max-migrations": {
+                description: "Maximum number of proposed migrations.",
+                optional: true,
+                minimum: 1,
+                maximum: 100,
+                default: 10,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Anybody,
+        description: "Only nodes on which the user has `Resource.Audit` are considered. Guests \
+            are only proposed for migration if the user has `Resource.Migrate` on them, \
+            migrations to other remotes additionally need `Resource.Delete` on the guest and \
+            `Resource.Migrate` on the guest path of the target remote.",
+    },
+    returns: { type: MigrationPlan },
+)]
+/// Propose guest migrations which even out the load of the nodes of all PVE remotes.
+///
+/// The load of a node is the higher one of its average CPU and memory usage over the given
+/// timeframe (default: hour).
+pub fn get_migration_plan(
+    timeframe: Option<RrdTimeframe>,
+    view: Option<String>,
+    allow_remote_migration: bool,
+    max_migrations: u64,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<MigrationPlan, Error> {
+    let auth_id: Authid = rpcenv
+        .get_auth_id()
+        .context("no authid available")?
+        .parse()?;
+
+    compute_plan(
+        &auth_id,
+        timeframe,
+        view.as_deref(),
+        allow_remote_migration,
+        max_migrations as usize,
+    )
+}
+
+#[api(
+    input: {
+        properties: {
+            timeframe: {
+                type: RrdTimeframe,
+                optional: true,
+            },
+            view: {
+                schema: VIEW_ID_SCHEMA,
+                optional: true,
+            },
+            "allow-remote-migration": {
+                description: "Also migrate guests to nodes of other remotes.",
+                optional: true,
+                default: false,
+            },
+            "max-migrations": {
+                description: "Maximum number of migrations.",
+                optional: true,
+                minimum: 1,
+                maximum: 100,
+                default: 10,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Anybody,
+        description: "Same as for the migration plan.",
+    },
+    returns: { schema: UPID_SCHEMA },
+)]
+/// Compute a migration plan and run its migrations one after another in a worker task.
+pub fn balance_load(
+    timeframe: Option<RrdTimeframe>,
+    view: Option<String>,
+    allow_remote_migration: bool,
+    max_migrations: u64,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<String, Error> {
+    let auth_id: Authid = rpcenv
+        .get_auth_id()
+        .context("no authid available")?
+        .parse()?;
+
+    let plan = compute_plan(
+        &auth_id,
+        timeframe,
+        view.as_deref(),
+        allow_remote_migration,
+        max_migrations as usize,
+    )?;
+
+    WorkerTask::spawn(
+        "load-balancing",
+        None,
+        auth_id.to_string(),
+        true,
+        move |_worker| async move { run_migration_plan(plan).await },
+    )
+}
+
+fn compute_plan(
+    auth_id: &Authid,
+    timeframe: Option<RrdTimeframe>,
+    view: Option<&str>,
+    allow_remote_migration: bool,
+    max_migrations: usize,
+) -> Result<MigrationPlan, Error> {
+    let user_info = CachedUserInfo::new()?;
+
+    if let Some(view) = view {
+        user_info.check_privs(auth_id, &["view", view], PRIV_RESOURCE_AUDIT, false)?;
+    } else if !user_info.any_privs_below(auth_id, &["resource"], PRIV_RESOURCE_AUDIT)? {
+        http_bail!(FORBIDDEN, "user has no access to resources");
+    }
+
+    let view = views::get_optional_view(view)?;
+
+    let (remotes_config, _) = pdm_config::remotes::config()?;
+
+    let remotes: Vec<String> = remotes_config
+        .into_iter()
+        .filter(|(_, remote)| remote.ty == RemoteType::Pve)
+        .map(|(remote_name, _)| remote_name)
+        .filter(|remote_name| match &view {
+            Some(view) => !view.can_skip_remote(remote_name),
+            None => {
+                user_info.lookup_privs(auth_id, &["resource", remote_name]) & PRIV_RESOURCE_AUDIT
+                    != 0
+            }
+        })
+        .collect();
+
+    let is_resource_included = |remote: &str, resource: &Resource| {
+        if let Some(view) = &view {
+            if !view.resource_matches(remote, resource) {
+                return false;
+            }
+        }
+
+        match resource {
+            Resource::PveQemu(qemu) => check_guest_permissions(
+                auth_id,
+                &user_info,
+                remote,
+                PRIV_RESOURCE_MIGRATE,
+                qemu.vmid,
+            ),
+            _ => true,
+        }
+    };
+
+    let (nodes, guests) = load_balancing::collect_state(
+        &remotes,
+        timeframe.unwrap_or(RrdTimeframe::Hour),
+        is_resource_included,
+    );
+
+    // guests migrated to another remote keep their VMID, so it must not be in use there
+    let used_vmids: HashSet<(String, u32)> = if allow_remote_migration {
+        used_vmids(&remotes)
+    } else {
+        HashSet::new()
+    };
+
+    let mapping_cache = RemoteMappingCache::get();
+
+    let plan = load_balancing::plan_migrations(
+        nodes,
+        guests,
+        max_migrations,
+        allow_remote_migration,
+        |guest, target| {
+            if guest.remote == target.remote {
+                return true;
+            }
+
+            !used_vmids.contains(&(target.remote.clone(), guest.vmid))
+                && mapping_cache
+                    .node_name_to_hostname(&target.remote, &target.node)
+                    .is_some()
+                && check_guest_permissions(
+                    auth_id,
+                    &user_info,
+                    &guest.remote,
+                    PRIV_RESOURCE_DELETE,
+                    guest.vmid,
+                )
+                && check_guest_permissions(
+                    auth_id,
+                    &user_info,
+                    &target.remote,
+                    PRIV_RESOURCE_MIGRATE,
+                    guest.vmid,
+                )
+        },
+    );
+
+    Ok(plan)
+}
+
+/// Collect the VMIDs of all guests of the given remotes.
+fn used_vmids(remotes: &[String]) -> HashSet<(String, u32)> {
+    let mut vmids = HashSet::new();
+
+    for remote in remotes {
+        let Some(cached) = crate::api::resources::get_cached_resources(remote, i64::MAX as u64)
+        else {
+            continue;
+        };
+
+        for resource in cached.resources {
+            match resource {
+                Resource::PveQemu(qemu) => vmids.insert((remote.clone(), qemu.vmid)),
+                Resource::PveLxc(lxc) => vmids.insert((remote.clone(), lxc.vmid)),
+                _ => continue,
+            };
+        }
+    }
+
+    vmids
+}
+
+async fn run_migration_plan(plan: MigrationPlan) -> Result<(), Error> {
+    let total = plan.migrations.len();
+
+    if total == 0 {
+        log::info!("no migrations necessary");
+        return Ok(());
+    }
+
+    log::info!(
+        "running {total} migrations, reducing the imbalance from {:.3} to {:.3}",
+        plan.imbalance,
+        plan.planned_imbalance,
+    );
+
+    let (remotes, _) = pdm_config::remotes::config()?;
+
+    let mut failed = 0;
+
+    for migration in &plan.migrations {
+        let label = format!(
+            "{}/qemu/{} ({})",
+            migration.remote, migration.vmid, migration.name
+        );
+
+        log::info!(
+            "{label}: migrating from {}/{} to {}/{}",
+            migration.remote,
+            migration.node,
+            migration.target_remote,
+            migration.target_node,
+        );
+
+        let result = async {
+            let source = get_remote(&remotes, &migration.remote)?;
+            match migration.kind {
+                MigrationKind::Local => migrate_local(source, migration, &label).await,
+                MigrationKind::Remote => {
+                    let target = get_remote(&remotes, &migration.target_remote)?;
+                    migrate_remote(source, target, migration, &label).await
+                }
+            }
+        }
+        .await;
+
+        match result {
+            Ok(()) => log::info!("{label}: migration finished successfully"),
+            Err(err) => {
+                log::error!("{label}: migration failed - {err:#}");
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!("{failed} of {total} migrations failed");
+    }
+
+    Ok(())
+}
+
+async fn migrate_local(
+    remote: &Remote,
+    migration: &ProposedMigration,
+    label: &str,
+) -> Result<(), Error> {
+    let client = connection::make_pve_client(remote)?;
+    let node = migration.node.as_str();
+
+    let preconditions = client
+        .qemu_migrate_preconditions(node, migration.vmid, Some(migration.target_node.clone()))
+        .await?;
+
+    if !preconditions.local_resources.is_empty() {
+        bail!(
+            "guest uses local resources: {}",
+            preconditions.local_resources.join(", ")
+        );
+    }
+
+    let params = pve_api_types::MigrateQemu {
+        bwlimit: None,
+        force: None,
+        migration_network: None,
+        migration_type: None,
+        online: Some(true),
+        target: migration.target_node.clone(),
+        targetstorage: None,
+        with_local_disks: (!preconditions.local_disks.is_empty()).then_some(true),
+        with_conntrack_state: None,
+    };
+
+    let upid = client.migrate_qemu(node, migration.vmid, params).await?;
+    let upid = new_remote_upid(remote.id.clone(), upid).await?;
+    log::info!("{label}: started task {upid}");
+
+    wait_for_remote_task(&client, node, &upid).await
+}
+
+async fn migrate_remote(
+    source: &Remote,
+    target: &Remote,
+    migration: &ProposedMigration,
+    label: &str,
+) -> Result<(), Error> {
+    let client = connection::make_pve_client(source)?;
+    let node = migration.node.as_str();
+
+    let hostname = RemoteMappingCache::get()
+        .node_name_to_hostname(&target.id, &migration.target_node)
+        .map(str::to_string)
+        .ok_or_else(|| {
+            format_err!(
+                "no hostname known for node {} of remote {}",
+                migration.target_node,
+                target.id
+            )
+        })?;
+
+    let target_node = select_migration_target_node(target, Some(&hostname))?;
+    let target_endpoint = build_migration_endpoint(target, target_node)?;
+
+    // "1" maps every storage and bridge to the one with the same name on the target
+    let params = pve_api_types::RemoteMigrateQemu {
+        target_bridge: vec!["1".to_string()],
+        target_storage: vec!["1".to_string()],
+        delete: Some(true),
+        online: Some(true),
+        target_vmid: None,
+        target_endpoint,
+        bwlimit: None,
+    };
+
+    let upid = client
+        .remote_migrate_qemu(node, migration.vmid, params)
+        .await?;
+    let upid = new_remote_upid(source.id.clone(), upid).await?;
+    log::info!("{label}: started task {upid}");
+
+    wait_for_remote_task(&client, node, &upid).await
+}
@@ -1,10 +1,12 @@
 //! Manage PVE instances.
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{bail, format_err, Context, Error};
 
 use proxmox_access_control::CachedUserInfo;
+use proxmox_client::HttpApiClient;
 use proxmox_router::{
     http_bail, http_err, list_subdirs_api_method, Permission, Router, RpcEnvironment, SubdirMap,
 };
@@ -35,10 +37,17 @@ use crate::connection::{self, probe_tls_connection};
 use crate::remote_tasks;
 use crate::remote_updates::get_available_updates_for_remote;
 
+pub mod bulk_action;
+pub mod cluster_nodes;
 mod firewall;
+mod firewall_config;
+mod firewall_drift;
+mod lifecycle;
+mod load_balancing;
 mod lxc;
 mod node;
 mod qemu;
+mod rolling_upgrade;
 mod rrddata;
 mod storage;
 pub mod tasks;
@@ -49,8 +58,10 @@ pub const ROUTER: Router = Router::new()
 
 #[sortable]
 const SUBDIRS: SubdirMap = &sorted!([
+    ("bulk-action", &bulk_action::ROUTER),
     ("remotes", &REMOTES_ROUTER),
     ("firewall", &firewall::PVE_FW_ROUTER),
+    ("load-balancing", &load_balancing::ROUTER),
     ("probe-tls", &Router::new().post(&API_METHOD_PROBE_TLS)),
     ("scan", &Router::new().post(&API_METHOD_SCAN_REMOTE_PVE)),
     (
@@ -69,12 +80,14 @@ const MAIN_ROUTER: Router = Router::new()
 
 #[sortable]
 const REMOTE_SUBDIRS: SubdirMap = &sorted!([
+    ("cluster-nodes", &cluster_nodes::ROUTER),
     ("lxc", &lxc::ROUTER),
     ("firewall", &firewall::CLUSTER_FW_ROUTER),
     ("nodes", &NODES_ROUTER),
     ("options", &OPTIONS_ROUTER),
     ("qemu", &qemu::ROUTER),
     ("resources", &RESOURCES_ROUTER),
+    ("rolling-upgrade", &rolling_upgrade::ROUTER),
     ("cluster-status", &STATUS_ROUTER),
     ("tasks", &tasks::ROUTER),
     ("updates", &Router::new().get(&API_METHOD_GET_UPDATES)),
@@ -96,6 +109,53 @@ pub async fn new_remote_upid(remote: String, upid: PveUpid) -> Result<RemoteUpid
     Ok(remote_upid)
 }
 
+/// Start a task on a PVE remote via an API endpoint which is not covered by [`PveClient`].
+///
+/// `path_and_query` is relative to `/api2/extjs`. The started task is tracked like the ones
+/// started via the regular client.
+async fn start_remote_task<P: serde::Serialize>(
+    remote: &str,
+    method: http::Method,
+    path_and_query: &str,
+    params: Option<P>,
+) -> Result<RemoteUpid, Error> {
+    let (remotes, _) = pdm_config::remotes::config()?;
+    let client = connection::make_raw_client(get_remote(&remotes, remote)?)?;
+
+    let path_and_query = format!("/api2/extjs{path_and_query}");
+    let upid: PveUpid = client
+        .request(method, &path_and_query, params)
+        .await?
+        .expect_json()?
+        .data;
+
+    new_remote_upid(remote.to_string(), upid).await
+}
+
+// pve-http-server TCP connection timeout is 5 seconds, use a lower amount with some margin for
+// latency in order to avoid re-opening TCP connections for every polling request.
+const TASK_POLLING_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Wait for a task on a PVE remote to finish, fails if the task did not finish successfully.
+async fn wait_for_remote_task(
+    client: &PveClient,
+    node: &str,
+    upid: &RemoteUpid,
+) -> Result<(), Error> {
+    loop {
+        tokio::time::sleep(TASK_POLLING_INTERVAL).await;
+
+        let status = client.get_task_status(node, upid.upid()).await?;
+
+        if !status.is_running() {
+            if status.finished_successfully() == Some(true) {
+                return Ok(());
+            }
+            bail!("task {upid} did not finish successfully");
+        }
+    }
+}
+
 pub(crate) fn get_remote<'a>(
     config: &'a SectionConfigData<Remote>,
     id: &str,
@@ -139,10 +199,11 @@ pub fn connect_to_remote_by_id(id: &str) -> Result<Arc<PveClient>, Error> {
 )]
 /// Return the list of PVE remotes
 fn list_remotes() -> Result<Vec<RemoteListEntry>, Error> {
+    let now = proxmox_time::epoch_i64();
     Ok(super::remotes::RemoteIterator::new()?
         .remote_type(RemoteType::Pve)
-        .into_names()
-        .map(|name| RemoteListEntry { remote: name })
+        .into_remotes()
+        .map(|remote| RemoteListEntry::new(&remote, now))
         .collect())
 }
 
@@ -322,7 +383,9 @@ fn check_guest_list_permissions(
 
     let user_info = CachedUserInfo::new()?;
 
-    if !user_info.any_privs_below(&auth_id, &["resource", remote], PRIV_RESOURCE_AUDIT)? {
+    if !user_info.any_privs_below(&auth_id, &["resource", remote], PRIV_RESOURCE_AUDIT)?
+        && !user_info.any_privs_below(&auth_id, &["tag"], PRIV_RESOURCE_AUDIT)?
+    {
         http_bail!(FORBIDDEN, "user has no access to resource list");
     }
 
@@ -332,7 +395,7 @@ fn check_guest_list_permissions(
     Ok((auth_id, user_info, top_level_allowed))
 }
 
-/// Shared permission check for a specific guest.
+/// Shared permission check for a specific guest, including the ACLs of its pool and tags.
 fn check_guest_permissions(
     auth_id: &Authid,
     user_info: &CachedUserInfo,
@@ -340,9 +403,7 @@ fn check_guest_permissions(
     privilege: u64,
     vmid: u32,
 ) -> bool {
-    let auth_privs =
-        user_info.lookup_privs(auth_id, &["resource", remote, "guest", &vmid.to_string()]);
-    auth_privs & privilege != 0
+    crate::acl::lookup_guest_privs(user_info, auth_id, remote, vmid) & privilege != 0
 }
 
 async fn find_node_for_vm(
@@ -373,12 +434,13 @@ fn check_guest_delete_perms(
         .context("no authid available")?
         .parse()?;
 
-    CachedUserInfo::new()?.check_privs(
-        &auth_id,
-        &["resource", remote, "guest", &vmid.to_string()],
-        PRIV_RESOURCE_DELETE,
-        false,
-    )
+    let user_info = CachedUserInfo::new()?;
+
+    if !check_guest_permissions(&auth_id, &user_info, remote, PRIV_RESOURCE_DELETE, vmid) {
+        http_bail!(FORBIDDEN, "missing PRIV_RESOURCE_DELETE on guest {vmid}");
+    }
+
+    Ok(())
 }
 
 #[api(
@@ -460,6 +522,16 @@ pub async fn scan_remote_pve(
         authid: authid.clone(),
         token,
         web_url: None,
+        token_created: None,
+        token_expire: None,
+        sync_nodes: None,
+        http_proxy: None,
+        connect_timeout: None,
+        request_timeout: None,
+        max_connections: None,
+        maintenance: None,
+        maintenance_nodes: None,
+        maintenance_end: None,
     };
 
     let client = connect_or_login(&remote)
@@ -551,6 +623,16 @@ pub async fn list_realm_remote_pve(
         authid: "root@pam".parse()?,
         token: String::new(),
         web_url: None,
+        token_created: None,
+        token_expire: None,
+        sync_nodes: None,
+        http_proxy: None,
+        connect_timeout: None,
+        request_timeout: None,
+        max_connections: None,
+        maintenance: None,
+        maintenance_nodes: None,
+        maintenance_end: None,
     };
 
     let client = connection::make_pve_client(&remote)?;
@@ -6,11 +6,15 @@ use proxmox_router::{
 };
 use proxmox_schema::api;
 use proxmox_sortable_macro::sortable;
+use serde_json::json;
 
+use pdm_api_types::guest_config::GuestConfigUpdate;
 use pdm_api_types::remotes::REMOTE_ID_SCHEMA;
+use pdm_api_types::resource::GuestType;
 use pdm_api_types::{
-    Authid, ConfigurationState, RemoteUpid, CIDR_FORMAT, NODE_SCHEMA, PRIV_RESOURCE_AUDIT,
-    PRIV_RESOURCE_MANAGE, PRIV_RESOURCE_MIGRATE, SNAPSHOT_NAME_SCHEMA, VMID_SCHEMA,
+    Authid, ConfigurationState, GuestBackupMode, RemoteUpid, CIDR_FORMAT, NODE_SCHEMA,
+    PRIV_RESOURCE_AUDIT, PRIV_RESOURCE_DELETE, PRIV_RESOURCE_MANAGE, PRIV_RESOURCE_MIGRATE,
+    PRIV_RESOURCE_MODIFY, PVE_STORAGE_ID_SCHEMA, SNAPSHOT_NAME_SCHEMA, VMID_SCHEMA,
 };
 
 use pve_api_types::{PendingConfigValue, QemuMigratePreconditions, StartQemuMigrationType};
@@ -19,7 +23,7 @@ use crate::api::pve::get_remote;
 
 use super::{
     check_guest_delete_perms, check_guest_list_permissions, check_guest_permissions,
-    connect_to_remote, connect_to_remote_by_id, find_node_for_vm, new_remote_upid,
+    connect_to_remote, connect_to_remote_by_id, find_node_for_vm, lifecycle, new_remote_upid,
 };
 
 pub const ROUTER: Router = Router::new()
@@ -28,10 +32,18 @@ pub const ROUTER: Router = Router::new()
 
 const QEMU_VM_ROUTER: Router = Router::new()
     .get(&list_subdirs_api_method!(QEMU_VM_SUBDIRS))
+    .delete(&API_METHOD_QEMU_DESTROY)
     .subdirs(QEMU_VM_SUBDIRS);
 #[sortable]
 const QEMU_VM_SUBDIRS: SubdirMap = &sorted!([
-    ("config", &Router::new().get(&API_METHOD_QEMU_GET_CONFIG)),
+    ("backup", &Router::new().post(&API_METHOD_QEMU_BACKUP)),
+    ("clone", &Router::new().post(&API_METHOD_QEMU_CLONE)),
+    (
+        "config",
+        &Router::new()
+            .get(&API_METHOD_QEMU_GET_CONFIG)
+            .put(&API_METHOD_QEMU_UPDATE_CONFIG)
+    ),
     ("pending", &Router::new().get(&API_METHOD_QEMU_GET_PENDING)),
     ("firewall", &super::firewall::QEMU_FW_ROUTER),
     ("rrddata", &super::rrddata::QEMU_RRD_ROUTER),
@@ -39,6 +51,7 @@ const QEMU_VM_SUBDIRS: SubdirMap = &sorted!([
     ("status", &Router::new().get(&API_METHOD_QEMU_GET_STATUS)),
     ("stop", &Router::new().post(&API_METHOD_QEMU_STOP)),
     ("shutdown", &Router::new().post(&API_METHOD_QEMU_SHUTDOWN)),
+    ("snapshot", &QEMU_SNAPSHOT_ROUTER),
     (
         "migrate",
         &Router::new()
@@ -51,6 +64,20 @@ const QEMU_VM_SUBDIRS: SubdirMap = &sorted!([
     ),
 ]);
 
+const QEMU_SNAPSHOT_ROUTER: Router = Router::new()
+    .post(&API_METHOD_QEMU_CREATE_SNAPSHOT)
+    .match_all("snapname", &QEMU_SNAPSHOT_ITEM_ROUTER);
+
+const QEMU_SNAPSHOT_ITEM_ROUTER: Router = Router::new()
+    .get(&list_subdirs_api_method!(QEMU_SNAPSHOT_SUBDIRS))
+    .delete(&API_METHOD_QEMU_DELETE_SNAPSHOT)
+    .subdirs(QEMU_SNAPSHOT_SUBDIRS);
+#[sortable]
+const QEMU_SNAPSHOT_SUBDIRS: SubdirMap = &sorted!([(
+    "rollback",
+    &Router::new().post(&API_METHOD_QEMU_ROLLBACK_SNAPSHOT)
+),]);
+
 #[api(
     input: {
         properties: {
@@ -67,7 +94,8 @@ const QEMU_VM_SUBDIRS: SubdirMap = &sorted!([
         items: { type: pve_api_types::VmEntry },
     },
     access: {
-        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_RESOURCE_AUDIT, false),
+        permission: &Permission::Anybody,
+        description: "Only guests with Resource.Audit on the remote, the guest, its pool or one of its tags are listed.",
     },
 )]
 /// Query the remote's list of qemu VMs. If no node is provided, the all nodes are queried.
@@ -76,8 +104,6 @@ pub async fn list_qemu(
     node: Option<String>,
     rpcenv: &mut dyn RpcEnvironment,
 ) -> Result<Vec<pve_api_types::VmEntry>, Error> {
-    // FIXME: top_level_allowed is always true because of schema check above, replace with Anybody
-    // and fine-grained checks once those are implemented for all API calls..
     let (auth_id, user_info, top_level_allowed) = check_guest_list_permissions(&remote, rpcenv)?;
 
     let pve = connect_to_remote_by_id(&remote)?;
@@ -180,6 +206,38 @@ pub async fn qemu_get_pending(
     Ok(pve.qemu_get_pending(&node, vmid).await?)
 }
 
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            update: {
+                type: GuestConfigUpdate,
+                flatten: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}", "guest", "{vmid}"], PRIV_RESOURCE_MODIFY, false),
+        description: "Adding or removing tags also requires PRIV_RESOURCE_MODIFY on /tag/{tag}.",
+    },
+)]
+/// Update the configuration of a qemu VM on a remote. Changes which cannot be applied to the
+/// running VM are left pending until its next restart.
+pub async fn qemu_update_config(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    update: GuestConfigUpdate,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<(), Error> {
+    lifecycle::update_config(remote, node, vmid, GuestType::Qemu, update, rpcenv).await
+}
+
 #[api(
     input: {
         properties: {
@@ -530,7 +588,7 @@ pub async fn qemu_remote_migrate(
             "guest",
             &target_vmid.unwrap_or(vmid).to_string(),
         ],
-    );
+    ) | crate::acl::lookup_guest_tag_privs(&user_info, &auth_id, &remote, vmid);
     if target_privs & PRIV_RESOURCE_MIGRATE == 0 {
         http_bail!(
             FORBIDDEN,
@@ -574,3 +632,304 @@ pub async fn qemu_remote_migrate(
 
     new_remote_upid(source, upid).await
 }
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            newid: {
+                schema: VMID_SCHEMA,
+            },
+            name: {
+                type: String,
+                optional: true,
+                description: "Name of the new VM.",
+            },
+            target: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            full: {
+                type: bool,
+                optional: true,
+                description: "Create a full copy of all disks instead of a linked clone. Required \
+                    when cloning a VM which is not a template.",
+            },
+            storage: {
+                schema: PVE_STORAGE_ID_SCHEMA,
+                optional: true,
+            },
+            pool: {
+                type: String,
+                optional: true,
+                description: "Add the new VM to this resource pool.",
+            },
+        },
+    },
+    returns: { type: RemoteUpid },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}", "guest", "{vmid}"], PRIV_RESOURCE_MANAGE, false),
+        description: "Also requires PRIV_RESOURCE_CREATE on /resource/{remote}/guest/{newid} and, \
+            if a pool is given, on /resource/{remote}/pool/{pool}.",
+    },
+)]
+/// Clone a remote VM, usually from a template.
+#[allow(clippy::too_many_arguments)]
+pub async fn qemu_clone(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    newid: u32,
+    name: Option<String>,
+    target: Option<String>,
+    full: Option<bool>,
+    storage: Option<String>,
+    pool: Option<String>,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<RemoteUpid, Error> {
+    let mut params = json!({});
+    if let Some(name) = name {
+        params["name"] = name.into();
+    }
+    if let Some(target) = target {
+        params["target"] = target.into();
+    }
+    if let Some(full) = full {
+        params["full"] = full.into();
+    }
+    if let Some(storage) = storage {
+        params["storage"] = storage.into();
+    }
+
+    lifecycle::clone_guest(
+        remote,
+        node,
+        vmid,
+        GuestType::Qemu,
+        newid,
+        pool,
+        params,
+        rpcenv,
+    )
+    .await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            purge: {
+                description: "Remove the VM from backup jobs, replication jobs and HA.",
+                optional: true,
+                default: false,
+            },
+            "destroy-unreferenced-disks": {
+                description: "Also destroy disks which are not referenced in the config but match the VMID.",
+                optional: true,
+                default: false,
+            },
+        },
+    },
+    returns: { type: RemoteUpid },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}", "guest", "{vmid}"], PRIV_RESOURCE_DELETE, false),
+    },
+)]
+/// Destroy a remote VM including its disks.
+pub async fn qemu_destroy(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    purge: bool,
+    destroy_unreferenced_disks: bool,
+) -> Result<RemoteUpid, Error> {
+    lifecycle::destroy_guest(
+        remote,
+        node,
+        vmid,
+        GuestType::Qemu,
+        purge,
+        destroy_unreferenced_disks,
+    )
+    .await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            snapname: { schema: SNAPSHOT_NAME_SCHEMA },
+            description: {
+                type: String,
+                optional: true,
+                description: "A description of the snapshot.",
+            },
+            vmstate: {
+                type: bool,
+                optional: true,
+                description: "Include the RAM of the VM in the snapshot.",
+            },
+        },
+    },
+    returns: { type: RemoteUpid },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}", "guest", "{vmid}"], PRIV_RESOURCE_MANAGE, false),
+    },
+)]
+/// Create a snapshot of a remote VM.
+pub async fn qemu_create_snapshot(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    snapname: String,
+    description: Option<String>,
+    vmstate: Option<bool>,
+) -> Result<RemoteUpid, Error> {
+    lifecycle::create_snapshot(
+        remote,
+        node,
+        vmid,
+        GuestType::Qemu,
+        snapname,
+        description,
+        vmstate,
+    )
+    .await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            snapname: { schema: SNAPSHOT_NAME_SCHEMA },
+            start: {
+                description: "Start the VM after the rollback.",
+                optional: true,
+                default: false,
+            },
+        },
+    },
+    returns: { type: RemoteUpid },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}", "guest", "{vmid}"], PRIV_RESOURCE_MANAGE, false),
+    },
+)]
+/// Roll a remote VM back to a snapshot.
+pub async fn qemu_rollback_snapshot(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    snapname: String,
+    start: bool,
+) -> Result<RemoteUpid, Error> {
+    lifecycle::rollback_snapshot(remote, node, vmid, GuestType::Qemu, snapname, start).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            snapname: { schema: SNAPSHOT_NAME_SCHEMA },
+            force: {
+                description: "Remove the snapshot from the config even if removing its disk snapshots fails.",
+                optional: true,
+                default: false,
+            },
+        },
+    },
+    returns: { type: RemoteUpid },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}", "guest", "{vmid}"], PRIV_RESOURCE_MANAGE, false),
+    },
+)]
+/// Delete a snapshot of a remote VM.
+pub async fn qemu_delete_snapshot(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    snapname: String,
+    force: bool,
+) -> Result<RemoteUpid, Error> {
+    lifecycle::delete_snapshot(remote, node, vmid, GuestType::Qemu, snapname, force).await
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+            vmid: { schema: VMID_SCHEMA },
+            storage: {
+                schema: PVE_STORAGE_ID_SCHEMA,
+            },
+            mode: {
+                type: GuestBackupMode,
+                optional: true,
+            },
+            "notes-template": {
+                type: String,
+                optional: true,
+                description: "Template for the notes of the backup, see the PVE documentation \
+                    of vzdump for the available variables.",
+            },
+            protected: {
+                description: "Protect the backup from being pruned.",
+                optional: true,
+                default: false,
+            },
+        },
+    },
+    returns: { type: RemoteUpid },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}", "guest", "{vmid}"], PRIV_RESOURCE_MANAGE, false),
+    },
+)]
+/// Back up a remote VM now.
+pub async fn qemu_backup(
+    remote: String,
+    node: Option<String>,
+    vmid: u32,
+    storage: String,
+    mode: Option<GuestBackupMode>,
+    notes_template: Option<String>,
+    protected: bool,
+) -> Result<RemoteUpid, Error> {
+    lifecycle::backup_guest(
+        remote,
+        node,
+        vmid,
+        storage,
+        mode.unwrap_or_default(),
+        notes_template,
+        protected,
+    )
+    .await
+}
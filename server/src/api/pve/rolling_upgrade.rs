@@ -0,0 +1,737 @@
+//! Upgrade the nodes of a PVE cluster one after another.
+//!
+//! Every node is put into maintenance mode, its guests are moved away, the packages are upgraded
+//! through a shell session on the node and the node is rebooted. Only once the node rejoined the
+//! quorate cluster, the next node is handled.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, format_err, Context, Error};
+use serde_json::json;
+
+use proxmox_product_config::{open_api_lockfile, ApiLockGuard};
+use proxmox_rest_server::WorkerTask;
+use proxmox_router::{http_bail, Permission, Router, RpcEnvironment, SubdirMap};
+use proxmox_schema::api;
+use proxmox_sortable_macro::sortable;
+
+use pdm_api_types::remote_updates::{NodeUpdateStatus, ProductRepositoryStatus};
+use pdm_api_types::remotes::{Remote, REMOTE_ID_SCHEMA};
+use pdm_api_types::rolling_upgrade::{
+    GuestPolicy, NodeUpgradeState, NodeUpgradeStatus, RollingUpgradeStatus,
+};
+use pdm_api_types::{
+    Authid, RemoteUpid, PRIV_RESOURCE_AUDIT, PRIV_SYS_CONSOLE, PRIV_SYS_MODIFY, UPID, UPID_SCHEMA,
+};
+use pve_api_types::{ClusterNodeStatus, ClusterResource, ClusterResourceType};
+
+use crate::api::remote_shell::run_remote_command;
+use crate::connection::{self, PveClient};
+use crate::remote_updates;
+
+use super::bulk_action::{nodes_in_maintenance, pick_migration_target};
+use super::{get_remote, new_remote_upid, wait_for_remote_task};
+
+pub const ROUTER: Router = Router::new()
+    .get(&API_METHOD_GET_ROLLING_UPGRADE)
+    .post(&API_METHOD_START_ROLLING_UPGRADE)
+    .subdirs(SUBDIRS);
+
+#[sortable]
+const SUBDIRS: SubdirMap = &sorted!([
+    (
+        "pause",
+        &Router::new().post(&API_METHOD_PAUSE_ROLLING_UPGRADE)
+    ),
+    (
+        "resume",
+        &Router::new().post(&API_METHOD_RESUME_ROLLING_UPGRADE)
+    ),
+]);
+
+const STATE_DIR: &str = concat!(pdm_buildcfg::PDM_STATE_DIR_M!(), "/rolling-upgrade");
+
+/// Maximum time the package upgrade of a node may take.
+const UPGRADE_TIMEOUT: Duration = Duration::from_secs(2 * 3600);
+
+/// Maximum time for short commands, like toggling the maintenance mode.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Maximum time until all guests left a node.
+const EVACUATE_TIMEOUT: Duration = Duration::from_secs(3600);
+
+/// Maximum time until a node went down after the reboot was requested.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// Maximum time until a rebooted node is back in the quorate cluster.
+const REBOOT_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Interval in which the cluster is polled while waiting, and the pause flag is checked.
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+fn state_file(remote: &str, extension: &str) -> PathBuf {
+    let mut path = PathBuf::from(STATE_DIR);
+    path.push(format!("{remote}.{extension}"));
+    path
+}
+
+/// Take the lock which is held while a rolling upgrade of the remote is running.
+fn lock_upgrade(remote: &str) -> Result<ApiLockGuard, Error> {
+    let options = proxmox_product_config::default_create_options();
+    proxmox_sys::fs::create_path(STATE_DIR, Some(options), Some(options))?;
+
+    open_api_lockfile(state_file(remote, "lck"), Some(Duration::ZERO), true)
+        .map_err(|_| format_err!("a rolling upgrade of remote '{remote}' is already running"))
+}
+
+fn read_status(remote: &str) -> Result<Option<RollingUpgradeStatus>, Error> {
+    let Some(content) = proxmox_sys::fs::file_read_optional_string(state_file(remote, "json"))?
+    else {
+        return Ok(None);
+    };
+
+    let mut status: RollingUpgradeStatus = serde_json::from_str(&content)?;
+
+    let upid: UPID = status.upid.parse()?;
+    status.running = proxmox_rest_server::worker_is_active_local(&upid);
+    status.paused = status.running && state_file(remote, "pause").exists();
+
+    Ok(Some(status))
+}
+
+fn write_status(status: &RollingUpgradeStatus) -> Result<(), Error> {
+    proxmox_sys::fs::replace_file(
+        state_file(&status.remote, "json"),
+        &serde_json::to_vec(status)?,
+        proxmox_product_config::default_create_options(),
+        true,
+    )
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_RESOURCE_AUDIT, false),
+    },
+    returns: { type: RollingUpgradeStatus },
+)]
+/// Get the status of the last rolling upgrade of a remote.
+pub fn get_rolling_upgrade(remote: String) -> Result<RollingUpgradeStatus, Error> {
+    match read_status(&remote)? {
+        Some(status) => Ok(status),
+        None => http_bail!(
+            NOT_FOUND,
+            "no rolling upgrade of remote '{remote}' was started"
+        ),
+    }
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+            "guest-policy": {
+                type: GuestPolicy,
+                optional: true,
+            },
+            reboot: {
+                description: "Reboot every node after upgrading its packages.",
+                optional: true,
+                default: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(
+            &["resource", "{remote}"],
+            PRIV_SYS_MODIFY | PRIV_SYS_CONSOLE,
+            false,
+        ),
+        description: "Needs `Sys.Modify` and `Sys.Console`, as the upgrade is run in a root shell.",
+    },
+    returns: { schema: UPID_SCHEMA },
+)]
+/// Upgrade all nodes of a PVE remote one after another.
+///
+/// Before a node is upgraded, it is put into maintenance mode and its running guests are migrated
+/// to other nodes or shut down. After the upgrade, the node is rebooted and the next node is only
+/// handled once it is back in the quorate cluster. Nodes without pending updates are skipped.
+///
+/// The upgrade stops at the first node which fails, leaving that node in maintenance mode.
+pub fn start_rolling_upgrade(
+    remote: String,
+    guest_policy: Option<GuestPolicy>,
+    reboot: bool,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<String, Error> {
+    let auth_id: Authid = rpcenv
+        .get_auth_id()
+        .context("no authid available")?
+        .parse()?;
+
+    let (remotes, _) = pdm_config::remotes::config()?;
+    let remote = get_remote(&remotes, &remote)?.clone();
+
+    let lock = lock_upgrade(&remote.id)?;
+
+    if let Err(err) = std::fs::remove_file(state_file(&remote.id, "pause")) {
+        if err.kind() != std::io::ErrorKind::NotFound {
+            return Err(err.into());
+        }
+    }
+
+    WorkerTask::spawn(
+        "rolling-upgrade",
+        Some(remote.id.clone()),
+        auth_id.to_string(),
+        true,
+        move |worker| async move {
+            let _lock = lock;
+
+            let status = RollingUpgradeStatus {
+                remote: remote.id.clone(),
+                upid: worker.upid().to_string(),
+                running: true,
+                paused: false,
+                guest_policy: guest_policy.unwrap_or_default(),
+                nodes: Vec::new(),
+            };
+
+            RollingUpgrade {
+                client: connection::make_pve_client(&remote)?,
+                remote,
+                reboot,
+                status,
+                worker,
+            }
+            .run()
+            .await
+        },
+    )
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Pause the running rolling upgrade of a remote before its next step.
+pub fn pause_rolling_upgrade(remote: String) -> Result<(), Error> {
+    match read_status(&remote)? {
+        Some(status) if status.running => {}
+        _ => http_bail!(
+            BAD_REQUEST,
+            "no rolling upgrade of remote '{remote}' is running"
+        ),
+    }
+
+    proxmox_sys::fs::replace_file(
+        state_file(&remote, "pause"),
+        b"",
+        proxmox_product_config::default_create_options(),
+        false,
+    )
+}
+
+#[api(
+    input: {
+        properties: {
+            remote: { schema: REMOTE_ID_SCHEMA },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{remote}"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Resume a paused rolling upgrade of a remote.
+pub fn resume_rolling_upgrade(remote: String) -> Result<(), Error> {
+    match std::fs::remove_file(state_file(&remote, "pause")) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            http_bail!(
+                BAD_REQUEST,
+                "the rolling upgrade of remote '{remote}' is not paused"
+            )
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// A guest which needs to be started again after the upgrade of its node.
+struct StoppedGuest {
+    vmid: u32,
+    ty: ClusterResourceType,
+}
+
+struct RollingUpgrade {
+    remote: Remote,
+    client: Arc<PveClient>,
+    reboot: bool,
+    status: RollingUpgradeStatus,
+    worker: Arc<WorkerTask>,
+}
+
+impl RollingUpgrade {
+    async fn run(mut self) -> Result<(), Error> {
+        self.status.nodes = self.pre_check().await?;
+        self.save();
+
+        for index in 0..self.status.nodes.len() {
+            if self.status.nodes[index].state == NodeUpgradeState::Skipped {
+                continue;
+            }
+
+            let node = self.status.nodes[index].node.clone();
+
+            if let Err(err) = self.upgrade_node(index, &node).await {
+                let err = err.context(format!("upgrading node {node} failed"));
+                self.status.nodes[index].state = NodeUpgradeState::Failed;
+                self.status.nodes[index].message = Some(format!("{err:#}"));
+                self.save();
+                return Err(err);
+            }
+
+            log::info!("node {node} was upgraded successfully");
+            self.set_state(index, NodeUpgradeState::Done);
+        }
+
+        Ok(())
+    }
+
+    /// Check that the cluster is healthy and the repositories of all nodes are set up properly,
+    /// and determine which nodes need to be upgraded.
+    async fn pre_check(&self) -> Result<Vec<NodeUpgradeStatus>, Error> {
+        let nodes = healthy_cluster_nodes(&self.client.cluster_status().await?)?;
+
+        let mut result = Vec::with_capacity(nodes.len());
+
+        for node in nodes {
+            let summary = remote_updates::refresh_node_update_summary(&self.remote, &node)
+                .await
+                .with_context(|| format!("could not check updates of node {node}"))?;
+
+            if summary.status != NodeUpdateStatus::Success {
+                bail!(
+                    "could not check updates of node {node}: {}",
+                    summary.status_message.unwrap_or_default()
+                );
+            }
+
+            match summary.repository_status {
+                ProductRepositoryStatus::Ok | ProductRepositoryStatus::NonProductionReady => {}
+                status => bail!("repositories of node {node} are not set up properly: {status:?}"),
+            }
+
+            let state = if summary.number_of_updates == 0 {
+                log::info!("node {node} is up to date, skipping it");
+                NodeUpgradeState::Skipped
+            } else {
+                log::info!(
+                    "node {node} has {} pending updates",
+                    summary.number_of_updates
+                );
+                NodeUpgradeState::Pending
+            };
+
+            result.push(NodeUpgradeStatus {
+                node,
+                state,
+                message: None,
+            });
+        }
+
+        Ok(result)
+    }
+
+    async fn upgrade_node(&mut self, index: usize, node: &str) -> Result<(), Error> {
+        self.checkpoint().await?;
+        self.set_state(index, NodeUpgradeState::Evacuating);
+
+        log::info!("enabling maintenance mode on node {node}");
+        self.run_command(
+            node,
+            &format!("ha-manager crm-command node-maintenance enable {node}"),
+            COMMAND_TIMEOUT,
+        )
+        .await?;
+
+        let stopped_guests = self.evacuate(node).await?;
+
+        self.checkpoint().await?;
+        self.set_state(index, NodeUpgradeState::Upgrading);
+
+        log::info!("upgrading packages on node {node}");
+        self.run_command(
+            node,
+            "apt-get update && DEBIAN_FRONTEND=noninteractive APT_LISTCHANGES_FRONTEND=none \
+                apt-get -y -o Dpkg::Options::=--force-confdef \
+                -o Dpkg::Options::=--force-confold dist-upgrade",
+            UPGRADE_TIMEOUT,
+        )
+        .await?;
+
+        if self.reboot {
+            self.checkpoint().await?;
+            self.set_state(index, NodeUpgradeState::Rebooting);
+            self.reboot_node(node).await?;
+        }
+
+        self.set_state(index, NodeUpgradeState::Restoring);
+
+        log::info!("disabling maintenance mode on node {node}");
+        self.run_command(
+            node,
+            &format!("ha-manager crm-command node-maintenance disable {node}"),
+            COMMAND_TIMEOUT,
+        )
+        .await?;
+
+        for guest in stopped_guests {
+            log::info!("starting guest {} on node {node}", guest.vmid);
+            let upid = match guest.ty {
+                ClusterResourceType::Lxc => {
+                    self.client
+                        .start_lxc_async(node, guest.vmid, Default::default())
+                        .await?
+                }
+                _ => {
+                    self.client
+                        .start_qemu_async(node, guest.vmid, Default::default())
+                        .await?
+                }
+            };
+            self.wait_for_task(node, upid).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Move all running guests off the node, returning the guests which were shut down.
+    ///
+    /// HA managed guests are moved by the maintenance mode, so they are not touched.
+    async fn evacuate(&self, node: &str) -> Result<Vec<StoppedGuest>, Error> {
+        let resources = self.client.cluster_resources(None).await?;
+
+        let mut node_memory: HashMap<String, (u64, u64)> = resources
+            .iter()
+            .filter(|resource| {
+                resource.ty == ClusterResourceType::Node
+                    && resource.status.as_deref() == Some("online")
+            })
+            .filter_map(|resource| {
+                Some((
+                    resource.node.clone()?,
+                    (
+                        resource.mem.unwrap_or_default(),
+                        resource.maxmem.unwrap_or_default() as u64,
+                    ),
+                ))
+            })
+            .collect();
+
+        let maintenance = nodes_in_maintenance(&self.remote, proxmox_time::epoch_i64());
+
+        let mut stopped = Vec::new();
+
+        for guest in resources
+            .iter()
+            .filter(|guest| is_running_guest(guest, node))
+        {
+            let vmid = guest.vmid.context("guest without vmid")?;
+
+            if guest.hastate.is_some() {
+                log::info!("guest {vmid} is HA managed, leaving it to the maintenance mode");
+                continue;
+            }
+
+            match self.status.guest_policy {
+                GuestPolicy::Migrate => {
+                    let target = pick_migration_target(
+                        &mut node_memory,
+                        node,
+                        guest.maxmem.unwrap_or_default() as u64,
+                        &maintenance,
+                    )
+                    .ok_or_else(|| format_err!("no node to migrate guest {vmid} to"))?;
+
+                    log::info!("migrating guest {vmid} to node {target}");
+                    let upid = self.migrate_guest(node, guest, vmid, target).await?;
+                    self.wait_for_task(node, upid).await?;
+                }
+                GuestPolicy::Shutdown => {
+                    log::info!("shutting down guest {vmid}");
+                    let upid = match guest.ty {
+                        ClusterResourceType::Lxc => {
+                            self.client
+                                .shutdown_lxc_async(node, vmid, Default::default())
+                                .await?
+                        }
+                        _ => {
+                            self.client
+                                .shutdown_qemu_async(node, vmid, Default::default())
+                                .await?
+                        }
+                    };
+                    self.wait_for_task(node, upid).await?;
+
+                    stopped.push(StoppedGuest { vmid, ty: guest.ty });
+                }
+            }
+        }
+
+        log::info!("waiting for all guests to leave node {node}");
+        let started = Instant::now();
+
+        loop {
+            let resources = self.client.cluster_resources(None).await?;
+            let remaining = resources
+                .iter()
+                .filter(|guest| is_running_guest(guest, node))
+                .count();
+
+            if remaining == 0 {
+                break;
+            }
+
+            if started.elapsed() > EVACUATE_TIMEOUT {
+                bail!("{remaining} guests are still running on node {node}");
+            }
+
+            self.sleep().await?;
+        }
+
+        Ok(stopped)
+    }
+
+    async fn migrate_guest(
+        &self,
+        node: &str,
+        guest: &ClusterResource,
+        vmid: u32,
+        target: String,
+    ) -> Result<pve_api_types::PveUpid, Error> {
+        let upid = match guest.ty {
+            ClusterResourceType::Lxc => {
+                let params = pve_api_types::MigrateLxc {
+                    bwlimit: None,
+                    online: None,
+                    restart: Some(true),
+                    target,
+                    target_storage: None,
+                    timeout: None,
+                };
+                self.client.migrate_lxc(node, vmid, params).await?
+            }
+            _ => {
+                let params = pve_api_types::MigrateQemu {
+                    bwlimit: None,
+                    force: None,
+                    migration_network: None,
+                    migration_type: None,
+                    online: Some(true),
+                    target,
+                    targetstorage: None,
+                    with_local_disks: None,
+                    with_conntrack_state: None,
+                };
+                self.client.migrate_qemu(node, vmid, params).await?
+            }
+        };
+
+        Ok(upid)
+    }
+
+    /// Reboot the node and wait until it is back in the quorate cluster.
+    async fn reboot_node(&self, node: &str) -> Result<(), Error> {
+        log::info!("rebooting node {node}");
+
+        connection::make_raw_client(&self.remote)?
+            .request(
+                http::Method::POST,
+                &format!("/api2/extjs/nodes/{node}/status"),
+                Some(json!({ "command": "reboot" })),
+            )
+            .await?
+            .nodata()?;
+
+        // The node cannot be reached while rebooting, so errors are expected until it is back.
+        let started = Instant::now();
+        while self.node_online(node).await.unwrap_or(true) {
+            if started.elapsed() > SHUTDOWN_TIMEOUT {
+                bail!("node {node} did not go down after requesting the reboot");
+            }
+            self.sleep().await?;
+        }
+
+        log::info!("node {node} is down, waiting for it to come back");
+
+        let started = Instant::now();
+        while !self.node_online(node).await.unwrap_or(false) {
+            if started.elapsed() > REBOOT_TIMEOUT {
+                bail!("node {node} did not rejoin the cluster after the reboot");
+            }
+            self.sleep().await?;
+        }
+
+        log::info!("node {node} is back online");
+
+        Ok(())
+    }
+
+    /// Check if the node is online and the cluster is quorate.
+    async fn node_online(&self, node: &str) -> Result<bool, Error> {
+        let cluster_status = self.client.cluster_status().await?;
+
+        let quorate = cluster_status
+            .iter()
+            .all(|entry| entry.quorate != Some(false));
+        let online = cluster_status
+            .iter()
+            .any(|entry| entry.name == node && entry.online == Some(true));
+
+        Ok(quorate && online)
+    }
+
+    async fn run_command(&self, node: &str, command: &str, timeout: Duration) -> Result<(), Error> {
+        let status = run_remote_command(&self.remote, node, command, timeout).await?;
+        if status != 0 {
+            bail!("command on node {node} failed with exit status {status}");
+        }
+        Ok(())
+    }
+
+    async fn wait_for_task(&self, node: &str, upid: pve_api_types::PveUpid) -> Result<(), Error> {
+        let upid: RemoteUpid = new_remote_upid(self.remote.id.clone(), upid).await?;
+        wait_for_remote_task(&self.client, node, &upid).await
+    }
+
+    /// Wait while the upgrade is paused, fails if the task was aborted.
+    async fn checkpoint(&self) -> Result<(), Error> {
+        let pause_file = state_file(&self.remote.id, "pause");
+        let mut paused = false;
+
+        loop {
+            if self.worker.abort_requested() {
+                bail!("rolling upgrade aborted");
+            }
+
+            if !pause_file.exists() {
+                if paused {
+                    log::info!("rolling upgrade resumed");
+                }
+                return Ok(());
+            }
+
+            if !paused {
+                log::info!("rolling upgrade paused");
+                paused = true;
+            }
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    async fn sleep(&self) -> Result<(), Error> {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        if self.worker.abort_requested() {
+            bail!("rolling upgrade aborted");
+        }
+        Ok(())
+    }
+
+    fn set_state(&mut self, index: usize, state: NodeUpgradeState) {
+        self.status.nodes[index].state = state;
+        self.save();
+    }
+
+    fn save(&self) {
+        if let Err(err) = write_status(&self.status) {
+            log::warn!("could not save rolling upgrade status: {err:#}");
+        }
+    }
+}
+
+/// Return the sorted node names of a quorate cluster, fails if any node is offline.
+fn healthy_cluster_nodes(cluster_status: &[ClusterNodeStatus]) -> Result<Vec<String>, Error> {
+    if cluster_status
+        .iter()
+        .any(|entry| entry.quorate == Some(false))
+    {
+        bail!("the cluster is not quorate");
+    }
+
+    let mut nodes = Vec::new();
+    for entry in cluster_status.iter().filter(|entry| entry.online.is_some()) {
+        if entry.online != Some(true) {
+            bail!("node {} is offline", entry.name);
+        }
+        nodes.push(entry.name.clone());
+    }
+    nodes.sort();
+
+    Ok(nodes)
+}
+
+fn is_running_guest(resource: &ClusterResource, node: &str) -> bool {
+    matches!(
+        resource.ty,
+        ClusterResourceType::Qemu | ClusterResourceType::Lxc
+    ) && resource.node.as_deref() == Some(node)
+        && resource.status.as_deref() == Some("running")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cluster_status(online: &[(&str, bool)], quorate: bool) -> Vec<ClusterNodeStatus> {
+        let mut status = vec![serde_json::from_value(json!({
+            "id": "cluster",
+            "name": "test",
+            "type": "cluster",
+            "quorate": quorate,
+        }))
+        .unwrap()];
+
+        for (name, online) in online {
+            status.push(
+                serde_json::from_value(json!({
+                    "id": format!("node/{name}"),
+                    "name": name,
+                    "type": "node",
+                    "online": online,
+                }))
+                .unwrap(),
+            );
+        }
+
+        status
+    }
+
+    #[test]
+    fn cluster_health() {
+        let status = cluster_status(&[("pve2", true), ("pve1", true), ("pve3", true)], true);
+        assert_eq!(
+            healthy_cluster_nodes(&status).unwrap(),
+            vec!["pve1", "pve2", "pve3"]
+        );
+
+        let status = cluster_status(&[("pve1", true), ("pve2", false)], true);
+        assert!(healthy_cluster_nodes(&status).is_err());
+
+        let status = cluster_status(&[("pve1", true), ("pve2", true)], false);
+        assert!(healthy_cluster_nodes(&status).is_err());
+    }
+}
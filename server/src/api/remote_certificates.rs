@@ -0,0 +1,61 @@
+//! API to re-trust changed certificates of remote nodes.
+
+use anyhow::Error;
+
+use proxmox_router::{Permission, Router};
+use proxmox_schema::api;
+
+use pdm_api_types::remotes::{PendingCertificate, REMOTE_ID_SCHEMA};
+use pdm_api_types::{CERT_FINGERPRINT_SHA256_SCHEMA, PRIV_RESOURCE_AUDIT, PRIV_RESOURCE_MODIFY};
+
+use crate::remote_certificates;
+
+use super::remotes::get_remote;
+
+pub const ROUTER: Router = Router::new()
+    .get(&API_METHOD_LIST_PENDING_CERTIFICATES)
+    .post(&API_METHOD_ACCEPT_CERTIFICATE);
+
+#[api(
+    input: {
+        properties: {
+            id: { schema: REMOTE_ID_SCHEMA },
+        },
+    },
+    returns: {
+        type: Array,
+        description: "Certificates which do not match the pinned fingerprint.",
+        items: { type: PendingCertificate },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{id}"], PRIV_RESOURCE_AUDIT, false),
+    },
+)]
+/// List the certificates presented by nodes of a remote which do not match the pinned
+/// fingerprint.
+pub fn list_pending_certificates(id: String) -> Result<Vec<PendingCertificate>, Error> {
+    let (remotes, _) = pdm_config::remotes::config()?;
+    let remote = get_remote(&remotes, &id)?;
+
+    Ok(remote_certificates::pending_certificates(remote))
+}
+
+#[api(
+    input: {
+        properties: {
+            id: { schema: REMOTE_ID_SCHEMA },
+            hostname: {
+                type: String,
+                description: "The address of the node, as configured in the remote.",
+            },
+            fingerprint: { schema: CERT_FINGERPRINT_SHA256_SCHEMA },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{id}"], PRIV_RESOURCE_MODIFY, false),
+    },
+)]
+/// Trust the pending certificate of a node by pinning its fingerprint.
+pub fn accept_certificate(id: String, hostname: String, fingerprint: String) -> Result<(), Error> {
+    remote_certificates::accept_certificate(&id, &hostname, &fingerprint)
+}
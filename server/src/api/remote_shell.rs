@@ -1,3 +1,6 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
 use anyhow::{bail, format_err, Context, Error};
 use futures::{FutureExt, TryFutureExt};
 use http::{
@@ -7,20 +10,23 @@ use http::{
 };
 use hyper::upgrade::Upgraded;
 use hyper_util::rt::TokioIo;
+use regex::Regex;
 use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 
 use proxmox_auth_api::{
     ticket::{Empty, Ticket},
     Keyring,
 };
 use proxmox_client::ApiPathBuilder;
-use proxmox_http::{websocket::WebSocket, Body};
+use proxmox_http::websocket::{WebSocket, WebSocketReader, WebSocketWriter};
+use proxmox_http::Body;
 use proxmox_router::{ApiHandler, ApiMethod, ApiResponseFuture, Permission, RpcEnvironment};
 use proxmox_schema::{api, IntegerSchema, ObjectSchema, StringSchema};
 use proxmox_sortable_macro::sortable;
 
 use pdm_api_types::{
-    remotes::{RemoteType, REMOTE_ID_SCHEMA},
+    remotes::{Remote, RemoteType, REMOTE_ID_SCHEMA},
     Authid, NODE_SCHEMA, PRIV_SYS_CONSOLE,
 };
 
@@ -169,82 +175,14 @@ fn upgrade_to_websocket(
 
             let (remotes, _digest) = pdm_config::remotes::config()?;
             let remote = get_remote(&remotes, &remote)?;
-            let (ticket, port) = match remote.ty {
-                RemoteType::Pve => {
-                    let pve = crate::connection::make_pve_client(remote)?;
-                    let pve_term_ticket = pve
-                        .node_shell_termproxy(
-                            &node,
-                            pve_api_types::NodeShellTermproxy {
-                                cmd: None,
-                                cmd_opts: None,
-                            },
-                        )
-                        .await?;
-                    (pve_term_ticket.ticket, pve_term_ticket.port)
-                }
-                RemoteType::Pbs => {
-                    let pbs = crate::connection::make_pbs_client(remote)?;
-                    let pbs_term_ticket = pbs.node_shell_termproxy().await?;
-                    (pbs_term_ticket.ticket, pbs_term_ticket.port as i64)
-                }
-            };
-
-            let raw_client = crate::connection::make_raw_client(remote)?;
-
-            let ws_key = proxmox_sys::linux::random_data(16)?;
-            let ws_key = proxmox_base64::encode(&ws_key);
-
-            let api_url = raw_client.api_url().clone().into_parts();
-
-            let mut builder = http::uri::Builder::new();
-            if let Some(scheme) = api_url.scheme {
-                builder = builder.scheme(scheme);
-            }
-            if let Some(authority) = api_url.authority {
-                builder = builder.authority(authority)
-            }
-            let api_path = ApiPathBuilder::new(format!("/api2/json/nodes/{node}/vncwebsocket"))
-                .arg("vncticket", ticket.clone())
-                .arg("port", port)
-                .build();
-            let uri = builder
-                .path_and_query(api_path)
-                .build()
-                .map_err(|err| format_err!("failed to build Uri - {err}"))?;
-
-            let auth = raw_client.login_auth()?;
-            let req = Request::builder()
-                .method(Method::GET)
-                .uri(uri)
-                .header(UPGRADE, "websocket")
-                .header(SEC_WEBSOCKET_VERSION, "13")
-                .header(SEC_WEBSOCKET_KEY, ws_key);
-
-            let req = auth.set_auth_headers(req).body(Body::empty())?;
-
-            let res = raw_client.http_client().request(req).await?;
-            if res.status() != StatusCode::SWITCHING_PROTOCOLS {
-                bail!("server didn't upgrade: {}", res.status());
-            }
+            let (remote_ws, preamble) = connect_remote_terminal(remote, &node).await?;
 
-            let pve_ws = hyper::upgrade::on(res)
-                .await
-                .map_err(|err| format_err!("failed to upgrade - {}", err))?;
-
-            let username = if let proxmox_client::AuthenticationKind::Token(ref token) = *auth {
-                token.userid.clone()
-            } else {
-                bail!("shell not supported with ticket-based authentication")
-            };
-
-            let preamble = format!("{username}:{ticket}\n", ticket = ticket);
             ws.mask = Some([0, 0, 0, 0]);
 
             if let Err(err) = ws
                 .proxy_connection(
                     TokioIo::new(incoming_ws),
-                    TokioIo::new(pve_ws),
+                    TokioIo::new(remote_ws),
                     preamble.as_bytes(),
                 )
                 .await
@@ -259,3 +197,207 @@ fn upgrade_to_websocket(
     }
     .boxed()
 }
+
+/// Open a shell on a remote node via its `termproxy` and `vncwebsocket` endpoints.
+///
+/// Returns the upgraded websocket connection together with the preamble which needs to be sent
+/// first to authenticate the session.
+pub(crate) async fn connect_remote_terminal(
+    remote: &Remote,
+    node: &str,
+) -> Result<(Upgraded, String), Error> {
+    let (ticket, port) = match remote.ty {
+        RemoteType::Pve => {
+            let pve = crate::connection::make_pve_client(remote)?;
+            let pve_term_ticket = pve
+                .node_shell_termproxy(
+                    node,
+                    pve_api_types::NodeShellTermproxy {
+                        cmd: None,
+                        cmd_opts: None,
+                    },
+                )
+                .await?;
+            (pve_term_ticket.ticket, pve_term_ticket.port)
+        }
+        RemoteType::Pbs => {
+            let pbs = crate::connection::make_pbs_client(remote)?;
+            let pbs_term_ticket = pbs.node_shell_termproxy().await?;
+            (pbs_term_ticket.ticket, pbs_term_ticket.port as i64)
+        }
+    };
+
+    let raw_client = crate::connection::make_raw_client(remote)?;
+
+    let ws_key = proxmox_sys::linux::random_data(16)?;
+    let ws_key = proxmox_base64::encode(&ws_key);
+
+    let api_url = raw_client.api_url().clone().into_parts();
+
+    let mut builder = http::uri::Builder::new();
+    if let Some(scheme) = api_url.scheme {
+        builder = builder.scheme(scheme);
+    }
+    if let Some(authority) = api_url.authority {
+        builder = builder.authority(authority)
+    }
+    let api_path = ApiPathBuilder::new(format!("/api2/json/nodes/{node}/vncwebsocket"))
+        .arg("vncticket", ticket.clone())
+        .arg("port", port)
+        .build();
+    let uri = builder
+        .path_and_query(api_path)
+        .build()
+        .map_err(|err| format_err!("failed to build Uri - {err}"))?;
+
+    let auth = raw_client.login_auth()?;
+    let req = Request::builder()
+        .method(Method::GET)
+        .uri(uri)
+        .header(UPGRADE, "websocket")
+        .header(SEC_WEBSOCKET_VERSION, "13")
+        .header(SEC_WEBSOCKET_KEY, ws_key);
+
+    let req = auth.set_auth_headers(req).body(Body::empty())?;
+
+    let res = raw_client.http_client().request(req).await?;
+    if res.status() != StatusCode::SWITCHING_PROTOCOLS {
+        bail!("server didn't upgrade: {}", res.status());
+    }
+
+    let remote_ws = hyper::upgrade::on(res)
+        .await
+        .map_err(|err| format_err!("failed to upgrade - {}", err))?;
+
+    let username = if let proxmox_client::AuthenticationKind::Token(ref token) = *auth {
+        token.userid.clone()
+    } else {
+        bail!("shell not supported with ticket-based authentication")
+    };
+
+    let preamble = format!("{username}:{ticket}\n");
+
+    Ok((remote_ws, preamble))
+}
+
+/// Marker printed after a command run via [`run_remote_command`], followed by its exit status.
+const EXIT_STATUS_MARKER: &str = "PDM_EXIT_STATUS=";
+
+/// Interval in which pings are sent to keep the terminal session alive.
+const TERMINAL_PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Run a shell command as `root` on a remote node and wait for it to finish.
+///
+/// The command is typed into a terminal session, so it must not require any input. Its output is
+/// logged line by line, which puts it into the log of the current worker task. Returns the exit
+/// status of the command.
+pub(crate) async fn run_remote_command(
+    remote: &Remote,
+    node: &str,
+    command: &str,
+    timeout: Duration,
+) -> Result<i32, Error> {
+    let (remote_ws, preamble) = connect_remote_terminal(remote, node).await?;
+    let (reader, writer) = tokio::io::split(TokioIo::new(remote_ws));
+
+    // control frames are not of interest, but the channel needs to stay open for the reader
+    let (control_sender, _control_receiver) = tokio::sync::mpsc::unbounded_channel();
+    let mut reader = BufReader::new(WebSocketReader::new(reader, control_sender));
+    let mut writer = WebSocketWriter::new(Some([0, 0, 0, 0]), writer);
+
+    writer.write_all(preamble.as_bytes()).await?;
+
+    let input = format!("{command}; echo \"{EXIT_STATUS_MARKER}$?\"; exit\n");
+    writer
+        .write_all(format!("0:{}:{input}", input.len()).as_bytes())
+        .await?;
+    writer.flush().await?;
+
+    let timeout = tokio::time::sleep(timeout);
+    tokio::pin!(timeout);
+    let mut ping = tokio::time::interval(TERMINAL_PING_INTERVAL);
+
+    let mut line = Vec::new();
+
+    loop {
+        tokio::select! {
+            _ = &mut timeout => bail!("command on node {node} did not finish in time"),
+            _ = ping.tick() => {
+                writer.write_all(b"2").await?;
+                writer.flush().await?;
+            }
+            read = reader.read_until(b'\n', &mut line) => {
+                if read? == 0 {
+                    bail!("terminal session on node {node} closed before the command finished");
+                }
+
+                let output = terminal_output_line(&line);
+                line.clear();
+
+                if let Some(status) = parse_exit_status(&output) {
+                    return Ok(status);
+                }
+
+                if !output.is_empty() {
+                    log::info!("{node}: {output}");
+                }
+            }
+        }
+    }
+}
+
+/// Turn a line of terminal output into plain text, dropping escape sequences and everything which
+/// was overwritten by a carriage return, like progress indicators.
+fn terminal_output_line(line: &[u8]) -> String {
+    static ESCAPE_SEQUENCES: OnceLock<Regex> = OnceLock::new();
+
+    let escape_sequences = ESCAPE_SEQUENCES.get_or_init(|| {
+        Regex::new(r"\x1b\[[0-9;?]*[ -/]*[@-~]|\x1b\][^\x07\x1b]*(\x07|\x1b\\)|\x1b[@-_]").unwrap()
+    });
+
+    let line = String::from_utf8_lossy(line);
+    let line = line.trim_end_matches(['\r', '\n']);
+    let line = line.rsplit('\r').next().unwrap_or_default();
+
+    escape_sequences
+        .replace_all(line, "")
+        .trim_end()
+        .to_string()
+}
+
+fn parse_exit_status(line: &str) -> Option<i32> {
+    line.strip_prefix(EXIT_STATUS_MARKER)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clean_terminal_output() {
+        assert_eq!(
+            terminal_output_line(b"\x1b[1;32mSetting up\x1b[0m pve-manager (8.4.1) ...\r\n"),
+            "Setting up pve-manager (8.4.1) ..."
+        );
+        assert_eq!(
+            terminal_output_line(b"Progress: [ 10%]\rProgress: [ 50%]\rdone\r\n"),
+            "done"
+        );
+        assert_eq!(
+            terminal_output_line(b"\x1b]0;root@pve1: ~\x07root@pve1:~# "),
+            "root@pve1:~#"
+        );
+    }
+
+    #[test]
+    fn exit_status_marker() {
+        assert_eq!(parse_exit_status("PDM_EXIT_STATUS=0"), Some(0));
+        assert_eq!(parse_exit_status("PDM_EXIT_STATUS=100"), Some(100));
+        // the echoed command line must not be taken for the result
+        assert_eq!(
+            parse_exit_status("root@pve1:~# apt-get update; echo \"PDM_EXIT_STATUS=$?\"; exit"),
+            None
+        );
+        assert_eq!(parse_exit_status("PDM_EXIT_STATUS=$?"), None);
+    }
+}
@@ -1,17 +1,19 @@
 use std::collections::HashMap;
 
 use anyhow::{Context, Error};
+use regex::Regex;
 
 use pdm_api_types::{
     remotes::REMOTE_ID_SCHEMA, Authid, RemoteUpid, TaskCount, TaskFilters, TaskListItem,
-    TaskStateType, TaskStatistics, PRIV_RESOURCE_AUDIT, PRIV_RESOURCE_MODIFY, UPID, VIEW_ID_SCHEMA,
+    TaskLogMatch, TaskStateType, TaskStatistics, PRIV_RESOURCE_AUDIT, PRIV_RESOURCE_MODIFY, UPID,
+    VIEW_ID_SCHEMA,
 };
 use proxmox_access_control::CachedUserInfo;
 use proxmox_rest_server::WorkerTask;
 use proxmox_router::{
     http_bail, http_err, list_subdirs_api_method, Permission, Router, RpcEnvironment, SubdirMap,
 };
-use proxmox_schema::api;
+use proxmox_schema::{api, param_bail};
 use proxmox_sortable_macro::sortable;
 
 use crate::remote_tasks;
@@ -30,13 +32,14 @@ const SUBDIRS: SubdirMap = &sorted!([
     (
         "refresh",
         &Router::new().post(&API_METHOD_REFRESH_REMOTE_TASKS)
-    )
+    ),
+    ("search", &Router::new().get(&API_METHOD_SEARCH_TASK_LOGS)),
 ]);
 
 #[api(
     access: {
         permission: &Permission::Anybody,
-        description: "Resource.Audit privileges on /resource/{remote} are needed to list tasks from a given remote."
+        description: "Resource.Audit privileges on /resource/{remote} are needed to list tasks from a given remote. Guest tasks are also included with Resource.Audit on the guest, its pool or one of its tags."
     },
     input: {
         properties: {
@@ -80,15 +83,8 @@ async fn list_tasks(
         user_info.check_privs(&auth_id, &["view", view], PRIV_RESOURCE_AUDIT, false)?;
     }
 
-    let check_privs = move |remote_name: &str| {
-        user_info
-            .check_privs(
-                &auth_id,
-                &["resource", remote_name],
-                PRIV_RESOURCE_AUDIT,
-                false,
-            )
-            .is_ok()
+    let check_privs = move |remote_name: &str, vmid: Option<u32>| {
+        can_audit_task(&user_info, &auth_id, remote_name, vmid)
     };
 
     let tasks = remote_tasks::get_tasks(filters, remote, check_privs, view).await?;
@@ -99,7 +95,7 @@ async fn list_tasks(
 #[api(
     access: {
         permission: &Permission::Anybody,
-        description: "Resource.Audit privileges on /resource/{remote} are needed to list tasks from a given remote."
+        description: "Resource.Audit privileges on /resource/{remote} are needed to list tasks from a given remote. Guest tasks are also included with Resource.Audit on the guest, its pool or one of its tags."
     },
     input: {
         properties: {
@@ -136,15 +132,8 @@ async fn task_statistics(
         user_info.check_privs(&auth_id, &["view", view], PRIV_RESOURCE_AUDIT, false)?;
     }
 
-    let check_privs = move |remote_name: &str| {
-        user_info
-            .check_privs(
-                &auth_id,
-                &["resource", remote_name],
-                PRIV_RESOURCE_AUDIT,
-                false,
-            )
-            .is_ok()
+    let check_privs = move |remote_name: &str, vmid: Option<u32>| {
+        can_audit_task(&user_info, &auth_id, remote_name, vmid)
     };
 
     let tasks = remote_tasks::get_tasks(filters, remote, check_privs, view).await?;
@@ -182,6 +171,88 @@ async fn task_statistics(
     Ok(TaskStatistics { by_type, by_remote })
 }
 
+#[api(
+    access: {
+        permission: &Permission::Anybody,
+        description: "Resource.Audit privileges on /resource/{remote} are needed to search the task logs of a given remote. Guest tasks are also included with Resource.Audit on the guest, its pool or one of its tags."
+    },
+    input: {
+        properties: {
+            pattern: {
+                type: String,
+                description: "Regular expression the log lines are matched against.",
+                max_length: 1024,
+            },
+            filters: {
+                type: TaskFilters,
+                flatten: true,
+            },
+            remote: {
+                schema: REMOTE_ID_SCHEMA,
+                optional: true,
+            },
+            view: {
+                schema: VIEW_ID_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+    returns: {
+        type: Array,
+        description: "Matching log lines of the tasks.",
+        items: {
+            type: TaskLogMatch
+        }
+    },
+)]
+/// Search the cached logs of failed remote tasks.
+///
+/// Only the logs of failed tasks are cached. `start` and `limit` apply to the returned lines.
+async fn search_task_logs(
+    pattern: String,
+    filters: TaskFilters,
+    remote: Option<String>,
+    view: Option<String>,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<Vec<TaskLogMatch>, Error> {
+    let auth_id = rpcenv
+        .get_auth_id()
+        .context("no authid available")?
+        .parse()?;
+    let user_info = CachedUserInfo::new()?;
+
+    let pattern = match Regex::new(&pattern) {
+        Ok(pattern) => pattern,
+        Err(err) => param_bail!("pattern", "invalid regular expression: {err}"),
+    };
+
+    if let Some(view) = &view {
+        user_info.check_privs(&auth_id, &["view", view], PRIV_RESOURCE_AUDIT, false)?;
+    }
+
+    let check_privs = move |remote_name: &str, vmid: Option<u32>| {
+        can_audit_task(&user_info, &auth_id, remote_name, vmid)
+    };
+
+    remote_tasks::search_task_logs(pattern, filters, remote, check_privs, view).await
+}
+
+/// Check whether a task is visible to the user, `vmid` is the guest a PVE task belongs to.
+fn can_audit_task(
+    user_info: &CachedUserInfo,
+    auth_id: &Authid,
+    remote: &str,
+    vmid: Option<u32>,
+) -> bool {
+    if user_info.lookup_privs(auth_id, &["resource", remote]) & PRIV_RESOURCE_AUDIT != 0 {
+        return true;
+    }
+
+    vmid.is_some_and(|vmid| {
+        crate::acl::lookup_guest_privs(user_info, auth_id, remote, vmid) & PRIV_RESOURCE_AUDIT != 0
+    })
+}
+
 #[api(
     input: {
         properties: {
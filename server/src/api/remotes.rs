@@ -13,22 +13,22 @@ use proxmox_router::{
 use proxmox_rrd_api_types::RrdMode;
 use proxmox_rrd_api_types::RrdTimeframe;
 use proxmox_schema::api;
+use proxmox_schema::api_types::PASSWORD_SCHEMA;
 use proxmox_schema::Schema;
 use proxmox_section_config::typed::SectionConfigData;
 use proxmox_sortable_macro::sortable;
-use proxmox_time::{epoch_i64, epoch_to_rfc2822};
 
 use pdm_api_types::remotes::{Remote, RemoteType, RemoteUpdater, REMOTE_ID_SCHEMA};
 use pdm_api_types::rrddata::RemoteDatapoint;
 use pdm_api_types::{Authid, ConfigDigest, PRIV_RESOURCE_AUDIT, PRIV_RESOURCE_MODIFY};
 
 use crate::api::metric_collection as metric_collection_api;
+use crate::api::remote_certificates;
 use crate::api::remote_tasks;
 use crate::api::remote_updates;
 use crate::metric_collection;
-use crate::{connection, pbs_client};
+use crate::{connection, remote_tokens};
 
-use super::pve;
 use super::rrd_common;
 use super::rrd_common::DataPoint;
 
@@ -57,7 +57,12 @@ const ITEM_ROUTER: Router = Router::new()
 
 #[sortable]
 const REMOTE_SUBDIRS: SubdirMap = &sorted!([
+    ("certificates", &remote_certificates::ROUTER),
     ("config", &Router::new().get(&API_METHOD_REMOTE_CONFIG)),
+    (
+        "rotate-token",
+        &Router::new().post(&API_METHOD_ROTATE_TOKEN)
+    ),
     ("version", &Router::new().get(&API_METHOD_VERSION)),
     (
         "rrddata",
@@ -265,58 +270,10 @@ pub async fn add_remote(mut entry: Remote, create_token: Option<String>) -> Resu
     }
 
     if let Some(create_token) = create_token {
-        let nodename = proxmox_sys::nodename();
-        let date = epoch_to_rfc2822(epoch_i64())?;
-        let comment = Some(format!("auto-generated by PDM host '{nodename}' on {date}"));
-
-        /// With the `Client`'s error type the message gets a bit long, shorten it:
-        fn short_create_err(err: proxmox_client::Error) -> Error {
-            format_err!("error creating token: {}", err.source().unwrap_or(&err))
-        }
-
-        // connect to remote and try to create a token
-        let (authid, token) = match entry.ty {
-            RemoteType::Pve => {
-                let client = pve::connect_or_login(&entry).await?;
-
-                let user = entry.authid.user();
-
-                let token = client
-                    .create_token(
-                        &user.to_string(),
-                        &create_token,
-                        pve_api_types::CreateToken {
-                            comment,
-                            expire: None,
-                            privsep: Some(false),
-                        },
-                    )
-                    .await
-                    .map_err(short_create_err)?;
-                (token.full_tokenid.parse()?, token.value)
-            }
-            RemoteType::Pbs => {
-                let client = pbs_client::connect_or_login(&entry).await?;
-
-                let tokenname = pbs_api_types::Tokenname::try_from(create_token)?;
-
-                let token = client
-                    .create_admin_token(
-                        entry.authid.user().to_owned(),
-                        tokenname,
-                        pbs_client::CreateToken {
-                            comment,
-                            enable: Some(true),
-                            expire: None,
-                        },
-                    )
-                    .await
-                    .map_err(short_create_err)?;
-                (token.tokenid.parse()?, token.value)
-            }
-        };
-        entry.authid = authid;
-        entry.token = token;
+        // connect to remote and create a least-privilege, expiring token
+        remote_tokens::create_token(&entry, &create_token)
+            .await?
+            .apply_to(&mut entry);
     }
 
     let name = entry.id.clone();
@@ -338,6 +295,22 @@ pub async fn add_remote(mut entry: Remote, create_token: Option<String>) -> Resu
 pub enum DeletableProperty {
     /// Delete the web-url property.
     WebUrl,
+    /// Delete the sync-nodes property.
+    SyncNodes,
+    /// Delete the http-proxy property.
+    HttpProxy,
+    /// Delete the connect-timeout property.
+    ConnectTimeout,
+    /// Delete the request-timeout property.
+    RequestTimeout,
+    /// Delete the max-connections property.
+    MaxConnections,
+    /// Delete the maintenance property.
+    Maintenance,
+    /// Delete the maintenance-nodes property.
+    MaintenanceNodes,
+    /// Delete the maintenance-end property.
+    MaintenanceEnd,
 }
 
 // FIXME: Support `OneOf` in schema so we can use a derived Updater for all product types?
@@ -388,6 +361,30 @@ pub fn update_remote(
                 DeletableProperty::WebUrl => {
                     entry.web_url = None;
                 }
+                DeletableProperty::SyncNodes => {
+                    entry.sync_nodes = None;
+                }
+                DeletableProperty::HttpProxy => {
+                    entry.http_proxy = None;
+                }
+                DeletableProperty::ConnectTimeout => {
+                    entry.connect_timeout = None;
+                }
+                DeletableProperty::RequestTimeout => {
+                    entry.request_timeout = None;
+                }
+                DeletableProperty::MaxConnections => {
+                    entry.max_connections = None;
+                }
+                DeletableProperty::Maintenance => {
+                    entry.maintenance = None;
+                }
+                DeletableProperty::MaintenanceNodes => {
+                    entry.maintenance_nodes = None;
+                }
+                DeletableProperty::MaintenanceEnd => {
+                    entry.maintenance_end = None;
+                }
             }
         }
     }
@@ -395,6 +392,12 @@ pub fn update_remote(
     if let Some(v) = updater.nodes {
         entry.nodes = v;
     }
+    let authid_changed = updater.authid.as_ref().is_some_and(|v| *v != entry.authid);
+    if authid_changed || updater.token.is_some() {
+        // manually set tokens are not managed by PDM
+        entry.token_created = None;
+        entry.token_expire = None;
+    }
     if let Some(v) = updater.authid {
         entry.authid = v;
     }
@@ -406,6 +409,38 @@ pub fn update_remote(
         entry.web_url = updater.web_url;
     }
 
+    if updater.sync_nodes.is_some() {
+        entry.sync_nodes = updater.sync_nodes;
+    }
+
+    if updater.http_proxy.is_some() {
+        entry.http_proxy = updater.http_proxy;
+    }
+
+    if updater.connect_timeout.is_some() {
+        entry.connect_timeout = updater.connect_timeout;
+    }
+
+    if updater.request_timeout.is_some() {
+        entry.request_timeout = updater.request_timeout;
+    }
+
+    if updater.max_connections.is_some() {
+        entry.max_connections = updater.max_connections;
+    }
+
+    if updater.maintenance.is_some() {
+        entry.maintenance = updater.maintenance;
+    }
+
+    if updater.maintenance_nodes.is_some() {
+        entry.maintenance_nodes = updater.maintenance_nodes;
+    }
+
+    if updater.maintenance_end.is_some() {
+        entry.maintenance_end = updater.maintenance_end;
+    }
+
     pdm_config::remotes::save_config(remotes)?;
 
     Ok(())
@@ -476,6 +511,27 @@ pub async fn remove_remote(id: String, delete_token: bool) -> Result<(), Error>
     Ok(())
 }
 
+#[api(
+    input: {
+        properties: {
+            id: { schema: REMOTE_ID_SCHEMA },
+            password: {
+                schema: PASSWORD_SCHEMA,
+                description: "Password of the token's user on the remote.",
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["resource", "{id}"], PRIV_RESOURCE_MODIFY, false),
+    },
+)]
+/// Replace the API token of a remote with a new one, if the token was created by PDM.
+///
+/// The token itself cannot manage tokens, so the password of its user is needed.
+pub async fn rotate_token(id: String, password: String) -> Result<(), Error> {
+    remote_tokens::rotate_token(&id, password).await
+}
+
 #[api(
     input: {
         properties: {
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 use std::sync::{LazyLock, RwLock};
 
@@ -9,6 +9,10 @@ use futures::FutureExt;
 use pbs_api_types::{
     DataStoreStatusListItem, DatastoreBackendConfig, DatastoreBackendType, NodeStatus,
 };
+use pdm_api_types::backup_coverage::{
+    BackupCoverage, DEFAULT_MAX_BACKUP_AGE, MAX_BACKUP_AGE_SCHEMA,
+};
+use pdm_api_types::firewall::GuestKind;
 use pdm_api_types::remotes::{Remote, RemoteType};
 use pdm_api_types::resource::{
     FailedRemote, NetworkFabricResource, NetworkZoneResource, PbsDatastoreResource,
@@ -31,8 +35,9 @@ use proxmox_sortable_macro::sortable;
 use proxmox_subscription::SubscriptionStatus;
 use pve_api_types::{ClusterResource, ClusterResourceNetworkType, ClusterResourceType};
 
+use crate::backup_coverage::{self, Guest};
 use crate::metric_collection::top_entities;
-use crate::{connection, views};
+use crate::{acl, connection, views};
 
 pub const ROUTER: Router = Router::new()
     .get(&list_subdirs_api_method!(SUBDIRS))
@@ -40,6 +45,10 @@ pub const ROUTER: Router = Router::new()
 
 #[sortable]
 const SUBDIRS: SubdirMap = &sorted!([
+    (
+        "backup-coverage",
+        &Router::new().get(&API_METHOD_GET_BACKUP_COVERAGE)
+    ),
     ("list", &Router::new().get(&API_METHOD_GET_RESOURCES)),
     ("status", &Router::new().get(&API_METHOD_GET_STATUS)),
     (
@@ -198,10 +207,11 @@ fn remote_type_matches_search_term(remote_type: RemoteType, term: &SearchTerm) -
 
 // Transient type for remote resources gathering and filtering on remote properties
 pub(crate) struct RemoteWithResources {
-    remote_name: String,
-    remote: Remote,
-    resources: Vec<Resource>,
-    error: Option<String>,
+    pub(crate) remote_name: String,
+    pub(crate) remote: Remote,
+    pub(crate) resources: Vec<Resource>,
+    pub(crate) error: Option<String>,
+    pub(crate) maintenance: bool,
 }
 
 impl From<RemoteWithResources> for RemoteResources {
@@ -210,6 +220,7 @@ impl From<RemoteWithResources> for RemoteResources {
             remote: val.remote_name,
             resources: val.resources,
             error: val.error,
+            maintenance: val.maintenance,
         }
     }
 }
@@ -317,7 +328,9 @@ pub(crate) async fn get_resources_impl(
         // on the view ACL object *if* a view parameter is passed.
         if let Some(view) = &view {
             user_info.check_privs(&auth_id, &["view", view], PRIV_RESOURCE_AUDIT, false)?;
-        } else if !user_info.any_privs_below(&auth_id, &["resource"], PRIV_RESOURCE_AUDIT)? {
+        } else if !user_info.any_privs_below(&auth_id, &["resource"], PRIV_RESOURCE_AUDIT)?
+            && !user_info.any_privs_below(&auth_id, &["tag"], PRIV_RESOURCE_AUDIT)?
+        {
             http_bail!(FORBIDDEN, "user has no access to resources");
         }
 
@@ -345,6 +358,11 @@ pub(crate) async fn get_resources_impl(
 
     let remotes_only = is_remotes_only(&filters);
 
+    // remotes where only single guests are visible, via their own, their pool's or their tags' ACLs
+    let mut guest_only_remotes = HashSet::new();
+
+    let now = proxmox_time::epoch_i64();
+
     for (remote_name, remote) in remotes_config {
         if let Some(view) = &view {
             if view.can_skip_remote(&remote_name) {
@@ -353,7 +371,16 @@ pub(crate) async fn get_resources_impl(
         } else if let Some(ref auth_id) = opt_auth_id {
             let remote_privs = user_info.lookup_privs(auth_id, &["resource", &remote_name]);
             if remote_privs & PRIV_RESOURCE_AUDIT == 0 {
-                continue;
+                let guest_privs = remote.ty == RemoteType::Pve
+                    && (user_info.any_privs_below(
+                        auth_id,
+                        &["resource", &remote_name],
+                        PRIV_RESOURCE_AUDIT,
+                    )? || user_info.any_privs_below(auth_id, &["tag"], PRIV_RESOURCE_AUDIT)?);
+                if !guest_privs {
+                    continue;
+                }
+                guest_only_remotes.insert(remote_name.clone());
             }
         }
 
@@ -368,12 +395,17 @@ pub(crate) async fn get_resources_impl(
             continue;
         }
         let filter = filters.clone();
+        let maintenance = remote.in_maintenance(now);
         let handle = tokio::spawn(async move {
-            let (mut resources, error) = match get_resources_for_remote(&remote, max_age).await {
-                Ok(resources) => (resources, None),
-                Err(error) => {
-                    tracing::debug!("failed to get resources from remote - {error:?}");
-                    (Vec::new(), Some(error.root_cause().to_string()))
+            let (mut resources, error) = if maintenance {
+                (Vec::new(), None)
+            } else {
+                match get_resources_for_remote(&remote, max_age).await {
+                    Ok(resources) => (resources, None),
+                    Err(error) => {
+                        tracing::debug!("failed to get resources from remote - {error:?}");
+                        (Vec::new(), Some(error.root_cause().to_string()))
+                    }
                 }
             };
 
@@ -399,6 +431,7 @@ pub(crate) async fn get_resources_impl(
                 remote,
                 resources,
                 error,
+                maintenance,
             }
         });
 
@@ -424,6 +457,22 @@ pub(crate) async fn get_resources_impl(
         }
     }
 
+    if let Some(auth_id) = &opt_auth_id {
+        remote_resources.retain_mut(|r| {
+            if !guest_only_remotes.contains(&r.remote_name) {
+                return true;
+            }
+
+            r.resources.retain(|resource| {
+                acl::lookup_resource_privs(&user_info, auth_id, &r.remote_name, resource)
+                    & PRIV_RESOURCE_AUDIT
+                    != 0
+            });
+
+            !r.resources.is_empty()
+        });
+    }
+
     if let Some(view) = &view {
         remote_resources.retain_mut(|r| {
             r.resources
@@ -431,13 +480,132 @@ pub(crate) async fn get_resources_impl(
 
             let has_any_matched_resources = !r.resources.is_empty();
             has_any_matched_resources
-                || (r.error.is_some() && view.is_remote_explicitly_included(&r.remote_name))
+                || ((r.error.is_some() || r.maintenance)
+                    && view.is_remote_explicitly_included(&r.remote_name))
         });
     }
 
     Ok(remote_resources)
 }
 
+#[api(
+    access: {
+        permission: &Permission::Anybody,
+        description: "Only guests and PBS datastores the user may audit are considered.",
+    },
+    input: {
+        properties: {
+            "max-age": {
+                description: "Maximum age (in seconds) of cached remote resources and backup lists.",
+                default: 300,
+                optional: true,
+            },
+            "max-backup-age": {
+                schema: MAX_BACKUP_AGE_SCHEMA,
+                optional: true,
+            },
+            all: {
+                description: "Also list guests with a recent backup.",
+                default: false,
+                optional: true,
+            },
+            view: {
+                schema: VIEW_ID_SCHEMA,
+                optional: true,
+            },
+        }
+    },
+    returns: { type: BackupCoverage },
+)]
+/// Report guests without a recent backup on any PBS remote.
+pub async fn get_backup_coverage(
+    max_age: u64,
+    max_backup_age: Option<u64>,
+    all: bool,
+    view: Option<String>,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<BackupCoverage, Error> {
+    let auth_id: Authid = rpcenv
+        .get_auth_id()
+        .context("no authid available")?
+        .parse()?;
+    let user_info = CachedUserInfo::new()?;
+
+    let remotes_with_resources =
+        get_resources_impl(max_age, None, None, view.as_deref(), Some(rpcenv)).await?;
+
+    let mut guests = Vec::new();
+    let mut failed_remotes = Vec::new();
+    for remote in remotes_with_resources {
+        if remote.remote.ty != RemoteType::Pve {
+            continue;
+        }
+        if let Some(error) = remote.error {
+            failed_remotes.push(FailedRemote {
+                name: remote.remote_name,
+                error,
+                remote_type: RemoteType::Pve,
+            });
+            continue;
+        }
+        for resource in remote.resources {
+            let (guest_type, vmid, name) = match resource {
+                Resource::PveQemu(qemu) if !qemu.template => {
+                    (GuestKind::Qemu, qemu.vmid, qemu.name)
+                }
+                Resource::PveLxc(lxc) if !lxc.template => (GuestKind::Lxc, lxc.vmid, lxc.name),
+                _ => continue,
+            };
+            guests.push(Guest {
+                remote: remote.remote_name.clone(),
+                guest_type,
+                vmid,
+                name,
+            });
+        }
+    }
+
+    let now = proxmox_time::epoch_i64();
+    let pbs_remotes = super::remotes::RemoteIterator::new()?
+        .remote_type(RemoteType::Pbs)
+        .into_iter()
+        .filter(|(name, remote)| {
+            !remote.in_maintenance(now)
+                && user_info
+                    .any_privs_below(&auth_id, &["resource", name], PRIV_RESOURCE_AUDIT)
+                    .unwrap_or(false)
+        })
+        .map(|(_, remote)| remote)
+        .collect();
+
+    let (backups, failed_pbs_remotes) = backup_coverage::get_backups(pbs_remotes, max_age).await;
+    failed_remotes.extend(failed_pbs_remotes);
+
+    let visible_backups = backups.iter().flat_map(|remote| {
+        let user_info = &user_info;
+        let auth_id = &auth_id;
+        remote
+            .groups
+            .iter()
+            .filter(move |group| {
+                let path = ["resource", &remote.remote, "datastore", &group.datastore];
+                user_info.lookup_privs(auth_id, &path) & PRIV_RESOURCE_AUDIT != 0
+            })
+            .map(move |group| (remote.remote.as_str(), group))
+    });
+
+    let mut coverage = backup_coverage::compute_coverage(
+        guests,
+        visible_backups,
+        max_backup_age.unwrap_or(DEFAULT_MAX_BACKUP_AGE),
+        now,
+        all,
+    );
+    coverage.failed_remotes = failed_remotes;
+
+    Ok(coverage)
+}
+
 #[api(
     // FIXME:: see list-like API calls in resource routers..
     access: {
@@ -469,7 +637,9 @@ pub async fn get_status(
         get_resources_impl(max_age, None, None, view.as_deref(), Some(rpcenv)).await?;
     let mut counts = ResourcesStatus::default();
     for remote_with_resources in remotes_with_resources {
-        if let Some(err) = remote_with_resources.error {
+        if remote_with_resources.maintenance {
+            counts.maintenance_remotes += 1;
+        } else if let Some(err) = remote_with_resources.error {
             counts.failed_remotes += 1;
             counts.failed_remotes_list.push(FailedRemote {
                 name: remote_with_resources.remote_name,
@@ -613,6 +783,11 @@ pub async fn get_subscription_status(
 
     let view = views::get_optional_view(view.as_deref())?;
 
+    let pool_keys = super::subscriptions::pool_keys().unwrap_or_else(|err| {
+        log::warn!("could not read subscription key pool: {err:#}");
+        Vec::new()
+    });
+
     let check_priv = |remote_name: &str| -> bool {
         user_info
             .check_privs(
@@ -624,6 +799,8 @@ pub async fn get_subscription_status(
             .is_ok()
     };
 
+    let now = proxmox_time::epoch_i64();
+
     for (remote_name, remote) in remotes_config {
         if let Some(view) = &view {
             if view.can_skip_remote(&remote_name) {
@@ -633,12 +810,22 @@ pub async fn get_subscription_status(
             continue;
         }
 
+        if remote.in_maintenance(now) {
+            continue;
+        }
+
         let view = view.clone();
+        let remote_keys: Vec<_> = pool_keys
+            .iter()
+            .filter(|key| key.remote.as_deref() == Some(remote_name.as_str()))
+            .cloned()
+            .collect();
 
         let future = async move {
             let (node_status, error) =
                 match get_subscription_info_for_remote(&remote, max_age).await {
                     Ok(mut node_status) => {
+                        super::subscriptions::mark_reissue_required(&mut node_status, &remote_keys);
                         node_status.retain(|node, _| {
                             if let Some(view) = &view {
                                 view.is_node_included(&remote.id, node)
@@ -897,10 +1084,13 @@ async fn fetch_remote_subscription_info(
                             sockets: info.sockets,
                             key: info.key,
                             serverid: info.serverid,
+                            nextduedate: info.nextduedate,
                             level: info
                                 .level
                                 .and_then(|level| level.parse().ok())
                                 .unwrap_or_default(),
+                            message: info.message,
+                            reissue_required: false,
                         }
                     }),
                 );
@@ -917,6 +1107,9 @@ async fn fetch_remote_subscription_info(
                     key: info.key,
                     level,
                     serverid: info.serverid,
+                    nextduedate: info.nextduedate,
+                    message: info.message,
+                    reissue_required: false,
                 }
             });
 
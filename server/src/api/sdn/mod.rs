@@ -2,6 +2,7 @@ use proxmox_router::{list_subdirs_api_method, Router, SubdirMap};
 use proxmox_sortable_macro::sortable;
 
 pub mod controllers;
+pub mod subnets;
 pub mod vnets;
 pub mod zones;
 
@@ -15,3 +16,12 @@ pub const SUBDIRS: SubdirMap = &sorted!([
 pub const ROUTER: Router = Router::new()
     .get(&list_subdirs_api_method!(SUBDIRS))
     .subdirs(SUBDIRS);
+
+/// Joins the properties to delete to the comma separated list the PVE API expects.
+fn delete_param<T: std::fmt::Display>(delete: &[T]) -> String {
+    delete
+        .iter()
+        .map(|property| property.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
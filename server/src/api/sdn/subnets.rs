@@ -0,0 +1,292 @@
+use anyhow::{Context, Error};
+
+use pbs_api_types::REMOTE_ID_SCHEMA;
+use pdm_api_types::{
+    sdn::{
+        SdnDhcpRange, SdnSubnetDeletableProperty, SubnetProperties, SDN_ID_SCHEMA,
+        SDN_SUBNET_ID_SCHEMA, SDN_SUBNET_SCHEMA,
+    },
+    Authid,
+};
+use proxmox_rest_server::WorkerTask;
+use proxmox_router::{Router, RpcEnvironment};
+use proxmox_schema::api;
+use serde_json::Value;
+
+use crate::sdn_client::LockedSdnClients;
+
+use super::vnets::vnet_zone;
+
+pub const ROUTER: Router = Router::new()
+    .post(&API_METHOD_CREATE_SUBNET)
+    .match_all("subnet", &SUBNET_ROUTER);
+
+const SUBNET_ROUTER: Router = Router::new()
+    .put(&API_METHOD_UPDATE_SUBNET)
+    .delete(&API_METHOD_DELETE_SUBNET);
+
+#[api(
+    input: {
+        properties: {
+            vnet: { schema: SDN_ID_SCHEMA },
+            subnet: { schema: SDN_SUBNET_SCHEMA },
+            remotes: {
+                type: Array,
+                description: "List of remotes on which the subnet should get created.",
+                items: {
+                    schema: REMOTE_ID_SCHEMA,
+                }
+            },
+            properties: {
+                type: SubnetProperties,
+                flatten: true,
+            },
+        },
+    },
+    returns: { schema: pdm_api_types::UPID_SCHEMA },
+)]
+/// Create a subnet in a VNet across multiple remotes
+async fn create_subnet(
+    vnet: String,
+    subnet: String,
+    remotes: Vec<String>,
+    properties: SubnetProperties,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<String, Error> {
+    let auth_id: Authid = rpcenv
+        .get_auth_id()
+        .context("no authid available")?
+        .parse()?;
+
+    let mut params = subnet_params(&properties)?;
+    params["subnet"] = subnet.clone().into();
+    params["type"] = "subnet".into();
+
+    let upid = WorkerTask::spawn(
+        "create_subnet",
+        None,
+        auth_id.to_string(),
+        false,
+        move |_worker| async move {
+            LockedSdnClients::from_remote_names(
+                remotes.into_iter().map(|remote| (remote.clone(), remote)),
+                false,
+            )
+            .await?
+            .for_each(async move |client, ctx| {
+                proxmox_log::info!(
+                    "creating subnet {subnet} in vnet {vnet} on remote {}",
+                    ctx.remote_id()
+                );
+                client.create_subnet(&vnet, params.clone()).await
+            })
+            .await?
+            .apply_and_release()
+            .await
+        },
+    )?;
+
+    Ok(upid)
+}
+
+#[api(
+    input: {
+        properties: {
+            vnet: { schema: SDN_ID_SCHEMA },
+            subnet: { schema: SDN_SUBNET_ID_SCHEMA },
+            remotes: {
+                type: Array,
+                description: "List of remotes on which the subnet should get updated.",
+                items: {
+                    schema: REMOTE_ID_SCHEMA,
+                }
+            },
+            properties: {
+                type: SubnetProperties,
+                flatten: true,
+            },
+            delete: {
+                type: Array,
+                optional: true,
+                description: "List of properties to delete.",
+                items: { type: SdnSubnetDeletableProperty },
+            },
+        },
+    },
+    returns: { schema: pdm_api_types::UPID_SCHEMA },
+)]
+/// Update a subnet of a VNet across multiple remotes
+async fn update_subnet(
+    vnet: String,
+    subnet: String,
+    remotes: Vec<String>,
+    properties: SubnetProperties,
+    delete: Option<Vec<SdnSubnetDeletableProperty>>,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<String, Error> {
+    let auth_id: Authid = rpcenv
+        .get_auth_id()
+        .context("no authid available")?
+        .parse()?;
+
+    let mut params = subnet_params(&properties)?;
+    if let Some(delete) = &delete {
+        params["delete"] = super::delete_param(delete).into();
+    }
+
+    let upid = WorkerTask::spawn(
+        "update_subnet",
+        None,
+        auth_id.to_string(),
+        false,
+        move |_worker| async move {
+            let remotes = remote_zones(remotes, &vnet).await?;
+
+            LockedSdnClients::from_remote_names(remotes, false)
+                .await?
+                .for_each(async move |client, ctx| {
+                    proxmox_log::info!(
+                        "updating subnet {subnet} in vnet {vnet} on remote {}",
+                        ctx.remote_id()
+                    );
+                    let id = pve_subnet_id(ctx.data(), &subnet);
+                    client.update_subnet(&vnet, &id, params.clone()).await
+                })
+                .await?
+                .apply_and_release()
+                .await
+        },
+    )?;
+
+    Ok(upid)
+}
+
+#[api(
+    input: {
+        properties: {
+            vnet: { schema: SDN_ID_SCHEMA },
+            subnet: { schema: SDN_SUBNET_ID_SCHEMA },
+            remotes: {
+                type: Array,
+                description: "List of remotes from which the subnet should get deleted.",
+                items: {
+                    schema: REMOTE_ID_SCHEMA,
+                }
+            },
+        },
+    },
+    returns: { schema: pdm_api_types::UPID_SCHEMA },
+)]
+/// Delete a subnet of a VNet across multiple remotes
+async fn delete_subnet(
+    vnet: String,
+    subnet: String,
+    remotes: Vec<String>,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<String, Error> {
+    let auth_id: Authid = rpcenv
+        .get_auth_id()
+        .context("no authid available")?
+        .parse()?;
+
+    let upid = WorkerTask::spawn(
+        "delete_subnet",
+        None,
+        auth_id.to_string(),
+        false,
+        move |_worker| async move {
+            let remotes = remote_zones(remotes, &vnet).await?;
+
+            LockedSdnClients::from_remote_names(remotes, false)
+                .await?
+                .for_each(async move |client, ctx| {
+                    proxmox_log::info!(
+                        "deleting subnet {subnet} in vnet {vnet} on remote {}",
+                        ctx.remote_id()
+                    );
+                    let id = pve_subnet_id(ctx.data(), &subnet);
+                    client.delete_subnet(&vnet, &id).await
+                })
+                .await?
+                .apply_and_release()
+                .await
+        },
+    )?;
+
+    Ok(upid)
+}
+
+/// Looks up the zone of the VNet on every remote, as PVE prefixes the subnet ids with it.
+async fn remote_zones(remotes: Vec<String>, vnet: &str) -> Result<Vec<(String, String)>, Error> {
+    let mut zones = Vec::with_capacity(remotes.len());
+
+    for remote in remotes {
+        let zone = vnet_zone(&remote, vnet).await?;
+        zones.push((remote, zone));
+    }
+
+    Ok(zones)
+}
+
+/// Returns the id PVE uses for a subnet, e.g. `zone1-10.0.0.0-24`.
+fn pve_subnet_id(zone: &str, subnet: &str) -> String {
+    format!("{zone}-{subnet}")
+}
+
+/// Converts the subnet properties to the parameters of the PVE API, which expects the DHCP ranges
+/// as property strings.
+fn subnet_params(properties: &SubnetProperties) -> Result<Value, Error> {
+    let mut params = serde_json::to_value(properties)?;
+
+    if let Some(ranges) = &properties.dhcp_range {
+        params["dhcp-range"] = ranges.iter().map(dhcp_range_property).collect();
+    }
+
+    Ok(params)
+}
+
+fn dhcp_range_property(range: &SdnDhcpRange) -> Value {
+    format!(
+        "start-address={},end-address={}",
+        range.start_address, range.end_address
+    )
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subnet_params_with_dhcp_ranges() {
+        let properties = SubnetProperties {
+            gateway: Some("10.0.0.1".to_string()),
+            snat: Some(true),
+            dhcp_range: Some(vec![
+                SdnDhcpRange {
+                    start_address: "10.0.0.100".to_string(),
+                    end_address: "10.0.0.150".to_string(),
+                },
+                SdnDhcpRange {
+                    start_address: "10.0.0.200".to_string(),
+                    end_address: "10.0.0.250".to_string(),
+                },
+            ]),
+            dhcp_dns_server: None,
+        };
+
+        assert_eq!(
+            subnet_params(&properties).unwrap(),
+            serde_json::json!({
+                "gateway": "10.0.0.1",
+                "snat": true,
+                "dhcp-range": [
+                    "start-address=10.0.0.100,end-address=10.0.0.150",
+                    "start-address=10.0.0.200,end-address=10.0.0.250",
+                ],
+            }),
+        );
+
+        assert_eq!(pve_subnet_id("zone1", "10.0.0.0-24"), "zone1-10.0.0.0-24");
+    }
+}
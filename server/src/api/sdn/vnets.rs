@@ -1,16 +1,19 @@
 use std::collections::HashSet;
 
-use anyhow::{Context, Error};
+use anyhow::{format_err, Context, Error};
 use pbs_api_types::REMOTE_ID_SCHEMA;
 use pdm_api_types::{
     remotes::RemoteType,
-    sdn::{CreateVnetRemote, ListVnet, SDN_ID_SCHEMA, VXLAN_ID_SCHEMA},
+    sdn::{CreateVnetRemote, ListVnet, UpdateVnet, SDN_ID_SCHEMA, VXLAN_ID_SCHEMA},
     Authid, PRIV_RESOURCE_AUDIT,
 };
 use proxmox_access_control::CachedUserInfo;
 use proxmox_rest_server::WorkerTask;
-use proxmox_router::{http_bail, Permission, Router, RpcEnvironment};
+use proxmox_router::{
+    http_bail, list_subdirs_api_method, Permission, Router, RpcEnvironment, SubdirMap,
+};
 use proxmox_schema::api;
+use proxmox_sortable_macro::sortable;
 use pve_api_types::{CreateVnet, SdnVnetType};
 
 use crate::api::pve;
@@ -19,7 +22,17 @@ use crate::{parallel_fetcher::ParallelFetcher, sdn_client::LockedSdnClients};
 
 pub const ROUTER: Router = Router::new()
     .get(&API_METHOD_LIST_VNETS)
-    .post(&API_METHOD_CREATE_VNET);
+    .post(&API_METHOD_CREATE_VNET)
+    .match_all("vnet", &VNET_ROUTER);
+
+#[sortable]
+const VNET_SUBDIRS: SubdirMap = &sorted!([("subnets", &super::subnets::ROUTER)]);
+
+const VNET_ROUTER: Router = Router::new()
+    .get(&list_subdirs_api_method!(VNET_SUBDIRS))
+    .put(&API_METHOD_UPDATE_VNET)
+    .delete(&API_METHOD_DELETE_VNET)
+    .subdirs(VNET_SUBDIRS);
 
 #[api(
     input: {
@@ -176,3 +189,124 @@ async fn create_vnet(
 
     Ok(upid)
 }
+
+#[api(
+    input: {
+        properties: {
+            vnet: { schema: SDN_ID_SCHEMA },
+            remotes: {
+                type: Array,
+                description: "List of remotes on which the VNet should get updated.",
+                items: {
+                    schema: REMOTE_ID_SCHEMA,
+                }
+            },
+            update: {
+                type: UpdateVnet,
+                flatten: true,
+            },
+        },
+    },
+    returns: { schema: pdm_api_types::UPID_SCHEMA },
+)]
+/// Update a VNet across multiple remotes
+async fn update_vnet(
+    vnet: String,
+    remotes: Vec<String>,
+    update: UpdateVnet,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<String, Error> {
+    let auth_id: Authid = rpcenv
+        .get_auth_id()
+        .context("no authid available")?
+        .parse()?;
+
+    let mut params = serde_json::to_value(&update)?;
+    if let Some(delete) = &update.delete {
+        params["delete"] = super::delete_param(delete).into();
+    }
+
+    let upid = WorkerTask::spawn(
+        "update_vnet",
+        None,
+        auth_id.to_string(),
+        false,
+        move |_worker| async move {
+            LockedSdnClients::from_remote_names(
+                remotes.into_iter().map(|remote| (remote.clone(), remote)),
+                false,
+            )
+            .await?
+            .for_each(async move |client, ctx| {
+                proxmox_log::info!("updating vnet {vnet} on remote {}", ctx.remote_id());
+                client.update_vnet(&vnet, params.clone()).await
+            })
+            .await?
+            .apply_and_release()
+            .await
+        },
+    )?;
+
+    Ok(upid)
+}
+
+#[api(
+    input: {
+        properties: {
+            vnet: { schema: SDN_ID_SCHEMA },
+            remotes: {
+                type: Array,
+                description: "List of remotes from which the VNet should get deleted.",
+                items: {
+                    schema: REMOTE_ID_SCHEMA,
+                }
+            },
+        },
+    },
+    returns: { schema: pdm_api_types::UPID_SCHEMA },
+)]
+/// Delete a VNet across multiple remotes
+async fn delete_vnet(
+    vnet: String,
+    remotes: Vec<String>,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<String, Error> {
+    let auth_id: Authid = rpcenv
+        .get_auth_id()
+        .context("no authid available")?
+        .parse()?;
+
+    let upid = WorkerTask::spawn(
+        "delete_vnet",
+        None,
+        auth_id.to_string(),
+        false,
+        move |_worker| async move {
+            LockedSdnClients::from_remote_names(
+                remotes.into_iter().map(|remote| (remote.clone(), remote)),
+                false,
+            )
+            .await?
+            .for_each(async move |client, ctx| {
+                proxmox_log::info!("deleting vnet {vnet} on remote {}", ctx.remote_id());
+                client.delete_vnet(&vnet).await
+            })
+            .await?
+            .apply_and_release()
+            .await
+        },
+    )?;
+
+    Ok(upid)
+}
+
+/// Returns the zone of a VNet on the given remote.
+pub(super) async fn vnet_zone(remote: &str, vnet: &str) -> Result<String, Error> {
+    pve::connect_to_remote_by_id(remote)?
+        .list_vnets(None, None)
+        .await?
+        .into_iter()
+        .find(|entry| entry.vnet == vnet)
+        .and_then(|entry| entry.zone)
+        .ok_or_else(|| format_err!("vnet {vnet} does not exist on remote {remote}"))
+}
@@ -5,7 +5,7 @@ use anyhow::{Context, Error};
 use pbs_api_types::REMOTE_ID_SCHEMA;
 use pdm_api_types::{
     remotes::RemoteType,
-    sdn::{CreateZoneRemote, ListZone, SDN_ID_SCHEMA, VXLAN_ID_SCHEMA},
+    sdn::{CreateZoneRemote, ListZone, UpdateZone, SDN_ID_SCHEMA, VXLAN_ID_SCHEMA},
     Authid, PRIV_RESOURCE_AUDIT,
 };
 use proxmox_access_control::CachedUserInfo;
@@ -13,6 +13,7 @@ use proxmox_rest_server::WorkerTask;
 use proxmox_router::{http_bail, Permission, Router, RpcEnvironment};
 use proxmox_schema::api;
 use pve_api_types::{CreateZone, ListZonesType};
+use serde_json::Value;
 
 use crate::api::pve;
 use crate::api::remotes::RemoteIterator;
@@ -20,7 +21,12 @@ use crate::{parallel_fetcher::ParallelFetcher, sdn_client::LockedSdnClients};
 
 pub const ROUTER: Router = Router::new()
     .get(&API_METHOD_LIST_ZONES)
-    .post(&API_METHOD_CREATE_ZONE);
+    .post(&API_METHOD_CREATE_ZONE)
+    .match_all("zone", &ZONE_ROUTER);
+
+const ZONE_ROUTER: Router = Router::new()
+    .put(&API_METHOD_UPDATE_ZONE)
+    .delete(&API_METHOD_DELETE_ZONE);
 
 #[api(
     input: {
@@ -200,3 +206,146 @@ async fn create_zone(
 
     Ok(upid)
 }
+
+#[api(
+    input: {
+        properties: {
+            zone: { schema: SDN_ID_SCHEMA },
+            remotes: {
+                type: Array,
+                description: "List of remotes on which the zone should get updated.",
+                items: {
+                    schema: REMOTE_ID_SCHEMA,
+                }
+            },
+            update: {
+                type: UpdateZone,
+                flatten: true,
+            },
+        },
+    },
+    returns: { schema: pdm_api_types::UPID_SCHEMA },
+)]
+/// Update a zone across multiple remotes
+async fn update_zone(
+    zone: String,
+    remotes: Vec<String>,
+    update: UpdateZone,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<String, Error> {
+    let auth_id: Authid = rpcenv
+        .get_auth_id()
+        .context("no authid available")?
+        .parse()?;
+
+    let params = update_zone_params(&update)?;
+
+    let upid = WorkerTask::spawn(
+        "update_zone",
+        None,
+        auth_id.to_string(),
+        false,
+        move |_worker| async move {
+            LockedSdnClients::from_remote_names(
+                remotes.into_iter().map(|remote| (remote.clone(), remote)),
+                false,
+            )
+            .await?
+            .for_each(async move |client, ctx| {
+                proxmox_log::info!("updating zone {zone} on remote {}", ctx.remote_id());
+                client.update_zone(&zone, params.clone()).await
+            })
+            .await?
+            .apply_and_release()
+            .await
+        },
+    )?;
+
+    Ok(upid)
+}
+
+/// Converts the zone update to the parameters of the PVE API, which expects the properties to
+/// delete as comma separated list.
+fn update_zone_params(update: &UpdateZone) -> Result<Value, Error> {
+    let mut params = serde_json::to_value(update)?;
+
+    if let Some(delete) = &update.delete {
+        params["delete"] = super::delete_param(delete).into();
+    }
+
+    Ok(params)
+}
+
+#[api(
+    input: {
+        properties: {
+            zone: { schema: SDN_ID_SCHEMA },
+            remotes: {
+                type: Array,
+                description: "List of remotes from which the zone should get deleted.",
+                items: {
+                    schema: REMOTE_ID_SCHEMA,
+                }
+            },
+        },
+    },
+    returns: { schema: pdm_api_types::UPID_SCHEMA },
+)]
+/// Delete a zone across multiple remotes
+async fn delete_zone(
+    zone: String,
+    remotes: Vec<String>,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<String, Error> {
+    let auth_id: Authid = rpcenv
+        .get_auth_id()
+        .context("no authid available")?
+        .parse()?;
+
+    let upid = WorkerTask::spawn(
+        "delete_zone",
+        None,
+        auth_id.to_string(),
+        false,
+        move |_worker| async move {
+            LockedSdnClients::from_remote_names(
+                remotes.into_iter().map(|remote| (remote.clone(), remote)),
+                false,
+            )
+            .await?
+            .for_each(async move |client, ctx| {
+                proxmox_log::info!("deleting zone {zone} on remote {}", ctx.remote_id());
+                client.delete_zone(&zone).await
+            })
+            .await?
+            .apply_and_release()
+            .await
+        },
+    )?;
+
+    Ok(upid)
+}
+
+#[cfg(test)]
+mod tests {
+    use pdm_api_types::sdn::SdnZoneDeletableProperty;
+
+    use super::*;
+
+    #[test]
+    fn zone_update_params() {
+        let update = UpdateZone {
+            controller: Some("evpn1".to_string()),
+            delete: Some(vec![
+                SdnZoneDeletableProperty::Mtu,
+                SdnZoneDeletableProperty::Nodes,
+            ]),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            update_zone_params(&update).unwrap(),
+            serde_json::json!({ "controller": "evpn1", "delete": "mtu,nodes" }),
+        );
+    }
+}
@@ -0,0 +1,988 @@
+//! Pool of subscription keys which can be assigned to the nodes of remotes.
+
+use std::collections::HashMap;
+
+use anyhow::{bail, format_err, Context, Error};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use proxmox_access_control::CachedUserInfo;
+use proxmox_config_digest::ConfigDigest;
+use proxmox_rest_server::WorkerTask;
+use proxmox_router::{
+    http_bail, http_err, list_subdirs_api_method, Permission, Router, RpcEnvironment, SubdirMap,
+};
+use proxmox_schema::{api, param_bail};
+use proxmox_sortable_macro::sortable;
+use proxmox_subscription::SubscriptionStatus;
+
+use pdm_api_types::remotes::{Remote, RemoteType, REMOTE_ID_SCHEMA};
+use pdm_api_types::subscription::{
+    NodeSubscriptionInfo, SubscriptionAutoAssignResult, SubscriptionKey, SubscriptionKeyAssignment,
+    SubscriptionKeyEntry, SubscriptionKeyState, SubscriptionKeyStatus, SUBSCRIPTION_KEY_SCHEMA,
+};
+use pdm_api_types::{Authid, NODE_SCHEMA, PRIV_SYS_AUDIT, PRIV_SYS_MODIFY};
+
+use crate::connection;
+
+use super::resources::get_subscription_info_for_remote;
+
+pub const ROUTER: Router = Router::new()
+    .get(&list_subdirs_api_method!(SUBDIRS))
+    .subdirs(SUBDIRS);
+
+#[sortable]
+const SUBDIRS: SubdirMap = &sorted!([
+    (
+        "auto-assign",
+        &Router::new().post(&API_METHOD_AUTO_ASSIGN_KEYS)
+    ),
+    ("keys", &KEYS_ROUTER),
+]);
+
+const KEYS_ROUTER: Router = Router::new()
+    .get(&API_METHOD_LIST_KEYS)
+    .post(&API_METHOD_ADD_KEY)
+    .match_all("key", &KEY_ROUTER);
+
+const KEY_ROUTER: Router = Router::new()
+    .get(&API_METHOD_READ_KEY)
+    .put(&API_METHOD_UPDATE_KEY)
+    .delete(&API_METHOD_REMOVE_KEY)
+    .subdirs(KEY_SUBDIRS);
+
+#[sortable]
+const KEY_SUBDIRS: SubdirMap = &sorted!([
+    ("assign", &Router::new().post(&API_METHOD_ASSIGN_KEY)),
+    ("check", &Router::new().post(&API_METHOD_CHECK_KEY)),
+]);
+
+/// Return all keys of the key pool.
+pub(crate) fn pool_keys() -> Result<Vec<SubscriptionKey>, Error> {
+    let (config, _digest) = pdm_config::subscription_keys::config()?;
+
+    Ok(config
+        .into_iter()
+        .map(|(_, SubscriptionKeyEntry::Key(key))| key)
+        .collect())
+}
+
+fn get_key(key: &str) -> Result<SubscriptionKey, Error> {
+    let (config, _digest) = pdm_config::subscription_keys::config()?;
+
+    match config.get(key) {
+        Some(SubscriptionKeyEntry::Key(key)) => Ok(key.clone()),
+        None => http_bail!(NOT_FOUND, "no such key '{key}' in the key pool"),
+    }
+}
+
+/// Determine the state of a pool key from the subscription info of the node it is assigned to.
+fn key_state(
+    key: &SubscriptionKey,
+    info: Option<&NodeSubscriptionInfo>,
+) -> (SubscriptionKeyState, Option<String>) {
+    if !key.is_assigned() {
+        return (SubscriptionKeyState::Free, None);
+    }
+
+    let Some(info) = info else {
+        return (
+            SubscriptionKeyState::Unknown,
+            Some("could not query the subscription of the node".into()),
+        );
+    };
+
+    if info.key.as_deref() != Some(key.key.as_str()) {
+        return (
+            SubscriptionKeyState::Unknown,
+            Some("the node uses a different key".into()),
+        );
+    }
+
+    match info.status {
+        SubscriptionStatus::Active | SubscriptionStatus::New => {
+            (SubscriptionKeyState::Active, info.message.clone())
+        }
+        _ => {
+            let serverid_changed = matches!(
+                (&key.serverid, &info.serverid),
+                (Some(activated), Some(current)) if activated != current
+            );
+            let serverid_rejected = info
+                .message
+                .as_deref()
+                .is_some_and(|message| message.to_lowercase().contains("server id"));
+
+            if serverid_changed || serverid_rejected {
+                (SubscriptionKeyState::ReissueRequired, info.message.clone())
+            } else {
+                (SubscriptionKeyState::Invalid, info.message.clone())
+            }
+        }
+    }
+}
+
+/// Flag nodes whose key is from the key pool, but needs to be reissued.
+pub(crate) fn mark_reissue_required(
+    node_status: &mut HashMap<String, Option<NodeSubscriptionInfo>>,
+    keys: &[SubscriptionKey],
+) {
+    for key in keys {
+        let Some(Some(info)) = key.node.as_ref().and_then(|node| node_status.get_mut(node)) else {
+            continue;
+        };
+
+        if key_state(key, Some(info)).0 == SubscriptionKeyState::ReissueRequired {
+            info.reissue_required = true;
+        }
+    }
+}
+
+async fn key_status(key: SubscriptionKey, max_age: u64) -> SubscriptionKeyStatus {
+    let (state, message) = match assigned_node_info(&key, max_age).await {
+        Ok(info) => key_state(&key, info.as_ref()),
+        Err(err) => (SubscriptionKeyState::Unknown, Some(format!("{err:#}"))),
+    };
+
+    SubscriptionKeyStatus {
+        product: key.product(),
+        level: key.level(),
+        sockets: key.sockets(),
+        state,
+        message,
+        config: key,
+    }
+}
+
+/// Get the subscription info of the node the key is assigned to.
+async fn assigned_node_info(
+    key: &SubscriptionKey,
+    max_age: u64,
+) -> Result<Option<NodeSubscriptionInfo>, Error> {
+    let (Some(remote), Some(node)) = (&key.remote, &key.node) else {
+        return Ok(None);
+    };
+
+    let (remotes, _) = pdm_config::remotes::config()?;
+    let remote = super::remotes::get_remote(&remotes, remote)?;
+
+    let mut node_info = get_subscription_info_for_remote(remote, max_age).await?;
+    Ok(node_info.remove(node).flatten())
+}
+
+fn check_remote_privs(remote: &str, rpcenv: &dyn RpcEnvironment) -> Result<(), Error> {
+    let auth_id: Authid = rpcenv
+        .get_auth_id()
+        .context("no authid available")?
+        .parse()?;
+
+    CachedUserInfo::new()?.check_privs(&auth_id, &["resource", remote], PRIV_SYS_MODIFY, false)
+}
+
+#[api(
+    input: {
+        properties: {
+            "max-age": {
+                description: "Maximum age (in seconds) of the cached subscription state of the nodes.",
+                default: 24*60*60,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["system"], PRIV_SYS_AUDIT, false),
+    },
+    returns: {
+        description: "The keys of the key pool.",
+        type: Array,
+        items: { type: SubscriptionKeyStatus },
+    },
+)]
+/// List the keys of the key pool with their state.
+pub async fn list_keys(
+    max_age: u64,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<Vec<SubscriptionKeyStatus>, Error> {
+    let (config, digest) = pdm_config::subscription_keys::config()?;
+
+    rpcenv["digest"] = digest.to_hex().into();
+
+    let futures = config
+        .into_iter()
+        .map(|(_, SubscriptionKeyEntry::Key(key))| key_status(key, max_age));
+
+    Ok(futures::future::join_all(futures).await)
+}
+
+#[api(
+    protected: true,
+    input: {
+        properties: {
+            key: {
+                schema: SUBSCRIPTION_KEY_SCHEMA,
+            },
+            comment: {
+                schema: pdm_api_types::SINGLE_LINE_COMMENT_SCHEMA,
+                optional: true,
+            },
+            digest: {
+                type: ConfigDigest,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["system"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Add a key to the key pool.
+pub fn add_key(
+    key: String,
+    comment: Option<String>,
+    digest: Option<ConfigDigest>,
+) -> Result<(), Error> {
+    let _lock = pdm_config::subscription_keys::lock_config()?;
+
+    let (mut config, config_digest) = pdm_config::subscription_keys::config()?;
+
+    config_digest.detect_modification(digest.as_ref())?;
+
+    if config.contains_key(&key) {
+        param_bail!("key", "key '{key}' is already in the key pool");
+    }
+
+    let entry = SubscriptionKeyEntry::Key(SubscriptionKey {
+        key: key.clone(),
+        comment,
+        ..Default::default()
+    });
+    config.insert(key, entry);
+
+    pdm_config::subscription_keys::save_config(&config)
+}
+
+#[api(
+    input: {
+        properties: {
+            key: {
+                schema: SUBSCRIPTION_KEY_SCHEMA,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["system"], PRIV_SYS_AUDIT, false),
+    },
+    returns: { type: SubscriptionKeyStatus },
+)]
+/// Get a key of the key pool with its current state.
+pub async fn read_key(
+    key: String,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<SubscriptionKeyStatus, Error> {
+    let (config, digest) = pdm_config::subscription_keys::config()?;
+
+    rpcenv["digest"] = digest.to_hex().into();
+
+    let key = match config.get(&key) {
+        Some(SubscriptionKeyEntry::Key(key)) => key.clone(),
+        None => http_bail!(NOT_FOUND, "no such key '{key}' in the key pool"),
+    };
+
+    Ok(key_status(key, 0).await)
+}
+
+#[api()]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+/// Deletable property name
+pub enum DeletableProperty {
+    /// Delete the comment.
+    Comment,
+    /// Release the key from the node it is assigned to, for example after reissuing it.
+    Assignment,
+}
+
+#[api(
+    protected: true,
+    input: {
+        properties: {
+            key: {
+                schema: SUBSCRIPTION_KEY_SCHEMA,
+            },
+            comment: {
+                schema: pdm_api_types::SINGLE_LINE_COMMENT_SCHEMA,
+                optional: true,
+            },
+            delete: {
+                description: "List of properties to delete.",
+                type: Array,
+                optional: true,
+                items: {
+                    type: DeletableProperty,
+                }
+            },
+            digest: {
+                type: ConfigDigest,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["system"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Update a key of the key pool.
+///
+/// Releasing the assignment only changes the key pool, the key stays on the node.
+pub fn update_key(
+    key: String,
+    comment: Option<String>,
+    delete: Option<Vec<DeletableProperty>>,
+    digest: Option<ConfigDigest>,
+) -> Result<(), Error> {
+    let _lock = pdm_config::subscription_keys::lock_config()?;
+
+    let (mut config, config_digest) = pdm_config::subscription_keys::config()?;
+
+    config_digest.detect_modification(digest.as_ref())?;
+
+    let SubscriptionKeyEntry::Key(entry) = config
+        .get_mut(&key)
+        .ok_or_else(|| http_err!(NOT_FOUND, "no such key '{key}' in the key pool"))?;
+
+    if let Some(delete) = delete {
+        for delete_prop in delete {
+            match delete_prop {
+                DeletableProperty::Comment => entry.comment = None,
+                DeletableProperty::Assignment => {
+                    entry.remote = None;
+                    entry.node = None;
+                    entry.serverid = None;
+                }
+            }
+        }
+    }
+
+    if comment.is_some() {
+        entry.comment = comment;
+    }
+
+    pdm_config::subscription_keys::save_config(&config)
+}
+
+#[api(
+    protected: true,
+    input: {
+        properties: {
+            key: {
+                schema: SUBSCRIPTION_KEY_SCHEMA,
+            },
+            digest: {
+                type: ConfigDigest,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["system"], PRIV_SYS_MODIFY, false),
+    },
+)]
+/// Remove a key from the key pool. The key is not removed from the node it is assigned to.
+pub fn remove_key(key: String, digest: Option<ConfigDigest>) -> Result<(), Error> {
+    let _lock = pdm_config::subscription_keys::lock_config()?;
+
+    let (mut config, config_digest) = pdm_config::subscription_keys::config()?;
+
+    config_digest.detect_modification(digest.as_ref())?;
+
+    if config.remove(&key).is_none() {
+        http_bail!(NOT_FOUND, "no such key '{key}' in the key pool");
+    }
+
+    pdm_config::subscription_keys::save_config(&config)
+}
+
+#[api(
+    protected: true,
+    input: {
+        properties: {
+            key: {
+                schema: SUBSCRIPTION_KEY_SCHEMA,
+            },
+            remote: {
+                schema: REMOTE_ID_SCHEMA,
+            },
+            node: {
+                schema: NODE_SCHEMA,
+                optional: true,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["system"], PRIV_SYS_MODIFY, false),
+        description: "Additionally, `Sys.Modify` on `/resource/{remote}` is required.",
+    },
+    returns: { type: SubscriptionKeyStatus },
+)]
+/// Upload a key of the key pool to a node.
+///
+/// The node must not have an active subscription, and for Proxmox VE nodes, the key must cover
+/// at least the number of CPU sockets of the node. The `node` is only required for Proxmox VE
+/// remotes.
+pub async fn assign_key(
+    key: String,
+    remote: String,
+    node: Option<String>,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<SubscriptionKeyStatus, Error> {
+    check_remote_privs(&remote, rpcenv)?;
+
+    let (remotes, _) = pdm_config::remotes::config()?;
+    let remote = super::remotes::get_remote(&remotes, &remote)?;
+
+    let node = match (remote.ty, node) {
+        (RemoteType::Pve, Some(node)) => node,
+        (RemoteType::Pve, None) => param_bail!("node", "a node is required for PVE remotes"),
+        (RemoteType::Pbs, _) => "localhost".to_string(),
+    };
+
+    let key = get_key(&key)?;
+
+    let reserved = reserve_keys(vec![SubscriptionKeyAssignment {
+        key: key.key.clone(),
+        remote: remote.id.clone(),
+        node: node.clone(),
+    }])?;
+    if reserved.is_empty() {
+        http_bail!(
+            BAD_REQUEST,
+            "key '{}' is already assigned, release it first",
+            key.key
+        );
+    }
+
+    upload_key(remote, &node, &key.key).await?;
+
+    Ok(key_status(get_key(&key.key)?, 0).await)
+}
+
+#[api(
+    protected: true,
+    input: {
+        properties: {
+            key: {
+                schema: SUBSCRIPTION_KEY_SCHEMA,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["system"], PRIV_SYS_MODIFY, false),
+        description: "Additionally, `Sys.Modify` on the remote the key is assigned to is required.",
+    },
+    returns: { type: SubscriptionKeyStatus },
+)]
+/// Check the key against the shop server on the node it is assigned to.
+pub async fn check_key(
+    key: String,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<SubscriptionKeyStatus, Error> {
+    let key = get_key(&key)?;
+
+    let (Some(remote), Some(node)) = (&key.remote, &key.node) else {
+        http_bail!(BAD_REQUEST, "key '{}' is not assigned to a node", key.key);
+    };
+
+    check_remote_privs(remote, rpcenv)?;
+
+    let (remotes, _) = pdm_config::remotes::config()?;
+    let remote = super::remotes::get_remote(&remotes, remote)?;
+
+    match remote.ty {
+        RemoteType::Pve => {
+            connection::make_raw_client(remote)?
+                .request(
+                    http::Method::POST,
+                    &format!("/api2/extjs/nodes/{node}/subscription"),
+                    Some(json!({ "force": true })),
+                )
+                .await?
+                .nodata()?;
+        }
+        RemoteType::Pbs => {
+            connection::make_pbs_client(remote)?
+                .check_subscription()
+                .await?
+        }
+    }
+
+    Ok(key_status(key, 0).await)
+}
+
+/// Reserve free keys of the key pool for the nodes they are about to be uploaded to.
+///
+/// The keys are checked and marked as assigned while holding the config lock, so concurrent
+/// assignments cannot pick the same key. Returns the assignments whose key could be reserved.
+fn reserve_keys(
+    assignments: Vec<SubscriptionKeyAssignment>,
+) -> Result<Vec<SubscriptionKeyAssignment>, Error> {
+    let _lock = pdm_config::subscription_keys::lock_config()?;
+    let (mut config, _digest) = pdm_config::subscription_keys::config()?;
+
+    let mut reserved = Vec::new();
+
+    for assignment in assignments {
+        let Some(SubscriptionKeyEntry::Key(entry)) = config.get_mut(&assignment.key) else {
+            continue;
+        };
+
+        if entry.is_assigned() {
+            continue;
+        }
+
+        entry.remote = Some(assignment.remote.clone());
+        entry.node = Some(assignment.node.clone());
+        entry.serverid = None;
+
+        reserved.push(assignment);
+    }
+
+    if !reserved.is_empty() {
+        pdm_config::subscription_keys::save_config(&config)?;
+    }
+
+    Ok(reserved)
+}
+
+/// Release the reservation of a key, unless it got assigned to a different node in the meantime.
+fn release_key(key: &str, remote: &str, node: &str) -> Result<(), Error> {
+    let _lock = pdm_config::subscription_keys::lock_config()?;
+    let (mut config, _digest) = pdm_config::subscription_keys::config()?;
+
+    let Some(SubscriptionKeyEntry::Key(entry)) = config.get_mut(key) else {
+        return Ok(());
+    };
+
+    if entry.remote.as_deref() != Some(remote) || entry.node.as_deref() != Some(node) {
+        return Ok(());
+    }
+
+    entry.remote = None;
+    entry.node = None;
+    entry.serverid = None;
+
+    pdm_config::subscription_keys::save_config(&config)
+}
+
+/// Upload a key reserved via [`reserve_keys`] to a node, and record the assignment in the key
+/// pool. The reservation is released if the upload fails.
+async fn upload_key(remote: &Remote, node: &str, key: &str) -> Result<(), Error> {
+    {
+        let _lock = pdm_config::subscription_keys::lock_config()?;
+        let (config, _digest) = pdm_config::subscription_keys::config()?;
+
+        match config.get(key) {
+            Some(SubscriptionKeyEntry::Key(entry))
+                if entry.remote.as_deref() == Some(&remote.id)
+                    && entry.node.as_deref() == Some(node) => {}
+            Some(_) => bail!("key '{key}' is not reserved for {}/{node}", remote.id),
+            None => bail!("key '{key}' is not in the key pool anymore"),
+        }
+    }
+
+    if let Err(err) = do_upload_key(remote, node, key).await {
+        if let Err(release_err) = release_key(key, &remote.id, node) {
+            log::error!("could not release reservation of key '{key}': {release_err:#}");
+        }
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+/// Upload a key to a node, and record the assignment and server ID in the key pool.
+async fn do_upload_key(remote: &Remote, node: &str, key: &str) -> Result<(), Error> {
+    let node_info = get_subscription_info_for_remote(remote, 0).await?;
+
+    let info = node_info
+        .get(node)
+        .ok_or_else(|| format_err!("remote '{}' has no node '{node}'", remote.id))?
+        .as_ref()
+        .ok_or_else(|| format_err!("could not query the subscription of node '{node}'"))?;
+
+    let pool_key = SubscriptionKey {
+        key: key.to_string(),
+        ..Default::default()
+    };
+    check_key_fits(&pool_key, remote.ty, info)?;
+
+    match remote.ty {
+        RemoteType::Pve => {
+            connection::make_raw_client(remote)?
+                .request(
+                    http::Method::PUT,
+                    &format!("/api2/extjs/nodes/{node}/subscription"),
+                    Some(json!({ "key": key })),
+                )
+                .await?
+                .nodata()?;
+        }
+        RemoteType::Pbs => {
+            connection::make_pbs_client(remote)?
+                .set_subscription(key)
+                .await?
+        }
+    }
+
+    // refresh the cached state, so the new key shows up right away
+    let serverid = match get_subscription_info_for_remote(remote, 0).await {
+        Ok(mut node_info) => node_info
+            .remove(node)
+            .flatten()
+            .and_then(|info| info.serverid),
+        Err(err) => {
+            log::warn!(
+                "could not refresh subscription state of '{}': {err:#}",
+                remote.id
+            );
+            info.serverid.clone()
+        }
+    };
+
+    let _lock = pdm_config::subscription_keys::lock_config()?;
+    let (mut config, _digest) = pdm_config::subscription_keys::config()?;
+
+    let ids: Vec<String> = config.iter().map(|(id, _)| id.to_string()).collect();
+
+    for id in ids {
+        let Some(SubscriptionKeyEntry::Key(entry)) = config.get_mut(&id) else {
+            continue;
+        };
+
+        if entry.key == key {
+            entry.remote = Some(remote.id.clone());
+            entry.node = Some(node.to_string());
+            entry.serverid = serverid.clone();
+        } else if entry.remote.as_deref() == Some(&remote.id) && entry.node.as_deref() == Some(node)
+        {
+            // the node got a new key, so the previous one is not in use anymore
+            entry.remote = None;
+            entry.node = None;
+            entry.serverid = None;
+        }
+    }
+
+    pdm_config::subscription_keys::save_config(&config)
+}
+
+/// Check that a key can be uploaded to a node.
+fn check_key_fits(
+    key: &SubscriptionKey,
+    ty: RemoteType,
+    info: &NodeSubscriptionInfo,
+) -> Result<(), Error> {
+    if key.product() != ty {
+        bail!("key '{}' is not for {ty} remotes", key.key);
+    }
+
+    if matches!(
+        info.status,
+        SubscriptionStatus::Active | SubscriptionStatus::New
+    ) {
+        bail!("the node already has an active subscription");
+    }
+
+    if let (Some(needed), Some(covered)) = (info.sockets, key.sockets()) {
+        if needed > covered {
+            bail!(
+                "key '{}' covers {covered} CPU sockets, but the node has {needed}",
+                key.key
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// A node without subscription, candidate for getting a key of the key pool.
+struct UnsubscribedNode {
+    remote: String,
+    ty: RemoteType,
+    node: String,
+    sockets: Option<i64>,
+}
+
+/// Pick a free key for every node, preferring the key with the fewest sockets which still covers
+/// the node.
+fn plan_assignments(
+    mut free_keys: Vec<SubscriptionKey>,
+    nodes: &[UnsubscribedNode],
+) -> Vec<SubscriptionKeyAssignment> {
+    free_keys.sort_by(|a, b| a.sockets().cmp(&b.sockets()).then(a.key.cmp(&b.key)));
+
+    let mut assignments = Vec::new();
+
+    for node in nodes {
+        let Some(index) = free_keys.iter().position(|key| {
+            key.product() == node.ty
+                && match (node.sockets, key.sockets()) {
+                    (Some(needed), Some(covered)) => needed <= covered,
+                    _ => true,
+                }
+        }) else {
+            continue;
+        };
+
+        let key = free_keys.remove(index);
+        assignments.push(SubscriptionKeyAssignment {
+            key: key.key,
+            remote: node.remote.clone(),
+            node: node.node.clone(),
+        });
+    }
+
+    assignments
+}
+
+#[api(
+    protected: true,
+    input: {
+        properties: {
+            remotes: {
+                type: Array,
+                description: "Only assign keys to nodes of these remotes.",
+                optional: true,
+                items: {
+                    schema: REMOTE_ID_SCHEMA,
+                },
+            },
+            "dry-run": {
+                description: "Only return which keys would be assigned to which nodes.",
+                optional: true,
+                default: false,
+            },
+        },
+    },
+    access: {
+        permission: &Permission::Privilege(&["system"], PRIV_SYS_MODIFY, false),
+        description: "Only remotes with `Sys.Modify` on `/resource/{remote}` are considered.",
+    },
+    returns: { type: SubscriptionAutoAssignResult },
+)]
+/// Assign free keys of the key pool to all nodes without a subscription.
+///
+/// The planned keys are reserved right away and uploaded one after another in a worker task.
+pub async fn auto_assign_keys(
+    remotes: Option<Vec<String>>,
+    dry_run: bool,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<SubscriptionAutoAssignResult, Error> {
+    let auth_id: Authid = rpcenv
+        .get_auth_id()
+        .context("no authid available")?
+        .parse()?;
+    let user_info = CachedUserInfo::new()?;
+
+    let (remotes_config, _) = pdm_config::remotes::config()?;
+
+    let mut nodes = Vec::new();
+
+    for (_, remote) in remotes_config.iter() {
+        if remotes
+            .as_ref()
+            .is_some_and(|remotes| !remotes.contains(&remote.id))
+        {
+            continue;
+        }
+
+        if user_info.lookup_privs(&auth_id, &["resource", &remote.id]) & PRIV_SYS_MODIFY == 0 {
+            continue;
+        }
+
+        let node_info = match get_subscription_info_for_remote(remote, 0).await {
+            Ok(node_info) => node_info,
+            Err(err) => {
+                log::warn!("could not query subscriptions of '{}': {err:#}", remote.id);
+                continue;
+            }
+        };
+
+        for (node, info) in node_info {
+            let Some(info) = info else {
+                continue;
+            };
+
+            if info.key.is_none() || info.status == SubscriptionStatus::NotFound {
+                nodes.push(UnsubscribedNode {
+                    remote: remote.id.clone(),
+                    ty: remote.ty,
+                    node,
+                    sockets: info.sockets,
+                });
+            }
+        }
+    }
+
+    nodes.sort_by(|a, b| a.remote.cmp(&b.remote).then(a.node.cmp(&b.node)));
+
+    let free_keys = pool_keys()?
+        .into_iter()
+        .filter(|key| !key.is_assigned())
+        .collect();
+
+    let assignments = plan_assignments(free_keys, &nodes);
+
+    if dry_run || assignments.is_empty() {
+        return Ok(SubscriptionAutoAssignResult {
+            assignments,
+            upid: None,
+        });
+    }
+
+    let assignments = reserve_keys(assignments)?;
+    if assignments.is_empty() {
+        bail!("the planned keys got assigned concurrently, please retry");
+    }
+
+    let planned = assignments.clone();
+
+    let upid = WorkerTask::spawn(
+        "subscription-assign",
+        None,
+        auth_id.to_string(),
+        true,
+        move |_worker| async move {
+            let mut failed = 0;
+
+            for assignment in planned {
+                let SubscriptionKeyAssignment { key, remote, node } = assignment;
+
+                let result = match remotes_config.get(&remote) {
+                    Some(remote) => upload_key(remote, &node, &key).await,
+                    None => {
+                        if let Err(err) = release_key(&key, &remote, &node) {
+                            log::error!("could not release reservation of key {key} - {err:#}");
+                        }
+                        Err(format_err!("remote '{remote}' does not exist anymore"))
+                    }
+                };
+
+                match result {
+                    Ok(()) => log::info!("{remote}/{node}: uploaded key {key}"),
+                    Err(err) => {
+                        log::error!("{remote}/{node}: could not upload key {key} - {err:#}");
+                        failed += 1;
+                    }
+                }
+            }
+
+            if failed > 0 {
+                bail!("uploading {failed} keys failed");
+            }
+
+            Ok(())
+        },
+    )?;
+
+    Ok(SubscriptionAutoAssignResult {
+        assignments,
+        upid: Some(upid),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(key: &str) -> SubscriptionKey {
+        SubscriptionKey {
+            key: key.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn node(remote: &str, ty: RemoteType, node: &str, sockets: Option<i64>) -> UnsubscribedNode {
+        UnsubscribedNode {
+            remote: remote.to_string(),
+            ty,
+            node: node.to_string(),
+            sockets,
+        }
+    }
+
+    #[test]
+    fn key_properties() {
+        let pve = key("pve4s-0123456789");
+        assert_eq!(pve.product(), RemoteType::Pve);
+        assert_eq!(pve.sockets(), Some(4));
+
+        let pbs = key("pbsc-0123456789");
+        assert_eq!(pbs.product(), RemoteType::Pbs);
+        assert_eq!(pbs.sockets(), None);
+    }
+
+    #[test]
+    fn assign_smallest_fitting_key() {
+        let keys = vec![
+            key("pve4s-0000000001"),
+            key("pve2s-0000000002"),
+            key("pve1s-0000000003"),
+            key("pbss-0000000004"),
+        ];
+
+        let nodes = [
+            node("cluster", RemoteType::Pve, "a", Some(2)),
+            node("cluster", RemoteType::Pve, "b", Some(2)),
+            node("cluster", RemoteType::Pve, "c", Some(8)),
+            node("backup", RemoteType::Pbs, "localhost", None),
+        ];
+
+        let assignments: Vec<(String, String)> = plan_assignments(keys, &nodes)
+            .into_iter()
+            .map(|assignment| (assignment.node, assignment.key))
+            .collect();
+
+        assert_eq!(
+            assignments,
+            [
+                ("a".to_string(), "pve2s-0000000002".to_string()),
+                ("b".to_string(), "pve4s-0000000001".to_string()),
+                ("localhost".to_string(), "pbss-0000000004".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reissue_required() {
+        let mut key = key("pve2c-0123456789");
+        key.remote = Some("cluster".into());
+        key.node = Some("a".into());
+        key.serverid = Some("AAAA".into());
+
+        let mut info = NodeSubscriptionInfo {
+            status: SubscriptionStatus::Active,
+            key: Some(key.key.clone()),
+            serverid: Some("AAAA".into()),
+            ..Default::default()
+        };
+        assert_eq!(key_state(&key, Some(&info)).0, SubscriptionKeyState::Active);
+
+        info.status = SubscriptionStatus::Invalid;
+        info.serverid = Some("BBBB".into());
+        assert_eq!(
+            key_state(&key, Some(&info)).0,
+            SubscriptionKeyState::ReissueRequired
+        );
+
+        info.serverid = Some("AAAA".into());
+        assert_eq!(
+            key_state(&key, Some(&info)).0,
+            SubscriptionKeyState::Invalid
+        );
+
+        info.key = None;
+        assert_eq!(
+            key_state(&key, Some(&info)).0,
+            SubscriptionKeyState::Unknown
+        );
+    }
+}
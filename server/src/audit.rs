@@ -0,0 +1,420 @@
+//! Audit log of all state-changing API calls.
+//!
+//! The REST server has no hook which sees both the parameters and the result of a call, so the
+//! API router is copied once at startup, wrapping the handler of every non-GET method. Calls of
+//! protected methods are only executed, and thus logged, by the privileged daemon.
+//!
+//! Every entry is appended as one JSON line to [`API_AUDIT_LOG_FN`], which is rotated together
+//! with the access log.
+
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+
+use anyhow::Error;
+use serde_json::Value;
+
+use proxmox_router::{
+    ApiAsyncHandlerFn, ApiFuture, ApiHandler, ApiHandlerFn, ApiMethod, Router, RpcEnvironment,
+    SubRoute,
+};
+use proxmox_sys::logrotate::LogRotate;
+
+use pdm_api_types::audit::AuditLogEntry;
+use pdm_api_types::{Authid, RemoteUpid};
+use pdm_buildcfg::API_AUDIT_LOG_FN;
+
+/// Parameter values of these names are replaced before being logged.
+const REDACTED_PARAMETERS: &[&str] = &["key", "password", "secret", "ticket", "token", "value"];
+
+/// Filter for reading the audit log.
+#[derive(Default)]
+pub struct AuditLogFilter {
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub user: Option<Authid>,
+    pub remote: Option<String>,
+}
+
+impl AuditLogFilter {
+    fn matches(&self, entry: &AuditLogEntry) -> bool {
+        self.since.is_none_or(|since| entry.time >= since)
+            && self.until.is_none_or(|until| entry.time <= until)
+            && self
+                .user
+                .as_ref()
+                .is_none_or(|user| entry.user.as_ref() == Some(user))
+            && self
+                .remote
+                .as_ref()
+                .is_none_or(|remote| entry.remote.as_ref() == Some(remote))
+    }
+}
+
+/// Return a copy of the router where every state-changing method writes an audit log entry.
+pub fn audited_router(router: &'static Router) -> &'static Router {
+    wrap_router(router, "")
+}
+
+fn wrap_router(router: &'static Router, path: &str) -> &'static Router {
+    let subroute = match &router.subroute {
+        Some(SubRoute::Map(map)) => {
+            let map: Vec<(&'static str, &'static Router)> = map
+                .iter()
+                .map(|&(name, router)| (name, wrap_router(router, &format!("{path}/{name}"))))
+                .collect();
+            Some(SubRoute::Map(Box::leak(map.into_boxed_slice())))
+        }
+        Some(SubRoute::MatchAll { router, param_name }) => Some(SubRoute::MatchAll {
+            router: wrap_router(*router, &format!("{path}/{{{param_name}}}")),
+            param_name: *param_name,
+        }),
+        None => None,
+    };
+
+    Box::leak(Box::new(Router {
+        get: router.get,
+        put: router.put.map(|method| wrap_method(method, "PUT", path)),
+        post: router.post.map(|method| wrap_method(method, "POST", path)),
+        delete: router
+            .delete
+            .map(|method| wrap_method(method, "DELETE", path)),
+        subroute,
+    }))
+}
+
+fn wrap_method(
+    method: &'static ApiMethod,
+    http_method: &'static str,
+    path: &str,
+) -> &'static ApiMethod {
+    let path: &'static str = Box::leak(path.to_string().into_boxed_str());
+
+    // streaming and raw HTTP handlers are left alone, they are not used for state changes
+    let handler = match *method.handler {
+        ApiHandler::Sync(handler) => ApiHandler::Sync(sync_handler(
+            move |param: Value, info: &ApiMethod, rpcenv: &mut dyn RpcEnvironment| {
+                let call = AuditCall::new(http_method, path, &param, rpcenv);
+                let result = handler(param, info, rpcenv);
+                call.finish(&result);
+                result
+            },
+        )),
+        ApiHandler::Async(handler) => ApiHandler::Async(async_handler(
+            move |param: Value, info: &'static ApiMethod, rpcenv: &mut dyn RpcEnvironment| {
+                let call = AuditCall::new(http_method, path, &param, rpcenv);
+                let future = handler(param, info, rpcenv);
+                Box::pin(async move {
+                    let result = future.await;
+                    call.finish(&result);
+                    result
+                })
+            },
+        )),
+        _ => return method,
+    };
+
+    Box::leak(Box::new(ApiMethod {
+        protected: method.protected,
+        reload_timezone: method.reload_timezone,
+        parameters: method.parameters,
+        returns: proxmox_schema::ReturnType {
+            optional: method.returns.optional,
+            schema: method.returns.schema,
+        },
+        handler: Box::leak(Box::new(handler)),
+        access: proxmox_router::ApiAccess {
+            description: method.access.description,
+            permission: method.access.permission,
+        },
+    }))
+}
+
+fn sync_handler<F>(handler: F) -> &'static ApiHandlerFn
+where
+    F: Fn(Value, &ApiMethod, &mut dyn RpcEnvironment) -> Result<Value, Error>
+        + Send
+        + Sync
+        + 'static,
+{
+    Box::leak(Box::new(handler))
+}
+
+fn async_handler<F>(handler: F) -> &'static ApiAsyncHandlerFn
+where
+    F: for<'a> Fn(Value, &'static ApiMethod, &'a mut dyn RpcEnvironment) -> ApiFuture<'a>
+        + Send
+        + Sync
+        + 'static,
+{
+    Box::leak(Box::new(handler))
+}
+
+/// The part of an audit log entry which is known before the call.
+struct AuditCall {
+    entry: AuditLogEntry,
+}
+
+impl AuditCall {
+    fn new(method: &str, path: &str, param: &Value, rpcenv: &dyn RpcEnvironment) -> Self {
+        let remote = param["remote"]
+            .as_str()
+            .or_else(|| {
+                // the remote config endpoints use `id` for the remote
+                path.starts_with("/remotes/remote")
+                    .then(|| param["id"].as_str())
+                    .flatten()
+            })
+            .map(str::to_string);
+
+        Self {
+            entry: AuditLogEntry {
+                time: proxmox_time::epoch_i64(),
+                user: rpcenv.get_auth_id().and_then(|id| id.parse().ok()),
+                client_ip: rpcenv.get_client_ip().map(|addr| addr.ip().to_string()),
+                method: method.to_string(),
+                path: fill_path(path, param),
+                remote,
+                parameters: redact(param.clone()),
+                success: false,
+                error: None,
+                upid: None,
+            },
+        }
+    }
+
+    fn finish(mut self, result: &Result<Value, Error>) {
+        match result {
+            Ok(value) => {
+                self.entry.success = true;
+                self.entry.upid = result_upid(value);
+            }
+            Err(err) => self.entry.error = Some(format!("{err:#}")),
+        }
+
+        if let Err(err) = append_entry(&self.entry) {
+            log::error!("could not write audit log entry: {err:#}");
+        }
+    }
+}
+
+/// Replace the `{param}` placeholders of the router path with the actual values.
+fn fill_path(template: &str, param: &Value) -> String {
+    let mut path = String::with_capacity(template.len());
+
+    for component in template.split('/').skip(1) {
+        path.push('/');
+        match component
+            .strip_prefix('{')
+            .and_then(|name| name.strip_suffix('}'))
+        {
+            Some(name) => match &param[name] {
+                Value::String(value) => path.push_str(value),
+                Value::Null => path.push_str(component),
+                value => path.push_str(&value.to_string()),
+            },
+            None => path.push_str(component),
+        }
+    }
+
+    path
+}
+
+fn is_secret(name: &str) -> bool {
+    REDACTED_PARAMETERS
+        .iter()
+        .any(|secret| name == *secret || name.ends_with(&format!("-{secret}")))
+}
+
+fn redact(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(name, value)| {
+                    if is_secret(&name) && !value.is_null() {
+                        (name, Value::String("<redacted>".into()))
+                    } else {
+                        (name, redact(value))
+                    }
+                })
+                .collect(),
+        ),
+        Value::Array(list) => Value::Array(list.into_iter().map(redact).collect()),
+        value => value,
+    }
+}
+
+/// Worker tasks are returned either as plain UPID, or as `upid` property of an object.
+///
+/// Tasks on remotes are returned as [`RemoteUpid`].
+fn result_upid(value: &Value) -> Option<String> {
+    match value {
+        Value::String(upid) if upid.starts_with("UPID:") => Some(upid.clone()),
+        Value::String(upid) => {
+            let upid = upid.parse::<RemoteUpid>().ok()?;
+            Some(upid.to_string())
+        }
+        Value::Object(map) => map.get("upid")?.as_str().map(str::to_string),
+        _ => None,
+    }
+}
+
+/// Create the audit log, owned by the API user, if it does not exist yet.
+pub fn create_audit_log() -> Result<(), Error> {
+    open_log()?;
+    Ok(())
+}
+
+fn open_log() -> Result<std::fs::File, Error> {
+    let file = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .mode(0o640)
+        .open(API_AUDIT_LOG_FN)?;
+
+    // the privileged daemon may create the file after a rotation
+    if nix::unistd::Uid::effective().is_root() {
+        let api_user = pdm_config::api_user()?;
+        nix::unistd::fchown(file.as_raw_fd(), Some(api_user.uid), Some(api_user.gid))?;
+    }
+
+    Ok(file)
+}
+
+fn append_entry(entry: &AuditLogEntry) -> Result<(), Error> {
+    let mut line = serde_json::to_vec(entry)?;
+    line.push(b'\n');
+
+    // a single write, so lines of both daemons do not get mixed up
+    open_log()?.write_all(&line)?;
+
+    Ok(())
+}
+
+/// Read the entries matching the filter from the audit log and its rotated files, newest first.
+///
+/// Stops after `limit` entries.
+pub fn read_entries(
+    filter: &AuditLogFilter,
+    limit: Option<usize>,
+) -> Result<Vec<AuditLogEntry>, Error> {
+    let logrotate = LogRotate::new(API_AUDIT_LOG_FN, true, None, None)?;
+
+    let mut entries = Vec::new();
+
+    for file in logrotate.files() {
+        let mut file_entries = Vec::new();
+
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            match serde_json::from_str::<AuditLogEntry>(&line) {
+                Ok(entry) => {
+                    if filter.matches(&entry) {
+                        file_entries.push(entry);
+                    }
+                }
+                Err(err) => log::warn!("skipping invalid audit log line: {err}"),
+            }
+        }
+
+        let older_than_since = file_entries
+            .first()
+            .zip(filter.since)
+            .is_some_and(|(first, since)| first.time < since);
+
+        entries.extend(file_entries.into_iter().rev());
+
+        if limit.is_some_and(|limit| entries.len() >= limit) {
+            break;
+        }
+
+        // the rotated files are only older
+        if older_than_since {
+            break;
+        }
+    }
+
+    if let Some(limit) = limit {
+        entries.truncate(limit);
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    #[test]
+    fn redact_secrets() {
+        let param = json!({
+            "id": "pve-1",
+            "token": "secret-value",
+            "nodes": [{ "hostname": "pve1", "fingerprint": "aa:bb" }],
+            "new-password": "hunter2",
+            "tokenid": "pdm-admin",
+            "comment": null,
+        });
+
+        assert_eq!(
+            redact(param),
+            json!({
+                "id": "pve-1",
+                "token": "<redacted>",
+                "nodes": [{ "hostname": "pve1", "fingerprint": "aa:bb" }],
+                "new-password": "<redacted>",
+                "tokenid": "pdm-admin",
+                "comment": null,
+            })
+        );
+    }
+
+    #[test]
+    fn fill_path_parameters() {
+        let param = json!({ "remote": "pve-1", "vmid": 100 });
+
+        assert_eq!(
+            fill_path("/pve/remotes/{remote}/qemu/{vmid}/start", &param),
+            "/pve/remotes/pve-1/qemu/100/start"
+        );
+        assert_eq!(
+            fill_path("/remotes/remote/{id}", &param),
+            "/remotes/remote/{id}"
+        );
+    }
+
+    #[test]
+    fn upid_from_result() {
+        let upid = "UPID:pdm:00000001:00000001:00000001:65920778:qmstart:100:root@pam:";
+
+        assert_eq!(result_upid(&json!(upid)).as_deref(), Some(upid));
+        assert_eq!(
+            result_upid(&json!({ "guests": [], "upid": upid })).as_deref(),
+            Some(upid)
+        );
+        assert_eq!(result_upid(&json!("done")), None);
+        assert_eq!(result_upid(&Value::Null), None);
+    }
+
+    #[test]
+    fn remote_upid_from_result() {
+        let upid =
+            "pbs:pbs-remote!UPID:pbs:000002B2:00000158:00000000:674D828C:logrotate::root@pam:";
+
+        assert_eq!(result_upid(&json!(upid)).as_deref(), Some(upid));
+        assert_eq!(result_upid(&json!({ "upid": upid })).as_deref(), Some(upid));
+
+        // the remote type is filled in for the old format without it
+        assert_eq!(
+            result_upid(&json!(
+                "pbs-remote!UPID:pbs:000002B2:00000158:00000000:674D828C:logrotate::root@pam:"
+            ))
+            .as_deref(),
+            Some(upid)
+        );
+        assert_eq!(result_upid(&json!("pbs-remote!done")), None);
+    }
+}
@@ -55,7 +55,7 @@ pub async fn check_auth(
     headers: &http::HeaderMap,
     method: &hyper::Method,
 ) -> Result<(String, Box<dyn UserInformation + Sync + Send>), AuthError> {
-    let user_info = CachedUserInfo::new()?;
+    let user_info = crate::acl::PdmUserInformation(CachedUserInfo::new()?);
 
     proxmox_auth_api::api::http_check_auth(headers, method)
         .map(move |name| (name, Box::new(user_info) as _))
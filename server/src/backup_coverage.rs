@@ -0,0 +1,358 @@
+//! Correlate the guests of PVE remotes with the backups stored on PBS remotes.
+//!
+//! PBS does not record which remote a backup was made from, so a backup group of type `vm` or
+//! `ct` is matched to every guest of the same type and VMID.
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, RwLock};
+
+use anyhow::Error;
+use futures::StreamExt;
+
+use pbs_api_types::BackupType;
+
+use pdm_api_types::backup_coverage::{BackupCoverage, BackupCoverageStatus, GuestBackupCoverage};
+use pdm_api_types::firewall::GuestKind;
+use pdm_api_types::remotes::{Remote, RemoteType};
+use pdm_api_types::resource::FailedRemote;
+
+use crate::connection;
+use crate::parallel_fetcher::ParallelFetcher;
+use crate::pbs_client::DatstoreListNamespaces;
+
+/// The most recent snapshot of a backup group.
+#[derive(Clone, Debug)]
+pub struct GroupBackup {
+    pub guest_type: GuestKind,
+    pub vmid: u32,
+    pub datastore: String,
+    pub namespace: String,
+    pub time: i64,
+}
+
+/// The backup groups of a PBS remote.
+#[derive(Clone)]
+pub struct RemoteBackups {
+    pub remote: String,
+    pub groups: Arc<Vec<GroupBackup>>,
+}
+
+/// A guest to check the backup coverage for.
+pub struct Guest {
+    pub remote: String,
+    pub guest_type: GuestKind,
+    pub vmid: u32,
+    pub name: String,
+}
+
+#[derive(Clone)]
+struct CachedBackups {
+    groups: Arc<Vec<GroupBackup>>,
+    timestamp: i64,
+}
+
+static CACHE: LazyLock<RwLock<HashMap<String, CachedBackups>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+fn get_cached_backups(remote: &str, max_age: u64) -> Option<Arc<Vec<GroupBackup>>> {
+    // there is no good way to recover from this, so panicking should be fine
+    let cache = CACHE.read().expect("mutex poisoned");
+
+    let cached = cache.get(remote)?;
+    let diff = proxmox_time::epoch_i64() - cached.timestamp;
+    if diff > max_age as i64 || diff < 0 {
+        None
+    } else {
+        Some(Arc::clone(&cached.groups))
+    }
+}
+
+fn update_cached_backups(remote: &str, groups: Arc<Vec<GroupBackup>>, now: i64) {
+    let mut cache = CACHE.write().expect("mutex poisoned");
+
+    if cache
+        .get(remote)
+        .is_some_and(|cached| cached.timestamp >= now)
+    {
+        return;
+    }
+
+    cache.insert(
+        remote.to_string(),
+        CachedBackups {
+            groups,
+            timestamp: now,
+        },
+    );
+}
+
+/// Get the guest backup groups of the given PBS remotes.
+///
+/// Listing all snapshots is expensive, so the result is cached for each remote and only fetched
+/// again if the cached data is older than `max_age` seconds.
+pub async fn get_backups(
+    remotes: Vec<Remote>,
+    max_age: u64,
+) -> (Vec<RemoteBackups>, Vec<FailedRemote>) {
+    let mut backups = Vec::new();
+    let mut to_fetch = Vec::new();
+
+    for remote in remotes {
+        match get_cached_backups(&remote.id, max_age) {
+            Some(groups) => backups.push(RemoteBackups {
+                remote: remote.id,
+                groups,
+            }),
+            None => to_fetch.push(remote),
+        }
+    }
+
+    let mut failed_remotes = Vec::new();
+
+    if to_fetch.is_empty() {
+        return (backups, failed_remotes);
+    }
+
+    let now = proxmox_time::epoch_i64();
+    let response = ParallelFetcher::new(())
+        .do_for_all_remotes(to_fetch.into_iter(), fetch_remote_backups)
+        .await;
+
+    for remote_response in response {
+        let remote = remote_response.remote().to_string();
+        match remote_response.into_data() {
+            Ok(groups) => {
+                let groups = Arc::new(groups);
+                update_cached_backups(&remote, Arc::clone(&groups), now);
+                backups.push(RemoteBackups { remote, groups });
+            }
+            Err(err) => {
+                log::error!("failed to list backups of remote '{remote}' - {err:#}");
+                failed_remotes.push(FailedRemote {
+                    name: remote,
+                    error: err.to_string(),
+                    remote_type: RemoteType::Pbs,
+                });
+            }
+        }
+    }
+
+    (backups, failed_remotes)
+}
+
+async fn fetch_remote_backups(
+    _context: (),
+    remote: Remote,
+    _node: String,
+) -> Result<Vec<GroupBackup>, Error> {
+    let client = connection::make_pbs_client(&remote)?;
+
+    let mut groups = Vec::new();
+
+    for datastore in client.list_datastores().await? {
+        let namespaces = client
+            .list_datastore_namespaces(DatstoreListNamespaces {
+                datastore: datastore.name.clone(),
+                parent: None,
+                max_depth: None,
+            })
+            .await?;
+
+        for namespace in namespaces {
+            let ns = namespace.ns.to_string();
+            let mut snapshots = client
+                .list_snapshots(&datastore.name, (!ns.is_empty()).then_some(ns.as_str()))
+                .await?;
+
+            let mut latest: HashMap<(GuestKind, u32), i64> = HashMap::new();
+            while let Some(snapshot) = snapshots.next().await {
+                let backup = snapshot?.backup;
+                let guest_type = match backup.group.ty {
+                    BackupType::Vm => GuestKind::Qemu,
+                    BackupType::Ct => GuestKind::Lxc,
+                    BackupType::Host => continue,
+                };
+                let Ok(vmid) = backup.group.id.parse() else {
+                    continue;
+                };
+
+                let time = latest.entry((guest_type, vmid)).or_insert(backup.time);
+                *time = (*time).max(backup.time);
+            }
+
+            groups.extend(
+                latest
+                    .into_iter()
+                    .map(|((guest_type, vmid), time)| GroupBackup {
+                        guest_type,
+                        vmid,
+                        datastore: datastore.name.clone(),
+                        namespace: ns.clone(),
+                        time,
+                    }),
+            );
+        }
+    }
+
+    Ok(groups)
+}
+
+/// Build the coverage report for `guests`.
+///
+/// `backups` are the backup groups to consider, together with the PBS remote they are stored
+/// on. Covered guests are only included in the guest list if `all` is set.
+pub fn compute_coverage<'a>(
+    guests: Vec<Guest>,
+    backups: impl Iterator<Item = (&'a str, &'a GroupBackup)>,
+    max_backup_age: u64,
+    now: i64,
+    all: bool,
+) -> BackupCoverage {
+    let mut latest: HashMap<(GuestKind, u32), (&str, &GroupBackup)> = HashMap::new();
+    for (remote, group) in backups {
+        latest
+            .entry((group.guest_type, group.vmid))
+            .and_modify(|entry| {
+                if group.time > entry.1.time {
+                    *entry = (remote, group);
+                }
+            })
+            .or_insert((remote, group));
+    }
+
+    let mut coverage = BackupCoverage {
+        max_backup_age,
+        ..Default::default()
+    };
+
+    for guest in guests {
+        let backup = latest.get(&(guest.guest_type, guest.vmid));
+
+        let status = match backup {
+            None => {
+                coverage.missing += 1;
+                BackupCoverageStatus::Missing
+            }
+            Some((_, group)) if now - group.time > max_backup_age as i64 => {
+                coverage.outdated += 1;
+                BackupCoverageStatus::Outdated
+            }
+            Some(_) => {
+                coverage.covered += 1;
+                if !all {
+                    continue;
+                }
+                BackupCoverageStatus::Covered
+            }
+        };
+
+        coverage.guests.push(GuestBackupCoverage {
+            remote: guest.remote,
+            vmid: guest.vmid,
+            guest_type: guest.guest_type,
+            name: guest.name,
+            status,
+            last_backup: backup.map(|(_, group)| group.time),
+            backup_remote: backup.map(|(remote, _)| remote.to_string()),
+            datastore: backup.map(|(_, group)| group.datastore.clone()),
+            namespace: backup.map(|(_, group)| group.namespace.clone()),
+        });
+    }
+
+    // oldest backups first, guests without any backup at the very top
+    coverage
+        .guests
+        .sort_by(|a, b| a.last_backup.cmp(&b.last_backup).then(a.vmid.cmp(&b.vmid)));
+
+    coverage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 24 * 3600;
+    const NOW: i64 = 100 * DAY;
+
+    fn guest(remote: &str, guest_type: GuestKind, vmid: u32) -> Guest {
+        Guest {
+            remote: remote.into(),
+            guest_type,
+            vmid,
+            name: format!("guest{vmid}"),
+        }
+    }
+
+    fn group(guest_type: GuestKind, vmid: u32, datastore: &str, time: i64) -> GroupBackup {
+        GroupBackup {
+            guest_type,
+            vmid,
+            datastore: datastore.into(),
+            namespace: String::new(),
+            time,
+        }
+    }
+
+    #[test]
+    fn classifies_guests() {
+        let guests = vec![
+            guest("pve", GuestKind::Qemu, 100),
+            guest("pve", GuestKind::Qemu, 101),
+            guest("pve", GuestKind::Lxc, 102),
+        ];
+        let groups = [
+            group(GuestKind::Qemu, 100, "store", NOW - DAY),
+            group(GuestKind::Qemu, 101, "store", NOW - 10 * DAY),
+            // same VMID, but a VM backup does not cover a container
+            group(GuestKind::Qemu, 102, "store", NOW - DAY),
+        ];
+
+        let coverage = compute_coverage(
+            guests,
+            groups.iter().map(|group| ("pbs", group)),
+            7 * DAY as u64,
+            NOW,
+            false,
+        );
+
+        assert_eq!(coverage.covered, 1);
+        assert_eq!(coverage.outdated, 1);
+        assert_eq!(coverage.missing, 1);
+
+        let vmids: Vec<u32> = coverage.guests.iter().map(|guest| guest.vmid).collect();
+        assert_eq!(vmids, [102, 101]);
+        assert_eq!(coverage.guests[0].status, BackupCoverageStatus::Missing);
+        assert_eq!(coverage.guests[0].last_backup, None);
+        assert_eq!(coverage.guests[1].status, BackupCoverageStatus::Outdated);
+        assert_eq!(coverage.guests[1].backup_remote.as_deref(), Some("pbs"));
+    }
+
+    #[test]
+    fn uses_most_recent_backup() {
+        let groups = [
+            group(GuestKind::Lxc, 200, "old", NOW - 30 * DAY),
+            group(GuestKind::Lxc, 200, "new", NOW - DAY),
+        ];
+        let other = [group(GuestKind::Lxc, 200, "older", NOW - 60 * DAY)];
+
+        let coverage = compute_coverage(
+            vec![guest("pve", GuestKind::Lxc, 200)],
+            groups
+                .iter()
+                .map(|group| ("pbs1", group))
+                .chain(other.iter().map(|group| ("pbs2", group))),
+            7 * DAY as u64,
+            NOW,
+            true,
+        );
+
+        assert_eq!(coverage.covered, 1);
+        assert_eq!(coverage.guests.len(), 1);
+
+        let guest = &coverage.guests[0];
+        assert_eq!(guest.status, BackupCoverageStatus::Covered);
+        assert_eq!(guest.last_backup, Some(NOW - DAY));
+        assert_eq!(guest.backup_remote.as_deref(), Some("pbs1"));
+        assert_eq!(guest.datastore.as_deref(), Some("new"));
+    }
+}
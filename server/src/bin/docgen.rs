@@ -27,10 +27,23 @@ fn main() -> Result<(), Error> {
     for arg in args.iter() {
         let text = match arg.as_ref() {
             "apidata.js" => generate_api_tree(),
+            "alerts.cfg" => {
+                dump_section_config(pdm_api_types::alerts::AlertRuleEntry::section_config())
+            }
+            "jobs.cfg" => {
+                dump_section_config(pdm_api_types::jobs::JobConfigEntry::section_config())
+            }
+            "subscription-keys.cfg" => dump_section_config(
+                pdm_api_types::subscription::SubscriptionKeyEntry::section_config(),
+            ),
             "domains.cfg" => dump_section_config(&pdm_config::domains::CONFIG),
             //TODO: needs pub changes in proxmox-access-control
             //"user.cfg" => dump_section_config(&proxmox_access_control::user::CONFIG)
+            "metricserver.cfg" => dump_section_config(
+                pdm_api_types::metric_server::MetricServerEntry::section_config(),
+            ),
             "remotes.cfg" => dump_section_config(pdm_api_types::remotes::Remote::section_config()),
+            "roles.cfg" => dump_section_config(pdm_api_types::RoleConfigEntry::section_config()),
             "views.cfg" => {
                 dump_section_config(pdm_api_types::views::ViewConfigEntry::section_config())
             }
@@ -142,6 +142,7 @@ async fn run(debug: bool) -> Result<(), Error> {
     proxmox_acme_api::init(configdir!("/acme"), false)?;
 
     metric_collection::init()?;
+    server::notifications::init();
 
     let api_user = pdm_config::api_user()?;
     let mut command_sock = proxmox_daemon::command_socket::CommandSocket::new(api_user.gid);
@@ -169,7 +170,11 @@ async fn run(debug: bool) -> Result<(), Error> {
             ("locale", "/usr/share/pdm-i18n"),
             ("docs", "/usr/share/doc/proxmox-datacenter-manager/html"),
         ])
-        .formatted_router(&["api2"], &server::api::ROUTER)
+        .formatted_router(
+            &["api2"],
+            server::audit::audited_router(&server::api::ROUTER),
+        )
+        .unformatted_router(&["metrics"], &server::api::metrics::ROUTER)
         // FIXME: disabled for testing on pure debian
         //.register_template("console", "/usr/share/pve-xtermjs/index.html.hbs")?
         .enable_access_log(
@@ -380,6 +385,7 @@ async fn schedule_tasks() -> Result<(), Error> {
     // - stats (rrd) collection
     // - ...?
     tasks::logrotate::schedule_task_log_rotate().await;
+    server::scheduled_jobs::schedule_jobs().await;
 
     Ok(())
 }
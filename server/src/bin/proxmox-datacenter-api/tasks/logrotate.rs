@@ -8,7 +8,7 @@ use proxmox_time::CalendarEvent;
 use pdm_api_types::Authid;
 use server::jobstate::{self, Job, JobState};
 
-/// Rotate task logs, auth logs and access logs.
+/// Rotate task logs, auth logs, audit logs and access logs.
 ///
 /// This task runs every day at midnight, except when it has never run before, then it runs
 /// immediately.
@@ -97,6 +97,20 @@ pub async fn schedule_task_log_rotate() {
                     log::info!("API authentication log was not rotated");
                 }
 
+                // the audit log is opened for every entry, so no need to tell the daemons
+                let mut logrotate = LogRotate::new(
+                    pdm_buildcfg::API_AUDIT_LOG_FN,
+                    true,
+                    Some(max_files),
+                    Some(options),
+                )?;
+
+                if logrotate.rotate(max_size)? {
+                    log::info!("API audit log was rotated");
+                } else {
+                    log::info!("API audit log was not rotated");
+                }
+
                 if has_rotated {
                     log::info!("cleaning up old task logs");
                     if let Err(err) = proxmox_rest_server::cleanup_old_tasks(true) {
@@ -23,9 +23,11 @@ use tokio::task::JoinHandle;
 use proxmox_config_digest::ConfigDigest;
 use proxmox_section_config::typed::SectionConfigData;
 
-use pdm_api_types::remotes::{Remote, RemoteType};
+use pdm_api_types::remotes::{NodeMembershipChange, Remote, RemoteType};
 
-use server::remote_cache::{self, RemoteMappingCache};
+use server::api::pve::cluster_nodes;
+use server::remote_cache::{self, ClusterMember, RemoteMappingCache};
+use server::remote_certificates;
 use server::task_utils;
 
 const CONFIG_POLL_INTERVAL: u64 = 60;
@@ -140,22 +142,32 @@ impl CachingTask {
             *entry = remote_cache::RemoteMapping::new(remote.ty);
         }
 
-        // Only PVE entries currently have a node cache, so skip non-PVE remotes:
-        if remote.ty != RemoteType::Pve {
-            return;
-        }
-
         // prune nodes which were removed:
         entry.hosts.retain(|hostname, info| {
-            let retain = remote.nodes.iter().any(|node| node.hostname == *hostname);
-            if !retain {
+            let Some(node) = remote.nodes.iter().find(|node| node.hostname == *hostname) else {
                 if let Some(node_name) = info.node_name() {
                     entry.node_to_host.remove(node_name);
                 }
+                return false;
+            };
+
+            // a changed certificate is resolved once the pinned fingerprint changed
+            if info
+                .pending_certificate
+                .as_ref()
+                .is_some_and(|change| change.pinned_fingerprint != node.fingerprint)
+            {
+                info.pending_certificate = None;
             }
-            retain
+
+            true
         });
 
+        // Only PVE entries currently have a node cache, so skip non-PVE remotes:
+        if remote.ty != RemoteType::Pve {
+            return;
+        }
+
         // make sure currently known hostnames exist in the cache at least empty:
         for node in &remote.nodes {
             if !entry.hosts.contains_key(&node.hostname) {
@@ -169,7 +181,19 @@ impl CachingTask {
 
     #[tracing::instrument(skip_all)]
     async fn query_node_names(config: SectionConfigData<Remote>) {
+        let now = proxmox_time::epoch_i64();
+
         for (_name, remote) in &config {
+            if remote.in_maintenance(now) {
+                log::trace!("skipping remote {:?} - in maintenance", remote.id);
+                continue;
+            }
+
+            if let Err(err) = remote_certificates::persist_recorded_changes(remote) {
+                log::error!("error recording changed certificates - {err:?}");
+            }
+            remote_certificates::accept_ca_trusted_certificates(remote);
+
             log::trace!("update remote {:?}", remote.id);
             if let Err(err) = Self::query_node_names_for_remote(remote).await {
                 log::error!("error updating node name cache - {err:?}");
@@ -183,13 +207,30 @@ impl CachingTask {
             return Ok(());
         }
 
+        let mut cluster_members = None;
+        let now = proxmox_time::epoch_i64();
+
         // now add new nodes
         for node in &remote.nodes {
+            if RemoteMappingCache::get().host_in_maintenance(remote, &node.hostname, now) {
+                log::debug!(
+                    "skipping remote {:?} node {:?} - in maintenance",
+                    remote.id,
+                    node.hostname
+                );
+                continue;
+            }
+
             log::debug!("querying remote {:?} node {:?}", remote.id, node.hostname);
 
             // if the host is new, we need to query its name
             let query_result = match query_node_name(remote, &node.hostname).await {
-                Ok(node_name) => Some(node_name),
+                Ok((node_name, members)) => {
+                    if cluster_members.is_none() {
+                        cluster_members = members;
+                    }
+                    Some(node_name)
+                }
                 Err(err) => {
                     log::error!(
                         "failed to query info for remote '{}' node '{}' - {err:?}",
@@ -203,6 +244,15 @@ impl CachingTask {
             let mut cache = RemoteMappingCache::write()?;
             if let Some(info) = cache.info_by_hostname_mut(&remote.id, &node.hostname) {
                 info.reachable = query_result.is_some();
+                // the node presents its pinned certificate again
+                if info.reachable
+                    && info
+                        .pending_certificate
+                        .as_ref()
+                        .is_some_and(|change| !change.trusted_by_ca)
+                {
+                    info.pending_certificate = None;
+                }
             }
             if let Some(node_name) = query_result {
                 cache.set_node_name(&remote.id, &node.hostname, Some(node_name));
@@ -210,19 +260,66 @@ impl CachingTask {
             cache.save()?;
         }
 
+        if let Some(members) = cluster_members {
+            let mut cache = RemoteMappingCache::write()?;
+            if let Some(entry) = cache.remotes.get_mut(&remote.id) {
+                entry.cluster_members = Some(members);
+            }
+            cache.save()?;
+
+            if remote.sync_nodes.unwrap_or(false) {
+                let changes = cluster_nodes::sync_cluster_nodes(remote.id.clone()).await?;
+                for change in changes {
+                    let action = match change.change {
+                        NodeMembershipChange::Joined => "added",
+                        NodeMembershipChange::Left => "removed",
+                    };
+                    log::info!(
+                        "{action} node {:?} ({}) of remote {:?}",
+                        change.node,
+                        change.hostname,
+                        remote.id,
+                    );
+                }
+            }
+        }
+
         Ok(())
     }
 }
 
 /// Calls `/cluster/status` directly on a specific node to find its name.
-async fn query_node_name(remote: &Remote, hostname: &str) -> Result<String, Error> {
+///
+/// If the node is part of a quorate cluster, the cluster members are returned as well.
+async fn query_node_name(
+    remote: &Remote,
+    hostname: &str,
+) -> Result<(String, Option<Vec<ClusterMember>>), Error> {
     log::trace!("querying node name {hostname:?} for remote {:?}", remote.id);
     let client = server::connection::make_pve_client_with_endpoint(remote, Some(hostname))?;
     let node_status_list = client.cluster_status().await?;
-    for node in node_status_list {
-        if node.local == Some(true) {
-            return Ok(node.name);
-        }
-    }
-    bail!("failed to connect to node {hostname}");
+
+    let Some(name) = node_status_list
+        .iter()
+        .find(|node| node.local == Some(true))
+        .map(|node| node.name.clone())
+    else {
+        bail!("failed to connect to node {hostname}");
+    };
+
+    let members = node_status_list
+        .iter()
+        .any(|entry| entry.quorate == Some(true))
+        .then(|| {
+            node_status_list
+                .into_iter()
+                .filter(|entry| entry.online.is_some())
+                .map(|entry| ClusterMember {
+                    name: entry.name,
+                    ip: entry.ip,
+                })
+                .collect()
+        });
+
+    Ok((name, members))
 }
@@ -1,12 +1,15 @@
 use anyhow::Error;
 use serde_json::json;
 
-//use proxmox_notify::context::pbs::PBS_CONTEXT;
 use proxmox_router::{cli::*, ApiHandler, RpcEnvironment};
 use proxmox_subscription::SubscriptionStatus;
 use proxmox_sys::fs::CreateOptions;
 
 use server::api;
+use server::notifications::ExpiringSubscription;
+
+/// Notify about remote subscriptions which expire within this many days.
+const SUBSCRIPTION_EXPIRY_NOTIFY_DAYS: i64 = 30;
 
 async fn wait_for_local_worker(upid_str: &str) -> Result<(), Error> {
     let upid: pbs_api_types::UPID = upid_str.parse()?;
@@ -41,7 +44,7 @@ async fn do_update(rpcenv: &mut dyn RpcEnvironment) -> Result<(), Error> {
 
     println!("updating apt package database");
     let param = json!({
-        "notify": true, // TODO: not yet supported anyway?!
+        "notify": true,
     });
     let method = &api::nodes::apt::API_METHOD_APT_UPDATE_DATABASE;
     match method.handler {
@@ -59,6 +62,16 @@ async fn do_update(rpcenv: &mut dyn RpcEnvironment) -> Result<(), Error> {
         log::error!("error checking certificates: {err}");
     }
 
+    println!("check if any remote subscription expires soon");
+    if let Err(err) = check_remote_subscriptions().await {
+        log::error!("error checking remote subscriptions: {err}");
+    }
+
+    println!("check if any remote API token expires soon");
+    if let Err(err) = server::remote_tokens::notify_expiring_tokens().await {
+        log::error!("error checking remote API tokens: {err}");
+    }
+
     // TODO: cleanup tasks like in PVE?
 
     Ok(())
@@ -87,6 +100,67 @@ async fn check_acme_certificates(rpcenv: &mut dyn RpcEnvironment) -> Result<(),
     Ok(())
 }
 
+async fn check_remote_subscriptions() -> Result<(), Error> {
+    let (remotes_config, _digest) = pdm_config::remotes::config()?;
+
+    let now = proxmox_time::epoch_i64();
+    let notify_before = now + SUBSCRIPTION_EXPIRY_NOTIFY_DAYS * 24 * 3600;
+
+    for (remote_name, remote) in remotes_config {
+        if remote.in_maintenance(now) {
+            log::info!("skipping subscription check of remote '{remote_name}' - in maintenance");
+            continue;
+        }
+
+        let node_info = match api::resources::get_subscription_info_for_remote(&remote, 0).await {
+            Ok(node_info) => node_info,
+            Err(err) => {
+                log::error!("could not get subscription info for remote '{remote_name}': {err}");
+                continue;
+            }
+        };
+
+        let expiring: Vec<ExpiringSubscription> = node_info
+            .into_iter()
+            .filter_map(|(node, info)| {
+                let info = info?;
+
+                let expired = info.status == SubscriptionStatus::Expired;
+                let expires_soon = info.status == SubscriptionStatus::Active
+                    && info
+                        .nextduedate
+                        .as_deref()
+                        .and_then(parse_due_date)
+                        .is_some_and(|due| due <= notify_before);
+
+                (expired || expires_soon).then_some(ExpiringSubscription {
+                    node,
+                    key: info.key,
+                    next_due_date: info.nextduedate,
+                    expired,
+                })
+            })
+            .collect();
+
+        if expiring.is_empty() {
+            continue;
+        }
+
+        if let Err(err) =
+            server::notifications::send_subscriptions_expiring(&remote_name, &expiring)
+        {
+            log::error!("could not send subscription notification for '{remote_name}': {err}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse a subscription due date in the `YYYY-MM-DD` format.
+fn parse_due_date(date: &str) -> Option<i64> {
+    proxmox_time::parse_rfc3339(&format!("{date}T00:00:00Z")).ok()
+}
+
 async fn run(rpcenv: &mut dyn RpcEnvironment) -> Result<(), Error> {
     let api_user = pdm_config::api_user()?;
     let file_opts = CreateOptions::new().owner(api_user.uid).group(api_user.gid);
@@ -96,9 +170,9 @@ async fn run(rpcenv: &mut dyn RpcEnvironment) -> Result<(), Error> {
     proxmox_rest_server::register_task_control_commands(&mut command_sock)?;
     command_sock.spawn(proxmox_rest_server::last_worker_future())?;
 
-    //proxmox_notify::context::set_context(&PBS_CONTEXT);
-
     proxmox_product_config::init(pdm_config::api_user()?, pdm_config::priv_user()?);
+    server::acl::init();
+    server::notifications::init();
     proxmox_acme_api::init(pdm_buildcfg::configdir!("/acme"), false)?;
 
     server::context::init()?;
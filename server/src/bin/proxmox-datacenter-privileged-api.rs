@@ -103,6 +103,8 @@ fn create_directories() -> Result<(), Error> {
     )?;
 
     server::jobstate::create_jobstate_dir()?;
+    server::notifications::create_spool_dir()?;
+    server::audit::create_audit_log()?;
 
     Ok(())
 }
@@ -112,6 +114,8 @@ async fn run() -> Result<(), Error> {
 
     proxmox_acme_api::init(configdir!("/acme"), true)?;
 
+    server::notifications::init();
+
     let api_user = pdm_config::api_user()?;
     let mut command_sock = proxmox_daemon::command_socket::CommandSocket::new(api_user.gid);
 
@@ -133,7 +137,10 @@ async fn run() -> Result<(), Error> {
 
     let config = ApiConfig::new(pdm_buildcfg::JS_DIR, RpcEnvironmentType::PRIVILEGED)
         .auth_handler_func(|h, m| Box::pin(auth::check_auth(h, m)))
-        .formatted_router(&["api2"], &server::api::ROUTER)
+        .formatted_router(
+            &["api2"],
+            server::audit::audited_router(&server::api::ROUTER),
+        )
         .enable_access_log(
             pdm_buildcfg::API_ACCESS_LOG_FN,
             Some(dir_opts),
@@ -248,6 +255,7 @@ async fn run() -> Result<(), Error> {
     });
 
     start_task_scheduler();
+    start_notification_worker();
 
     server.await?;
     log::info!("server shutting down, waiting for active workers to complete");
@@ -266,6 +274,14 @@ fn start_task_scheduler() {
     });
 }
 
+fn start_notification_worker() {
+    tokio::spawn(async move {
+        let notification_worker = pin!(server::notifications::notification_worker());
+        let abort_future = pin!(proxmox_daemon::shutdown_future());
+        futures::future::select(notification_worker, abort_future).await;
+    });
+}
+
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 fn next_minute() -> Instant {
@@ -6,6 +6,7 @@
 use std::collections::HashMap;
 use std::future::Future;
 use std::pin::{pin, Pin};
+use std::sync::atomic::{AtomicI64, Ordering};
 use std::sync::Arc;
 use std::sync::Mutex as StdMutex;
 use std::sync::Once;
@@ -20,14 +21,19 @@ use serde::Serialize;
 
 use proxmox_acme_api::CertificateInfo;
 use proxmox_client::{Client, HttpApiClient, HttpApiResponse, HttpApiResponseStream, TlsOptions};
+use proxmox_http::{HttpOptions, ProxyConfig};
 
 use pdm_api_types::remotes::{NodeUrl, Remote, RemoteType, TlsProbeOutcome};
 use pve_api_types::client::PveClientImpl;
 
 use crate::pbs_client::PbsClient;
+use crate::remote_certificates::{PinnedCertificate, RejectedCertificates};
 
 static INSTANCE: OnceLock<Box<dyn ClientFactory + Send + Sync>> = OnceLock::new();
 
+/// Timeout for API requests to remotes without a `request-timeout`.
+pub const DEFAULT_REQUEST_TIMEOUT: u64 = 60;
+
 /// Connection Info returned from [`prepare_connect_client`]
 struct ConnectInfo {
     prefix: String,
@@ -51,17 +57,40 @@ impl ConnectInfo {
         }
     }
 }
+
+/// The HTTP proxy configured for a remote.
+fn proxy_config(remote: &Remote) -> Result<Option<ProxyConfig>, Error> {
+    remote
+        .http_proxy
+        .as_deref()
+        .map(ProxyConfig::parse_proxy_url)
+        .transpose()
+}
+
+/// The timeout for API requests to a remote.
+fn request_timeout(remote: &Remote) -> Duration {
+    Duration::from_secs(remote.request_timeout.unwrap_or(DEFAULT_REQUEST_TIMEOUT))
+}
+
+/// Returns a [`proxmox_client::Client`] set up to connect to a specific node of a remote.
 ///
-/// Returns a [`proxmox_client::Client`] set up to connect to a specific node.
+/// Connections go through the HTTP proxy of the remote, if one is configured. If the node has a
+/// pinned fingerprint, a mismatching certificate is recorded, see [`crate::remote_certificates`],
+/// and counted in `rejected`.
 fn prepare_connect_client_to_node(
+    remote: &Remote,
     node: &NodeUrl,
     default_port: u16,
     pve_compat: bool,
+    rejected: &RejectedCertificates,
 ) -> Result<Client, Error> {
     let mut options = TlsOptions::default();
 
     if let Some(fp) = &node.fingerprint {
-        options = TlsOptions::parse_fingerprint(fp)?;
+        let pinned = PinnedCertificate::new(&remote.id, &node.hostname, fp, rejected.clone())?;
+        options = TlsOptions::Callback(Box::new(
+            move |valid: bool, chain: &mut X509StoreContextRef| pinned.verify(valid, chain),
+        ));
     }
 
     let host_port: Authority = node.hostname.parse()?;
@@ -73,8 +102,12 @@ fn prepare_connect_client_to_node(
     )
     .parse()?;
 
-    let mut client =
-        proxmox_client::Client::with_options(uri.clone(), options, Default::default())?;
+    let http_options = HttpOptions {
+        proxy_config: proxy_config(remote)?,
+        ..Default::default()
+    };
+
+    let mut client = proxmox_client::Client::with_options(uri.clone(), options, http_options)?;
     client.set_pve_compatibility(pve_compat);
     Ok(client)
 }
@@ -99,7 +132,13 @@ fn prepare_connect_client(
 
     let info = ConnectInfo::for_remote(remote);
 
-    let client = prepare_connect_client_to_node(node, info.default_port, info.pve_compat)?;
+    let client = prepare_connect_client_to_node(
+        remote,
+        node,
+        info.default_port,
+        info.pve_compat,
+        &RejectedCertificates::default(),
+    )?;
 
     Ok((client, info))
 }
@@ -130,20 +169,39 @@ fn prepare_connect_multi_client(remote: &Remote) -> Result<(MultiClient, Connect
 
     let info = ConnectInfo::for_remote(remote);
 
+    let proxy = proxy_config(remote)?;
+
     let mut clients = Vec::new();
 
     for node in &remote.nodes {
+        let rejected = RejectedCertificates::default();
+        let client = prepare_connect_client_to_node(
+            remote,
+            node,
+            info.default_port,
+            info.pve_compat,
+            &rejected,
+        )?;
+
+        let connect_address = match &proxy {
+            Some(proxy) => format!("{}:{}", proxy.host, proxy.port),
+            None => client
+                .api_url()
+                .authority()
+                .ok_or_else(|| format_err!("missing authority for node {}", node.hostname))?
+                .to_string(),
+        };
+
         clients.push(MultiClientEntry {
-            client: Arc::new(prepare_connect_client_to_node(
-                node,
-                info.default_port,
-                info.pve_compat,
-            )?),
+            client: Arc::new(client),
             hostname: node.hostname.clone(),
+            connect_address,
+            last_response: Arc::new(AtomicI64::new(0)),
+            rejected,
         });
     }
 
-    Ok((MultiClient::new(remote.id.clone(), clients), info))
+    Ok((MultiClient::new(remote, clients), info))
 }
 
 /// Like [`connect()`], but with failover support for remotes which can have multiple nodes.
@@ -259,6 +317,15 @@ pub trait ClientFactory {
 
     /// Create a new API client for raw acess to the given remote
     fn make_raw_client(&self, remote: &Remote) -> Result<Box<Client>, Error>;
+
+    /// Create a new API client for raw access to the given remote.
+    ///
+    /// In case the remote has a user configured (instead of an API token), it will connect and get
+    /// a ticket, so that further connections are properly authenticated. Otherwise it behaves
+    /// identically as [`make_raw_client`].
+    ///
+    /// Note: currently does not support two factor authentication.
+    async fn make_raw_client_and_login(&self, remote: &Remote) -> Result<Box<Client>, Error>;
 }
 
 /// Default production client factory
@@ -394,6 +461,10 @@ impl ClientFactory for DefaultClientFactory {
         let client = connect_or_login(remote, None).await?;
         Ok(Box::new(PbsClient(client)))
     }
+
+    async fn make_raw_client_and_login(&self, remote: &Remote) -> Result<Box<Client>, Error> {
+        Ok(Box::new(connect_or_login(remote, None).await?))
+    }
 }
 
 fn instance() -> &'static (dyn ClientFactory + Send + Sync) {
@@ -459,6 +530,17 @@ pub async fn make_pbs_client_and_login(remote: &Remote) -> Result<Box<PbsClient>
     instance().make_pbs_client_and_login(remote).await
 }
 
+/// Create a new API client for raw access to the given remote.
+///
+/// In case the remote has a user configured (instead of an API token), it will connect and get a
+/// ticket, so that further connections are properly authenticated. Otherwise it behaves
+/// identically as [`make_raw_client`].
+///
+/// Note: currently does not support two factor authentication.
+pub async fn make_raw_client_and_login(remote: &Remote) -> Result<Box<Client>, Error> {
+    instance().make_raw_client_and_login(remote).await
+}
+
 /// Initialize the [`ClientFactory`] instance.
 ///
 /// Will panic if the instance has already been set.
@@ -474,6 +556,12 @@ pub fn init(instance: Box<dyn ClientFactory + Send + Sync>) {
 struct MultiClientEntry {
     client: Arc<Client>,
     hostname: String,
+    /// The address connections are made to, which is the proxy if the remote uses one.
+    connect_address: String,
+    /// When the node last responded to a request (UNIX epoch), 0 if it never did.
+    last_response: Arc<AtomicI64>,
+    /// Certificates of the node rejected for not matching the pinned fingerprint.
+    rejected: RejectedCertificates,
 }
 
 /// This is another wrapper around the actual HTTP client responsible for dealing with connection
@@ -487,14 +575,44 @@ struct MultiClient {
     state: StdMutex<MultiClientState>,
     remote: String,
     timeout: Duration,
+    connect_timeout: Option<Duration>,
 }
 
 impl MultiClient {
-    fn new(remote: String, entries: Vec<MultiClientEntry>) -> Self {
+    /// Nodes which responded within this time (in seconds) are not checked for the connect
+    /// timeout, as there likely still is an open connection to them.
+    const CONNECT_CHECK_INTERVAL: i64 = 30;
+
+    fn new(remote: &Remote, entries: Vec<MultiClientEntry>) -> Self {
         Self {
             state: StdMutex::new(MultiClientState::new(remote.clone(), entries)),
-            remote,
-            timeout: Duration::from_secs(60),
+            remote: remote.id.clone(),
+            timeout: request_timeout(remote),
+            connect_timeout: remote.connect_timeout.map(Duration::from_secs),
+        }
+    }
+
+    /// Check if a connection to a node can be established within the connect timeout of the
+    /// remote, so unreachable nodes are skipped without waiting for the request timeout.
+    async fn check_connect(
+        &self,
+        connect_address: &str,
+        last_response: &AtomicI64,
+    ) -> Result<(), Error> {
+        let Some(connect_timeout) = self.connect_timeout else {
+            return Ok(());
+        };
+
+        let last_response = last_response.load(Ordering::Relaxed);
+        if proxmox_time::epoch_i64() - last_response < Self::CONNECT_CHECK_INTERVAL {
+            return Ok(());
+        }
+
+        let connect = tokio::net::TcpStream::connect(connect_address);
+        match tokio::time::timeout(connect_timeout, connect).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(err)) => Err(err.into()),
+            Err(_) => bail!("connection timed out after {}s", connect_timeout.as_secs()),
         }
     }
 
@@ -513,12 +631,13 @@ impl MultiClient {
 struct MultiClientState {
     /// The current index *not* modulo the client count.
     current: usize,
-    remote: String,
+    /// The remote config, to check which nodes are in maintenance mode.
+    remote: Remote,
     entries: Vec<MultiClientEntry>,
 }
 
 impl MultiClientState {
-    fn new(remote: String, entries: Vec<MultiClientEntry>) -> Self {
+    fn new(remote: Remote, entries: Vec<MultiClientEntry>) -> Self {
         let mut this = Self {
             current: 0,
             remote,
@@ -544,7 +663,7 @@ impl MultiClientState {
             let entry = self.get_entry();
             log::error!("marking client {} as unreachable", entry.hostname);
             if let Ok(mut cache) = crate::remote_cache::RemoteMappingCache::write() {
-                cache.mark_host_reachable(&self.remote, &entry.hostname, false);
+                cache.mark_host_reachable(&self.remote.id, &entry.hostname, false);
                 let _ = cache.save();
             }
             self.next();
@@ -552,21 +671,62 @@ impl MultiClientState {
         }
     }
 
-    /// Skip ahead as long as we're pointing to an unreachable.
+    /// Skip ahead as long as we're pointing to an unreachable or a node in maintenance mode.
     fn skip_unreachable(&mut self) {
         let cache = crate::remote_cache::RemoteMappingCache::get();
+        let in_maintenance = self.in_maintenance(&cache);
         // loop at most as many times as we have entries...
         for _ in 0..self.entries.len() {
+            let skip_maintenance = in_maintenance[self.index()];
             let entry = self.get_entry();
-            if !cache.host_is_reachable(&self.remote, &entry.hostname) {
+            if !cache.host_is_reachable(&self.remote.id, &entry.hostname) {
                 log::error!("skipping host {} - marked unreachable", entry.hostname);
                 self.next();
+            } else if skip_maintenance {
+                log::debug!("skipping host {} - in maintenance", entry.hostname);
+                self.next();
             } else {
                 return;
             }
         }
     }
 
+    /// Skip ahead as long as we're pointing to a node in maintenance mode.
+    ///
+    /// This is checked on every request, so nodes are used again once their maintenance ended.
+    fn skip_maintenance(&mut self) {
+        let cache = crate::remote_cache::RemoteMappingCache::get();
+        let in_maintenance = self.in_maintenance(&cache);
+        for _ in 0..self.entries.len() {
+            if !in_maintenance[self.index()] {
+                return;
+            }
+            log::debug!(
+                "skipping host {} - in maintenance",
+                self.get_entry().hostname
+            );
+            self.next();
+        }
+    }
+
+    /// Check which entries belong to nodes in maintenance mode right now.
+    ///
+    /// If all of them are, none is skipped, so the remote stays usable.
+    fn in_maintenance(&self, cache: &crate::remote_cache::RemoteMappingCache) -> Vec<bool> {
+        let now = proxmox_time::epoch_i64();
+        let in_maintenance: Vec<bool> = self
+            .entries
+            .iter()
+            .map(|entry| cache.host_in_maintenance(&self.remote, &entry.hostname, now))
+            .collect();
+
+        if in_maintenance.iter().all(|skip| *skip) {
+            vec![false; in_maintenance.len()]
+        } else {
+            in_maintenance
+        }
+    }
+
     /// Get `current` as an *index* (i.e. modulo `entries.len()`).
     fn index(&self) -> usize {
         self.current % self.entries.len()
@@ -615,6 +775,9 @@ struct TryClient {
     client: Arc<Client>,
     reachable: bool,
     hostname: String,
+    connect_address: String,
+    last_response: Arc<AtomicI64>,
+    rejected: RejectedCertificates,
 }
 
 impl TryClient {
@@ -624,6 +787,9 @@ impl TryClient {
             client: Arc::clone(&entry.client),
             hostname: entry.hostname.clone(),
             reachable: true,
+            connect_address: entry.connect_address.clone(),
+            last_response: Arc::clone(&entry.last_response),
+            rejected: entry.rejected.clone(),
         }
     }
 
@@ -636,6 +802,9 @@ impl TryClient {
             client: Arc::clone(&entry.client),
             hostname: entry.hostname.clone(),
             reachable: false,
+            connect_address: entry.connect_address.clone(),
+            last_response: Arc::clone(&entry.last_response),
+            rejected: entry.rejected.clone(),
         }
     }
 }
@@ -670,7 +839,9 @@ impl MultiClient {
 
             match start_current {
                 None => {
-                    // first attempt, just use the current client and remember the starting index
+                    // first attempt, use the current client unless its node is in maintenance mode
+                    // and remember the starting index
+                    state.skip_maintenance();
                     let (client, index) = state.get();
                     start_current = Some((index, index));
                     log::trace!("trying reachable client {index}");
@@ -724,12 +895,16 @@ macro_rules! try_request {
 
             let mut last_err = None;
             let mut timed_out = false;
+            let mut certificate_mismatch = None;
             // The iterator in use here will automatically mark a client as faulty if we move on to
             // the `next()` one.
             for TryClient {
                 client,
                 hostname,
                 reachable,
+                connect_address,
+                last_response,
+                rejected,
             } in $self.try_clients()
             {
                 if let Some(err) = last_err.take() {
@@ -742,12 +917,24 @@ macro_rules! try_request {
                     log::error!("client timed out on request {path}, trying another remote");
                 }
 
+                if let Err(err) = $self.check_connect(&connect_address, &last_response).await {
+                    log::error!("cannot connect to {hostname:?} via {connect_address} - {err}");
+                    timed_out = true;
+                    continue;
+                }
+
+                let rejected_before = rejected.count();
                 let request = client.$how($method.clone(), $path_and_query, params.as_ref());
                 match tokio::time::timeout($self.timeout, request).await {
                     Ok(Err(proxmox_client::Error::Client(err))) => {
+                        // the TLS verify callback rejected a certificate during this request
+                        if rejected.count() != rejected_before {
+                            certificate_mismatch = Some(hostname);
+                        }
                         last_err = Some(err);
                     }
                     Ok(result) => {
+                        last_response.store(proxmox_time::epoch_i64(), Ordering::Relaxed);
                         if !reachable {
                             log::error!("marking {hostname:?} as reachable again!");
                             if let Ok(mut cache) = crate::remote_cache::RemoteMappingCache::write()
@@ -767,7 +954,13 @@ macro_rules! try_request {
             if let Some(err) = last_err {
                 let path = $path_and_query;
                 log::error!("client error on request {path}, giving up - {err:?}");
-                Err(proxmox_client::Error::Client(err))
+                match certificate_mismatch {
+                    Some(hostname) => Err(proxmox_client::Error::Anyhow(format_err!(
+                        "certificate fingerprint mismatch for {hostname:?} - the new certificate \
+                        needs to be trusted"
+                    ))),
+                    None => Err(proxmox_client::Error::Client(err)),
+                }
             } else if timed_out {
                 let path = $path_and_query;
                 log::error!("client timed out on request {path}, no remotes reachable, giving up");
@@ -2,17 +2,23 @@
 
 pub mod acl;
 pub mod api;
+pub mod audit;
 pub mod auth;
+pub mod backup_coverage;
 pub mod context;
 pub mod env;
 pub mod jobstate;
 pub mod metric_collection;
+pub mod notifications;
 pub mod parallel_fetcher;
 pub mod remote_cache;
+pub mod remote_certificates;
 pub mod remote_tasks;
+pub mod remote_tokens;
 pub mod remote_updates;
 pub mod report;
 pub mod resource_cache;
+pub mod scheduled_jobs;
 pub mod task_utils;
 pub mod views;
 
@@ -0,0 +1,448 @@
+//! Threshold based alerting on the collected metrics.
+//!
+//! Alert rules from `alerts.cfg` are evaluated against the RRD cache after every metric
+//! collection run. The state of all alerts is persisted, so that alerts keep firing (and are not
+//! sent out again) across daemon restarts.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+
+use proxmox_rrd_api_types::{RrdMode, RrdTimeframe};
+use proxmox_sys::fs::CreateOptions;
+
+use pdm_api_types::alerts::{Alert, AlertMetric, AlertRule, AlertRuleEntry, AlertState};
+use pdm_api_types::resource::Resource;
+
+use crate::notifications;
+
+use super::rrd_cache;
+
+/// Location of the alert state file.
+const ALERT_STATE_FILE: &str = concat!(pdm_buildcfg::PDM_STATE_DIR_M!(), "/alert-state.json");
+
+/// Resolved alerts are kept in the state for this many seconds.
+const RESOLVED_ALERT_RETENTION: i64 = 24 * 60 * 60;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+/// A single alert together with the resource it was raised for.
+pub struct AlertEntry {
+    /// The alert.
+    pub alert: Alert,
+    /// The resource at the time the alert was last updated.
+    pub resource: Resource,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "kebab-case")]
+/// Alert state file content.
+struct State {
+    /// Alerts, keyed by rule and global resource ID.
+    alerts: HashMap<String, AlertEntry>,
+}
+
+/// Manage and persist the alert state.
+struct AlertStateFile {
+    /// Path to the persisted state
+    path: PathBuf,
+    /// File owner/perms for the persisted state file
+    file_options: CreateOptions,
+    /// The current state
+    state: State,
+}
+
+impl AlertStateFile {
+    /// Load the existing state file. If the file does not exist or fails to load, the state will
+    /// be empty.
+    fn new(statefile: PathBuf, file_options: CreateOptions) -> Self {
+        let state = Self::load_or_default(&statefile)
+            .inspect_err(|err| log::error!("could not load alert state: {err}"))
+            .unwrap_or_default();
+
+        Self {
+            path: statefile,
+            file_options,
+            state,
+        }
+    }
+
+    /// Persist the state to the statefile.
+    fn save(&self) -> Result<(), Error> {
+        let data = serde_json::to_vec_pretty(&self.state)?;
+        proxmox_sys::fs::replace_file(&self.path, &data, self.file_options, true)?;
+
+        Ok(())
+    }
+
+    fn load_or_default(path: &Path) -> Result<State, Error> {
+        let content = proxmox_sys::fs::file_read_optional_string(path)?;
+
+        if let Some(content) = content {
+            Ok(serde_json::from_str(&content)?)
+        } else {
+            Ok(Default::default())
+        }
+    }
+}
+
+fn load_state() -> Result<AlertStateFile, Error> {
+    let api_uid = pdm_config::api_user()?.uid;
+    let api_gid = pdm_config::api_group()?.gid;
+
+    let file_options = CreateOptions::new().owner(api_uid).group(api_gid);
+
+    Ok(AlertStateFile::new(ALERT_STATE_FILE.into(), file_options))
+}
+
+/// Get all alerts from the persisted state.
+///
+/// If `include_resolved` is `false`, only firing alerts are returned.
+pub fn get_alerts(include_resolved: bool) -> Result<Vec<AlertEntry>, Error> {
+    let state = load_state()?;
+
+    let mut alerts: Vec<AlertEntry> = state
+        .state
+        .alerts
+        .into_values()
+        .filter(|entry| include_resolved || entry.alert.state == AlertState::Firing)
+        .collect();
+
+    alerts.sort_by(|a, b| {
+        (&a.alert.rule, &a.alert.resource_id).cmp(&(&b.alert.rule, &b.alert.resource_id))
+    });
+
+    Ok(alerts)
+}
+
+/// Result of checking a series of usage values against a threshold.
+#[derive(Debug, PartialEq)]
+enum Evaluation {
+    /// The threshold was exceeded for the whole duration.
+    Above(f64),
+    /// The most recent value exceeds the threshold, but not yet for the whole duration.
+    Pending(f64),
+    /// The most recent value is not above the threshold.
+    Below(f64),
+    /// There is no usable data.
+    NoData,
+}
+
+/// Check a series of usage values against a threshold.
+///
+/// Trailing gaps are ignored, since the most recent RRD slot is often not filled yet.
+fn check_threshold(
+    values: &[Option<f64>],
+    resolution: u64,
+    duration: u64,
+    threshold: f64,
+) -> Evaluation {
+    let end = match values.iter().rposition(|v| v.is_some()) {
+        Some(idx) => idx + 1,
+        None => return Evaluation::NoData,
+    };
+    let values = &values[..end];
+
+    let last = match values.last() {
+        Some(Some(last)) => *last,
+        _ => return Evaluation::NoData,
+    };
+
+    if last <= threshold {
+        return Evaluation::Below(last);
+    }
+
+    let needed = duration.div_ceil(resolution.max(1)).max(1) as usize;
+    if values.len() < needed {
+        return Evaluation::Pending(last);
+    }
+
+    let exceeded = values[values.len() - needed..]
+        .iter()
+        .all(|value| value.is_some_and(|value| value > threshold));
+
+    if exceeded {
+        Evaluation::Above(last)
+    } else {
+        Evaluation::Pending(last)
+    }
+}
+
+/// Returns the base name of the RRD files of a resource, if metrics are collected for it.
+fn rrd_base_name(remote: &str, resource: &Resource) -> Option<String> {
+    match resource {
+        Resource::PveStorage(_)
+        | Resource::PveQemu(_)
+        | Resource::PveLxc(_)
+        | Resource::PveNode(_) => Some(format!("pve/{remote}/{}", resource.id())),
+        // pbs node datapoints are always saved with 'host' instead of nodename
+        Resource::PbsNode(_) => Some(format!("pbs/{remote}/host")),
+        Resource::PbsDatastore(_) => Some(format!("pbs/{remote}/datastore/{}", resource.id())),
+        Resource::PveNetwork(_) => None,
+    }
+}
+
+fn extract(base: &str, metric: &str) -> Option<proxmox_rrd::Entry> {
+    rrd_cache::get_cache()
+        .extract_data(base, metric, RrdTimeframe::Hour, RrdMode::Average)
+        .inspect_err(|err| log::error!("could not extract {metric} for {base}: {err}"))
+        .ok()
+        .flatten()
+}
+
+/// Calculate a usage series in percent from a `used` and `total` metric.
+fn ratio_series(base: &str, used: &str, total: &str) -> Option<(u64, Vec<Option<f64>>)> {
+    let used = extract(base, used)?;
+    let total = extract(base, total)?;
+
+    // skip if we don't have the same amount of data for used and total
+    if used.data.len() != total.data.len() {
+        return None;
+    }
+
+    let data = used
+        .data
+        .iter()
+        .zip(total.data.iter())
+        .map(|(used, total)| match (used, total) {
+            (Some(used), Some(total)) if *total > 0.0 => Some(100.0 * used / total),
+            _ => None,
+        })
+        .collect();
+
+    Some((used.resolution, data))
+}
+
+/// Get the usage series in percent for a metric, together with its resolution.
+fn usage_series(base: &str, metric: AlertMetric) -> Option<(u64, Vec<Option<f64>>)> {
+    match metric {
+        AlertMetric::Cpu => {
+            let cpu = extract(base, "cpu_current")?;
+            let data = cpu.data.iter().map(|v| v.map(|v| v * 100.0)).collect();
+            Some((cpu.resolution, data))
+        }
+        AlertMetric::Memory => ratio_series(base, "mem_used", "mem_total"),
+        AlertMetric::Disk => ratio_series(base, "disk_used", "disk_total"),
+    }
+}
+
+fn send_notification(alert: &Alert) {
+    if let Err(err) = notifications::send_alert(alert) {
+        log::error!(
+            "could not send notification for alert '{}' on '{}': {err}",
+            alert.rule,
+            alert.resource_id
+        );
+    }
+}
+
+/// Update the state of a single alert according to the latest evaluation.
+fn update_alert(
+    state: &mut State,
+    key: String,
+    rule: &AlertRule,
+    remote: &str,
+    resource: Resource,
+    evaluation: Evaluation,
+    now: i64,
+) {
+    let existing = state
+        .alerts
+        .get_mut(&key)
+        .filter(|entry| entry.alert.state == AlertState::Firing);
+
+    match (evaluation, existing) {
+        (Evaluation::Above(value) | Evaluation::Pending(value), Some(entry)) => {
+            entry.alert.value = value;
+            entry.alert.threshold = rule.threshold();
+            entry.resource = resource;
+        }
+        (Evaluation::Above(value), None) => {
+            let alert = Alert {
+                rule: rule.id.clone(),
+                remote: remote.to_string(),
+                resource_id: resource.global_id().to_string(),
+                resource_type: resource.resource_type(),
+                resource_name: resource.name().to_string(),
+                metric: rule.metric,
+                value,
+                threshold: rule.threshold(),
+                state: AlertState::Firing,
+                since: now,
+                resolved: None,
+            };
+            send_notification(&alert);
+            state.alerts.insert(key, AlertEntry { alert, resource });
+        }
+        (Evaluation::Below(value), Some(entry)) => {
+            entry.alert.value = value;
+            entry.alert.state = AlertState::Resolved;
+            entry.alert.resolved = Some(now);
+            entry.resource = resource;
+            send_notification(&entry.alert);
+        }
+        (Evaluation::Pending(_) | Evaluation::Below(_) | Evaluation::NoData, None)
+        | (Evaluation::NoData, Some(_)) => {}
+    }
+}
+
+/// Keep the firing alerts of a rule on a remote which was not evaluated.
+///
+/// Otherwise they would be dropped from the state without ever being resolved.
+fn keep_firing_alerts(state: &State, checked: &mut HashSet<String>, rule: &str, remote: &str) {
+    checked.extend(
+        state
+            .alerts
+            .iter()
+            .filter(|(_, entry)| {
+                entry.alert.state == AlertState::Firing
+                    && entry.alert.rule == rule
+                    && entry.alert.remote == remote
+            })
+            .map(|(key, _)| key.clone()),
+    );
+}
+
+/// Evaluate all enabled alert rules and update the persisted alert state.
+///
+/// This function blocks, use `spawn_blocking` when calling it from an async context.
+pub(super) fn evaluate_alert_rules() -> Result<(), Error> {
+    let (rules, _) = pdm_config::alerts::config()?;
+    let (remotes, _) = pdm_config::remotes::config()?;
+
+    let mut state = load_state()?;
+    let now = proxmox_time::epoch_i64();
+
+    let mut checked = HashSet::new();
+
+    for (_, AlertRuleEntry::Rule(rule)) in rules.iter() {
+        if !rule.enabled() {
+            continue;
+        }
+
+        for (remote_name, remote) in remotes.iter() {
+            if rule
+                .remote
+                .as_ref()
+                .is_some_and(|remote| remote != remote_name)
+            {
+                continue;
+            }
+
+            // no fresh data is collected while in maintenance
+            if remote.in_maintenance(now) {
+                keep_firing_alerts(&state.state, &mut checked, &rule.id, remote_name);
+                continue;
+            }
+
+            // FIXME: find better way to enumerate nodes/guests/etc.(instead of relying on the cache)
+            let Some(data) =
+                crate::api::resources::get_cached_resources(remote_name, i64::MAX as u64)
+            else {
+                keep_firing_alerts(&state.state, &mut checked, &rule.id, remote_name);
+                continue;
+            };
+
+            for resource in data.resources {
+                if resource.resource_type() != rule.resource_type {
+                    continue;
+                }
+
+                let Some(base) = rrd_base_name(remote_name, &resource) else {
+                    continue;
+                };
+
+                let key = format!("{}/{}", rule.id, resource.global_id());
+                checked.insert(key.clone());
+
+                let evaluation = match usage_series(&base, rule.metric) {
+                    Some((resolution, data)) => {
+                        check_threshold(&data, resolution, rule.duration(), rule.threshold())
+                    }
+                    None => Evaluation::NoData,
+                };
+
+                update_alert(
+                    &mut state.state,
+                    key,
+                    rule,
+                    remote_name,
+                    resource,
+                    evaluation,
+                    now,
+                );
+            }
+        }
+    }
+
+    // Drop alerts of removed rules and resources, as well as old resolved alerts.
+    state
+        .state
+        .alerts
+        .retain(|key, entry| match entry.alert.state {
+            AlertState::Firing => checked.contains(key),
+            AlertState::Resolved => entry
+                .alert
+                .resolved
+                .is_some_and(|resolved| now - resolved < RESOLVED_ALERT_RETENTION),
+        });
+
+    state.save()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_data() {
+        assert_eq!(check_threshold(&[], 60, 0, 80.0), Evaluation::NoData);
+        assert_eq!(
+            check_threshold(&[None, None], 60, 0, 80.0),
+            Evaluation::NoData
+        );
+    }
+
+    #[test]
+    fn trailing_gaps_are_ignored() {
+        let data = [Some(50.0), Some(90.0), None, None];
+        assert_eq!(check_threshold(&data, 60, 0, 80.0), Evaluation::Above(90.0));
+    }
+
+    #[test]
+    fn below_threshold() {
+        let data = [Some(95.0), Some(95.0), Some(80.0)];
+        assert_eq!(check_threshold(&data, 60, 0, 80.0), Evaluation::Below(80.0));
+    }
+
+    #[test]
+    fn duration_must_be_exceeded() {
+        let data = [Some(50.0), Some(90.0), Some(91.0), Some(92.0)];
+        assert_eq!(
+            check_threshold(&data, 60, 180, 80.0),
+            Evaluation::Above(92.0)
+        );
+        assert_eq!(
+            check_threshold(&data, 60, 240, 80.0),
+            Evaluation::Pending(92.0)
+        );
+
+        // a gap within the duration does not count as exceeded
+        let data = [Some(90.0), None, Some(91.0), Some(92.0)];
+        assert_eq!(
+            check_threshold(&data, 60, 180, 80.0),
+            Evaluation::Pending(92.0)
+        );
+    }
+
+    #[test]
+    fn not_enough_data_for_duration() {
+        let data = [Some(90.0), Some(91.0)];
+        assert_eq!(
+            check_threshold(&data, 60, 600, 80.0),
+            Evaluation::Pending(91.0)
+        );
+    }
+}
@@ -19,9 +19,10 @@ use proxmox_sys::fs::CreateOptions;
 use pdm_api_types::remotes::{Remote, RemoteType};
 
 use crate::metric_collection::rrd_task::CollectionStats;
-use crate::{connection, task_utils};
+use crate::{connection, notifications, task_utils};
 
 use super::{
+    alerts,
     rrd_task::{RrdStoreRequest, RrdStoreResult},
     state::{MetricCollectionState, RemoteStatus},
 };
@@ -117,6 +118,9 @@ impl MetricCollectionTask {
             self.fetch_remotes(&remotes, &to_fetch).await;
             let elapsed = now.elapsed();
 
+            // The RRD task handles requests in order, so once the stats are stored, all metrics
+            // fetched in this run are as well and the alert rules see the fresh data.
+            let (stored_tx, stored_rx) = oneshot::channel();
             if let Err(err) = self
                 .metric_data_tx
                 .send(RrdStoreRequest::CollectionStats {
@@ -125,10 +129,23 @@ impl MetricCollectionTask {
                         // TODO: use as_millis_f64 once stabilized
                         total_time: elapsed.as_secs_f64() * 1000.,
                     },
+                    channel: stored_tx,
                 })
                 .await
             {
                 log::error!("could not send collection stats to rrd task: {err}");
+                return;
+            }
+
+            if stored_rx.await.is_err() {
+                log::error!("rrd task did not confirm storing the collected metrics");
+                return;
+            }
+
+            match tokio::task::spawn_blocking(alerts::evaluate_alert_rules).await {
+                Ok(Err(err)) => log::error!("could not evaluate alert rules: {err}"),
+                Err(err) => log::error!("could not join alert evaluation task: {err}"),
+                Ok(Ok(())) => {}
             }
         }
     }
@@ -225,6 +242,16 @@ impl MetricCollectionTask {
                 continue;
             }
 
+            if remote_config
+                .get(remote_name)
+                .is_some_and(|remote| remote.in_maintenance(now))
+            {
+                log::debug!(
+                    "skipping metric collection for remote '{remote_name}' - in maintenance"
+                );
+                continue;
+            }
+
             // unwrap is okay here, acquire_* will only fail if `close` has been
             // called on the semaphore.
             let permit = Arc::clone(&semaphore).acquire_owned().await.unwrap();
@@ -243,6 +270,17 @@ impl MetricCollectionTask {
         while let Some(res) = handles.join_next().await {
             match res {
                 Ok((name, status)) => {
+                    let had_error = self
+                        .state
+                        .get_status(&name)
+                        .is_some_and(|status| status.error.is_some());
+
+                    if let Some(error) = &status.error {
+                        if !had_error {
+                            Self::notify_collection_failed(name.clone(), error.clone()).await;
+                        }
+                    }
+
                     self.state.set_status(name, status);
                 }
                 Err(err) => {
@@ -252,6 +290,20 @@ impl MetricCollectionTask {
         }
     }
 
+    /// Send a notification for a remote which started failing metric collection.
+    async fn notify_collection_failed(remote: String, error: String) {
+        let res = tokio::task::spawn_blocking(move || {
+            notifications::send_metric_collection_failed(&remote, &error)
+        })
+        .await;
+
+        match res {
+            Ok(Err(err)) => log::error!("could not send metric collection notification: {err:#}"),
+            Err(err) => log::error!("could not send metric collection notification: {err}"),
+            Ok(Ok(())) => {}
+        }
+    }
+
     /// Fetch metric data from remotes which are overdue for collection.
     ///
     /// Use this on startup of the metric collection loop as well as
@@ -448,6 +500,10 @@ pub(super) mod tests {
         ) -> Result<Box<PbsClient>, Error> {
             bail!("not implemented")
         }
+
+        async fn make_raw_client_and_login(&self, _remote: &Remote) -> Result<Box<Client>, Error> {
+            bail!("not implemented")
+        }
     }
 
     struct TestPveClient {
@@ -516,6 +572,16 @@ pub(super) mod tests {
                     authid: Authid::root_auth_id().clone(),
                     token: "".into(),
                     web_url: None,
+                    token_created: None,
+                    token_expire: None,
+                    sync_nodes: None,
+                    http_proxy: None,
+                    connect_timeout: None,
+                    request_timeout: None,
+                    max_connections: None,
+                    maintenance: None,
+                    maintenance_nodes: None,
+                    maintenance_end: None,
                 },
             );
         }
@@ -665,4 +731,47 @@ pub(super) mod tests {
         drop(task);
         assert_eq!(handle.await.unwrap(), 1);
     }
+
+    #[tokio::test]
+    async fn test_fetch_remotes_skips_maintenance() {
+        // Arrange
+        test_init();
+
+        let (tx, rx) = tokio::sync::mpsc::channel(10);
+        let handle = tokio::task::spawn(fake_rrd_task(rx));
+
+        let mut config = make_remote_config();
+        for name in ["pve-0-pass", "pve-1-pass", "pve-2-fail"] {
+            config.get_mut(name).unwrap().maintenance = Some(true);
+        }
+        // maintenance already ended
+        config.get_mut("pve-0-pass").unwrap().maintenance_end = Some(1);
+
+        let state_file = NamedTempFile::new(get_create_options()).unwrap();
+        let state = MetricCollectionState::new(state_file.path().into(), get_create_options());
+
+        let (_control_tx, control_rx) = tokio::sync::mpsc::channel(10);
+
+        let mut task = MetricCollectionTask {
+            state,
+            metric_data_tx: tx,
+            control_message_rx: control_rx,
+        };
+
+        // Act
+        let to_fetch = config
+            .iter()
+            .map(|(name, _)| name.into())
+            .collect::<Vec<String>>();
+        task.fetch_remotes(&config, &to_fetch).await;
+
+        // Assert
+        assert!(task.state.get_status("pve-0-pass").is_some());
+        assert!(task.state.get_status("pve-1-pass").is_none());
+        assert!(task.state.get_status("pve-2-fail").is_none());
+        assert!(task.state.get_status("pve-3-fail").is_some());
+
+        drop(task);
+        assert_eq!(handle.await.unwrap(), 1);
+    }
 }
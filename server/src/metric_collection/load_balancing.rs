@@ -0,0 +1,433 @@
+//! Guest placement recommendations based on the collected metrics.
+//!
+//! The load of a node is the higher one of its average CPU and memory usage. A plan is built by
+//! greedily picking the migration which reduces the standard deviation of all node loads the
+//! most, until no migration improves the balance noticeably anymore.
+
+use proxmox_rrd_api_types::{RrdMode, RrdTimeframe};
+
+use pdm_api_types::load_balancing::{MigrationKind, MigrationPlan, NodeLoad, ProposedMigration};
+use pdm_api_types::resource::Resource;
+
+use super::rrd_cache;
+
+/// Migrations must not push the memory usage of the target node above this ratio.
+const MAX_TARGET_MEMORY_USAGE: f64 = 0.9;
+
+/// Migrations which reduce the imbalance by less than this are not worth it.
+const MIN_IMPROVEMENT: f64 = 0.005;
+
+/// Migrations to another remote are more expensive, so they need to be this much better than
+/// migrations within a cluster to be preferred.
+const REMOTE_MIGRATION_PENALTY: f64 = 2.0;
+
+/// Capacity and average usage of a node.
+#[derive(Clone, Debug)]
+pub struct NodeState {
+    pub remote: String,
+    pub node: String,
+    /// Number of CPUs.
+    pub maxcpu: f64,
+    /// Total memory in bytes.
+    pub maxmem: f64,
+    /// Average CPU usage in number of CPUs.
+    pub cpu: f64,
+    /// Average memory usage in bytes.
+    pub mem: f64,
+}
+
+impl NodeState {
+    fn cpu_usage(&self) -> f64 {
+        if self.maxcpu > 0.0 {
+            self.cpu / self.maxcpu
+        } else {
+            0.0
+        }
+    }
+
+    fn mem_usage(&self) -> f64 {
+        if self.maxmem > 0.0 {
+            self.mem / self.maxmem
+        } else {
+            0.0
+        }
+    }
+
+    fn load(&self) -> f64 {
+        self.cpu_usage().max(self.mem_usage())
+    }
+
+    fn load_with(&self, cpu: f64, mem: f64) -> f64 {
+        let cpu = if self.maxcpu > 0.0 {
+            (self.cpu + cpu) / self.maxcpu
+        } else {
+            0.0
+        };
+        let mem = if self.maxmem > 0.0 {
+            (self.mem + mem) / self.maxmem
+        } else {
+            0.0
+        };
+
+        cpu.max(mem)
+    }
+}
+
+/// Average usage of a running guest.
+#[derive(Clone, Debug)]
+pub struct GuestState {
+    pub remote: String,
+    pub vmid: u32,
+    pub name: String,
+    pub node: String,
+    /// Average CPU usage in number of CPUs.
+    pub cpu: f64,
+    /// Average memory usage in bytes.
+    pub mem: f64,
+}
+
+/// Average of all data points of a metric within the timeframe.
+fn average(base: &str, metric: &str, timeframe: RrdTimeframe) -> Option<f64> {
+    let values = rrd_cache::get_cache()
+        .extract_data(base, metric, timeframe, RrdMode::Average)
+        .ok()??;
+
+    let (sum, count) = values
+        .data
+        .iter()
+        .flatten()
+        .filter(|value| value.is_finite())
+        .fold((0.0, 0), |(sum, count), value| (sum + value, count + 1));
+
+    (count > 0).then(|| sum / count as f64)
+}
+
+/// Collect the nodes and running VMs of the given PVE remotes from the resource cache.
+///
+/// The usage values are averaged over the RRD data of the given timeframe, the current values
+/// are used if there is no RRD data yet. Nodes and guests for which `is_resource_included`
+/// returns `false` are skipped.
+pub fn collect_state(
+    remotes: &[String],
+    timeframe: RrdTimeframe,
+    is_resource_included: impl Fn(&str, &Resource) -> bool,
+) -> (Vec<NodeState>, Vec<GuestState>) {
+    let mut nodes = Vec::new();
+    let mut guests = Vec::new();
+
+    for remote in remotes {
+        let Some(cached) = crate::api::resources::get_cached_resources(remote, i64::MAX as u64)
+        else {
+            continue;
+        };
+
+        for resource in &cached.resources {
+            let base = format!("pve/{remote}/{}", resource.id());
+
+            match resource {
+                Resource::PveNode(node) if node.status == "online" => {
+                    if !is_resource_included(remote, resource) {
+                        continue;
+                    }
+
+                    let cpu = average(&base, "cpu_current", timeframe).unwrap_or(node.cpu);
+                    let mem = average(&base, "mem_used", timeframe).unwrap_or(node.mem as f64);
+
+                    nodes.push(NodeState {
+                        remote: remote.clone(),
+                        node: node.node.clone(),
+                        maxcpu: node.maxcpu,
+                        maxmem: node.maxmem as f64,
+                        cpu: cpu * node.maxcpu,
+                        mem,
+                    });
+                }
+                Resource::PveQemu(qemu) if qemu.status == "running" && !qemu.template => {
+                    if !is_resource_included(remote, resource) {
+                        continue;
+                    }
+
+                    let cpu = average(&base, "cpu_current", timeframe).unwrap_or(qemu.cpu);
+                    let mem = average(&base, "mem_used", timeframe).unwrap_or(qemu.mem as f64);
+
+                    guests.push(GuestState {
+                        remote: remote.clone(),
+                        vmid: qemu.vmid,
+                        name: qemu.name.clone(),
+                        node: qemu.node.clone(),
+                        cpu: cpu * qemu.maxcpu,
+                        mem,
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    (nodes, guests)
+}
+
+/// Running sums to calculate the standard deviation of the node loads cheaply.
+struct LoadStats {
+    sum: f64,
+    sum_of_squares: f64,
+    count: f64,
+}
+
+impl LoadStats {
+    fn new(loads: &[f64]) -> Self {
+        Self {
+            sum: loads.iter().sum(),
+            sum_of_squares: loads.iter().map(|load| load * load).sum(),
+            count: loads.len() as f64,
+        }
+    }
+
+    fn std_dev(&self) -> f64 {
+        if self.count == 0.0 {
+            return 0.0;
+        }
+        let mean = self.sum / self.count;
+        (self.sum_of_squares / self.count - mean * mean)
+            .max(0.0)
+            .sqrt()
+    }
+
+    /// Standard deviation after replacing the `old` loads with the `new` ones.
+    fn std_dev_with(&self, old: [f64; 2], new: [f64; 2]) -> f64 {
+        let stats = LoadStats {
+            sum: self.sum - old[0] - old[1] + new[0] + new[1],
+            sum_of_squares: self.sum_of_squares - old[0] * old[0] - old[1] * old[1]
+                + new[0] * new[0]
+                + new[1] * new[1],
+            count: self.count,
+        };
+        stats.std_dev()
+    }
+}
+
+/// Build a migration plan which evens out the load of the given nodes.
+///
+/// `can_migrate` decides whether a guest may be moved to a node at all, for example because of
+/// missing privileges.
+pub fn plan_migrations(
+    mut nodes: Vec<NodeState>,
+    guests: Vec<GuestState>,
+    max_migrations: usize,
+    allow_remote_migration: bool,
+    can_migrate: impl Fn(&GuestState, &NodeState) -> bool,
+) -> MigrationPlan {
+    let initial_loads: Vec<f64> = nodes.iter().map(NodeState::load).collect();
+    let imbalance = LoadStats::new(&initial_loads).std_dev();
+
+    let mut guest_nodes: Vec<Option<usize>> = guests
+        .iter()
+        .map(|guest| {
+            nodes
+                .iter()
+                .position(|node| node.remote == guest.remote && node.node == guest.node)
+        })
+        .collect();
+    let mut moved = vec![false; guests.len()];
+
+    let mut migrations = Vec::new();
+
+    while migrations.len() < max_migrations {
+        let loads: Vec<f64> = nodes.iter().map(NodeState::load).collect();
+        let stats = LoadStats::new(&loads);
+        let current = stats.std_dev();
+        let mean = stats.sum / stats.count.max(1.0);
+
+        // (guest, target, improvement, score)
+        let mut best: Option<(usize, usize, f64, f64)> = None;
+
+        for (guest_index, guest) in guests.iter().enumerate() {
+            let Some(source) = guest_nodes[guest_index] else {
+                continue;
+            };
+
+            // only moving guests away from nodes above the average can improve the balance
+            if moved[guest_index] || loads[source] <= mean {
+                continue;
+            }
+
+            for (target, target_node) in nodes.iter().enumerate() {
+                if target == source {
+                    continue;
+                }
+
+                let is_remote = target_node.remote != guest.remote;
+                if is_remote && !allow_remote_migration {
+                    continue;
+                }
+
+                if target_node.maxmem <= 0.0
+                    || (target_node.mem + guest.mem) / target_node.maxmem > MAX_TARGET_MEMORY_USAGE
+                {
+                    continue;
+                }
+
+                let new_source = nodes[source].load_with(-guest.cpu, -guest.mem);
+                let new_target = target_node.load_with(guest.cpu, guest.mem);
+
+                let improvement = current
+                    - stats.std_dev_with([loads[source], loads[target]], [new_source, new_target]);
+
+                let score = if is_remote {
+                    improvement / REMOTE_MIGRATION_PENALTY
+                } else {
+                    improvement
+                };
+
+                if score < MIN_IMPROVEMENT {
+                    continue;
+                }
+
+                if best.is_some_and(|(_, _, _, best_score)| best_score >= score) {
+                    continue;
+                }
+
+                if !can_migrate(guest, target_node) {
+                    continue;
+                }
+
+                best = Some((guest_index, target, improvement, score));
+            }
+        }
+
+        let Some((guest_index, target, improvement, _)) = best else {
+            break;
+        };
+
+        let guest = &guests[guest_index];
+        let source = guest_nodes[guest_index].expect("guest without node was selected");
+
+        nodes[source].cpu -= guest.cpu;
+        nodes[source].mem -= guest.mem;
+        nodes[target].cpu += guest.cpu;
+        nodes[target].mem += guest.mem;
+
+        guest_nodes[guest_index] = Some(target);
+        moved[guest_index] = true;
+
+        let target_node = &nodes[target];
+
+        migrations.push(ProposedMigration {
+            remote: guest.remote.clone(),
+            vmid: guest.vmid,
+            name: guest.name.clone(),
+            node: guest.node.clone(),
+            target_remote: target_node.remote.clone(),
+            target_node: target_node.node.clone(),
+            kind: if target_node.remote == guest.remote {
+                MigrationKind::Local
+            } else {
+                MigrationKind::Remote
+            },
+            cpu: guest.cpu,
+            memory: guest.mem.max(0.0) as u64,
+            score: improvement,
+        });
+    }
+
+    let planned_loads: Vec<f64> = nodes.iter().map(NodeState::load).collect();
+    let planned_imbalance = LoadStats::new(&planned_loads).std_dev();
+
+    let nodes = nodes
+        .into_iter()
+        .zip(initial_loads)
+        .zip(planned_loads)
+        .map(|((node, load), planned_load)| NodeLoad {
+            cpu: node.cpu_usage(),
+            memory: node.mem_usage(),
+            remote: node.remote,
+            node: node.node,
+            load,
+            planned_load,
+        })
+        .collect();
+
+    MigrationPlan {
+        imbalance,
+        planned_imbalance,
+        migrations,
+        nodes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+    fn node(remote: &str, node: &str, cpu: f64, mem: f64) -> NodeState {
+        NodeState {
+            remote: remote.into(),
+            node: node.into(),
+            maxcpu: 16.0,
+            maxmem: 64.0 * GIB,
+            cpu,
+            mem: mem * GIB,
+        }
+    }
+
+    fn guest(remote: &str, node: &str, vmid: u32, cpu: f64, mem: f64) -> GuestState {
+        GuestState {
+            remote: remote.into(),
+            vmid,
+            name: format!("vm{vmid}"),
+            node: node.into(),
+            cpu,
+            mem: mem * GIB,
+        }
+    }
+
+    #[test]
+    fn balances_within_cluster() {
+        let nodes = vec![node("a", "pve1", 8.0, 48.0), node("a", "pve2", 1.0, 8.0)];
+        let guests = vec![
+            guest("a", "pve1", 100, 4.0, 16.0),
+            guest("a", "pve1", 101, 2.0, 16.0),
+            guest("a", "pve2", 102, 1.0, 8.0),
+        ];
+
+        let plan = plan_migrations(nodes, guests, 10, false, |_, _| true);
+
+        assert!(!plan.migrations.is_empty());
+        assert!(plan.planned_imbalance < plan.imbalance);
+        for migration in &plan.migrations {
+            assert_eq!(migration.node, "pve1");
+            assert_eq!(migration.target_node, "pve2");
+            assert_eq!(migration.kind, MigrationKind::Local);
+            assert!(migration.score > 0.0);
+        }
+    }
+
+    #[test]
+    fn remote_migrations_need_to_be_allowed() {
+        let nodes = vec![node("a", "pve1", 8.0, 48.0), node("b", "pve1", 1.0, 8.0)];
+        let guests = vec![guest("a", "pve1", 100, 4.0, 16.0)];
+
+        let plan = plan_migrations(nodes.clone(), guests.clone(), 10, false, |_, _| true);
+        assert!(plan.migrations.is_empty());
+        assert_eq!(plan.imbalance, plan.planned_imbalance);
+
+        let plan = plan_migrations(nodes.clone(), guests.clone(), 10, true, |_, _| true);
+        assert_eq!(plan.migrations.len(), 1);
+        assert_eq!(plan.migrations[0].kind, MigrationKind::Remote);
+        assert_eq!(plan.migrations[0].target_remote, "b");
+
+        let plan = plan_migrations(nodes, guests, 10, true, |_, _| false);
+        assert!(plan.migrations.is_empty());
+    }
+
+    #[test]
+    fn memory_limit_of_target_is_respected() {
+        let nodes = vec![node("a", "pve1", 14.0, 40.0), node("a", "pve2", 1.0, 40.0)];
+        let guests = vec![guest("a", "pve1", 100, 8.0, 24.0)];
+
+        let plan = plan_migrations(nodes, guests, 10, false, |_, _| true);
+        assert!(plan.migrations.is_empty());
+    }
+}
@@ -0,0 +1,322 @@
+//! Export collected metrics to external metric servers.
+//!
+//! Every data point which is stored in the RRD database is also sent to all enabled metric
+//! servers from `metricserver.cfg`, tagged with the ID of the remote it was collected from.
+
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{format_err, Error};
+use serde_json::{Map, Value};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpStream, UdpSocket};
+
+use proxmox_metrics::{Metrics, MetricsData};
+
+use pdm_api_types::metric_server::{Graphite, GraphiteProtocol, MetricServerEntry};
+use pdm_api_types::remotes::RemoteType;
+
+const DEFAULT_GRAPHITE_PORT: u16 = 2003;
+const DEFAULT_GRAPHITE_PATH: &str = "proxmox-datacenter-manager";
+const DEFAULT_MTU: u16 = 1500;
+const DEFAULT_INFLUXDB_BUCKET: &str = "proxmox";
+const DEFAULT_INFLUXDB_ORGANIZATION: &str = "proxmox";
+const DEFAULT_INFLUXDB_MAX_BODY_SIZE: usize = 25_000_000;
+
+/// A single metric data point of a remote.
+pub(super) struct ExportDataPoint {
+    /// ID of the object, e.g. `qemu/100` or `datastore/store1`.
+    pub(super) id: String,
+    /// Name of the metric.
+    pub(super) metric: String,
+    /// Timestamp of the data point (UNIX epoch).
+    pub(super) timestamp: i64,
+    /// The value.
+    pub(super) value: f64,
+}
+
+/// Return all enabled metric servers.
+///
+/// Errors while loading the config are logged, in this case no metric servers are returned.
+pub(super) fn enabled_metric_servers() -> Vec<MetricServerEntry> {
+    match pdm_config::metric_server::config() {
+        Ok((config, _)) => config
+            .into_iter()
+            .map(|(_, entry)| entry)
+            .filter(|entry| entry.enabled())
+            .collect(),
+        Err(err) => {
+            log::error!("could not read metric server config: {err}");
+            Vec::new()
+        }
+    }
+}
+
+/// Send the data points of a remote to the given metric servers.
+pub(super) async fn send_data_to_metric_servers(
+    servers: Vec<MetricServerEntry>,
+    remote: String,
+    remote_type: RemoteType,
+    data: Vec<ExportDataPoint>,
+) {
+    let mut influxdb_connections = Vec::new();
+    let mut graphite_servers = Vec::new();
+
+    for server in servers {
+        match server {
+            MetricServerEntry::InfluxDbUdp(config) => {
+                let connection = proxmox_metrics::influxdb_udp(&config.host, config.mtu);
+                influxdb_connections.push((connection, config.name));
+            }
+            MetricServerEntry::InfluxDbHttp(config) => {
+                let connection = proxmox_metrics::influxdb_http(
+                    &config.url,
+                    config
+                        .organization
+                        .as_deref()
+                        .unwrap_or(DEFAULT_INFLUXDB_ORGANIZATION),
+                    config.bucket.as_deref().unwrap_or(DEFAULT_INFLUXDB_BUCKET),
+                    config.token.as_deref(),
+                    config.verify_tls.unwrap_or(true),
+                    config
+                        .max_body_size
+                        .unwrap_or(DEFAULT_INFLUXDB_MAX_BODY_SIZE),
+                );
+
+                match connection {
+                    Ok(connection) => influxdb_connections.push((connection, config.name)),
+                    Err(err) => {
+                        log::error!("could not connect to metric server {}: {err}", config.name)
+                    }
+                }
+            }
+            MetricServerEntry::Graphite(config) => graphite_servers.push(config),
+        }
+    }
+
+    if !influxdb_connections.is_empty() {
+        match influxdb_data(&remote, remote_type, &data) {
+            Ok(values) => send_to_influxdb(&values, influxdb_connections).await,
+            Err(err) => log::error!("could not convert metric data for InfluxDB: {err}"),
+        }
+    }
+
+    if !graphite_servers.is_empty() {
+        let data = graphite_data(&remote, &data);
+
+        let futures = graphite_servers.iter().map(|config| {
+            let data = graphite_data_with_path(config, &data);
+            async move {
+                if let Err(err) = send_to_graphite(config, &data).await {
+                    log::error!("error sending to metric server {}: {err}", config.name);
+                }
+            }
+        });
+
+        futures::future::join_all(futures).await;
+    }
+}
+
+async fn send_to_influxdb(values: &[Arc<MetricsData>], connections: Vec<(Metrics, String)>) {
+    let (channels, names): (Vec<Metrics>, Vec<String>) = connections.into_iter().unzip();
+
+    let results = proxmox_metrics::send_data_to_channels(values, &channels).await;
+    for (res, name) in results.into_iter().zip(names.iter()) {
+        if let Err(err) = res {
+            log::error!("error sending into channel of {name}: {err}");
+        }
+    }
+
+    futures::future::join_all(
+        channels
+            .into_iter()
+            .zip(names)
+            .map(|(channel, name)| async move {
+                if let Err(err) = channel.join().await {
+                    log::error!("error sending to metric server {name}: {err}");
+                }
+            }),
+    )
+    .await;
+}
+
+/// Convert data points into InfluxDB measurements.
+///
+/// All metrics of an object with the same timestamp are combined into a single measurement, the
+/// measurement name is the type of the object, e.g. `qemu`, `node` or `datastore`.
+fn influxdb_data(
+    remote: &str,
+    remote_type: RemoteType,
+    data: &[ExportDataPoint],
+) -> Result<Vec<Arc<MetricsData>>, Error> {
+    let mut grouped: BTreeMap<(&str, i64), Map<String, Value>> = BTreeMap::new();
+
+    for point in data {
+        grouped
+            .entry((point.id.as_str(), point.timestamp))
+            .or_default()
+            .insert(point.metric.clone(), point.value.into());
+    }
+
+    let mut values = Vec::new();
+
+    for ((id, timestamp), fields) in grouped {
+        let measurement = id.split('/').next().unwrap_or(id);
+
+        let data = MetricsData::new(measurement, timestamp, fields)?
+            .tag("remote", remote)
+            .tag("remote-type", remote_type.to_string())
+            .tag("id", id);
+
+        values.push(Arc::new(data));
+    }
+
+    Ok(values)
+}
+
+/// Replace characters which have a special meaning in a Graphite metric path.
+fn graphite_escape(component: &str) -> String {
+    component
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Convert data points into Graphite plaintext lines, without the configurable root path.
+fn graphite_data(remote: &str, data: &[ExportDataPoint]) -> Vec<String> {
+    data.iter()
+        .map(|point| {
+            let mut path = graphite_escape(remote);
+            for component in point.id.split('/') {
+                path.push('.');
+                path.push_str(&graphite_escape(component));
+            }
+
+            format!(
+                "{path}.{metric} {value} {timestamp}\n",
+                metric = graphite_escape(&point.metric),
+                value = point.value,
+                timestamp = point.timestamp,
+            )
+        })
+        .collect()
+}
+
+fn graphite_data_with_path(config: &Graphite, lines: &[String]) -> Vec<String> {
+    let root = config.path.as_deref().unwrap_or(DEFAULT_GRAPHITE_PATH);
+    lines.iter().map(|line| format!("{root}.{line}")).collect()
+}
+
+/// Split lines into chunks which fit into a single datagram.
+fn split_into_chunks(lines: &[String], max_size: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    for line in lines {
+        if !current.is_empty() && current.len() + line.len() > max_size {
+            chunks.push(std::mem::take(&mut current));
+        }
+        current.push_str(line);
+    }
+
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+
+    chunks
+}
+
+async fn send_to_graphite(config: &Graphite, lines: &[String]) -> Result<(), Error> {
+    let port = config.port.unwrap_or(DEFAULT_GRAPHITE_PORT);
+    let addr = tokio::net::lookup_host((config.server.as_str(), port))
+        .await?
+        .next()
+        .ok_or_else(|| format_err!("could not resolve '{}'", config.server))?;
+
+    match config.proto.unwrap_or_default() {
+        GraphiteProtocol::Udp => {
+            let bind_addr: SocketAddr = if addr.is_ipv6() {
+                "[::]:0".parse()?
+            } else {
+                "0.0.0.0:0".parse()?
+            };
+
+            let socket = UdpSocket::bind(bind_addr).await?;
+            socket.connect(addr).await?;
+
+            // leave room for the IP and UDP headers
+            let mtu = config.mtu.unwrap_or(DEFAULT_MTU) as usize;
+            let max_size = mtu.saturating_sub(if addr.is_ipv6() { 48 } else { 28 });
+
+            for chunk in split_into_chunks(lines, max_size) {
+                socket.send(chunk.as_bytes()).await?;
+            }
+        }
+        GraphiteProtocol::Tcp => {
+            let timeout = Duration::from_secs(config.timeout.unwrap_or(1));
+            let mut stream = tokio::time::timeout(timeout, TcpStream::connect(addr))
+                .await
+                .map_err(|_| format_err!("connection to {addr} timed out"))??;
+
+            stream.write_all(lines.concat().as_bytes()).await?;
+            stream.shutdown().await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(id: &str, metric: &str, value: f64) -> ExportDataPoint {
+        ExportDataPoint {
+            id: id.into(),
+            metric: metric.into(),
+            timestamp: 1700000000,
+            value,
+        }
+    }
+
+    #[test]
+    fn graphite_lines() {
+        let data = [
+            point("qemu/100", "cpu_current", 0.5),
+            point("storage/pve1.example/local", "disk_used", 1024.0),
+        ];
+
+        let lines = graphite_data("my-remote", &data);
+
+        assert_eq!(
+            lines,
+            [
+                "my-remote.qemu.100.cpu_current 0.5 1700000000\n",
+                "my-remote.storage.pve1_example.local.disk_used 1024 1700000000\n",
+            ]
+        );
+    }
+
+    #[test]
+    fn chunks_respect_max_size() {
+        let lines: Vec<String> = (0..10).map(|i| format!("a.b.c {i} 0\n")).collect();
+        let line_len = lines[0].len();
+
+        let chunks = split_into_chunks(&lines, line_len * 3);
+        assert_eq!(chunks.len(), 4);
+        assert!(chunks.iter().all(|chunk| chunk.len() <= line_len * 3));
+        assert_eq!(chunks.concat(), lines.concat());
+
+        // lines longer than the maximum size are still sent on their own
+        let chunks = split_into_chunks(&lines, 1);
+        assert_eq!(chunks.len(), 10);
+    }
+}
@@ -10,7 +10,10 @@ use tokio::sync::oneshot;
 use pdm_api_types::MetricCollectionStatus;
 use pdm_buildcfg::PDM_STATE_DIR_M;
 
+pub mod alerts;
 mod collection_task;
+pub mod load_balancing;
+mod metric_server;
 pub mod rrd_cache;
 mod rrd_task;
 mod state;
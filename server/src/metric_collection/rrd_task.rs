@@ -8,6 +8,9 @@ use proxmox_rrd::rrd::DataSourceType;
 use pbs_api_types::{MetricDataPoint, MetricDataType, Metrics};
 use pve_api_types::{ClusterMetrics, ClusterMetricsData, ClusterMetricsDataType};
 
+use pdm_api_types::remotes::RemoteType;
+
+use super::metric_server::{self, ExportDataPoint};
 use super::rrd_cache::RrdCache;
 
 /// Store request for the RRD task.
@@ -44,6 +47,9 @@ pub(super) enum RrdStoreRequest {
         timestamp: i64,
         /// Statistics.
         stats: CollectionStats,
+        /// Oneshot channel which is signaled once the stats, and with them all data of the
+        /// collection run sent before them, are stored.
+        channel: oneshot::Sender<()>,
     },
 }
 
@@ -66,6 +72,8 @@ pub(super) async fn store_in_rrd_task(
     mut receiver: Receiver<RrdStoreRequest>,
 ) -> Result<(), Error> {
     while let Some(msg) = receiver.recv().await {
+        export_to_metric_servers(&msg);
+
         let cache_clone = Arc::clone(&cache);
         // Involves some blocking file IO
         let res = tokio::task::spawn_blocking(move || {
@@ -114,8 +122,14 @@ pub(super) async fn store_in_rrd_task(
                         log::error!("could not send RrdStoreStoreResult to metric collection task");
                     };
                 }
-                RrdStoreRequest::CollectionStats { timestamp, stats } => {
-                    store_stats(&cache_clone, &stats, timestamp)
+                RrdStoreRequest::CollectionStats {
+                    timestamp,
+                    stats,
+                    channel,
+                } => {
+                    store_stats(&cache_clone, &stats, timestamp);
+                    // The collection task might not wait for the result.
+                    let _ = channel.send(());
                 }
             };
         })
@@ -129,6 +143,66 @@ pub(super) async fn store_in_rrd_task(
     Ok(())
 }
 
+/// Send the metric data of a store request to all enabled metric servers.
+///
+/// Sending happens in a separate task, so that slow metric servers do not delay storing the data
+/// in the RRD.
+fn export_to_metric_servers(msg: &RrdStoreRequest) {
+    let (remote, remote_type, data) = match msg {
+        RrdStoreRequest::Pve {
+            remote, metrics, ..
+        } => (remote, RemoteType::Pve, export_data_pve(metrics)),
+        RrdStoreRequest::Pbs {
+            remote, metrics, ..
+        } => (remote, RemoteType::Pbs, export_data_pbs(metrics)),
+        RrdStoreRequest::CollectionStats { .. } => return,
+    };
+
+    if data.is_empty() {
+        return;
+    }
+
+    let servers = metric_server::enabled_metric_servers();
+    if servers.is_empty() {
+        return;
+    }
+
+    tokio::spawn(metric_server::send_data_to_metric_servers(
+        servers,
+        remote.clone(),
+        remote_type,
+        data,
+    ));
+}
+
+fn export_data_pve(metrics: &ClusterMetrics) -> Vec<ExportDataPoint> {
+    metrics
+        .data
+        .iter()
+        .filter(|data_point| !matches!(data_point.ty, ClusterMetricsDataType::UnknownEnumValue(_)))
+        .map(|data_point| ExportDataPoint {
+            id: data_point.id.clone(),
+            metric: data_point.metric.clone(),
+            timestamp: data_point.timestamp,
+            value: data_point.value,
+        })
+        .collect()
+}
+
+fn export_data_pbs(metrics: &Metrics) -> Vec<ExportDataPoint> {
+    metrics
+        .data
+        .iter()
+        .filter(|data_point| !matches!(data_point.ty, MetricDataType::UnknownEnumValue(_)))
+        .map(|data_point| ExportDataPoint {
+            id: data_point.id.clone(),
+            metric: data_point.metric.clone(),
+            timestamp: data_point.timestamp,
+            value: data_point.value,
+        })
+        .collect()
+}
+
 fn store_metric_pve(cache: &RrdCache, remote_name: &str, data_point: &ClusterMetricsData) {
     let name = format!(
         "pve/{remote_name}/{id}/{metric}",
@@ -0,0 +1,79 @@
+use std::path::Path;
+
+use proxmox_access_control::types::User;
+use proxmox_notify::context::Context;
+use proxmox_notify::renderer::TemplateSource;
+use proxmox_notify::Error;
+
+const PDM_VENDOR_TEMPLATE_DIR: &str = "/usr/share/proxmox-datacenter-manager/templates";
+const PDM_OVERRIDE_TEMPLATE_DIR: &str = pdm_buildcfg::configdir!("/notification-templates");
+
+const DEFAULT_CONFIG: &str = "\
+sendmail: mail-to-root
+    comment Send mails to root@pam's email address
+    mailto-user root@pam
+
+
+matcher: default-matcher
+    mode all
+    target mail-to-root
+    comment Route all notifications to mail-to-root
+";
+
+/// Notification context for Proxmox Datacenter Manager.
+#[derive(Debug)]
+pub struct PdmContext;
+
+pub static PDM_CONTEXT: PdmContext = PdmContext;
+
+impl Context for PdmContext {
+    fn lookup_email_for_user(&self, user: &str) -> Option<String> {
+        let (config, _digest) = proxmox_access_control::user::config()
+            .inspect_err(|err| log::error!("could not read user config: {err}"))
+            .ok()?;
+
+        config
+            .lookup::<User>("user", user)
+            .ok()
+            .and_then(|user| user.email)
+    }
+
+    fn default_sendmail_author(&self) -> String {
+        format!("Proxmox Datacenter Manager - {}", proxmox_sys::nodename())
+    }
+
+    fn default_sendmail_from(&self) -> String {
+        pdm_config::node::config()
+            .ok()
+            .and_then(|(config, _digest)| config.email_from)
+            .unwrap_or_else(|| String::from("root"))
+    }
+
+    fn http_proxy_config(&self) -> Option<String> {
+        let (config, _digest) = pdm_config::node::config().ok()?;
+        config.http_proxy
+    }
+
+    fn default_config(&self) -> &'static str {
+        DEFAULT_CONFIG
+    }
+
+    fn lookup_template(
+        &self,
+        filename: &str,
+        namespace: Option<&str>,
+        source: TemplateSource,
+    ) -> Result<Option<String>, Error> {
+        let base = match source {
+            TemplateSource::Vendor => PDM_VENDOR_TEMPLATE_DIR,
+            TemplateSource::Override => PDM_OVERRIDE_TEMPLATE_DIR,
+        };
+
+        let path = Path::new(base)
+            .join(namespace.unwrap_or("default"))
+            .join(filename);
+
+        proxmox_sys::fs::file_read_optional_string(path)
+            .map_err(|err| Error::Generic(format!("could not load template: {err}")))
+    }
+}
@@ -0,0 +1,338 @@
+//! Notification support for Proxmox Datacenter Manager.
+//!
+//! Notifications are sent through the targets and matchers configured in `notifications.cfg`.
+//! Since the private part of the notification config is only readable by `root`, notifications
+//! emitted by unprivileged processes are queued in a spool directory and sent out by the
+//! [`notification_worker`] running in the privileged API daemon.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::Error;
+use serde::Serialize;
+use serde_json::json;
+
+use proxmox_apt_api_types::APTUpdateInfo;
+use proxmox_notify::{Notification, Severity};
+use proxmox_sys::fs::{create_path, CreateOptions};
+
+use pdm_api_types::alerts::{Alert, AlertState};
+use pdm_api_types::NativeUpid;
+
+use crate::remote_tasks::task_cache::TaskCacheItem;
+
+mod context;
+
+/// Directory where unprivileged processes queue their notifications.
+const SPOOL_DIR: &str = concat!(pdm_buildcfg::PDM_STATE_DIR_M!(), "/notifications");
+
+/// Interval at which the spool directory is checked for queued notifications.
+const SPOOL_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Initialize the notification system by setting the PDM specific context.
+pub fn init() {
+    proxmox_notify::context::set_context(&context::PDM_CONTEXT);
+}
+
+/// Create the directory which is used to queue notifications sent from an unprivileged process.
+pub fn create_spool_dir() -> Result<(), Error> {
+    let api_user = pdm_config::api_user()?;
+    let opts = CreateOptions::new().owner(api_user.uid).group(api_user.gid);
+
+    create_path(SPOOL_DIR, None, Some(opts))?;
+    Ok(())
+}
+
+async fn send_queued_notifications() -> Result<(), Error> {
+    let mut read_dir = tokio::fs::read_dir(SPOOL_DIR).await?;
+
+    let mut notifications = Vec::new();
+
+    while let Some(entry) = read_dir.next_entry().await? {
+        let path = entry.path();
+
+        if path.extension().is_some_and(|ext| ext == "json") {
+            let bytes = tokio::fs::read(&path).await?;
+            match serde_json::from_slice::<Notification>(&bytes) {
+                Ok(notification) => notifications.push(notification),
+                Err(err) => log::error!("could not parse queued notification {path:?}: {err}"),
+            }
+
+            // There is no retry mechanism, if sending fails the notification is lost. For retries
+            // we would need to keep track of which targets were already notified successfully.
+            tokio::fs::remove_file(path).await?;
+        }
+    }
+
+    if notifications.is_empty() {
+        return Ok(());
+    }
+
+    // Make sure that we send the oldest notification first
+    notifications.sort_unstable_by_key(|n| n.timestamp());
+
+    tokio::task::spawn_blocking(move || {
+        let config = pdm_config::notifications::config()?;
+        for notification in notifications {
+            if let Err(err) = proxmox_notify::api::common::send(&config, &notification) {
+                log::error!("failed to send notification: {err}");
+            }
+        }
+
+        Ok(())
+    })
+    .await?
+}
+
+/// Periodically send out notifications queued by unprivileged processes.
+///
+/// Must only be run in a privileged process.
+pub async fn notification_worker() {
+    loop {
+        let delay_target = Instant::now() + SPOOL_CHECK_INTERVAL;
+
+        if let Err(err) = send_queued_notifications().await {
+            log::error!("notification worker task error: {err}");
+        }
+
+        tokio::time::sleep_until(tokio::time::Instant::from_std(delay_target)).await;
+    }
+}
+
+/// Send a notification, or queue it in the spool directory if we're not running as `root`.
+///
+/// This function blocks, use `spawn_blocking` when calling it from an async context.
+fn send_notification(notification: Notification) -> Result<(), Error> {
+    if cfg!(test) {
+        return Ok(());
+    }
+
+    if nix::unistd::Uid::current().is_root() {
+        let config = pdm_config::notifications::config()?;
+        proxmox_notify::api::common::send(&config, &notification)?;
+    } else {
+        let data = serde_json::to_vec(&notification)?;
+        let path = Path::new(SPOOL_DIR).join(format!("{id}.json", id = notification.id()));
+
+        let api_user = pdm_config::api_user()?;
+        let opts = CreateOptions::new().owner(api_user.uid).group(api_user.gid);
+        proxmox_sys::fs::replace_file(path, &data, opts, true)?;
+        log::info!("queued notification (id={id})", id = notification.id());
+    }
+
+    Ok(())
+}
+
+/// Build the common metadata fields of a notification.
+fn metadata(ty: &str, remote: Option<&str>) -> HashMap<String, String> {
+    let mut metadata = HashMap::from([
+        ("hostname".into(), proxmox_sys::nodename().into()),
+        ("type".into(), ty.into()),
+    ]);
+
+    if let Some(remote) = remote {
+        metadata.insert("remote".into(), remote.into());
+    }
+
+    metadata
+}
+
+/// Send a notification for a task on a remote which finished with an error.
+pub fn send_remote_task_failed(task: &TaskCacheItem) -> Result<(), Error> {
+    let remote = task.upid.remote();
+
+    let (node, worker_type, worker_id) = match task.upid.native_upid()? {
+        NativeUpid::PveUpid(upid) => (upid.node, upid.worker_type, upid.worker_id),
+        NativeUpid::PbsUpid(upid) => (upid.node, upid.worker_type, upid.worker_id),
+    };
+
+    let data = json!({
+        "remote": remote,
+        "remote-type": task.upid.remote_type(),
+        "node": node,
+        "upid": task.upid.to_string(),
+        "worker-type": worker_type,
+        "worker-id": worker_id,
+        "status": task.status,
+        "starttime": task.starttime,
+        "endtime": task.endtime,
+    });
+
+    let mut metadata = metadata("remote-task-failed", Some(remote));
+    metadata.insert("remote-type".into(), task.upid.remote_type().to_string());
+    metadata.insert("worker-type".into(), worker_type);
+
+    let notification =
+        Notification::from_template(Severity::Error, "remote-task-failed", data, metadata);
+
+    send_notification(notification)
+}
+
+/// Send a notification for a remote which failed metric collection.
+pub fn send_metric_collection_failed(remote: &str, error: &str) -> Result<(), Error> {
+    let data = json!({
+        "remote": remote,
+        "error": error,
+    });
+
+    let notification = Notification::from_template(
+        Severity::Warning,
+        "metric-collection-failed",
+        data,
+        metadata("metric-collection-failed", Some(remote)),
+    );
+
+    send_notification(notification)
+}
+
+/// Send a notification for a remote whose API token expires soon or has expired already.
+pub fn send_token_expiring(remote: &str, expire: i64) -> Result<(), Error> {
+    let data = json!({
+        "remote": remote,
+        "expire": proxmox_time::epoch_to_rfc2822(expire)?,
+        "expired": expire <= proxmox_time::epoch_i64(),
+    });
+
+    let notification = Notification::from_template(
+        Severity::Warning,
+        "token-expiring",
+        data,
+        metadata("token-expiring", Some(remote)),
+    );
+
+    send_notification(notification)
+}
+
+/// Send a notification about pending package updates on the Proxmox Datacenter Manager host.
+pub fn send_updates_available(updates: &[&APTUpdateInfo]) -> Result<(), Error> {
+    let hostname = proxmox_sys::nodename().to_string();
+
+    let data = json!({
+        "hostname": hostname,
+        "updates": updates,
+    });
+
+    let notification = Notification::from_template(
+        Severity::Info,
+        "package-updates",
+        data,
+        metadata("package-updates", None),
+    );
+
+    send_notification(notification)
+}
+
+/// Nodes of a remote which have new pending package updates.
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RemoteNodeUpdates {
+    /// The node's name.
+    pub node: String,
+    /// The number of available updates on this node.
+    pub number_of_updates: u32,
+}
+
+/// Send a notification about new pending package updates on the nodes of a remote.
+pub fn send_remote_updates_available(
+    remote: &str,
+    nodes: &[RemoteNodeUpdates],
+) -> Result<(), Error> {
+    let data = json!({
+        "remote": remote,
+        "nodes": nodes,
+    });
+
+    let notification = Notification::from_template(
+        Severity::Info,
+        "remote-package-updates",
+        data,
+        metadata("remote-package-updates", Some(remote)),
+    );
+
+    send_notification(notification)
+}
+
+/// A node of a remote whose subscription expires soon or has expired already.
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExpiringSubscription {
+    /// The node's name.
+    pub node: String,
+    /// The subscription key.
+    pub key: Option<String>,
+    /// The next due date of the subscription, if known.
+    pub next_due_date: Option<String>,
+    /// Whether the subscription has already expired.
+    pub expired: bool,
+}
+
+/// Send a notification about expiring subscriptions on the nodes of a remote.
+pub fn send_subscriptions_expiring(
+    remote: &str,
+    nodes: &[ExpiringSubscription],
+) -> Result<(), Error> {
+    let severity = if nodes.iter().any(|node| node.expired) {
+        Severity::Error
+    } else {
+        Severity::Warning
+    };
+
+    let data = json!({
+        "remote": remote,
+        "nodes": nodes,
+    });
+
+    let notification = Notification::from_template(
+        severity,
+        "subscription-expiring",
+        data,
+        metadata("subscription-expiring", Some(remote)),
+    );
+
+    send_notification(notification)
+}
+
+/// Send a notification for an alert which started firing or was resolved.
+pub fn send_alert(alert: &Alert) -> Result<(), Error> {
+    let (severity, resolved) = match alert.state {
+        AlertState::Firing => (Severity::Warning, false),
+        AlertState::Resolved => (Severity::Info, true),
+    };
+
+    let data = json!({
+        "rule": alert.rule,
+        "remote": alert.remote,
+        "resource-id": alert.resource_id,
+        "resource-name": alert.resource_name,
+        "resource-type": alert.resource_type,
+        "metric": alert.metric,
+        "value": format!("{:.1}", alert.value),
+        "threshold": format!("{:.1}", alert.threshold),
+        "since": alert.since,
+        "resolved": resolved,
+    });
+
+    let mut metadata = metadata("alert", Some(&alert.remote));
+    metadata.insert("alert-rule".into(), alert.rule.clone());
+
+    let notification = Notification::from_template(severity, "alert", data, metadata);
+
+    send_notification(notification)
+}
+
+/// Send the system report generated by a scheduled job.
+pub fn send_system_report(job: &str, report: &str) -> Result<(), Error> {
+    let data = json!({
+        "hostname": proxmox_sys::nodename(),
+        "job": job,
+        "report": report,
+    });
+
+    let mut metadata = metadata("system-report", None);
+    metadata.insert("job-id".into(), job.into());
+
+    let notification = Notification::from_template(Severity::Info, "system-report", data, metadata);
+
+    send_notification(notification)
+}
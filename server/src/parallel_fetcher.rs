@@ -1,7 +1,7 @@
 //! Helpers that can be used to parallelize API requests to remotes.
 //!
 //! ```no_run
-//! # use anyhow::Error;
+//! # use anyhow::{format_err, Error};
 //! #
 //! # use pdm_api_types::remotes::{RemoteType, Remote};
 //! # use server::parallel_fetcher::ParallelFetcher;
@@ -61,6 +61,7 @@
 //! # }
 //! ```
 
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::future::Future;
 use std::sync::Arc;
@@ -251,7 +252,8 @@ impl<C> ParallelFetcherBuilder<C> {
 
     /// Set the maximum number of parallel connections per remote.
     ///
-    /// This only really affects PVE remotes with multiple cluster members.
+    /// This only really affects PVE remotes with multiple cluster members. Remotes with
+    /// `max-connections` configured use that limit instead.
     pub fn max_connections_per_remote(mut self, limit: usize) -> Self {
         self.max_connections_per_remote = Some(limit);
         self
@@ -288,6 +290,8 @@ impl<C: Clone + Send + 'static> ParallelFetcher<C> {
     }
 
     /// Invoke a function `func` for all nodes of a given list of remotes in parallel.
+    ///
+    /// Cluster nodes in maintenance mode are skipped.
     pub async fn do_for_all_remote_nodes<A, F, T, Ft>(
         self,
         remotes: A,
@@ -353,7 +357,9 @@ impl<C: Clone + Send + 'static> ParallelFetcher<C> {
         let mut node_responses = Vec::new();
 
         let mut permit = Some(Arc::clone(&semaphore).acquire_owned().await.unwrap());
-        let per_remote_semaphore = Arc::new(Semaphore::new(max_connections_per_remote));
+        let per_remote_semaphore = Arc::new(Semaphore::new(
+            remote.max_connections.unwrap_or(max_connections_per_remote),
+        ));
 
         match remote.ty {
             RemoteType::Pve => {
@@ -378,8 +384,18 @@ impl<C: Clone + Send + 'static> ParallelFetcher<C> {
                 };
 
                 let mut nodes_join_set = JoinSet::new();
+                let now = proxmox_time::epoch_i64();
 
                 for node in nodes {
+                    if remote.node_in_maintenance(&node.node, now) {
+                        log::debug!(
+                            "skipping node {} of remote {} - in maintenance",
+                            node.node,
+                            remote.id
+                        );
+                        continue;
+                    }
+
                     let permit = if let Some(permit) = permit.take() {
                         permit
                     } else {
@@ -471,6 +487,81 @@ impl<C: Clone + Send + 'static> ParallelFetcher<C> {
         }
     }
 
+    /// Invoke a function `func` for each `(remote, item)` pair in parallel.
+    ///
+    /// Calls for the same remote share the per-remote connection limit, like the nodes of a
+    /// remote in [`ParallelFetcher::do_for_all_remote_nodes`]. The results are returned in the
+    /// order of the passed items.
+    pub async fn do_for_all_remote_items<A, I, F, T, Ft>(
+        self,
+        items: A,
+        func: F,
+    ) -> Vec<Result<T, Error>>
+    where
+        A: Iterator<Item = (Remote, I)>,
+        I: Send + 'static,
+        F: Fn(C, Remote, I) -> Ft + Clone + Send + 'static,
+        Ft: Future<Output = Result<T, Error>> + Send + 'static,
+        T: Send + Debug + 'static,
+    {
+        let total_connections_semaphore = Arc::new(Semaphore::new(self.max_connections));
+        let mut per_remote_semaphores: HashMap<String, Arc<Semaphore>> = HashMap::new();
+
+        let mut item_join_set = JoinSet::new();
+        let mut count = 0;
+
+        for (index, (remote, item)) in items.enumerate() {
+            count += 1;
+
+            let total_connections_semaphore = Arc::clone(&total_connections_semaphore);
+            let per_remote_semaphore = Arc::clone(
+                per_remote_semaphores
+                    .entry(remote.id.clone())
+                    .or_insert_with(|| {
+                        Arc::new(Semaphore::new(
+                            remote
+                                .max_connections
+                                .unwrap_or(self.max_connections_per_remote),
+                        ))
+                    }),
+            );
+
+            let context = self.context.clone();
+            let func = func.clone();
+            let future = async move {
+                // Wait for the remote first, so that items of a busy remote do not hold on to
+                // permits other remotes could use.
+                let _per_remote_connections_permit =
+                    per_remote_semaphore.acquire_owned().await.unwrap();
+                let _permit = total_connections_semaphore.acquire_owned().await.unwrap();
+
+                (index, func(context, remote, item).await)
+            };
+
+            if let Some(log_context) = LogContext::current() {
+                item_join_set.spawn(log_context.scope(future));
+            } else {
+                item_join_set.spawn(future);
+            }
+        }
+
+        let mut results: Vec<Option<Result<T, Error>>> = (0..count).map(|_| None).collect();
+
+        while let Some(a) = item_join_set.join_next().await {
+            match a {
+                Ok((index, result)) => results[index] = Some(result),
+                Err(err) => {
+                    log::error!("join error when waiting for future: {err}")
+                }
+            }
+        }
+
+        results
+            .into_iter()
+            .map(|result| result.unwrap_or_else(|| Err(format_err!("task did not finish"))))
+            .collect()
+    }
+
     /// Invoke a function `func` for all passed remotes in parallel.
     pub async fn do_for_all_remotes<A, F, T, Ft>(
         self,
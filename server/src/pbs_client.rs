@@ -7,13 +7,14 @@
 use anyhow::bail; // don't import Error as default error in here
 use http_body_util::BodyExt;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 
 use proxmox_client::{ApiPathBuilder, ApiResponseData, Error, HttpApiClient};
 use proxmox_router::stream::JsonRecords;
 use proxmox_schema::api;
 use proxmox_section_config::typed::SectionConfigData;
 
-use pbs_api_types::{Authid, BasicRealmInfo, Tokenname, TokennameRef, Userid};
+use pbs_api_types::{Authid, BasicRealmInfo, TokennameRef, Userid};
 
 use pdm_api_types::remotes::{Remote, RemoteType};
 
@@ -120,6 +121,19 @@ pub struct DatstoreListNamespaces {
     pub max_depth: Option<usize>,
 }
 
+#[api]
+/// Parameters for verifying a datastore on demand.
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct VerifyDatastoreParams {
+    /// Do not verify backups that are already verified if their verification is not outdated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ignore_verified: Option<bool>,
+    /// Days after which a verification becomes outdated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outdated_after: Option<i64>,
+}
+
 #[api]
 /// Parameters for updating the APT database
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -240,35 +254,27 @@ impl PbsClient {
         }
     }
 
-    /// create an API-Token on the PBS remote and give the token admin ACL on everything.
-    pub async fn create_admin_token(
+    /// Create an API-Token on the PBS remote.
+    ///
+    /// NOTE: While PVE has configurable privilege separation between user and tokens, PBS
+    /// avoided that to make tokens safer by default, so the token has no privileges until ACLs
+    /// are given out explicitly with [`update_acl`](Self::update_acl).
+    pub async fn create_token(
         &self,
-        userid: Userid,
-        tokenid: Tokenname,
+        userid: &Userid,
+        tokenid: &TokennameRef,
         params: CreateToken,
     ) -> Result<CreateTokenResponse, Error> {
         let path = format!(
             "/api2/extjs/access/users/{userid}/token/{}",
             tokenid.as_str()
         );
-        let token = self.0.post(&path, &params).await?.expect_json()?.data;
-
-        // NOTE: While PVE has configurable privilege separation between user and tokens, PBS
-        // avoided that to make tokens safer by default, so we need to give out an ACL explicitly.
-        //
-        // for now always make the resulting token a full admin one, but we probably want to allow
-        // having some very coarse roles here, like admin and audit for when PDM is used mostly for
-        // monitoring.
-        let acl = UpdateAcl {
-            auth_id: (userid, Some(tokenid)).into(),
-            path: "/".to_string(),
-            role: pbs_api_types::Role::Admin,
-            propagate: true,
-        };
-
-        self.0.put("/api2/extjs/access/acl", &acl).await?;
+        Ok(self.0.post(&path, &params).await?.expect_json()?.data)
+    }
 
-        Ok(token)
+    /// Add or update an ACL entry on the PBS remote.
+    pub async fn update_acl(&self, acl: &UpdateAcl) -> Result<(), Error> {
+        self.0.put("/api2/extjs/access/acl", acl).await?.nodata()
     }
 
     /// Delete API token from the PBS remote.
@@ -314,6 +320,87 @@ impl PbsClient {
         Ok(self.0.get(path).await?.expect_json()?.data)
     }
 
+    /// Return the garbage collection job status of the datastores.
+    pub async fn list_gc_jobs(
+        &self,
+        datastore: Option<&str>,
+    ) -> Result<Vec<pbs_api_types::GarbageCollectionJobStatus>, Error> {
+        let path = ApiPathBuilder::new("/api2/extjs/admin/gc")
+            .maybe_arg("store", &datastore)
+            .build();
+        Ok(self.0.get(&path).await?.expect_json()?.data)
+    }
+
+    /// Start a garbage collection on a datastore.
+    pub async fn start_garbage_collection(
+        &self,
+        datastore: &str,
+    ) -> Result<pbs_api_types::UPID, Error> {
+        let path = format!("/api2/extjs/admin/datastore/{datastore}/gc");
+        Ok(self.0.post_without_body(&path).await?.expect_json()?.data)
+    }
+
+    /// Verify all backups of a datastore.
+    pub async fn start_verification(
+        &self,
+        datastore: &str,
+        params: VerifyDatastoreParams,
+    ) -> Result<pbs_api_types::UPID, Error> {
+        let path = format!("/api2/extjs/admin/datastore/{datastore}/verify");
+        Ok(self.0.post(&path, &params).await?.expect_json()?.data)
+    }
+
+    /// List the prune jobs, optionally only the ones of a datastore.
+    pub async fn list_prune_jobs(
+        &self,
+        datastore: Option<&str>,
+    ) -> Result<Vec<pbs_api_types::PruneJobStatus>, Error> {
+        let path = ApiPathBuilder::new("/api2/extjs/admin/prune")
+            .maybe_arg("store", &datastore)
+            .build();
+        Ok(self.0.get(&path).await?.expect_json()?.data)
+    }
+
+    /// Run a prune job now.
+    pub async fn run_prune_job(&self, id: &str) -> Result<pbs_api_types::UPID, Error> {
+        let path = format!("/api2/extjs/admin/prune/{id}/run");
+        Ok(self.0.post_without_body(&path).await?.expect_json()?.data)
+    }
+
+    /// List the verification jobs, optionally only the ones of a datastore.
+    pub async fn list_verification_jobs(
+        &self,
+        datastore: Option<&str>,
+    ) -> Result<Vec<pbs_api_types::VerificationJobStatus>, Error> {
+        let path = ApiPathBuilder::new("/api2/extjs/admin/verify")
+            .maybe_arg("store", &datastore)
+            .build();
+        Ok(self.0.get(&path).await?.expect_json()?.data)
+    }
+
+    /// Run a verification job now.
+    pub async fn run_verification_job(&self, id: &str) -> Result<pbs_api_types::UPID, Error> {
+        let path = format!("/api2/extjs/admin/verify/{id}/run");
+        Ok(self.0.post_without_body(&path).await?.expect_json()?.data)
+    }
+
+    /// List the sync jobs, optionally only the ones of a datastore.
+    pub async fn list_sync_jobs(
+        &self,
+        datastore: Option<&str>,
+    ) -> Result<Vec<pbs_api_types::SyncJobStatus>, Error> {
+        let path = ApiPathBuilder::new("/api2/extjs/admin/sync")
+            .maybe_arg("store", &datastore)
+            .build();
+        Ok(self.0.get(&path).await?.expect_json()?.data)
+    }
+
+    /// Run a sync job now.
+    pub async fn run_sync_job(&self, id: &str) -> Result<pbs_api_types::UPID, Error> {
+        let path = format!("/api2/extjs/admin/sync/{id}/run");
+        Ok(self.0.post_without_body(&path).await?.expect_json()?.data)
+    }
+
     /// Return backup server metrics.
     pub async fn metrics(
         &self,
@@ -338,6 +425,28 @@ impl PbsClient {
             .data)
     }
 
+    /// Set the subscription key of the PBS remote, the key is checked right away.
+    pub async fn set_subscription(&self, key: &str) -> Result<(), Error> {
+        self.0
+            .put(
+                "/api2/extjs/nodes/localhost/subscription",
+                &json!({ "key": key }),
+            )
+            .await?
+            .nodata()
+    }
+
+    /// Check the subscription key of the PBS remote against the shop server.
+    pub async fn check_subscription(&self) -> Result<(), Error> {
+        self.0
+            .post(
+                "/api2/extjs/nodes/localhost/subscription",
+                &json!({ "force": true }),
+            )
+            .await?
+            .nodata()
+    }
+
     /// Return a list of available system updates.
     pub async fn list_available_updates(&self) -> Result<Vec<pbs_api_types::APTUpdateInfo>, Error> {
         Ok(self
@@ -22,10 +22,11 @@ use std::sync::{Arc, Mutex};
 use anyhow::{Context as _, Error};
 use serde::{Deserialize, Serialize};
 
+use proxmox_acme_api::CertificateInfo;
 use proxmox_product_config::replace_config;
 use proxmox_product_config::{open_api_lockfile, ApiLockGuard};
 
-use pdm_api_types::remotes::RemoteType;
+use pdm_api_types::remotes::{Remote, RemoteType};
 use pdm_config::ConfigVersionCache;
 
 const CACHE_FILENAME: &str = concat!(
@@ -224,6 +225,17 @@ impl RemoteMappingCache {
         self.info_by_hostname(remote, hostname)
             .is_none_or(|info| info.reachable)
     }
+
+    /// Check if a host belongs to a node in maintenance mode at `now`.
+    pub fn host_in_maintenance(&self, remote: &Remote, hostname: &str, now: i64) -> bool {
+        if remote.in_maintenance(now) {
+            return true;
+        }
+
+        self.info_by_hostname(&remote.id, hostname)
+            .and_then(|info| info.node_name())
+            .is_some_and(|node_name| remote.node_in_maintenance(node_name, now))
+    }
 }
 
 /// An entry for a remote in a [`RemoteMappingCache`].
@@ -237,6 +249,10 @@ pub struct RemoteMapping {
 
     /// Maps a node name to a hostname, for where we have that info.
     pub node_to_host: HashMap<String, String>,
+
+    /// The members of the cluster, as last reported by a node of the quorate cluster.
+    #[serde(default)]
+    pub cluster_members: Option<Vec<ClusterMember>>,
 }
 
 impl RemoteMapping {
@@ -245,6 +261,7 @@ impl RemoteMapping {
             ty,
             hosts: HashMap::new(),
             node_to_host: HashMap::new(),
+            cluster_members: None,
         }
     }
 
@@ -274,6 +291,10 @@ pub struct HostInfo {
     /// This means we were able to reach the node.
     /// When a client fails to connect it may update this to mark it as unreachable.
     pub reachable: bool,
+
+    /// A certificate presented by the host which does not match the pinned fingerprint.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending_certificate: Option<CertificateChange>,
 }
 
 impl HostInfo {
@@ -282,6 +303,7 @@ impl HostInfo {
             hostname,
             node_name: None,
             reachable: true,
+            pending_certificate: None,
         }
     }
 
@@ -289,3 +311,29 @@ impl HostInfo {
         self.node_name.as_deref()
     }
 }
+
+/// A member of a cluster found in [`RemoteMapping`].
+#[derive(Clone, Deserialize, Serialize)]
+pub struct ClusterMember {
+    /// The cluster side node name.
+    pub name: String,
+
+    /// The address of the node in the cluster network, if known.
+    pub ip: Option<String>,
+}
+
+/// A changed certificate of a host found in [`HostInfo`], waiting to be trusted.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct CertificateChange {
+    /// The newly presented certificate.
+    pub certificate: CertificateInfo,
+
+    /// The fingerprint which was pinned when the certificate was first seen.
+    pub pinned_fingerprint: Option<String>,
+
+    /// When the certificate was first seen.
+    pub first_seen: i64,
+
+    /// The certificate chains to a CA trusted for remotes.
+    pub trusted_by_ca: bool,
+}
@@ -0,0 +1,430 @@
+//! Detection of changed node certificates and re-trusting them.
+//!
+//! Nodes of remotes are usually pinned to the fingerprint of their certificate at the time they
+//! were added. When a node renews its certificate, connections fail with a fingerprint mismatch.
+//! The newly presented certificate is then recorded and offered to be trusted, which replaces the
+//! pinned fingerprint.
+//!
+//! Recording happens in the TLS verify callback, so changes are only remembered in memory there.
+//! The remote node mapping task persists them in the [`RemoteMappingCache`].
+//!
+//! Certificates chaining to one of the CAs in [`REMOTE_CA_FILENAME`] and matching the address of
+//! the node are accepted right away, the remote node mapping task pins their fingerprint later on.
+
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use anyhow::{bail, format_err, Error};
+use http::uri::Authority;
+use openssl::hash::MessageDigest;
+use openssl::stack::{Stack, StackRef};
+use openssl::x509::store::X509StoreBuilder;
+use openssl::x509::verify::X509VerifyParam;
+use openssl::x509::{X509Ref, X509StoreContext, X509StoreContextRef, X509};
+
+use pdm_buildcfg::configdir;
+use proxmox_acme_api::CertificateInfo;
+use proxmox_time::epoch_i64;
+
+use pdm_api_types::remotes::{PendingCertificate, Remote};
+
+use crate::remote_cache::{CertificateChange, HostInfo, RemoteMappingCache};
+
+/// PEM encoded CA certificates. Node certificates issued by one of them are trusted even if they
+/// do not match the pinned fingerprint.
+pub const REMOTE_CA_FILENAME: &str = configdir!("/remote-ca.pem");
+
+/// Certificate changes seen by the TLS verify callback, by remote and hostname.
+///
+/// An entry is dropped once the node presents its pinned certificate again.
+static RECORDED_CHANGES: Mutex<BTreeMap<(String, String), CertificateChange>> =
+    Mutex::new(BTreeMap::new());
+
+/// The CAs loaded from [`REMOTE_CA_FILENAME`], with the modification time of the file, if it
+/// exists.
+static REMOTE_CAS: Mutex<Option<(Option<SystemTime>, Arc<Vec<X509>>)>> = Mutex::new(None);
+
+/// Counts the certificates rejected by the TLS verify callback of a client.
+///
+/// This allows telling whether a failed request was caused by a changed certificate.
+#[derive(Clone, Default)]
+pub(crate) struct RejectedCertificates(Arc<AtomicUsize>);
+
+impl RejectedCertificates {
+    pub fn count(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn increment(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// The pinned certificate fingerprint of a node, used to verify its TLS connections.
+pub(crate) struct PinnedCertificate {
+    remote: String,
+    hostname: String,
+    /// The host part of `hostname`, which a CA trusted certificate has to be issued for.
+    host: String,
+    fingerprint: String,
+    expected: Vec<u8>,
+    /// The CAs from [`REMOTE_CA_FILENAME`], loaded up front to keep file I/O out of the callback.
+    remote_cas: Arc<Vec<X509>>,
+    rejected: RejectedCertificates,
+}
+
+impl PinnedCertificate {
+    pub fn new(
+        remote: &str,
+        hostname: &str,
+        fingerprint: &str,
+        rejected: RejectedCertificates,
+    ) -> Result<Self, Error> {
+        let expected = hex::decode(fingerprint.replace(':', ""))
+            .map_err(|err| format_err!("invalid fingerprint {fingerprint:?} - {err}"))?;
+
+        let host_port: Authority = hostname.parse()?;
+        let host = host_port
+            .host()
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string();
+
+        let remote_cas = remote_cas().unwrap_or_else(|err| {
+            log::error!("failed to load {REMOTE_CA_FILENAME} - {err:#}");
+            Arc::new(Vec::new())
+        });
+
+        Ok(Self {
+            remote: remote.to_string(),
+            hostname: hostname.to_string(),
+            host,
+            fingerprint: fingerprint.to_string(),
+            expected,
+            remote_cas,
+            rejected,
+        })
+    }
+
+    /// TLS verify callback.
+    ///
+    /// Like with [`TlsOptions::Fingerprint`](proxmox_client::TlsOptions::Fingerprint),
+    /// certificates trusted by the system are always accepted. A certificate which does not match
+    /// the pinned fingerprint is recorded and only accepted if it chains to a CA from
+    /// [`REMOTE_CA_FILENAME`] and is valid for the address of the node.
+    pub fn verify(&self, valid: bool, chain: &mut X509StoreContextRef) -> bool {
+        if valid {
+            return true;
+        }
+
+        // the leaf certificate decides, no matter at which depth the verification failed
+        let Some(leaf) = chain
+            .chain()
+            .and_then(|certs| certs.get(0))
+            .or_else(|| chain.current_cert())
+        else {
+            log::error!("no certificate in chain?");
+            return false;
+        };
+
+        let fingerprint = match leaf.digest(MessageDigest::sha256()) {
+            Ok(fingerprint) => fingerprint,
+            Err(err) => {
+                log::error!("failed to calculate certificate fingerprint - {err}");
+                return false;
+            }
+        };
+
+        if fingerprint.as_ref() == self.expected.as_slice() {
+            self.forget_change();
+            return true;
+        }
+
+        let trusted_by_ca = match self.chains_to_remote_ca(leaf, chain.chain()) {
+            Ok(trusted) => trusted,
+            Err(err) => {
+                log::error!("failed to verify certificate with {REMOTE_CA_FILENAME} - {err:#}");
+                false
+            }
+        };
+
+        if let Err(err) = self.record_change(leaf, trusted_by_ca) {
+            log::error!(
+                "failed to record changed certificate of {:?} (remote {:?}) - {err:#}",
+                self.hostname,
+                self.remote,
+            );
+        }
+
+        if !trusted_by_ca {
+            self.rejected.increment();
+        }
+
+        trusted_by_ca
+    }
+
+    /// Remember a certificate not matching the pinned fingerprint.
+    ///
+    /// This only touches memory, [`persist_recorded_changes`] writes the change to the remote
+    /// mapping cache.
+    fn record_change(&self, cert: &X509Ref, trusted_by_ca: bool) -> Result<(), Error> {
+        let certificate = CertificateInfo::from_pem("", &cert.to_pem()?)?;
+
+        let mut recorded = RECORDED_CHANGES.lock().unwrap();
+        let key = (self.remote.clone(), self.hostname.clone());
+
+        // the callback runs on every connection attempt, only log new changes
+        let known = recorded.get(&key).is_some_and(|change| {
+            change.certificate.fingerprint == certificate.fingerprint
+                && change.pinned_fingerprint.as_deref() == Some(self.fingerprint.as_str())
+        });
+        if known {
+            return Ok(());
+        }
+
+        log::warn!(
+            "certificate fingerprint mismatch for {:?} (remote {:?}) - pinned {}, presented {}",
+            self.hostname,
+            self.remote,
+            self.fingerprint,
+            certificate.fingerprint.as_deref().unwrap_or("unknown"),
+        );
+
+        recorded.insert(
+            key,
+            CertificateChange {
+                certificate,
+                pinned_fingerprint: Some(self.fingerprint.clone()),
+                first_seen: epoch_i64(),
+                trusted_by_ca,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// The node presented its pinned certificate, drop a change recorded earlier.
+    fn forget_change(&self) {
+        let mut recorded = RECORDED_CHANGES.lock().unwrap();
+        if !recorded.is_empty() {
+            recorded.remove(&(self.remote.clone(), self.hostname.clone()));
+        }
+    }
+
+    /// Verify a certificate against the CAs in [`REMOTE_CA_FILENAME`], if there are any.
+    ///
+    /// The certificate also has to be issued for the host the node is configured with, otherwise
+    /// any certificate of the CA would be accepted for any node.
+    fn chains_to_remote_ca(
+        &self,
+        cert: &X509Ref,
+        chain: Option<&StackRef<X509>>,
+    ) -> Result<bool, Error> {
+        if self.remote_cas.is_empty() {
+            return Ok(false);
+        }
+
+        let mut store = X509StoreBuilder::new()?;
+        for ca in self.remote_cas.iter() {
+            store.add_cert(ca.clone())?;
+        }
+
+        let mut param = X509VerifyParam::new()?;
+        match self.host.parse::<IpAddr>() {
+            Ok(ip) => param.set_ip(ip)?,
+            Err(_) => param.set_host(&self.host)?,
+        }
+        store.set_param(&param)?;
+        let store = store.build();
+
+        let mut intermediates = Stack::new()?;
+        for intermediate in chain.into_iter().flat_map(|certs| certs.iter().skip(1)) {
+            intermediates.push(intermediate.to_owned())?;
+        }
+
+        let mut context = X509StoreContext::new()?;
+        Ok(context.init(&store, cert, &intermediates, |context| {
+            context.verify_cert()
+        })?)
+    }
+}
+
+/// Get the CAs from [`REMOTE_CA_FILENAME`], if it exists.
+///
+/// The file is only parsed again if its modification time changed.
+fn remote_cas() -> Result<Arc<Vec<X509>>, Error> {
+    let mtime = match std::fs::metadata(REMOTE_CA_FILENAME) {
+        Ok(metadata) => Some(metadata.modified()?),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut cached = REMOTE_CAS.lock().unwrap();
+    if let Some((cached_mtime, cas)) = &*cached {
+        if *cached_mtime == mtime {
+            return Ok(Arc::clone(cas));
+        }
+    }
+
+    let cas = match proxmox_sys::fs::file_read_optional_string(REMOTE_CA_FILENAME)? {
+        Some(pem) => X509::stack_from_pem(pem.as_bytes())?,
+        None => Vec::new(),
+    };
+    let cas = Arc::new(cas);
+    *cached = Some((mtime, Arc::clone(&cas)));
+
+    Ok(cas)
+}
+
+/// Write the certificate changes recorded for the nodes of a remote to the remote mapping cache.
+///
+/// Changes recorded against a fingerprint which is no longer pinned are dropped.
+pub fn persist_recorded_changes(remote: &Remote) -> Result<(), Error> {
+    let changes: Vec<(String, CertificateChange)> = {
+        let mut recorded = RECORDED_CHANGES.lock().unwrap();
+        recorded.retain(|(remote_id, hostname), change| {
+            remote_id != &remote.id
+                || remote.nodes.iter().any(|node| {
+                    node.hostname == *hostname && node.fingerprint == change.pinned_fingerprint
+                })
+        });
+        recorded
+            .iter()
+            .filter(|((remote_id, _), _)| remote_id == &remote.id)
+            .map(|((_, hostname), change)| (hostname.clone(), change.clone()))
+            .collect()
+    };
+
+    let cache = RemoteMappingCache::get();
+    let changes: Vec<_> = changes
+        .into_iter()
+        .filter(|(hostname, change)| {
+            let current = cache
+                .info_by_hostname(&remote.id, hostname)
+                .and_then(|info| info.pending_certificate.as_ref());
+            !current.is_some_and(|current| {
+                current.certificate.fingerprint == change.certificate.fingerprint
+                    && current.pinned_fingerprint == change.pinned_fingerprint
+                    && current.trusted_by_ca == change.trusted_by_ca
+            })
+        })
+        .collect();
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    let mut cache = RemoteMappingCache::write()?;
+    let Some(entry) = cache.remotes.get_mut(&remote.id) else {
+        return Ok(());
+    };
+
+    for (hostname, change) in changes {
+        let info = entry
+            .hosts
+            .entry(hostname.clone())
+            .or_insert_with(|| HostInfo::new(hostname));
+        info.pending_certificate = Some(change);
+    }
+
+    cache.save()
+}
+
+/// List the certificates presented by the nodes of a remote which do not match their pinned
+/// fingerprint.
+pub fn pending_certificates(remote: &Remote) -> Vec<PendingCertificate> {
+    let cache = RemoteMappingCache::get();
+    let Some(mapping) = cache.remotes.get(&remote.id) else {
+        return Vec::new();
+    };
+
+    remote
+        .nodes
+        .iter()
+        .filter_map(|node| {
+            let info = mapping.hosts.get(&node.hostname)?;
+            let change = info.pending_certificate.as_ref()?;
+            Some(PendingCertificate {
+                hostname: node.hostname.clone(),
+                node: info.node_name().map(str::to_string),
+                pinned_fingerprint: node.fingerprint.clone(),
+                certificate: change.certificate.clone(),
+                first_seen: change.first_seen,
+                trusted_by_ca: change.trusted_by_ca,
+            })
+        })
+        .collect()
+}
+
+/// Trust the pending certificate of a node by pinning its fingerprint.
+///
+/// The `fingerprint` has to match the pending certificate, so only the certificate the caller
+/// actually looked at gets trusted.
+pub fn accept_certificate(remote_id: &str, hostname: &str, fingerprint: &str) -> Result<(), Error> {
+    let _lock = pdm_config::remotes::lock_config()?;
+    let (mut remotes, _) = pdm_config::remotes::config()?;
+
+    let remote = remotes
+        .get_mut(remote_id)
+        .ok_or_else(|| format_err!("no such remote {remote_id:?}"))?;
+
+    let change = RemoteMappingCache::get()
+        .info_by_hostname(remote_id, hostname)
+        .and_then(|info| info.pending_certificate.clone())
+        .ok_or_else(|| format_err!("no pending certificate for {hostname:?}"))?;
+
+    let new_fingerprint = match change.certificate.fingerprint {
+        Some(pending) if pending.eq_ignore_ascii_case(fingerprint) => pending,
+        _ => bail!("fingerprint does not match the pending certificate of {hostname:?}"),
+    };
+
+    let node = remote
+        .nodes
+        .iter_mut()
+        .find(|node| node.hostname == hostname)
+        .ok_or_else(|| format_err!("{hostname:?} not configured for remote {remote_id:?}"))?;
+    node.fingerprint = Some(new_fingerprint);
+
+    pdm_config::remotes::save_config(remotes)?;
+
+    RECORDED_CHANGES
+        .lock()
+        .unwrap()
+        .remove(&(remote_id.to_string(), hostname.to_string()));
+
+    let mut cache = RemoteMappingCache::write()?;
+    if let Some(info) = cache.info_by_hostname_mut(remote_id, hostname) {
+        info.pending_certificate = None;
+        info.reachable = true;
+    }
+    cache.save()
+}
+
+/// Pin the fingerprints of pending certificates of a remote which chain to a CA from
+/// [`REMOTE_CA_FILENAME`].
+pub fn accept_ca_trusted_certificates(remote: &Remote) {
+    for pending in pending_certificates(remote) {
+        if !pending.trusted_by_ca {
+            continue;
+        }
+
+        let Some(fingerprint) = pending.certificate.fingerprint.as_deref() else {
+            continue;
+        };
+
+        match accept_certificate(&remote.id, &pending.hostname, fingerprint) {
+            Ok(()) => log::info!(
+                "pinned new certificate {fingerprint} of {:?} (remote {:?}), issued by a trusted CA",
+                pending.hostname,
+                remote.id,
+            ),
+            Err(err) => log::error!(
+                "failed to pin new certificate of {:?} (remote {:?}) - {err:#}",
+                pending.hostname,
+                remote.id,
+            ),
+        }
+    }
+}
@@ -1,20 +1,28 @@
 use std::path::Path;
 
 use anyhow::Error;
+use regex::Regex;
 
-use pdm_api_types::{NativeUpid, RemoteUpid, TaskFilters, TaskListItem, TaskStateType};
+use pdm_api_types::{
+    NativeUpid, RemoteUpid, TaskFilters, TaskListItem, TaskLogMatch, TaskStateType,
+};
 use pve_api_types::PveUpid;
 
 pub mod refresh_task;
 pub mod task_cache;
+pub mod task_logs;
 
 use task_cache::{GetTasks, TaskCache, TaskCacheItem};
+use task_logs::TaskLogCache;
 
 use crate::views;
 
 /// Base directory for the remote task cache.
 pub const REMOTE_TASKS_DIR: &str = concat!(pdm_buildcfg::PDM_CACHE_DIR_M!(), "/remote-tasks");
 
+/// Directory for the cached logs of failed remote tasks.
+const REMOTE_TASK_LOGS_DIR: &str = concat!(pdm_buildcfg::PDM_CACHE_DIR_M!(), "/remote-tasks/logs");
+
 /// Maximum size at which the journal will applied early when adding new tasks.
 const JOURNAL_MAX_SIZE: u64 = 5 * 1024 * 1024;
 
@@ -32,7 +40,7 @@ const NUMBER_OF_UNCOMPRESSED_FILES: u32 = 2;
 pub async fn get_tasks(
     filters: TaskFilters,
     remote_filter: Option<String>,
-    check_privs: impl Fn(&str) -> bool + Send + 'static,
+    check_privs: impl Fn(&str, Option<u32>) -> bool + Send + 'static,
     view: Option<String>,
 ) -> Result<Vec<TaskListItem>, Error> {
     let view = views::get_optional_view(view.as_deref())?;
@@ -62,11 +70,14 @@ pub async fn get_tasks(
 
                 match task.upid.native_upid() {
                     Ok(NativeUpid::PveUpid(pve_upid)) => {
+                        // PVE uses the VMID as worker ID for guest tasks
+                        let vmid = pve_upid.worker_id.as_deref().and_then(|id| id.parse().ok());
+
                         if let Some(view) = &view {
                             if !view.is_node_included(task.upid.remote(), &pve_upid.node) {
                                 return None;
                             }
-                        } else if !check_privs(task.upid.remote()) {
+                        } else if !check_privs(task.upid.remote(), vmid) {
                             return None;
                         }
                         Some(TaskListItem {
@@ -87,7 +98,7 @@ pub async fn get_tasks(
                             if !view.is_node_included(task.upid.remote(), &pbs_upid.node) {
                                 return None;
                             }
-                        } else if !check_privs(task.upid.remote()) {
+                        } else if !check_privs(task.upid.remote(), None) {
                             return None;
                         }
                         Some(TaskListItem {
@@ -162,6 +173,68 @@ pub async fn get_tasks(
     .await?
 }
 
+/// Search the cached logs of all tasks matching the filters for lines matching `pattern`.
+///
+/// Only the logs of failed tasks are cached. `start` and `limit` of the filters apply to the
+/// returned lines, not to the tasks.
+pub async fn search_task_logs(
+    pattern: Regex,
+    filters: TaskFilters,
+    remote_filter: Option<String>,
+    check_privs: impl Fn(&str, Option<u32>) -> bool + Send + 'static,
+    view: Option<String>,
+) -> Result<Vec<TaskLogMatch>, Error> {
+    let start = filters.start as usize;
+    let limit = match filters.limit {
+        0 => usize::MAX,
+        limit => limit as usize,
+    };
+
+    let task_filters = TaskFilters {
+        start: 0,
+        limit: 0,
+        ..filters
+    };
+
+    let tasks = get_tasks(task_filters, remote_filter, check_privs, view).await?;
+
+    tokio::task::spawn_blocking(move || {
+        let log_cache = get_log_cache();
+        let mut matches = Vec::new();
+
+        for task in tasks {
+            let upid: RemoteUpid = match task.upid.parse() {
+                Ok(upid) => upid,
+                Err(err) => {
+                    log::error!("could not parse UPID: {err:#}");
+                    continue;
+                }
+            };
+
+            let lines = match log_cache.search(&upid, task.starttime, &pattern) {
+                Ok(lines) => lines,
+                Err(err) => {
+                    log::error!("could not search log of task '{upid}': {err:#}");
+                    continue;
+                }
+            };
+
+            matches.extend(lines.into_iter().map(|(line_number, line)| TaskLogMatch {
+                task: task.clone(),
+                line_number,
+                line,
+            }));
+
+            if matches.len() >= start.saturating_add(limit) {
+                break;
+            }
+        }
+
+        Ok(matches.into_iter().skip(start).take(limit).collect())
+    })
+    .await?
+}
+
 /// Insert a newly created PVE task into the list of tracked tasks.
 ///
 /// Any tracked task will be polled with a short interval until the task
@@ -232,3 +305,17 @@ pub fn get_cache() -> Result<TaskCache, Error> {
 
     Ok(cache)
 }
+
+/// Get a new [`TaskLogCache`] instance.
+///
+/// Logs are kept as long as the tasks themselves are kept in the task archive.
+pub fn get_log_cache() -> TaskLogCache {
+    let file_options = proxmox_product_config::default_create_options();
+
+    TaskLogCache::new(
+        Path::new(REMOTE_TASK_LOGS_DIR),
+        file_options,
+        ROTATE_AFTER,
+        KEEP_OLD_FILES,
+    )
+}
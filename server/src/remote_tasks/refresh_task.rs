@@ -6,15 +6,17 @@ use anyhow::Error;
 use tokio::{sync::Semaphore, task::JoinSet};
 
 use pdm_api_types::remotes::{Remote, RemoteType};
-use pdm_api_types::RemoteUpid;
+use pdm_api_types::{NativeUpid, RemoteUpid, TaskStateType};
 use proxmox_section_config::typed::SectionConfigData;
 
 use crate::api;
 use crate::connection;
+use crate::notifications;
 use crate::parallel_fetcher::ParallelFetcher;
 use crate::pbs_client;
 use crate::remote_tasks::{
     task_cache::{GetTasks, NodeFetchSuccessMap, State, TaskCache, TaskCacheItem},
+    task_logs::TaskLogCache,
     KEEP_OLD_FILES, ROTATE_AFTER,
 };
 
@@ -42,7 +44,7 @@ const CHECK_ROTATE_INTERVAL: Duration = Duration::from_secs(3600);
 /// lead to more writes, but should yield better performance.
 const APPLY_JOURNAL_INTERVAL: Duration = Duration::from_secs(3600);
 
-/// Maximum number of concurrent connections per remote.
+/// Maximum number of concurrent connections per remote, unless the remote sets `max-connections`.
 const CONNECTIONS_PER_PVE_REMOTE: usize = 5;
 
 /// Maximum number of total concurrent connections.
@@ -51,6 +53,12 @@ const MAX_CONNECTIONS: usize = 20;
 /// Maximum number of tasks to fetch from a single remote in one API call.
 const MAX_TASKS_TO_FETCH: u64 = 5000;
 
+/// Maximum number of concurrently fetched task logs, across all remotes.
+const MAX_CONCURRENT_LOG_FETCHES: usize = 5;
+
+/// Maximum number of lines stored for the log of a single task.
+const MAX_CACHED_LOG_LINES: u64 = 50000;
+
 /// (Ephemeral) Remote task fetching task state.
 pub struct TaskState {
     /// Time at which we last checked for archive rotation.
@@ -62,6 +70,8 @@ pub struct TaskState {
     /// Time at which we polled active tasks. This is done to ensure that
     /// active tasks are never stuck in the 'active' state
     last_active_poll: Instant,
+    /// Failed tasks for which a notification was already sent, mapped to their start time.
+    notified_failed_tasks: HashMap<RemoteUpid, i64>,
 }
 
 impl TaskState {
@@ -115,6 +125,7 @@ impl Default for TaskState {
             last_fetch: now - TASK_FETCH_INTERVAL,
             last_journal_apply: now - APPLY_JOURNAL_INTERVAL,
             last_active_poll: now - POLL_ACTIVE_INTERVAL,
+            notified_failed_tasks: HashMap::new(),
         }
     }
 }
@@ -178,7 +189,23 @@ pub async fn handle_timer_tick(task_state: &mut TaskState) -> Result<(), Error>
         get_remotes_with_finished_tasks(&remote_config, &poll_results)
     };
 
-    let (all_tasks, update_state_for_remote) = fetch_remotes(remotes, Arc::new(cache_state)).await;
+    let now = proxmox_time::epoch_i64();
+    let remotes: Vec<Remote> = remotes
+        .into_iter()
+        .filter(|remote| !remote.in_maintenance(now))
+        .collect();
+
+    let cache_state = Arc::new(cache_state);
+    let (all_tasks, update_state_for_remote) =
+        fetch_remotes(remotes, Arc::clone(&cache_state)).await;
+
+    notify_failed_tasks(
+        &cache_state,
+        &mut task_state.notified_failed_tasks,
+        &all_tasks,
+    )
+    .await;
+    cache_failed_task_logs(&get_all_remotes(&remote_config), &all_tasks).await;
 
     if !all_tasks.is_empty()
         || poll_results
@@ -196,7 +223,10 @@ pub async fn refresh_taskcache(remotes: Vec<Remote>) -> Result<(), Error> {
     let cache = super::get_cache()?;
     let cache_state = cache.read_state();
 
-    let (all_tasks, update_state_for_remote) = fetch_remotes(remotes, Arc::new(cache_state)).await;
+    let (all_tasks, update_state_for_remote) =
+        fetch_remotes(remotes.clone(), Arc::new(cache_state)).await;
+
+    cache_failed_task_logs(&remotes, &all_tasks).await;
 
     if !all_tasks.is_empty() {
         update_task_cache(cache, all_tasks, update_state_for_remote, HashMap::new()).await?;
@@ -330,6 +360,151 @@ async fn fetch_tasks_from_single_node(
     }
 }
 
+/// Send notifications for newly fetched tasks which finished with an error.
+///
+/// Only tasks which ended after the node's current cutoff timestamp are considered, so that the
+/// initial backfill of the task archive does not trigger a flood of notifications, while
+/// long-running tasks which started before the last fetch are still reported. Since tasks may be
+/// returned by more than one fetch, the UPIDs of notified tasks are remembered in `notified`
+/// until they drop out of the fetched time window.
+async fn notify_failed_tasks(
+    cache_state: &State,
+    notified: &mut HashMap<RemoteUpid, i64>,
+    tasks: &[TaskCacheItem],
+) {
+    let window_start = proxmox_time::epoch_i64() - (KEEP_OLD_FILES as u64 * ROTATE_AFTER) as i64;
+    notified.retain(|_, starttime| *starttime >= window_start);
+
+    let failed_tasks: Vec<TaskCacheItem> = tasks
+        .iter()
+        .filter(|task| is_new_failed_task(cache_state, notified, task))
+        .cloned()
+        .collect();
+
+    if failed_tasks.is_empty() {
+        return;
+    }
+
+    for task in &failed_tasks {
+        notified.insert(task.upid.clone(), task.starttime);
+    }
+
+    let res = tokio::task::spawn_blocking(move || {
+        for task in failed_tasks {
+            if let Err(err) = notifications::send_remote_task_failed(&task) {
+                log::error!(
+                    "could not send notification for failed task '{}': {err:#}",
+                    task.upid
+                );
+            }
+        }
+    })
+    .await;
+
+    if let Err(err) = res {
+        log::error!("could not send notifications for failed tasks: {err}");
+    }
+}
+
+/// Check whether a task finished with an error.
+fn is_failed_task(task: &TaskCacheItem) -> bool {
+    task.endtime.is_some()
+        && task
+            .status
+            .as_deref()
+            .is_some_and(|status| TaskStateType::new_from_str(status) == TaskStateType::Error)
+}
+
+/// Check whether a task finished with an error after the previous fetch and was not notified yet.
+fn is_new_failed_task(
+    cache_state: &State,
+    notified: &HashMap<RemoteUpid, i64>,
+    task: &TaskCacheItem,
+) -> bool {
+    if !is_failed_task(task) || notified.contains_key(&task.upid) {
+        return false;
+    }
+
+    let node = match task.upid.native_upid() {
+        Ok(NativeUpid::PveUpid(upid)) => upid.node,
+        Ok(NativeUpid::PbsUpid(upid)) => upid.node,
+        Err(_) => return false,
+    };
+
+    cache_state
+        .cutoff_timestamp(task.upid.remote(), &node)
+        .is_some_and(|cutoff| task.endtime.is_some_and(|endtime| endtime > cutoff))
+}
+
+/// Fetch the logs of failed tasks which are not cached yet and store them in the task log cache.
+async fn cache_failed_task_logs(remotes: &[Remote], tasks: &[TaskCacheItem]) {
+    let log_cache = super::get_log_cache();
+
+    let to_fetch: Vec<(Remote, TaskCacheItem)> = tasks
+        .iter()
+        .filter(|task| is_failed_task(task) && !log_cache.contains(&task.upid, task.starttime))
+        .filter_map(|task| {
+            let remote = remotes
+                .iter()
+                .find(|remote| remote.id == task.upid.remote())?;
+            Some((remote.clone(), task.clone()))
+        })
+        .collect();
+
+    let upids: Vec<RemoteUpid> = to_fetch.iter().map(|(_, task)| task.upid.clone()).collect();
+
+    let results = ParallelFetcher::builder(log_cache)
+        .max_connections(MAX_CONCURRENT_LOG_FETCHES)
+        .build()
+        .do_for_all_remote_items(to_fetch.into_iter(), cache_task_log)
+        .await;
+
+    for (upid, result) in upids.iter().zip(results) {
+        if let Err(err) = result {
+            log::error!("could not cache log of task '{upid}': {err:#}");
+        }
+    }
+}
+
+/// Fetch the log of a single task and store it in the task log cache.
+async fn cache_task_log(
+    log_cache: TaskLogCache,
+    remote: Remote,
+    task: TaskCacheItem,
+) -> Result<(), Error> {
+    let lines: Vec<String> = match remote.ty {
+        RemoteType::Pve => {
+            let node = task.upid.pve_upid()?.node;
+
+            connection::make_pve_client(&remote)?
+                .get_task_log(
+                    &node,
+                    task.upid.upid(),
+                    None,
+                    Some(MAX_CACHED_LOG_LINES),
+                    None,
+                )
+                .await?
+                .data
+                .into_iter()
+                .map(|line| line.t)
+                .collect()
+        }
+        RemoteType::Pbs => connection::make_pbs_client(&remote)?
+            .get_task_log(task.upid.upid(), None, Some(MAX_CACHED_LOG_LINES), None)
+            .await?
+            .data
+            .into_iter()
+            .map(|line| line.t)
+            .collect(),
+    };
+
+    let upid = task.upid.clone();
+    let starttime = task.starttime;
+
+    tokio::task::spawn_blocking(move || log_cache.store(&upid, starttime, &lines)).await?
+}
+
 /// Return all remotes from the given config.
 fn get_all_remotes(remote_config: &SectionConfigData<Remote>) -> Vec<Remote> {
     remote_config
@@ -363,8 +538,17 @@ fn get_remotes_with_finished_tasks(
 /// Rotate the task cache if necessary.
 ///
 /// Returns Ok(true) the cache's files were rotated.
+///
+/// Cached task logs which are older than the oldest archive file are removed as well.
 async fn rotate_cache(cache: TaskCache) -> Result<bool, Error> {
-    tokio::task::spawn_blocking(move || cache.write()?.rotate(proxmox_time::epoch_i64())).await?
+    tokio::task::spawn_blocking(move || {
+        let now = proxmox_time::epoch_i64();
+        let did_rotate = cache.write()?.rotate(now)?;
+        super::get_log_cache().prune(now)?;
+
+        Ok(did_rotate)
+    })
+    .await?
 }
 
 /// Apply the task cache journal.
@@ -406,6 +590,7 @@ async fn poll_tracked_tasks(
     total_connections_semaphore: Arc<Semaphore>,
 ) -> Result<HashMap<RemoteUpid, PollResult>, Error> {
     let mut join_set = JoinSet::new();
+    let now = proxmox_time::epoch_i64();
 
     for task in tracked_tasks.cloned() {
         let permit = Arc::clone(&total_connections_semaphore)
@@ -420,6 +605,8 @@ async fn poll_tracked_tasks(
             let _permit = permit;
 
             match remote {
+                // keep tracking the task until the maintenance is over
+                Some(remote) if remote.in_maintenance(now) => (task, PollResult::Running),
                 Some(remote) => poll_single_tracked_task(remote, task).await,
                 None => {
                     log::info!(
@@ -0,0 +1,217 @@
+//! Cache for the logs of failed remote tasks.
+//!
+//! Logs are stored zstd compressed, grouped into one directory per rotation period of the task
+//! archive, so that they expire after the same amount of time as the archived tasks themselves.
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Error};
+use regex::Regex;
+
+use proxmox_sys::fs::CreateOptions;
+
+use pdm_api_types::RemoteUpid;
+
+/// File name extension of cached task logs.
+const LOG_EXTENSION: &str = "log.zst";
+
+/// Cache for task logs.
+#[derive(Clone)]
+pub struct TaskLogCache {
+    /// Path where the cached logs should be placed.
+    base_path: PathBuf,
+    /// File permissions for the cache's files.
+    create_options: CreateOptions,
+    /// Length of a rotation period in seconds.
+    rotate_after: u64,
+    /// Number of rotation periods for which logs are kept.
+    keep_periods: u32,
+}
+
+impl TaskLogCache {
+    /// Create a new task log cache instance.
+    pub fn new<P: AsRef<Path>>(
+        path: P,
+        create_options: CreateOptions,
+        rotate_after: u64,
+        keep_periods: u32,
+    ) -> Self {
+        Self {
+            base_path: path.as_ref().into(),
+            create_options,
+            rotate_after,
+            keep_periods,
+        }
+    }
+
+    /// Start of the rotation period a task with the given starttime belongs to.
+    fn period_start(&self, starttime: i64) -> i64 {
+        starttime - starttime.rem_euclid(self.rotate_after.max(1) as i64)
+    }
+
+    fn log_path(&self, upid: &RemoteUpid, starttime: i64) -> PathBuf {
+        // UPIDs may contain characters which are not allowed in file names.
+        let filename = format!("{}.{LOG_EXTENSION}", hex::encode(upid.to_string()));
+
+        self.base_path
+            .join(self.period_start(starttime).to_string())
+            .join(filename)
+    }
+
+    /// Check whether the log of a task is already cached.
+    pub fn contains(&self, upid: &RemoteUpid, starttime: i64) -> bool {
+        self.log_path(upid, starttime).exists()
+    }
+
+    /// Compress and store the log lines of a task.
+    pub fn store(&self, upid: &RemoteUpid, starttime: i64, lines: &[String]) -> Result<(), Error> {
+        let path = self.log_path(upid, starttime);
+
+        if let Some(parent) = path.parent() {
+            proxmox_sys::fs::create_path(
+                parent,
+                Some(self.create_options),
+                Some(self.create_options),
+            )
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+
+        let mut data = lines.join("\n");
+        data.push('\n');
+
+        let compressed = zstd::encode_all(data.as_bytes(), zstd::DEFAULT_COMPRESSION_LEVEL)
+            .context("failed to compress task log")?;
+
+        proxmox_sys::fs::replace_file(&path, &compressed, self.create_options, false)
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// Return all lines of the cached log of a task which match the pattern, together with their
+    /// line number.
+    ///
+    /// If the log is not cached, no lines are returned.
+    pub fn search(
+        &self,
+        upid: &RemoteUpid,
+        starttime: i64,
+        pattern: &Regex,
+    ) -> Result<Vec<(u64, String)>, Error> {
+        let path = self.log_path(upid, starttime);
+
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let reader =
+            BufReader::new(zstd::stream::read::Decoder::new(file).with_context(|| {
+                format!("failed to create zstd decoder for {}", path.display())
+            })?);
+
+        let mut matches = Vec::new();
+
+        for (index, line) in reader.lines().enumerate() {
+            let line = line.with_context(|| format!("failed to read {}", path.display()))?;
+
+            if pattern.is_match(&line) {
+                matches.push((index as u64 + 1, line));
+            }
+        }
+
+        Ok(matches)
+    }
+
+    /// Remove the logs of all rotation periods which are older than the retention time.
+    ///
+    /// `now` is supposed to be a UNIX timestamp (seconds).
+    pub fn prune(&self, now: i64) -> Result<(), Error> {
+        let oldest_kept = self.period_start(now)
+            - (self.keep_periods.saturating_sub(1) as u64 * self.rotate_after) as i64;
+
+        let entries = match std::fs::read_dir(&self.base_path) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+
+        for entry in entries {
+            let path = entry?.path();
+
+            let Some(period) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.parse::<i64>().ok())
+            else {
+                continue;
+            };
+
+            if period < oldest_kept {
+                std::fs::remove_dir_all(&path)
+                    .with_context(|| format!("failed to remove {}", path.display()))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::temp::NamedTempDir;
+
+    use super::*;
+
+    fn upid(starttime: i64) -> RemoteUpid {
+        format!("pve-remote!UPID:pve:00039E4D:002638B8:{starttime:08X}:vzdump::root@pam:")
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn store_and_search() -> Result<(), Error> {
+        let tmp_dir = NamedTempDir::new()?;
+        let cache = TaskLogCache::new(tmp_dir.path(), CreateOptions::new(), 100, 2);
+
+        let lines = vec![
+            "INFO: starting new backup job".to_string(),
+            "ERROR: vzdump archive: write failed - No space left on device".to_string(),
+            "INFO: Failed at 2025-01-01 00:00:00".to_string(),
+        ];
+
+        cache.store(&upid(1050), 1050, &lines)?;
+        assert!(cache.contains(&upid(1050), 1050));
+        assert!(!cache.contains(&upid(1060), 1060));
+
+        let pattern = Regex::new("(?i)no space left")?;
+        assert_eq!(
+            cache.search(&upid(1050), 1050, &pattern)?,
+            vec![(2, lines[1].clone())],
+        );
+        assert!(cache.search(&upid(1060), 1060, &pattern)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn prune_old_periods() -> Result<(), Error> {
+        let tmp_dir = NamedTempDir::new()?;
+        let cache = TaskLogCache::new(tmp_dir.path(), CreateOptions::new(), 100, 2);
+
+        let lines = vec!["TASK ERROR: failed".to_string()];
+        cache.store(&upid(950), 950, &lines)?;
+        cache.store(&upid(1050), 1050, &lines)?;
+        cache.store(&upid(1150), 1150, &lines)?;
+
+        cache.prune(1199)?;
+
+        assert!(!cache.contains(&upid(950), 950));
+        assert!(cache.contains(&upid(1050), 1050));
+        assert!(cache.contains(&upid(1150), 1150));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,429 @@
+//! Creation and rotation of the API tokens used to access remotes.
+//!
+//! Tokens created by PDM are privilege separated and only get the ACLs PDM needs to manage the
+//! remote, which do not include managing users, tokens or ACLs. They expire after
+//! [`TOKEN_LIFETIME`], so they have to be rotated with the password of the token's user before
+//! that happens. The admin is notified about tokens which expire soon.
+
+use std::error::Error as _;
+
+use anyhow::{bail, format_err, Error};
+use serde_json::json;
+
+use proxmox_client::{Client, HttpApiClient};
+use proxmox_time::{epoch_i64, epoch_to_rfc2822};
+
+use pdm_api_types::remotes::{Remote, RemoteType};
+use pdm_api_types::Authid;
+
+use crate::{connection, notifications, pbs_client};
+
+/// Lifetime of the API tokens created by PDM.
+pub const TOKEN_LIFETIME: i64 = 90 * 24 * 3600;
+
+/// The admin is notified about tokens which expire within this time.
+pub const TOKEN_ROTATION_THRESHOLD: i64 = 14 * 24 * 3600;
+
+/// The role created on PVE remotes for the tokens of PDM, given on `/`.
+const PVE_ROLE: &str = "PDMRemote";
+
+/// The privileges of [`PVE_ROLE`].
+const PVE_ROLE_PRIVS: &[&str] = &[
+    "Sys.Audit",
+    "Sys.PowerMgmt",
+    "Sys.Syslog",
+    "VM.Audit",
+    "VM.PowerMgmt",
+    "VM.Migrate",
+    "VM.Allocate",
+    "VM.Clone",
+    "VM.Config.CDROM",
+    "VM.Config.CPU",
+    "VM.Config.Cloudinit",
+    "VM.Config.Disk",
+    "VM.Config.HWType",
+    "VM.Config.Memory",
+    "VM.Config.Network",
+    "VM.Config.Options",
+    "VM.Snapshot",
+    "VM.Snapshot.Rollback",
+    "VM.Backup",
+    "VM.Console",
+    "Datastore.Audit",
+    "Datastore.AllocateSpace",
+    "SDN.Audit",
+    "SDN.Allocate",
+    "SDN.Use",
+    "Pool.Audit",
+    "Mapping.Audit",
+    "Mapping.Use",
+];
+
+/// The role created on PVE remotes for the tokens of PDM, given on `/nodes`.
+const PVE_NODE_ROLE: &str = "PDMRemoteNode";
+
+/// The privileges of [`PVE_NODE_ROLE`].
+///
+/// Refreshing the package database requires `Sys.Modify` and the node shell `Sys.Console`, both are
+/// only given on the nodes instead of the whole cluster.
+const PVE_NODE_ROLE_PRIVS: &[&str] = &["Sys.Modify", "Sys.Console"];
+
+/// The roles and the paths they are given on to the tokens on PVE remotes.
+const PVE_TOKEN_ACLS: &[(&str, &str, &[&str])] = &[
+    ("/", PVE_ROLE, PVE_ROLE_PRIVS),
+    ("/nodes", PVE_NODE_ROLE, PVE_NODE_ROLE_PRIVS),
+];
+
+/// The ACLs given to the tokens on PBS remotes.
+///
+/// Refreshing the package database requires `Sys.Modify`, which only the `Admin` role has, so it
+/// is given on the one path which needs it.
+fn pbs_token_acls() -> [(&'static str, pbs_api_types::Role); 4] {
+    use pbs_api_types::Role;
+
+    [
+        ("/", Role::Audit),
+        ("/datastore", Role::DatastoreAdmin),
+        ("/remote", Role::RemoteSyncOperator),
+        ("/system/apt/updates", Role::Admin),
+    ]
+}
+
+/// A newly created API token.
+pub struct RemoteToken {
+    pub authid: Authid,
+    pub value: String,
+    pub created: i64,
+    pub expire: i64,
+}
+
+impl RemoteToken {
+    /// Use this token to access the remote.
+    pub fn apply_to(self, remote: &mut Remote) {
+        remote.authid = self.authid;
+        remote.token = self.value;
+        remote.token_created = Some(self.created);
+        remote.token_expire = Some(self.expire);
+    }
+}
+
+/// With the `Client`'s error type the message gets a bit long, shorten it:
+fn short_create_err(err: proxmox_client::Error) -> Error {
+    format_err!("error creating token: {}", err.source().unwrap_or(&err))
+}
+
+/// A client logged in with the credentials of a user on a remote.
+enum UserClient {
+    Pve(Box<Client>),
+    Pbs(Box<pbs_client::PbsClient>),
+}
+
+impl UserClient {
+    /// Log in with the remote's credentials, which may also be a user and password.
+    async fn login(remote: &Remote) -> Result<Self, Error> {
+        Ok(match remote.ty {
+            RemoteType::Pve => Self::Pve(connection::make_raw_client_and_login(remote).await?),
+            RemoteType::Pbs => Self::Pbs(connection::make_pbs_client_and_login(remote).await?),
+        })
+    }
+
+    async fn create_token(&self, remote: &Remote, tokenname: &str) -> Result<RemoteToken, Error> {
+        match self {
+            Self::Pve(client) => {
+                for (_path, role, privs) in PVE_TOKEN_ACLS {
+                    ensure_pve_role(client, role, privs).await?;
+                }
+                create_pve_token(client, remote, tokenname).await
+            }
+            Self::Pbs(client) => create_pbs_token(client, remote, tokenname).await,
+        }
+    }
+
+    async fn delete_token(&self, authid: &Authid) -> Result<(), Error> {
+        let Some(tokenname) = authid.tokenname() else {
+            bail!("{authid} is not an API token");
+        };
+        let user = authid.user();
+
+        match self {
+            Self::Pve(client) => client
+                .delete(&format!(
+                    "/api2/extjs/access/users/{user}/token/{}",
+                    tokenname.as_str()
+                ))
+                .await
+                .and_then(|response| response.nodata())
+                .map_err(Error::from),
+            Self::Pbs(client) => client
+                .delete_token(user, tokenname)
+                .await
+                .map_err(Error::from),
+        }
+    }
+}
+
+/// Create a new token named `tokenname` on the remote, authenticating with the remote's
+/// current credentials, which may also be a user and password.
+pub async fn create_token(remote: &Remote, tokenname: &str) -> Result<RemoteToken, Error> {
+    UserClient::login(remote)
+        .await?
+        .create_token(remote, tokenname)
+        .await
+}
+
+fn token_comment(now: i64) -> Result<String, Error> {
+    let nodename = proxmox_sys::nodename();
+    let date = epoch_to_rfc2822(now)?;
+    Ok(format!("auto-generated by PDM host '{nodename}' on {date}"))
+}
+
+/// Create or update a role for the tokens of PDM on a PVE remote.
+async fn ensure_pve_role(client: &Client, role: &str, privs: &[&str]) -> Result<(), Error> {
+    let privs = privs.join(",");
+
+    let updated = client
+        .put(
+            &format!("/api2/extjs/access/roles/{role}"),
+            &json!({ "privs": privs }),
+        )
+        .await
+        .and_then(|response| response.nodata());
+
+    if updated.is_err() {
+        client
+            .post(
+                "/api2/extjs/access/roles",
+                &json!({ "roleid": role, "privs": privs }),
+            )
+            .await
+            .and_then(|response| response.nodata())
+            .map_err(|err| format_err!("error creating role {role:?}: {err}"))?;
+    }
+
+    Ok(())
+}
+
+async fn create_pve_token(
+    client: &Client,
+    remote: &Remote,
+    tokenname: &str,
+) -> Result<RemoteToken, Error> {
+    let created = epoch_i64();
+    let expire = created + TOKEN_LIFETIME;
+    let user = remote.authid.user();
+
+    let token: pve_api_types::CreateTokenResponse = client
+        .post(
+            &format!("/api2/extjs/access/users/{user}/token/{tokenname}"),
+            &json!({
+                "comment": token_comment(created)?,
+                "expire": expire,
+                "privsep": 1,
+            }),
+        )
+        .await
+        .map_err(short_create_err)?
+        .expect_json()?
+        .data;
+
+    for (path, role, _privs) in PVE_TOKEN_ACLS {
+        client
+            .put(
+                "/api2/extjs/access/acl",
+                &json!({
+                    "path": path,
+                    "roles": role,
+                    "tokens": token.full_tokenid,
+                    "propagate": 1,
+                }),
+            )
+            .await
+            .and_then(|response| response.nodata())
+            .map_err(|err| format_err!("error setting ACL for token on {path:?}: {err}"))?;
+    }
+
+    Ok(RemoteToken {
+        authid: token.full_tokenid.parse()?,
+        value: token.value,
+        created,
+        expire,
+    })
+}
+
+async fn create_pbs_token(
+    client: &pbs_client::PbsClient,
+    remote: &Remote,
+    tokenname: &str,
+) -> Result<RemoteToken, Error> {
+    let created = epoch_i64();
+    let expire = created + TOKEN_LIFETIME;
+    let tokenname = pbs_api_types::Tokenname::try_from(tokenname.to_string())?;
+
+    let token = client
+        .create_token(
+            remote.authid.user(),
+            &tokenname,
+            pbs_client::CreateToken {
+                comment: Some(token_comment(created)?),
+                enable: Some(true),
+                expire: Some(expire),
+            },
+        )
+        .await
+        .map_err(short_create_err)?;
+
+    let authid: Authid = token.tokenid.parse()?;
+
+    for (path, role) in pbs_token_acls() {
+        let acl = pbs_client::UpdateAcl {
+            path: path.to_string(),
+            auth_id: authid.clone(),
+            role,
+            propagate: true,
+        };
+        client
+            .update_acl(&acl)
+            .await
+            .map_err(|err| format_err!("error setting ACL for token on {path:?}: {err}"))?;
+    }
+
+    Ok(RemoteToken {
+        authid,
+        value: token.value,
+        created,
+        expire,
+    })
+}
+
+/// Suffix format of rotated token names.
+const ROTATION_SUFFIX_FORMAT: &str = "%Y%m%d%H%M";
+const ROTATION_SUFFIX_LEN: usize = "-YYYYMMDDHHMM".len();
+
+/// Derive the name of a rotated token by replacing the timestamp suffix of the current name.
+fn rotated_token_name(name: &str, now: i64) -> Result<String, Error> {
+    let base = match name.len().checked_sub(ROTATION_SUFFIX_LEN) {
+        Some(pos)
+            if name.as_bytes()[pos] == b'-'
+                && name[pos + 1..].bytes().all(|b| b.is_ascii_digit()) =>
+        {
+            &name[..pos]
+        }
+        _ => name,
+    };
+
+    let suffix = proxmox_time::strftime_utc(ROTATION_SUFFIX_FORMAT, now)?;
+    Ok(format!("{base}-{suffix}"))
+}
+
+/// Replace the token of a remote with a new one and delete the old token on the remote.
+///
+/// The token cannot manage tokens itself, so the new one is created with the `password` of the
+/// token's user. Only remotes with a token created by PDM can be rotated.
+pub async fn rotate_token(remote_id: &str, password: String) -> Result<(), Error> {
+    let (remotes, _) = pdm_config::remotes::config()?;
+
+    let remote = remotes
+        .get(remote_id)
+        .ok_or_else(|| format_err!("no such remote {remote_id:?}"))?;
+
+    if remote.token_expire.is_none() {
+        bail!("the token of remote {remote_id:?} was not created by PDM, cannot rotate it");
+    }
+
+    let old_authid = remote.authid.clone();
+    let Some(old_tokenname) = old_authid.tokenname() else {
+        bail!("remote {remote_id:?} does not use an API token");
+    };
+
+    let tokenname = rotated_token_name(old_tokenname.as_str(), epoch_i64())?;
+
+    let mut login = remote.clone();
+    login.authid = old_authid.user().clone().into();
+    login.token = password;
+
+    let client = UserClient::login(&login).await?;
+    let token = client.create_token(&login, &tokenname).await?;
+    let new_authid = token.authid.clone();
+
+    // Only lock the config once the token exists, talking to the remote can take a while.
+    let updated = {
+        let _lock = pdm_config::remotes::lock_config()?;
+        let (mut remotes, _) = pdm_config::remotes::config()?;
+
+        match remotes.get_mut(remote_id) {
+            Some(remote) if remote.authid == old_authid => {
+                token.apply_to(remote);
+                pdm_config::remotes::save_config(remotes)?;
+                true
+            }
+            _ => false,
+        }
+    };
+
+    // Delete whichever token is not used anymore.
+    let unused = if updated { &old_authid } else { &new_authid };
+    if let Err(err) = client.delete_token(unused).await {
+        log::warn!("could not delete token {unused} on remote {remote_id:?}: {err}");
+    }
+
+    if !updated {
+        bail!("remote {remote_id:?} was changed while its token was rotated");
+    }
+
+    Ok(())
+}
+
+/// Notify the admin about tokens created by PDM which expire soon, so that they are rotated.
+pub async fn notify_expiring_tokens() -> Result<(), Error> {
+    let (remotes, _) = pdm_config::remotes::config()?;
+
+    let now = epoch_i64();
+    let notify_before = now + TOKEN_ROTATION_THRESHOLD;
+
+    for (remote_id, remote) in remotes {
+        let Some(expire) = remote.token_expire else {
+            continue;
+        };
+
+        if expire > notify_before {
+            continue;
+        }
+
+        if remote.in_maintenance(now) {
+            log::info!("not checking API token of remote '{remote_id}' - in maintenance");
+            continue;
+        }
+
+        log::info!("API token of remote '{remote_id}' expires soon");
+
+        if let Err(err) = notifications::send_token_expiring(&remote_id, expire) {
+            log::error!("could not send token expiry notification for '{remote_id}': {err}");
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rotated_token_name;
+
+    #[test]
+    fn rotated_token_names() {
+        // 2025-01-02 03:04:00 UTC
+        let now = 1735787040;
+
+        assert_eq!(
+            rotated_token_name("pdm-admin", now).unwrap(),
+            "pdm-admin-202501020304"
+        );
+        assert_eq!(
+            rotated_token_name("pdm-admin-202410010000", now).unwrap(),
+            "pdm-admin-202501020304"
+        );
+        assert_eq!(
+            rotated_token_name("pdm-2024", now).unwrap(),
+            "pdm-2024-202501020304"
+        );
+    }
+}
@@ -15,6 +15,7 @@ use pdm_api_types::RemoteUpid;
 use pdm_buildcfg::PDM_CACHE_DIR_M;
 
 use crate::connection;
+use crate::notifications::{self, RemoteNodeUpdates};
 use crate::parallel_fetcher::ParallelFetcher;
 
 pub const UPDATE_CACHE: &str = concat!(PDM_CACHE_DIR_M!(), "/remote-updates.json");
@@ -53,6 +54,22 @@ pub async fn list_available_updates(
     Ok(updates.updates)
 }
 
+/// Fetch the update summary of a remote node, including the status of its repositories.
+///
+/// The cached summary of the node is updated as well.
+pub async fn refresh_node_update_summary(
+    remote: &Remote,
+    node: &str,
+) -> Result<NodeUpdateSummary, Error> {
+    let summary: NodeUpdateSummary = fetch_available_updates((), remote.clone(), node.to_string())
+        .await?
+        .into();
+
+    update_cached_summary_for_node(remote.clone(), node.into(), summary.clone()).await?;
+
+    Ok(summary)
+}
+
 /// Trigger `apt update` on a remote node.
 ///
 /// The function returns a `[RemoteUpid]` for the started update task.
@@ -206,6 +223,13 @@ async fn update_cached_summary_for_node(
 
 /// Refresh the remote update cache.
 pub async fn refresh_update_summary_cache(remotes: Vec<Remote>) -> Result<(), Error> {
+    let now = proxmox_time::epoch_i64();
+
+    // remotes in maintenance are not queried and keep their last known state
+    let (in_maintenance, remotes): (Vec<Remote>, Vec<Remote>) = remotes
+        .into_iter()
+        .partition(|remote| remote.in_maintenance(now));
+
     let fetcher = ParallelFetcher::new(());
 
     let fetch_response = fetcher
@@ -215,9 +239,12 @@ pub async fn refresh_update_summary_cache(remotes: Vec<Remote>) -> Result<(), Er
     let mut content = get_cached_summary_or_default()?;
 
     // Clean out any remotes that might have been removed from the remote config in the meanwhile.
-    content
-        .remotes
-        .retain(|remote, _| fetch_response.iter().any(|r| r.remote() == remote));
+    content.remotes.retain(|remote, _| {
+        fetch_response.iter().any(|r| r.remote() == remote)
+            || in_maintenance.iter().any(|r| r.id == *remote)
+    });
+
+    let mut new_updates = Vec::new();
 
     for remote_response in fetch_response {
         let remote_name = remote_response.remote().to_string();
@@ -234,17 +261,36 @@ pub async fn refresh_update_summary_cache(remotes: Vec<Remote>) -> Result<(), Er
         match remote_response.nodes() {
             Ok(node_responses) => {
                 // Clean out any nodes that might have been removed from the cluster in the meanwhile.
-                entry
-                    .nodes
-                    .retain(|name, _| node_responses.iter().any(|n| n.node_name() == name));
+                // Nodes in maintenance were skipped and keep their last known state.
+                let remote = remotes.iter().find(|remote| remote.id == remote_name);
+                entry.nodes.retain(|name, _| {
+                    node_responses.iter().any(|n| n.node_name() == name)
+                        || remote.is_some_and(|remote| remote.node_in_maintenance(name, now))
+                });
 
                 entry.status = RemoteUpdateStatus::Success;
 
+                let mut nodes_with_new_updates = Vec::new();
+
                 for node_response in node_responses {
                     let node_name = node_response.node_name().to_string();
 
                     match node_response.data() {
                         Ok(update_info) => {
+                            let previous_updates = entry
+                                .nodes
+                                .get(&node_name)
+                                .map(|node| node.number_of_updates)
+                                .unwrap_or(0);
+                            let number_of_updates = update_info.updates.len() as u32;
+
+                            if number_of_updates > previous_updates {
+                                nodes_with_new_updates.push(RemoteNodeUpdates {
+                                    node: node_name.clone(),
+                                    number_of_updates,
+                                });
+                            }
+
                             entry.nodes.insert(node_name, update_info.clone().into());
                         }
                         Err(err) => {
@@ -266,6 +312,10 @@ pub async fn refresh_update_summary_cache(remotes: Vec<Remote>) -> Result<(), Er
                         }
                     }
                 }
+
+                if !nodes_with_new_updates.is_empty() {
+                    new_updates.push((remote_name, nodes_with_new_updates));
+                }
             }
             Err(err) => {
                 entry.status = RemoteUpdateStatus::Error;
@@ -278,6 +328,19 @@ pub async fn refresh_update_summary_cache(remotes: Vec<Remote>) -> Result<(), Er
     let options = proxmox_product_config::default_create_options();
     proxmox_sys::fs::replace_file(UPDATE_CACHE, &serde_json::to_vec(&content)?, options, true)?;
 
+    if !new_updates.is_empty() {
+        tokio::task::spawn_blocking(move || {
+            for (remote, nodes) in new_updates {
+                if let Err(err) = notifications::send_remote_updates_available(&remote, &nodes) {
+                    log::error!(
+                        "could not send update notification for remote '{remote}': {err:#}"
+                    );
+                }
+            }
+        })
+        .await?;
+    }
+
     Ok(())
 }
 
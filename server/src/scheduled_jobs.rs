@@ -0,0 +1,326 @@
+//! Jobs from `jobs.cfg` which the Datacenter Manager runs on a schedule.
+//!
+//! The API daemon checks once a minute which jobs are due and runs each of them in its own worker
+//! task. The state of the last run is kept by the [`jobstate`](crate::jobstate) module.
+
+use std::time::Duration;
+
+use anyhow::{bail, Context, Error};
+
+use proxmox_rest_server::WorkerTask;
+
+use pdm_api_types::jobs::{JobConfigEntry, JobType, ScheduledJob};
+use pdm_api_types::remotes::{Remote, RemoteType};
+use pdm_api_types::{Authid, RemoteUpid};
+
+use crate::connection;
+use crate::jobstate::{self, Job, JobState};
+use crate::parallel_fetcher::ParallelFetcher;
+
+/// Job type used for the state files and worker tasks of scheduled jobs.
+pub const SCHEDULED_JOB_TYPE: &str = "scheduled-job";
+
+/// Interval in which the status of started remote tasks is polled.
+const TASK_POLLING_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Start all enabled jobs which are due.
+pub async fn schedule_jobs() {
+    let config = match pdm_config::jobs::config() {
+        Ok((config, _digest)) => config,
+        Err(err) => {
+            log::error!("unable to read scheduled job config - {err:#}");
+            return;
+        }
+    };
+
+    let now = proxmox_time::epoch_i64();
+
+    for (_, JobConfigEntry::Job(job)) in config {
+        if !job.enabled() {
+            continue;
+        }
+
+        let state = match JobState::load(SCHEDULED_JOB_TYPE, &job.id) {
+            Ok(state) => state,
+            Err(err) => {
+                log::error!("could not load state of job '{}' - {err:#}", job.id);
+                continue;
+            }
+        };
+
+        if !is_due(&job, &state, now) {
+            continue;
+        }
+
+        let id = job.id.clone();
+        if let Err(err) = run_job(job, Authid::root_auth_id()) {
+            log::error!("unable to start scheduled job '{id}' - {err:#}");
+        }
+    }
+}
+
+/// Check whether the next run of the job, based on its last run, is not in the future.
+fn is_due(job: &ScheduledJob, state: &JobState, now: i64) -> bool {
+    if let JobState::Started { .. } = state {
+        return false;
+    }
+
+    match jobstate::compute_schedule_status(state, Some(&job.schedule)) {
+        Ok(status) => status.next_run.is_some_and(|next| next <= now),
+        Err(err) => {
+            log::error!("could not compute next run of job '{}' - {err:#}", job.id);
+            false
+        }
+    }
+}
+
+/// Run a job in a new worker task, returning the UPID of the task.
+///
+/// Fails if the job is currently running.
+pub fn run_job(job: ScheduledJob, auth_id: &Authid) -> Result<String, Error> {
+    let mut state = Job::new(SCHEDULED_JOB_TYPE, &job.id)
+        .with_context(|| format!("job '{}' is already running", job.id))?;
+
+    WorkerTask::spawn(
+        SCHEDULED_JOB_TYPE,
+        Some(job.id.clone()),
+        auth_id.to_string(),
+        false,
+        move |worker| async move {
+            state.start(&worker.upid().to_string())?;
+
+            log::info!("running {} job '{}'", job.job_type, job.id);
+
+            let result = match job.job_type {
+                JobType::AptRefresh => refresh_apt(&job).await,
+                JobType::GuestAction => guest_action(&job).await,
+                JobType::GarbageCollection => garbage_collection(&job).await,
+                JobType::Report => report(&job).await,
+            };
+
+            let status = worker.create_state(&result);
+
+            if let Err(err) = state.finish(status) {
+                log::error!("could not finish job state for job '{}' - {err:#}", job.id);
+            }
+
+            result
+        },
+    )
+}
+
+/// Return the remotes of the given type the job acts on.
+///
+/// Remotes in maintenance mode are skipped.
+fn job_remotes(job: &ScheduledJob, ty: Option<RemoteType>) -> Result<Vec<Remote>, Error> {
+    let (config, _digest) = pdm_config::remotes::config()?;
+    let now = proxmox_time::epoch_i64();
+
+    Ok(config
+        .into_iter()
+        .map(|(_, remote)| remote)
+        .filter(|remote| ty.is_none_or(|ty| remote.ty == ty) && job.includes_remote(&remote.id))
+        .filter(|remote| {
+            let in_maintenance = remote.in_maintenance(now);
+            if in_maintenance {
+                log::info!("{}: skipped, remote is in maintenance", remote.id);
+            }
+            !in_maintenance
+        })
+        .collect())
+}
+
+/// Refresh the package database on all nodes of the remotes, then update the cached summary.
+async fn refresh_apt(job: &ScheduledJob) -> Result<(), Error> {
+    let remotes = job_remotes(job, None)?;
+
+    let response = ParallelFetcher::new(())
+        .do_for_all_remote_nodes(remotes.clone().into_iter(), |(), remote, node| async move {
+            let upid = crate::remote_updates::update_apt_database(&remote, &node).await?;
+            wait_for_task(&remote, &upid).await
+        })
+        .await;
+
+    let mut failed = 0;
+
+    for remote_response in response {
+        let (remote, nodes) = remote_response.into_remote_and_nodes();
+
+        let nodes = match nodes {
+            Ok(nodes) => nodes,
+            Err(err) => {
+                log::error!("{remote}: could not refresh package database - {err:#}");
+                failed += 1;
+                continue;
+            }
+        };
+
+        for node in nodes {
+            match node.data() {
+                Ok(()) => log::info!("{remote}/{}: refreshed package database", node.node_name()),
+                Err(err) => {
+                    log::error!(
+                        "{remote}/{}: could not refresh package database - {err:#}",
+                        node.node_name()
+                    );
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    crate::remote_updates::refresh_update_summary_cache(remotes).await?;
+
+    if failed > 0 {
+        bail!("refreshing the package database failed on {failed} remotes or nodes");
+    }
+
+    Ok(())
+}
+
+async fn guest_action(job: &ScheduledJob) -> Result<(), Error> {
+    let (Some(action), Some(search)) = (job.action, job.search.clone()) else {
+        bail!("guest-action jobs need an action and a search");
+    };
+
+    crate::api::pve::bulk_action::run_scheduled_action(action, search, job.remotes.as_deref()).await
+}
+
+/// Run garbage collection on all datastores of the PBS remotes, one datastore after another.
+async fn garbage_collection(job: &ScheduledJob) -> Result<(), Error> {
+    let mut failed = 0;
+
+    for remote in job_remotes(job, Some(RemoteType::Pbs))? {
+        let datastores = match connection::make_pbs_client(&remote)?
+            .list_datastores()
+            .await
+        {
+            Ok(datastores) => datastores,
+            Err(err) => {
+                log::error!("{}: could not list datastores - {err:#}", remote.id);
+                failed += 1;
+                continue;
+            }
+        };
+
+        for datastore in datastores {
+            let label = format!("{}/{}", remote.id, datastore.name);
+
+            match datastore_gc(&remote, &datastore.name).await {
+                Ok(()) => log::info!("{label}: garbage collection finished"),
+                Err(err) => {
+                    log::error!("{label}: garbage collection failed - {err:#}");
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!("garbage collection failed for {failed} remotes or datastores");
+    }
+
+    Ok(())
+}
+
+async fn datastore_gc(remote: &Remote, datastore: &str) -> Result<(), Error> {
+    let upid = connection::make_pbs_client(remote)?
+        .start_garbage_collection(datastore)
+        .await?;
+    let upid = crate::api::pbs::new_remote_upid(remote.id.clone(), upid).await?;
+
+    log::info!("{}/{datastore}: started task {upid}", remote.id);
+
+    wait_for_task(remote, &upid).await
+}
+
+/// Generate a system report and send it as notification.
+async fn report(job: &ScheduledJob) -> Result<(), Error> {
+    let id = job.id.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let report = crate::report::generate_report();
+        crate::notifications::send_system_report(&id, &report)
+    })
+    .await??;
+
+    log::info!("sent system report");
+
+    Ok(())
+}
+
+/// Wait for a task on a remote to finish, fails if the task did not finish successfully.
+async fn wait_for_task(remote: &Remote, upid: &RemoteUpid) -> Result<(), Error> {
+    loop {
+        tokio::time::sleep(TASK_POLLING_INTERVAL).await;
+
+        let exitstatus = match remote.ty {
+            RemoteType::Pve => {
+                let node = upid.pve_upid()?.node;
+                let status = connection::make_pve_client(remote)?
+                    .get_task_status(&node, upid.upid())
+                    .await?;
+                if status.is_running() {
+                    continue;
+                }
+                status.exitstatus
+            }
+            RemoteType::Pbs => {
+                let status = connection::make_pbs_client(remote)?
+                    .get_task_status(upid.upid())
+                    .await?;
+                if status.is_running() {
+                    continue;
+                }
+                status.exitstatus
+            }
+        };
+
+        match exitstatus.as_deref() {
+            Some("OK") => return Ok(()),
+            Some(status) if status.starts_with("WARNINGS") => return Ok(()),
+            Some(status) => bail!("task {upid} failed - {status}"),
+            None => bail!("task {upid} finished without exit status"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(schedule: &str) -> ScheduledJob {
+        ScheduledJob {
+            id: "test-job".to_string(),
+            job_type: JobType::Report,
+            schedule: schedule.to_string(),
+            remotes: None,
+            search: None,
+            action: None,
+            comment: None,
+            disable: None,
+        }
+    }
+
+    #[test]
+    fn due_jobs() {
+        // 2024-01-01 00:30:00 UTC, minute-aligned in every time zone
+        let created = 1704069000;
+        let state = JobState::Created { time: created };
+
+        assert!(!is_due(&job("minutely"), &state, created + 30));
+        assert!(is_due(&job("minutely"), &state, created + 60));
+        assert!(!is_due(&job("daily"), &state, created + 60));
+        assert!(!is_due(
+            &job("not a schedule"),
+            &state,
+            created + 3600 * 24 * 365
+        ));
+
+        let started = JobState::Started {
+            upid: "UPID:pdm:00000001:00000001:00000001:65920778:scheduled-job:test-job:root@pam:"
+                .to_string(),
+        };
+        assert!(!is_due(&job("minutely"), &started, created + 3600));
+    }
+}
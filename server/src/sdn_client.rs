@@ -5,22 +5,29 @@ use std::time::Duration;
 use anyhow::{self, bail, Context};
 
 use futures::{future::join_all, stream::FuturesUnordered, StreamExt, TryFutureExt};
+use http::Method;
 use pdm_api_types::{remotes::Remote, RemoteUpid};
+use proxmox_client::{ApiPathBuilder, Client, HttpApiClient};
 use pve_api_types::{
     client::PveClient, CreateSdnLock, CreateVnet, CreateZone, PveUpid, ReleaseSdnLock, ReloadSdn,
     RollbackSdn,
 };
+use serde_json::Value;
 
 use crate::api::pve::{connect, get_remote};
+use crate::connection;
 
 /// Wrapper for [`PveClient`] for representing a locked SDN configuration.
 ///
 /// It stores the client that has been locked, as well as the lock_token that is required for
 /// making changes to the SDN configuration. It provides methods that proxy the respective SDN
 /// endpoints, where it adds the lock_token when making the proxied calls.
+///
+/// SDN endpoints which are not covered by [`PveClient`] are called via a raw client.
 pub struct LockedSdnClient {
     lock_token: String,
     client: Arc<dyn PveClient + Send + Sync>,
+    raw_client: Box<Client>,
 }
 
 #[derive(Debug)]
@@ -70,6 +77,7 @@ impl LockedSdnClient {
         allow_pending: impl Into<Option<bool>>,
     ) -> Result<Self, LockedSdnClientError> {
         let client = connect(remote)?;
+        let raw_client = connection::make_raw_client(remote)?;
 
         let params = CreateSdnLock {
             allow_pending: allow_pending.into(),
@@ -78,10 +86,38 @@ impl LockedSdnClient {
         client
             .acquire_sdn_lock(params)
             .await
-            .map(|lock_token| Self { lock_token, client })
+            .map(|lock_token| Self {
+                lock_token,
+                client,
+                raw_client,
+            })
             .map_err(LockedSdnClientError::from)
     }
 
+    /// Makes a request to an SDN endpoint of the remote, adding the lock_token to the parameters.
+    async fn raw_request(
+        &self,
+        method: Method,
+        path: &str,
+        mut params: Value,
+    ) -> Result<(), proxmox_client::Error> {
+        params["lock-token"] = self.lock_token.clone().into();
+
+        self.raw_client
+            .request(method, &format!("/api2/extjs{path}"), Some(params))
+            .await?
+            .nodata()
+    }
+
+    /// Deletes an SDN object on the remote, passing the lock_token as query parameter.
+    async fn raw_delete(&self, path: &str) -> Result<(), proxmox_client::Error> {
+        let path = ApiPathBuilder::new(format!("/api2/extjs{path}"))
+            .arg("lock-token", &self.lock_token)
+            .build();
+
+        self.raw_client.delete(&path).await?.nodata()
+    }
+
     /// proxies [`PveClient::create_vnet`] and adds lock_token to the passed parameters before
     /// making the call.
     pub async fn create_vnet(&self, mut params: CreateVnet) -> Result<(), proxmox_client::Error> {
@@ -98,6 +134,67 @@ impl LockedSdnClient {
         self.client.create_zone(params).await
     }
 
+    /// updates the zone with the given PVE parameters.
+    pub async fn update_zone(
+        &self,
+        zone: &str,
+        params: Value,
+    ) -> Result<(), proxmox_client::Error> {
+        self.raw_request(Method::PUT, &format!("/cluster/sdn/zones/{zone}"), params)
+            .await
+    }
+
+    /// deletes the zone, it must not contain any vnets.
+    pub async fn delete_zone(&self, zone: &str) -> Result<(), proxmox_client::Error> {
+        self.raw_delete(&format!("/cluster/sdn/zones/{zone}")).await
+    }
+
+    /// updates the vnet with the given PVE parameters.
+    pub async fn update_vnet(
+        &self,
+        vnet: &str,
+        params: Value,
+    ) -> Result<(), proxmox_client::Error> {
+        self.raw_request(Method::PUT, &format!("/cluster/sdn/vnets/{vnet}"), params)
+            .await
+    }
+
+    /// deletes the vnet, it must not contain any subnets.
+    pub async fn delete_vnet(&self, vnet: &str) -> Result<(), proxmox_client::Error> {
+        self.raw_delete(&format!("/cluster/sdn/vnets/{vnet}")).await
+    }
+
+    /// creates a subnet in the vnet with the given PVE parameters.
+    pub async fn create_subnet(
+        &self,
+        vnet: &str,
+        params: Value,
+    ) -> Result<(), proxmox_client::Error> {
+        let path = format!("/cluster/sdn/vnets/{vnet}/subnets");
+        self.raw_request(Method::POST, &path, params).await
+    }
+
+    /// updates a subnet of the vnet, `subnet` is the PVE subnet id.
+    pub async fn update_subnet(
+        &self,
+        vnet: &str,
+        subnet: &str,
+        params: Value,
+    ) -> Result<(), proxmox_client::Error> {
+        let path = format!("/cluster/sdn/vnets/{vnet}/subnets/{subnet}");
+        self.raw_request(Method::PUT, &path, params).await
+    }
+
+    /// deletes a subnet of the vnet, `subnet` is the PVE subnet id.
+    pub async fn delete_subnet(
+        &self,
+        vnet: &str,
+        subnet: &str,
+    ) -> Result<(), proxmox_client::Error> {
+        self.raw_delete(&format!("/cluster/sdn/vnets/{vnet}/subnets/{subnet}"))
+            .await
+    }
+
     /// applies the changes made while the client was locked and returns the original [`PveClient`] if the
     /// changes have been applied successfully.
     pub async fn apply_and_release(
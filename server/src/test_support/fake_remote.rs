@@ -50,6 +50,16 @@ impl RemoteConfig for FakeRemoteConfig {
                     authid: Authid::root_auth_id().clone(),
                     token: "".into(),
                     web_url: None,
+                    token_created: None,
+                    token_expire: None,
+                    sync_nodes: None,
+                    http_proxy: None,
+                    connect_timeout: None,
+                    request_timeout: None,
+                    max_connections: None,
+                    maintenance: None,
+                    maintenance_nodes: None,
+                    maintenance_end: None,
                 },
             );
         }
@@ -120,6 +130,10 @@ impl ClientFactory for FakeClientFactory {
     async fn make_pbs_client_and_login(&self, _remote: &Remote) -> Result<Box<PbsClient>, Error> {
         bail!("not implemented")
     }
+
+    async fn make_raw_client_and_login(&self, _remote: &Remote) -> Result<Box<Client>, Error> {
+        bail!("not implemented")
+    }
 }
 
 struct FakePveClient {
@@ -10,6 +10,8 @@ use proxmox_yew_comp::tfa::TfaView;
 use proxmox_yew_comp::{AclEdit, AclView, AuthView, TokenPanel, UserPanel};
 
 mod permission_path_selector;
+mod roles;
+use roles::RoleGrid;
 mod webauthn;
 pub use webauthn::WebauthnPanel;
 
@@ -128,6 +130,19 @@ pub fn access_control() -> Html {
                     .into()
             },
         )
+        .with_item_builder(
+            TabBarItem::new()
+                .key("roles")
+                .icon_class("fa fa-id-badge")
+                .label(tr!("Roles")),
+            |_| {
+                Container::new()
+                    .class("pwt-content-spacer")
+                    .class(pwt::css::FlexFit)
+                    .with_child(RoleGrid::new())
+                    .into()
+            },
+        )
         .with_item_builder(
             TabBarItem::new()
                 .key("realms")
@@ -1,16 +1,22 @@
+use std::collections::BTreeSet;
 use std::rc::Rc;
 
+use anyhow::Error;
 use yew::html::IntoPropValue;
 
 use pwt::prelude::*;
 use pwt::widget::form::Combobox;
+use pwt::AsyncPool;
 
 use pwt_macros::{builder, widget};
 
+use pdm_api_types::resource::{RemoteResources, Resource};
+
 static PREDEFINED_PATHS: &[&str] = &[
     "/",
     "/access",
     "/access/acl",
+    "/access/roles",
     "/access/users",
     "/resource",
     "/system",
@@ -25,6 +31,7 @@ static PREDEFINED_PATHS: &[&str] = &[
     "/system/status",
     "/system/tasks",
     "/system/time",
+    "/tag",
     "/view",
 ];
 
@@ -44,21 +51,63 @@ impl PermissionPathSelector {
     }
 }
 
-enum Msg {}
+enum Msg {
+    ResourcesLoaded(Result<Vec<RemoteResources>, Error>),
+}
 
 struct PdmPermissionPathSelector {
     items: Rc<Vec<AttrValue>>,
+    _async_pool: AsyncPool,
 }
 
-impl PdmPermissionPathSelector {}
+impl PdmPermissionPathSelector {
+    /// Remote, pool and tag paths of the resources, in addition to the predefined ones.
+    fn paths_from_resources(remote_resources: Vec<RemoteResources>) -> Vec<AttrValue> {
+        let mut remote_paths = BTreeSet::new();
+        let mut tag_paths = BTreeSet::new();
+
+        for remote in remote_resources {
+            remote_paths.insert(format!("/resource/{}", remote.remote));
+
+            for resource in remote.resources {
+                let (pool, tags) = match resource {
+                    Resource::PveQemu(guest) => (guest.pool, guest.tags),
+                    Resource::PveLxc(guest) => (guest.pool, guest.tags),
+                    _ => continue,
+                };
+
+                // nested pools also get an entry for each of their parents
+                let mut pool_path = format!("/resource/{}/pool", remote.remote);
+                for component in pool.split('/').filter(|component| !component.is_empty()) {
+                    pool_path.push('/');
+                    pool_path.push_str(component);
+                    remote_paths.insert(pool_path.clone());
+                }
+
+                tag_paths.extend(tags.into_iter().map(|tag| format!("/tag/{tag}")));
+            }
+        }
+
+        PREDEFINED_PATHS
+            .iter()
+            .map(|path| AttrValue::from(*path))
+            .chain(remote_paths.into_iter().map(AttrValue::from))
+            .chain(tag_paths.into_iter().map(AttrValue::from))
+            .collect()
+    }
+}
 
 impl Component for PdmPermissionPathSelector {
     type Message = Msg;
     type Properties = PermissionPathSelector;
 
-    fn create(_ctx: &Context<Self>) -> Self {
-        // TODO: fetch resources & remotes from the backend to improve the pre-defined selection of
-        // acl paths
+    fn create(ctx: &Context<Self>) -> Self {
+        let async_pool = AsyncPool::new();
+        async_pool.send_future(ctx.link().clone(), async move {
+            let result = crate::pdm_client().resources(None, None).await;
+            Msg::ResourcesLoaded(result.map_err(Error::from))
+        });
+
         Self {
             items: Rc::new(
                 PREDEFINED_PATHS
@@ -66,11 +115,22 @@ impl Component for PdmPermissionPathSelector {
                     .map(|i| AttrValue::from(*i))
                     .collect(),
             ),
+            _async_pool: async_pool,
         }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, _msg: Self::Message) -> bool {
-        false
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::ResourcesLoaded(Ok(remote_resources)) => {
+                self.items = Rc::new(Self::paths_from_resources(remote_resources));
+                true
+            }
+            Msg::ResourcesLoaded(Err(err)) => {
+                // the predefined paths are still usable, and any path can be entered manually
+                log::error!("could not load resources for the ACL path selection: {err}");
+                false
+            }
+        }
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
@@ -0,0 +1,324 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+
+use anyhow::Error;
+
+use yew::virtual_dom::{Key, VComp, VNode};
+
+use proxmox_yew_comp::form::delete_empty_values;
+use proxmox_yew_comp::percent_encoding::percent_encode_component;
+use proxmox_yew_comp::{http_delete, http_get, http_post, http_put, EditWindow, SchemaValidation};
+use proxmox_yew_comp::{
+    LoadableComponent, LoadableComponentContext, LoadableComponentMaster,
+    LoadableComponentScopeExt, LoadableComponentState,
+};
+
+use pwt::css::FontStyle;
+use pwt::prelude::*;
+use pwt::state::{Selection, Store};
+use pwt::widget::data_table::{DataTable, DataTableColumn, DataTableHeader};
+use pwt::widget::form::{DisplayField, Field, FormContext};
+use pwt::widget::{Button, ConfirmDialog, Container, InputPanel, Toolbar};
+
+use pdm_api_types::{
+    RoleListItem, PRIVILEGES, PRIVILEGE_LIST_SCHEMA, ROLE_ID_SCHEMA, SINGLE_LINE_COMMENT_SCHEMA,
+};
+
+async fn create_role(base_url: AttrValue, form_ctx: FormContext) -> Result<(), Error> {
+    let data = form_ctx.get_submit_data();
+    http_post(base_url.as_str(), Some(data)).await
+}
+
+async fn update_role(base_url: AttrValue, form_ctx: FormContext) -> Result<(), Error> {
+    let data = form_ctx.get_submit_data();
+    let id = form_ctx.read().get_field_text("roleid");
+    let params = delete_empty_values(&data, &["comment"], true);
+    let id = percent_encode_component(&id);
+
+    http_put(&format!("{base_url}/{id}"), Some(params)).await
+}
+
+#[derive(PartialEq, Clone, Properties)]
+pub struct RoleGrid {
+    #[prop_or("/access/roles".into())]
+    base_url: AttrValue,
+}
+
+impl RoleGrid {
+    pub fn new() -> Self {
+        yew::props!(Self {})
+    }
+}
+
+impl Default for RoleGrid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<RoleGrid> for VNode {
+    fn from(val: RoleGrid) -> Self {
+        VComp::new::<LoadableComponentMaster<RoleGridComp>>(Rc::new(val), None).into()
+    }
+}
+
+pub enum Msg {
+    LoadFinished(Vec<RoleListItem>),
+    Remove(Key),
+    Reload,
+}
+
+#[derive(PartialEq)]
+pub enum ViewState {
+    Create,
+    Edit,
+    Remove,
+}
+
+#[doc(hidden)]
+pub struct RoleGridComp {
+    state: LoadableComponentState<ViewState>,
+    store: Store<RoleListItem>,
+    columns: Rc<Vec<DataTableHeader<RoleListItem>>>,
+    selection: Selection,
+}
+
+pwt::impl_deref_mut_property!(RoleGridComp, state, LoadableComponentState<ViewState>);
+
+impl RoleGridComp {
+    fn columns() -> Rc<Vec<DataTableHeader<RoleListItem>>> {
+        let columns = vec![
+            DataTableColumn::new(tr!("Name"))
+                .flex(2)
+                .get_property(|value: &RoleListItem| value.roleid.as_str())
+                .sort_order(true)
+                .into(),
+            DataTableColumn::new(tr!("Built-In"))
+                .flex(1)
+                .render(|value: &RoleListItem| {
+                    if value.builtin {
+                        tr!("Yes").into()
+                    } else {
+                        tr!("No").into()
+                    }
+                })
+                .into(),
+            DataTableColumn::new(tr!("Privileges"))
+                .flex(5)
+                .render(|value: &RoleListItem| value.privs.join(", ").into())
+                .into(),
+            DataTableColumn::new(tr!("Comment"))
+                .flex(3)
+                .render(|value: &RoleListItem| value.comment.clone().unwrap_or_default().into())
+                .into(),
+        ];
+
+        Rc::new(columns)
+    }
+
+    fn selected_custom_role(&self) -> Option<Key> {
+        let key = self.selection.selected_key()?;
+        let store = self.store.read();
+        let role = store.lookup_record(&key)?;
+        (!role.builtin).then_some(key)
+    }
+
+    fn create_add_dialog(&self, ctx: &LoadableComponentContext<Self>) -> Html {
+        let props = ctx.props();
+        EditWindow::new(tr!("Add") + ": " + &tr!("Role"))
+            .renderer(|_form_ctx| input_panel(None))
+            .on_submit({
+                let base_url = props.base_url.clone();
+                move |form| create_role(base_url.clone(), form)
+            })
+            .on_done(ctx.link().clone().callback(|_| Msg::Reload))
+            .into()
+    }
+
+    fn create_edit_dialog(&self, selection: Key, ctx: &LoadableComponentContext<Self>) -> Html {
+        let props = ctx.props();
+        let id = selection.to_string();
+        EditWindow::new(tr!("Edit") + ": " + &tr!("Role"))
+            .renderer(move |_form_ctx| input_panel(Some(id.clone())))
+            .on_submit({
+                let base_url = props.base_url.clone();
+                move |form| update_role(base_url.clone(), form)
+            })
+            .loader(format!(
+                "{}/{}",
+                props.base_url,
+                percent_encode_component(&selection)
+            ))
+            .on_done(ctx.link().callback(|_| Msg::Reload))
+            .into()
+    }
+}
+
+impl LoadableComponent for RoleGridComp {
+    type Properties = RoleGrid;
+    type Message = Msg;
+    type ViewState = ViewState;
+
+    fn create(ctx: &proxmox_yew_comp::LoadableComponentContext<Self>) -> Self {
+        let selection = Selection::new().on_select({
+            let link = ctx.link().clone();
+            move |_| link.send_redraw()
+        });
+        Self {
+            state: LoadableComponentState::new(),
+            store: Store::with_extract_key(|role: &RoleListItem| role.roleid.as_str().into()),
+            columns: Self::columns(),
+            selection,
+        }
+    }
+
+    fn update(
+        &mut self,
+        ctx: &proxmox_yew_comp::LoadableComponentContext<Self>,
+        msg: Self::Message,
+    ) -> bool {
+        match msg {
+            Msg::LoadFinished(data) => self.store.set_data(data),
+            Msg::Remove(key) => {
+                let id = percent_encode_component(&key);
+                let link = ctx.link().clone();
+                let base_url = ctx.props().base_url.clone();
+                ctx.link().spawn(async move {
+                    if let Err(err) = http_delete(format!("{base_url}/{id}"), None).await {
+                        link.show_error(
+                            tr!("Error"),
+                            tr!("Could not delete '{0}': '{1}'", key.to_string(), err),
+                            true,
+                        );
+                    }
+                    link.send_message(Msg::Reload);
+                });
+            }
+            Msg::Reload => {
+                ctx.link().change_view(None);
+                ctx.link().send_reload();
+            }
+        }
+        true
+    }
+
+    fn toolbar(&self, ctx: &proxmox_yew_comp::LoadableComponentContext<Self>) -> Option<Html> {
+        let selection = self.selected_custom_role();
+        let link = ctx.link();
+        Some(
+            Toolbar::new()
+                .border_bottom(true)
+                .with_child(
+                    Button::new(tr!("Add"))
+                        .on_activate(link.change_view_callback(|_| Some(ViewState::Create))),
+                )
+                .with_child(
+                    Button::new(tr!("Edit"))
+                        .disabled(selection.is_none())
+                        .on_activate(link.change_view_callback(move |_| Some(ViewState::Edit))),
+                )
+                .with_child(
+                    Button::new(tr!("Remove"))
+                        .disabled(selection.is_none())
+                        .on_activate(link.change_view_callback(move |_| Some(ViewState::Remove))),
+                )
+                .into(),
+        )
+    }
+
+    fn load(
+        &self,
+        ctx: &proxmox_yew_comp::LoadableComponentContext<Self>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), anyhow::Error>>>> {
+        let base_url = ctx.props().base_url.clone();
+        let link = ctx.link().clone();
+        Box::pin(async move {
+            let data: Vec<RoleListItem> = http_get(base_url.as_str(), None).await?;
+            link.send_message(Msg::LoadFinished(data));
+            Ok(())
+        })
+    }
+
+    fn main_view(&self, ctx: &proxmox_yew_comp::LoadableComponentContext<Self>) -> Html {
+        let link = ctx.link().clone();
+        // built-in roles are skipped by the dialog view
+        DataTable::new(self.columns.clone(), self.store.clone())
+            .on_row_dblclick(move |_: &mut _| link.change_view(Some(ViewState::Edit)))
+            .selection(self.selection.clone())
+            .into()
+    }
+
+    fn dialog_view(
+        &self,
+        ctx: &proxmox_yew_comp::LoadableComponentContext<Self>,
+        view_state: &Self::ViewState,
+    ) -> Option<Html> {
+        match view_state {
+            ViewState::Create => Some(self.create_add_dialog(ctx)),
+            ViewState::Edit => self
+                .selected_custom_role()
+                .map(|key| self.create_edit_dialog(key, ctx)),
+            ViewState::Remove => self.selected_custom_role().map(|key| {
+                ConfirmDialog::new(
+                    tr!("Confirm"),
+                    tr!("Are you sure you want to remove '{0}'", key.to_string()),
+                )
+                .on_confirm({
+                    let link = ctx.link().clone();
+                    let key = key.clone();
+                    move |_| {
+                        link.send_message(Msg::Remove(key.clone()));
+                    }
+                })
+                .into()
+            }),
+        }
+    }
+}
+
+fn input_panel(roleid: Option<String>) -> Html {
+    let mut input_panel = InputPanel::new().padding(4);
+
+    match roleid {
+        None => input_panel.add_field(
+            tr!("Name"),
+            Field::new()
+                .name("roleid")
+                .schema(&ROLE_ID_SCHEMA)
+                .required(true),
+        ),
+        Some(roleid) => input_panel.add_field(
+            tr!("Name"),
+            DisplayField::new().name("roleid").value(roleid),
+        ),
+    }
+
+    let available = PRIVILEGES
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    input_panel
+        .with_large_field(
+            tr!("Privileges"),
+            Field::new()
+                .name("privs")
+                .schema(&PRIVILEGE_LIST_SCHEMA)
+                .placeholder("Resource.Audit,Resource.Manage")
+                .required(true),
+        )
+        .with_custom_child(
+            Container::new()
+                .class(FontStyle::LabelSmall)
+                .with_child(tr!("Available privileges: {0}", available)),
+        )
+        .with_large_field(
+            tr!("Comment"),
+            Field::new()
+                .name("comment")
+                .schema(&SINGLE_LINE_COMMENT_SCHEMA),
+        )
+        .into()
+}
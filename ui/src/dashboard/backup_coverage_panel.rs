@@ -0,0 +1,176 @@
+use std::rc::Rc;
+
+use anyhow::Error;
+use yew::virtual_dom::{VComp, VNode};
+
+use proxmox_yew_comp::utils::render_epoch_short;
+use proxmox_yew_comp::Status;
+use pwt::css::{self, AlignItems, TextAlign};
+use pwt::prelude::*;
+use pwt::state::SharedState;
+use pwt::widget::{error_message, Column, Container, Fa, List, ListTile, Panel, Row};
+
+use pdm_api_types::backup_coverage::{BackupCoverage, BackupCoverageStatus};
+use pdm_api_types::firewall::GuestKind;
+
+use crate::dashboard::create_title_with_icon;
+use crate::LoadResult;
+
+use super::loading_column;
+
+/// Maximum number of guests listed in the panel.
+const MAX_LISTED_GUESTS: usize = 10;
+
+#[derive(PartialEq, Clone, Properties)]
+pub struct BackupCoveragePanel {
+    coverage: Option<BackupCoverage>,
+}
+
+impl BackupCoveragePanel {
+    /// Create a new backup coverage panel from the given report
+    pub fn new(coverage: Option<BackupCoverage>) -> Self {
+        yew::props!(Self { coverage })
+    }
+}
+
+impl From<BackupCoveragePanel> for VNode {
+    fn from(value: BackupCoveragePanel) -> Self {
+        let comp = VComp::new::<PdmBackupCoveragePanel>(Rc::new(value), None);
+        VNode::from(comp)
+    }
+}
+
+pub struct PdmBackupCoveragePanel {}
+
+impl yew::Component for PdmBackupCoveragePanel {
+    type Message = ();
+    type Properties = BackupCoveragePanel;
+
+    fn create(_ctx: &yew::Context<Self>) -> Self {
+        Self {}
+    }
+
+    fn view(&self, ctx: &yew::Context<Self>) -> yew::Html {
+        let Some(coverage) = &ctx.props().coverage else {
+            return loading_column().into();
+        };
+
+        let summary = Row::new()
+            .padding(4)
+            .gap(4)
+            .class(AlignItems::Center)
+            .class(css::JustifyContent::SpaceAround)
+            .with_child(render_count(
+                Status::Success,
+                tr!("Covered"),
+                coverage.covered,
+            ))
+            .with_child(render_count(
+                Status::Warning,
+                tr!("Outdated"),
+                coverage.outdated,
+            ))
+            .with_child(render_count(
+                Status::Error,
+                tr!("Missing"),
+                coverage.missing,
+            ));
+
+        let uncovered: Vec<_> = coverage
+            .guests
+            .iter()
+            .filter(|guest| guest.status != BackupCoverageStatus::Covered)
+            .collect();
+
+        let mut column = Column::new().class(css::FlexFit).with_child(summary);
+
+        if uncovered.is_empty() {
+            return column
+                .with_child(
+                    Row::new()
+                        .padding(4)
+                        .gap(2)
+                        .with_child(Fa::new("info-circle").fixed_width())
+                        .with_child(tr!("All guests have a recent backup.")),
+                )
+                .into();
+        }
+
+        let mut tiles: Vec<ListTile> = uncovered
+            .iter()
+            .take(MAX_LISTED_GUESTS)
+            .map(|guest| {
+                let (status, last_backup) = match guest.last_backup {
+                    Some(time) => (Status::Warning, render_epoch_short(time)),
+                    None => (Status::Error, tr!("never")),
+                };
+                let icon = match guest.guest_type {
+                    GuestKind::Qemu => "desktop",
+                    GuestKind::Lxc => "cube",
+                };
+                ListTile::new()
+                    .with_child(Fa::from(status))
+                    .with_child(Fa::new(icon))
+                    .with_child(Container::new().padding_x(2).with_child(format!(
+                        "{} - {} ({})",
+                        guest.remote, guest.vmid, guest.name
+                    )))
+                    .with_child(
+                        Container::new()
+                            .class(TextAlign::Right)
+                            .padding_end(2)
+                            .with_child(last_backup),
+                    )
+            })
+            .collect();
+
+        if uncovered.len() > MAX_LISTED_GUESTS {
+            tiles.push(
+                ListTile::new()
+                    .with_child(Container::new())
+                    .with_child(Container::new())
+                    .with_child(
+                        Container::new()
+                            .padding_x(2)
+                            .with_child(tr!("and {0} more", uncovered.len() - MAX_LISTED_GUESTS)),
+                    )
+                    .with_child(Container::new()),
+            );
+        }
+
+        column.add_child(
+            List::new(tiles.len() as u64, move |idx: u64| {
+                tiles[idx as usize].clone()
+            })
+            .padding_x(4)
+            .class(css::Flex::Fill)
+            .grid_template_columns("auto auto 1fr auto"),
+        );
+
+        column.into()
+    }
+}
+
+fn render_count(status: Status, text: String, count: u64) -> Column {
+    Column::new()
+        .class(AlignItems::Center)
+        .gap(1)
+        .with_child(Fa::from(status).large_2x())
+        .with_child(html! {<span class="pwt-font-title-medium">{count}</span>})
+        .with_child(text)
+}
+
+pub fn create_backup_coverage_panel(
+    coverage: SharedState<LoadResult<BackupCoverage, Error>>,
+) -> Panel {
+    let coverage = coverage.read();
+    Panel::new()
+        .title(create_title_with_icon("floppy-o", tr!("Backup Coverage")))
+        .with_child(BackupCoveragePanel::new(coverage.data.clone()))
+        .with_optional_child(
+            coverage
+                .error
+                .as_ref()
+                .map(|err| error_message(&err.to_string())),
+        )
+}
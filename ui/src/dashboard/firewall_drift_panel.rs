@@ -0,0 +1,127 @@
+use std::rc::Rc;
+
+use anyhow::Error;
+use yew::virtual_dom::{VComp, VNode};
+
+use proxmox_yew_comp::utils::render_epoch_short;
+use proxmox_yew_comp::Status;
+use pwt::css::{self, AlignItems, TextAlign};
+use pwt::prelude::*;
+use pwt::state::SharedState;
+use pwt::widget::{error_message, Column, Container, Fa, List, ListTile, Panel, Row};
+
+use pdm_api_types::firewall::FirewallDriftReport;
+
+use crate::dashboard::create_title_with_icon;
+use crate::LoadResult;
+
+use super::loading_column;
+
+#[derive(PartialEq, Clone, Properties)]
+pub struct FirewallDriftPanel {
+    report: Option<FirewallDriftReport>,
+}
+
+impl FirewallDriftPanel {
+    /// Create a new firewall drift panel from the given report
+    pub fn new(report: Option<FirewallDriftReport>) -> Self {
+        yew::props!(Self { report })
+    }
+}
+
+impl From<FirewallDriftPanel> for VNode {
+    fn from(value: FirewallDriftPanel) -> Self {
+        let comp = VComp::new::<PdmFirewallDriftPanel>(Rc::new(value), None);
+        VNode::from(comp)
+    }
+}
+
+pub struct PdmFirewallDriftPanel {}
+
+impl yew::Component for PdmFirewallDriftPanel {
+    type Message = ();
+    type Properties = FirewallDriftPanel;
+
+    fn create(_ctx: &yew::Context<Self>) -> Self {
+        Self {}
+    }
+
+    fn view(&self, ctx: &yew::Context<Self>) -> yew::Html {
+        let Some(report) = &ctx.props().report else {
+            return loading_column().into();
+        };
+
+        let reference = match (&report.reference, report.baseline_time) {
+            (Some(remote), _) => tr!("Compared to remote '{0}'", remote),
+            (None, Some(time)) => tr!("Compared to baseline from {0}", render_epoch_short(time)),
+            (None, None) => tr!("Compared to baseline"),
+        };
+
+        let mut column = Column::new()
+            .class(css::FlexFit)
+            .with_child(Container::new().padding(4).with_child(reference));
+
+        let tiles: Vec<ListTile> = report
+            .remotes
+            .iter()
+            .filter(|remote| remote.error.is_some() || !remote.differences.is_empty())
+            .map(|remote| {
+                let (status, text) = match &remote.error {
+                    Some(err) => (Status::Unknown, err.clone()),
+                    None => (
+                        Status::Warning,
+                        tr!("One difference" | "{n} differences" % remote.differences.len()),
+                    ),
+                };
+                ListTile::new()
+                    .with_child(Fa::from(status))
+                    .with_child(Container::new().padding_x(2).with_child(remote.remote.clone()))
+                    .with_child(
+                        Container::new()
+                            .class(TextAlign::Right)
+                            .padding_end(2)
+                            .with_child(text),
+                    )
+            })
+            .collect();
+
+        if tiles.is_empty() {
+            return column
+                .with_child(
+                    Row::new()
+                        .padding(4)
+                        .gap(2)
+                        .class(AlignItems::Center)
+                        .with_child(Fa::from(Status::Success).fixed_width())
+                        .with_child(tr!("No remote drifted from the reference.")),
+                )
+                .into();
+        }
+
+        column.add_child(
+            List::new(tiles.len() as u64, move |idx: u64| {
+                tiles[idx as usize].clone()
+            })
+            .padding_x(4)
+            .class(css::Flex::Fill)
+            .grid_template_columns("auto 1fr auto"),
+        );
+
+        column.into()
+    }
+}
+
+pub fn create_firewall_drift_panel(
+    report: SharedState<LoadResult<FirewallDriftReport, Error>>,
+) -> Panel {
+    let report = report.read();
+    Panel::new()
+        .title(create_title_with_icon("shield", tr!("Firewall Drift")))
+        .with_child(FirewallDriftPanel::new(report.data.clone()))
+        .with_optional_child(
+            report
+                .error
+                .as_ref()
+                .map(|err| error_message(&err.to_string())),
+        )
+}
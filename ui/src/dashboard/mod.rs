@@ -28,6 +28,12 @@ pub use status_row::DashboardStatusRow;
 
 mod filtered_tasks;
 
+mod backup_coverage_panel;
+pub use backup_coverage_panel::create_backup_coverage_panel;
+
+mod firewall_drift_panel;
+pub use firewall_drift_panel::create_firewall_drift_panel;
+
 mod pbs_datastores_panel;
 pub use pbs_datastores_panel::create_pbs_datastores_panel;
 
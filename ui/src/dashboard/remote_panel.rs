@@ -72,6 +72,11 @@ impl Component for PdmRemotePanel {
         let status = props.status.clone().unwrap();
 
         let (remote_icon, remote_text, failure) = match (status.failed_remotes, status.remotes) {
+            (0, 0) if status.maintenance_remotes > 0 => (
+                Fa::new("wrench"),
+                tr!("All remotes are in maintenance."),
+                false,
+            ),
             (0, 0) => (
                 Fa::from(Status::Warning),
                 tr!("No remotes configured."),
@@ -127,6 +132,15 @@ impl Component for PdmRemotePanel {
                     .class(css::TextAlign::Center)
                     .with_child(remote_text),
             )
+            .with_optional_child(
+                (status.maintenance_remotes > 0 && status.failed_remotes + status.remotes > 0)
+                    .then(|| {
+                        Container::new()
+                            .class(css::TextAlign::Center)
+                            .with_child(tr!("One remote is in maintenance."
+                                | "{n} remotes are in maintenance." % status.maintenance_remotes))
+                    }),
+            )
             .into()
     }
 }
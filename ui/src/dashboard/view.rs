@@ -22,15 +22,17 @@ use crate::dashboard::refresh_config_edit::{
 use crate::dashboard::subscription_info::create_subscriptions_dialog;
 use crate::dashboard::tasks::get_task_options;
 use crate::dashboard::{
-    create_guest_panel, create_node_panel, create_pbs_datastores_panel,
-    create_refresh_config_edit_window, create_remote_panel, create_resource_tree, create_sdn_panel,
-    create_subscription_panel, create_task_summary_panel, create_top_entities_panel,
-    DashboardStatusRow,
+    create_backup_coverage_panel, create_firewall_drift_panel, create_guest_panel,
+    create_node_panel, create_pbs_datastores_panel, create_refresh_config_edit_window,
+    create_remote_panel, create_resource_tree, create_sdn_panel, create_subscription_panel,
+    create_task_summary_panel, create_top_entities_panel, DashboardStatusRow,
 };
 use crate::remotes::AddWizard;
 use crate::widget::RedrawController;
 use crate::{pdm_client, LoadResult};
 
+use pdm_api_types::backup_coverage::BackupCoverage;
+use pdm_api_types::firewall::FirewallDriftReport;
 use pdm_api_types::remotes::RemoteType;
 use pdm_api_types::resource::ResourcesStatus;
 use pdm_api_types::subscription::RemoteSubscriptions;
@@ -82,6 +84,8 @@ pub enum LoadingResult {
     TopEntities(Result<pdm_client::types::TopEntities, proxmox_client::Error>),
     TaskStatistics(Result<TaskStatistics, Error>),
     SubscriptionInfo(Result<Vec<RemoteSubscriptions>, Error>),
+    BackupCoverage(Result<BackupCoverage, Error>),
+    FirewallDrift(Result<FirewallDriftReport, Error>),
     All,
 }
 
@@ -121,6 +125,8 @@ struct WidgetRenderArgs {
     subscriptions: SharedState<LoadResult<Vec<RemoteSubscriptions>, Error>>,
     top_entities: SharedState<LoadResult<TopEntities, proxmox_client::Error>>,
     statistics: SharedState<LoadResult<TaskStatistics, Error>>,
+    backup_coverage: SharedState<LoadResult<BackupCoverage, Error>>,
+    firewall_drift: SharedState<LoadResult<FirewallDriftReport, Error>>,
     redraw_controller: RedrawController,
 }
 
@@ -135,6 +141,8 @@ fn render_widget(
         subscriptions,
         top_entities,
         statistics,
+        backup_coverage,
+        firewall_drift,
         redraw_controller,
     } = render_args;
 
@@ -167,6 +175,8 @@ fn render_widget(
             create_task_summary_panel(statistics, remotes, hours, since)
         }
         WidgetType::ResourceTree => create_resource_tree(redraw_controller),
+        WidgetType::BackupCoverage => create_backup_coverage_panel(backup_coverage),
+        WidgetType::FirewallDrift => create_firewall_drift_panel(firewall_drift),
     };
 
     if let Some(title) = &item.title {
@@ -191,7 +201,8 @@ impl ViewComp {
         if let Some(data) = self.template.data.as_ref() {
             let link = ctx.link().clone();
             let (_, since) = get_task_options(self.refresh_config.task_last_hours);
-            let (status, top_entities, tasks) = required_api_calls(&data.layout);
+            let (status, top_entities, tasks, backup_coverage, firewall_drift) =
+                required_api_calls(&data.layout);
 
             self.loading = true;
             let view = ctx.props().view.clone();
@@ -235,6 +246,24 @@ impl ViewComp {
                     }
                 };
 
+                let backup_coverage_future = async {
+                    if backup_coverage {
+                        let mut params = json!({
+                            "max-age": max_age,
+                        });
+                        add_view_filter(&mut params);
+                        let res = http_get("/resources/backup-coverage", Some(params)).await;
+                        link.send_message(Msg::LoadingResult(LoadingResult::BackupCoverage(res)));
+                    }
+                };
+
+                let firewall_drift_future = async {
+                    if firewall_drift {
+                        let res = http_get("/pve/firewall/drift", None).await;
+                        link.send_message(Msg::LoadingResult(LoadingResult::FirewallDrift(res)));
+                    }
+                };
+
                 let subs_future = async {
                     let mut params = json!({
                         "verbose": true,
@@ -244,7 +273,14 @@ impl ViewComp {
                     link.send_message(Msg::LoadingResult(LoadingResult::SubscriptionInfo(res)));
                 };
 
-                join!(status_future, entities_future, tasks_future, subs_future);
+                join!(
+                    status_future,
+                    entities_future,
+                    tasks_future,
+                    subs_future,
+                    backup_coverage_future,
+                    firewall_drift_future
+                );
                 link.send_message(Msg::LoadingResult(LoadingResult::All));
             });
         } else {
@@ -254,11 +290,14 @@ impl ViewComp {
     }
 }
 
-// returns which api calls are required: status, top_entities, task statistics
-fn required_api_calls(layout: &ViewLayout) -> (bool, bool, bool) {
+// returns which api calls are required: status, top_entities, task statistics, backup coverage,
+// firewall drift
+fn required_api_calls(layout: &ViewLayout) -> (bool, bool, bool, bool, bool) {
     let mut status = false;
     let mut top_entities = false;
     let mut task_statistics = false;
+    let mut backup_coverage = false;
+    let mut firewall_drift = false;
     match layout {
         ViewLayout::Rows { rows } => {
             for row in rows {
@@ -279,13 +318,21 @@ fn required_api_calls(layout: &ViewLayout) -> (bool, bool, bool) {
                         WidgetType::ResourceTree => {
                             // each list must do it itself
                         }
+                        WidgetType::BackupCoverage => backup_coverage = true,
+                        WidgetType::FirewallDrift => firewall_drift = true,
                     }
                 }
             }
         }
     }
 
-    (status, top_entities, task_statistics)
+    (
+        status,
+        top_entities,
+        task_statistics,
+        backup_coverage,
+        firewall_drift,
+    )
 }
 
 impl Component for ViewComp {
@@ -325,6 +372,8 @@ impl Component for ViewComp {
                 top_entities: SharedState::new(LoadResult::new()),
                 statistics: SharedState::new(LoadResult::new()),
                 subscriptions: SharedState::new(LoadResult::new()),
+                backup_coverage: SharedState::new(LoadResult::new()),
+                firewall_drift: SharedState::new(LoadResult::new()),
                 redraw_controller: RedrawController::new(),
             },
         }
@@ -347,6 +396,12 @@ impl Component for ViewComp {
                 LoadingResult::SubscriptionInfo(subscriptions) => {
                     self.render_args.subscriptions.write().update(subscriptions);
                 }
+                LoadingResult::BackupCoverage(coverage) => {
+                    self.render_args.backup_coverage.write().update(coverage)
+                }
+                LoadingResult::FirewallDrift(drift) => {
+                    self.render_args.firewall_drift.write().update(drift)
+                }
                 LoadingResult::All => {
                     self.loading = false;
                     if self.load_finished_time.is_none() {
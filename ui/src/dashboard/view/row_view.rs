@@ -642,4 +642,12 @@ fn create_menu(ctx: &yew::Context<RowViewComp>, new_coords: Position) -> Menu {
             MenuItem::new(tr!("Resource Tree"))
                 .on_select(create_callback(WidgetType::ResourceTree)),
         )
+        .with_item(
+            MenuItem::new(tr!("Backup Coverage"))
+                .on_select(create_callback(WidgetType::BackupCoverage)),
+        )
+        .with_item(
+            MenuItem::new(tr!("Firewall Drift"))
+                .on_select(create_callback(WidgetType::FirewallDrift)),
+        )
 }
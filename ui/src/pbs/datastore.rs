@@ -11,6 +11,9 @@ use pbs_api_types::DataStoreConfig;
 
 use crate::pbs::SnapshotList;
 
+mod jobs;
+use jobs::DatastoreJobList;
+
 mod overview;
 use overview::DataStoreOverview;
 
@@ -87,6 +90,17 @@ impl Component for DatastorePanelComp {
                     move |_| SnapshotList::new(remote.clone(), name.clone()).into()
                 },
             )
+            .with_item_builder(
+                TabBarItem::new()
+                    .key("jobs")
+                    .label(tr!("Jobs"))
+                    .icon_class("fa fa-clock-o"),
+                {
+                    let remote = props.remote.clone();
+                    let name = props.config.name.clone();
+                    move |_| DatastoreJobList::new(remote.clone(), name.clone()).into()
+                },
+            )
             .into()
     }
 }
@@ -0,0 +1,311 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+
+use anyhow::Error;
+use yew::virtual_dom::{Key, VComp, VNode};
+
+use proxmox_yew_comp::utils::render_epoch_short;
+use proxmox_yew_comp::{
+    LoadableComponent, LoadableComponentContext, LoadableComponentMaster,
+    LoadableComponentScopeExt, LoadableComponentState,
+};
+use pwt::prelude::*;
+use pwt::state::{Selection, Store};
+use pwt::widget::data_table::{DataTable, DataTableColumn, DataTableHeader};
+use pwt::widget::{Button, Toolbar};
+
+use pdm_api_types::RemoteUpid;
+
+use crate::pdm_client;
+
+#[derive(Clone, Copy, PartialEq)]
+enum JobKind {
+    GarbageCollection,
+    Prune,
+    Sync,
+    Verification,
+}
+
+impl JobKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            JobKind::GarbageCollection => "gc",
+            JobKind::Prune => "prune",
+            JobKind::Sync => "sync",
+            JobKind::Verification => "verification",
+        }
+    }
+
+    fn render(self) -> String {
+        match self {
+            JobKind::GarbageCollection => tr!("Garbage Collection"),
+            JobKind::Prune => tr!("Prune"),
+            JobKind::Sync => tr!("Sync"),
+            JobKind::Verification => tr!("Verification"),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+struct JobEntry {
+    kind: JobKind,
+    id: String,
+    schedule: Option<String>,
+    last_run_state: Option<String>,
+    last_run_endtime: Option<i64>,
+    next_run: Option<i64>,
+}
+
+impl JobEntry {
+    fn new(kind: JobKind, id: String, schedule: Option<String>) -> Self {
+        Self {
+            kind,
+            id,
+            schedule,
+            last_run_state: None,
+            last_run_endtime: None,
+            next_run: None,
+        }
+    }
+
+    fn with_status(mut self, status: pbs_api_types::JobScheduleStatus) -> Self {
+        self.last_run_state = status.last_run_state;
+        self.last_run_endtime = status.last_run_endtime;
+        self.next_run = status.next_run;
+        self
+    }
+
+    fn key(&self) -> Key {
+        Key::from(format!("{}/{}", self.kind.as_str(), self.id))
+    }
+}
+
+#[derive(Clone, PartialEq, Properties)]
+/// The garbage collection, prune, sync and verification jobs of a PBS datastore.
+pub struct DatastoreJobList {
+    remote: AttrValue,
+    datastore: AttrValue,
+}
+
+impl DatastoreJobList {
+    pub fn new(remote: impl Into<AttrValue>, datastore: impl Into<AttrValue>) -> Self {
+        yew::props!(Self {
+            remote: remote.into(),
+            datastore: datastore.into(),
+        })
+    }
+}
+
+impl From<DatastoreJobList> for VNode {
+    fn from(val: DatastoreJobList) -> Self {
+        VComp::new::<LoadableComponentMaster<DatastoreJobListComp>>(Rc::new(val), None).into()
+    }
+}
+
+enum Msg {
+    LoadFinished(Vec<JobEntry>),
+    Run,
+    Verify,
+    TaskStarted(Result<RemoteUpid, proxmox_client::Error>),
+}
+
+#[doc(hidden)]
+struct DatastoreJobListComp {
+    state: LoadableComponentState<()>,
+    store: Store<JobEntry>,
+    columns: Rc<Vec<DataTableHeader<JobEntry>>>,
+    selection: Selection,
+}
+
+pwt::impl_deref_mut_property!(DatastoreJobListComp, state, LoadableComponentState<()>);
+
+impl DatastoreJobListComp {
+    fn columns() -> Rc<Vec<DataTableHeader<JobEntry>>> {
+        Rc::new(vec![
+            DataTableColumn::new(tr!("Type"))
+                .flex(1)
+                .render(|entry: &JobEntry| entry.kind.render().into())
+                .into(),
+            DataTableColumn::new("ID")
+                .flex(2)
+                .get_property(|entry: &JobEntry| entry.id.as_str())
+                .sort_order(true)
+                .into(),
+            DataTableColumn::new(tr!("Schedule"))
+                .flex(1)
+                .render(|entry: &JobEntry| entry.schedule.as_deref().unwrap_or("-").into())
+                .into(),
+            DataTableColumn::new(tr!("Last Run"))
+                .flex(1)
+                .render(|entry: &JobEntry| match entry.last_run_endtime {
+                    Some(endtime) => render_epoch_short(endtime).into(),
+                    None => "-".into(),
+                })
+                .into(),
+            DataTableColumn::new(tr!("Status"))
+                .flex(1)
+                .render(|entry: &JobEntry| entry.last_run_state.as_deref().unwrap_or("-").into())
+                .into(),
+            DataTableColumn::new(tr!("Next Run"))
+                .flex(1)
+                .render(|entry: &JobEntry| match entry.next_run {
+                    Some(next_run) => render_epoch_short(next_run).into(),
+                    None => "-".into(),
+                })
+                .into(),
+        ])
+    }
+
+    async fn load_jobs(remote: &str, datastore: &str) -> Result<Vec<JobEntry>, Error> {
+        let client = pdm_client();
+
+        let mut jobs = Vec::new();
+
+        for job in client.pbs_datastore_gc_status(remote, datastore).await? {
+            let mut entry = JobEntry::new(JobKind::GarbageCollection, job.store, job.schedule);
+            entry.last_run_state = job.last_run_state;
+            entry.last_run_endtime = job.last_run_endtime;
+            entry.next_run = job.next_run;
+            jobs.push(entry);
+        }
+        for job in client.pbs_list_prune_jobs(remote, datastore).await? {
+            let config = job.config;
+            jobs.push(
+                JobEntry::new(JobKind::Prune, config.id, Some(config.schedule))
+                    .with_status(job.status),
+            );
+        }
+        for job in client.pbs_list_sync_jobs(remote, datastore).await? {
+            let config = job.config;
+            jobs.push(
+                JobEntry::new(JobKind::Sync, config.id, config.schedule).with_status(job.status),
+            );
+        }
+        for job in client.pbs_list_verification_jobs(remote, datastore).await? {
+            let config = job.config;
+            jobs.push(
+                JobEntry::new(JobKind::Verification, config.id, config.schedule)
+                    .with_status(job.status),
+            );
+        }
+
+        Ok(jobs)
+    }
+}
+
+impl LoadableComponent for DatastoreJobListComp {
+    type Properties = DatastoreJobList;
+    type Message = Msg;
+    type ViewState = ();
+
+    fn create(ctx: &LoadableComponentContext<Self>) -> Self {
+        let selection = Selection::new().on_select({
+            let link = ctx.link().clone();
+            move |_| link.send_redraw()
+        });
+        Self {
+            state: LoadableComponentState::new(),
+            store: Store::with_extract_key(|entry: &JobEntry| entry.key()),
+            columns: Self::columns(),
+            selection,
+        }
+    }
+
+    fn update(&mut self, ctx: &LoadableComponentContext<Self>, msg: Self::Message) -> bool {
+        let props = ctx.props();
+        match msg {
+            Msg::LoadFinished(jobs) => self.store.set_data(jobs),
+            Msg::Run => {
+                let Some(key) = self.selection.selected_key() else {
+                    return false;
+                };
+                let Some(entry) = self.store.read().lookup_record(&key).cloned() else {
+                    return false;
+                };
+
+                let remote = props.remote.clone();
+                let datastore = props.datastore.clone();
+                let link = ctx.link().clone();
+                ctx.link().spawn(async move {
+                    let client = pdm_client();
+                    let result = match entry.kind {
+                        JobKind::GarbageCollection => {
+                            client.pbs_datastore_start_gc(&remote, &datastore).await
+                        }
+                        JobKind::Prune => {
+                            client
+                                .pbs_run_prune_job(&remote, &datastore, &entry.id)
+                                .await
+                        }
+                        JobKind::Sync => {
+                            client
+                                .pbs_run_sync_job(&remote, &datastore, &entry.id)
+                                .await
+                        }
+                        JobKind::Verification => {
+                            client
+                                .pbs_run_verification_job(&remote, &datastore, &entry.id)
+                                .await
+                        }
+                    };
+                    link.send_message(Msg::TaskStarted(result));
+                });
+            }
+            Msg::Verify => {
+                let remote = props.remote.clone();
+                let datastore = props.datastore.clone();
+                let link = ctx.link().clone();
+                ctx.link().spawn(async move {
+                    let result = pdm_client()
+                        .pbs_datastore_verify(&remote, &datastore, Some(true), None)
+                        .await;
+                    link.send_message(Msg::TaskStarted(result));
+                });
+            }
+            Msg::TaskStarted(result) => match result {
+                Ok(upid) => ctx.link().show_task_progres(upid.to_string()),
+                Err(err) => ctx.link().show_error(tr!("Error"), err.to_string(), true),
+            },
+        }
+        true
+    }
+
+    fn toolbar(&self, ctx: &LoadableComponentContext<Self>) -> Option<Html> {
+        let link = ctx.link();
+        Some(
+            Toolbar::new()
+                .border_bottom(true)
+                .with_child(
+                    Button::new(tr!("Run now"))
+                        .disabled(self.selection.selected_key().is_none())
+                        .on_activate(link.callback(|_| Msg::Run)),
+                )
+                .with_child(
+                    Button::new(tr!("Verify All")).on_activate(link.callback(|_| Msg::Verify)),
+                )
+                .into(),
+        )
+    }
+
+    fn load(
+        &self,
+        ctx: &LoadableComponentContext<Self>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Error>>>> {
+        let props = ctx.props();
+        let remote = props.remote.clone();
+        let datastore = props.datastore.clone();
+        let link = ctx.link().clone();
+        Box::pin(async move {
+            let jobs = Self::load_jobs(&remote, &datastore).await?;
+            link.send_message(Msg::LoadFinished(jobs));
+            Ok(())
+        })
+    }
+
+    fn main_view(&self, _ctx: &LoadableComponentContext<Self>) -> Html {
+        DataTable::new(self.columns.clone(), self.store.clone())
+            .selection(self.selection.clone())
+            .into()
+    }
+}
@@ -27,6 +27,7 @@ use pdm_api_types::{
     RemoteUpid,
 };
 
+use crate::widget::GuestConfigEditWindow;
 use crate::{get_deep_url, renderer::render_tree_column, widget::MigrateWindow};
 
 use super::{
@@ -126,6 +127,7 @@ impl std::fmt::Display for Action {
 pub enum ViewState {
     Confirm(Action, String),  // ID
     MigrateWindow(GuestInfo), // ID
+    EditConfigWindow(GuestInfo),
 }
 
 pub enum Msg {
@@ -558,6 +560,11 @@ impl LoadableComponent for PveTreeComp {
                     })
                     .into(),
             ),
+            ViewState::EditConfigWindow(guest_info) => Some(
+                GuestConfigEditWindow::new(props.remote.clone(), *guest_info)
+                    .on_done(ctx.link().change_view_callback(|_| None))
+                    .into(),
+            ),
         }
     }
 
@@ -725,6 +732,13 @@ fn columns(
                         }))
                         .tip(tr!("Migrate"))
                     }))
+                    .with_optional_child(guest_info.map(|(guest_info, _, _)| {
+                        Tooltip::new(ActionIcon::new("fa fa-fw fa-pencil").on_activate({
+                            let link = link.clone();
+                            move |_| link.change_view(Some(ViewState::EditConfigWindow(guest_info)))
+                        }))
+                        .tip(tr!("Edit Configuration"))
+                    }))
                     .with_child(
                         Tooltip::new(ActionIcon::new("fa fa-external-link").on_activate({
                             let link = link.clone();
@@ -0,0 +1,193 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+
+use anyhow::Error;
+use serde_json::json;
+
+use yew::virtual_dom::{Key, VComp, VNode};
+
+use proxmox_yew_comp::percent_encoding::percent_encode_component;
+use proxmox_yew_comp::utils::render_epoch_short;
+use proxmox_yew_comp::{http_get, http_post};
+use proxmox_yew_comp::{
+    LoadableComponent, LoadableComponentContext, LoadableComponentMaster,
+    LoadableComponentScopeExt, LoadableComponentState,
+};
+
+use pwt::prelude::*;
+use pwt::state::{Selection, Store};
+use pwt::widget::data_table::{DataTable, DataTableColumn, DataTableHeader};
+use pwt::widget::{Button, Toolbar};
+
+use pdm_api_types::remotes::PendingCertificate;
+
+/// Shows the certificates presented by nodes of a remote which do not match the pinned
+/// fingerprint, and allows to trust them.
+#[derive(PartialEq, Clone, Properties)]
+pub struct PendingCertificates {
+    remote: AttrValue,
+}
+
+impl PendingCertificates {
+    pub fn new(remote: impl Into<AttrValue>) -> Self {
+        yew::props!(Self {
+            remote: remote.into()
+        })
+    }
+}
+
+impl From<PendingCertificates> for VNode {
+    fn from(val: PendingCertificates) -> Self {
+        VComp::new::<LoadableComponentMaster<PendingCertificatesComp>>(Rc::new(val), None).into()
+    }
+}
+
+pub enum Msg {
+    LoadFinished(Vec<PendingCertificate>),
+    Accept,
+}
+
+#[doc(hidden)]
+pub struct PendingCertificatesComp {
+    state: LoadableComponentState<()>,
+    store: Store<PendingCertificate>,
+    selection: Selection,
+    columns: Rc<Vec<DataTableHeader<PendingCertificate>>>,
+}
+
+pwt::impl_deref_mut_property!(PendingCertificatesComp, state, LoadableComponentState<()>);
+
+impl PendingCertificatesComp {
+    fn url(ctx: &LoadableComponentContext<Self>) -> String {
+        format!(
+            "/remotes/remote/{}/certificates",
+            percent_encode_component(&ctx.props().remote)
+        )
+    }
+
+    fn selected_certificate(&self) -> Option<PendingCertificate> {
+        let key = self.selection.selected_key()?;
+        self.store.read().lookup_record(&key).cloned()
+    }
+
+    fn columns() -> Rc<Vec<DataTableHeader<PendingCertificate>>> {
+        Rc::new(vec![
+            DataTableColumn::new(tr!("Address"))
+                .flex(1)
+                .get_property(|entry: &PendingCertificate| entry.hostname.as_str())
+                .sort_order(true)
+                .into(),
+            DataTableColumn::new(tr!("Node"))
+                .flex(1)
+                .render(|entry: &PendingCertificate| entry.node.as_deref().unwrap_or("-").into())
+                .into(),
+            DataTableColumn::new(tr!("New Fingerprint"))
+                .flex(3)
+                .render(|entry: &PendingCertificate| {
+                    entry
+                        .certificate
+                        .fingerprint
+                        .as_deref()
+                        .unwrap_or("-")
+                        .into()
+                })
+                .into(),
+            DataTableColumn::new(tr!("Issuer"))
+                .flex(2)
+                .get_property(|entry: &PendingCertificate| entry.certificate.issuer.as_str())
+                .into(),
+            DataTableColumn::new(tr!("First Seen"))
+                .width("150px")
+                .render(|entry: &PendingCertificate| {
+                    let first_seen = render_epoch_short(entry.first_seen);
+                    if entry.trusted_by_ca {
+                        format!("{first_seen} ({})", tr!("trusted CA")).into()
+                    } else {
+                        first_seen.into()
+                    }
+                })
+                .into(),
+        ])
+    }
+}
+
+impl LoadableComponent for PendingCertificatesComp {
+    type Properties = PendingCertificates;
+    type Message = Msg;
+    type ViewState = ();
+
+    fn create(ctx: &LoadableComponentContext<Self>) -> Self {
+        Self {
+            state: LoadableComponentState::new(),
+            store: Store::with_extract_key(|entry: &PendingCertificate| {
+                Key::from(entry.hostname.as_str())
+            }),
+            selection: Selection::new().on_select({
+                let link = ctx.link().clone();
+                move |_| link.send_redraw()
+            }),
+            columns: Self::columns(),
+        }
+    }
+
+    fn update(&mut self, ctx: &LoadableComponentContext<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::LoadFinished(data) => self.store.set_data(data),
+            Msg::Accept => {
+                let Some(entry) = self.selected_certificate() else {
+                    return false;
+                };
+                let Some(fingerprint) = entry.certificate.fingerprint else {
+                    return false;
+                };
+                let url = Self::url(ctx);
+                let link = ctx.link().clone();
+                ctx.link().spawn(async move {
+                    let param = json!({
+                        "hostname": entry.hostname,
+                        "fingerprint": fingerprint,
+                    });
+                    if let Err(err) = http_post::<()>(url, Some(param)).await {
+                        link.show_error(tr!("Error"), err, true);
+                    }
+                    link.send_reload();
+                });
+            }
+        }
+        true
+    }
+
+    fn toolbar(&self, ctx: &LoadableComponentContext<Self>) -> Option<Html> {
+        Some(
+            Toolbar::new()
+                .border_bottom(true)
+                .with_child(
+                    Button::new(tr!("Accept"))
+                        .disabled(self.selected_certificate().is_none())
+                        .on_activate(ctx.link().callback(|_| Msg::Accept)),
+                )
+                .into(),
+        )
+    }
+
+    fn load(
+        &self,
+        ctx: &LoadableComponentContext<Self>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Error>>>> {
+        let url = Self::url(ctx);
+        let link = ctx.link().clone();
+        Box::pin(async move {
+            let data: Vec<PendingCertificate> = http_get(url, None).await?;
+            link.send_message(Msg::LoadFinished(data));
+            Ok(())
+        })
+    }
+
+    fn main_view(&self, _ctx: &LoadableComponentContext<Self>) -> Html {
+        DataTable::new(self.columns.clone(), self.store.clone())
+            .class(pwt::css::FlexFit)
+            .selection(self.selection.clone())
+            .into()
+    }
+}
@@ -0,0 +1,153 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+
+use anyhow::Error;
+
+use yew::virtual_dom::{Key, VComp, VNode};
+
+use proxmox_yew_comp::percent_encoding::percent_encode_component;
+use proxmox_yew_comp::{http_get, http_post};
+use proxmox_yew_comp::{
+    LoadableComponent, LoadableComponentContext, LoadableComponentMaster,
+    LoadableComponentScopeExt, LoadableComponentState,
+};
+
+use pwt::prelude::*;
+use pwt::state::Store;
+use pwt::widget::data_table::{DataTable, DataTableColumn, DataTableHeader};
+use pwt::widget::{Button, Toolbar};
+
+use pdm_api_types::remotes::{NodeMembershipChange, NodeMembershipEntry};
+
+/// Shows the nodes which joined or left the cluster of a PVE remote, and allows to update the
+/// node list of the remote accordingly.
+#[derive(PartialEq, Clone, Properties)]
+pub struct ClusterNodeSync {
+    remote: AttrValue,
+}
+
+impl ClusterNodeSync {
+    pub fn new(remote: impl Into<AttrValue>) -> Self {
+        yew::props!(Self {
+            remote: remote.into()
+        })
+    }
+}
+
+impl From<ClusterNodeSync> for VNode {
+    fn from(val: ClusterNodeSync) -> Self {
+        VComp::new::<LoadableComponentMaster<ClusterNodeSyncComp>>(Rc::new(val), None).into()
+    }
+}
+
+pub enum Msg {
+    LoadFinished(Vec<NodeMembershipEntry>),
+    Apply,
+}
+
+#[doc(hidden)]
+pub struct ClusterNodeSyncComp {
+    state: LoadableComponentState<()>,
+    store: Store<NodeMembershipEntry>,
+    columns: Rc<Vec<DataTableHeader<NodeMembershipEntry>>>,
+}
+
+pwt::impl_deref_mut_property!(ClusterNodeSyncComp, state, LoadableComponentState<()>);
+
+impl ClusterNodeSyncComp {
+    fn url(ctx: &LoadableComponentContext<Self>) -> String {
+        format!(
+            "/pve/remotes/{}/cluster-nodes",
+            percent_encode_component(&ctx.props().remote)
+        )
+    }
+
+    fn columns() -> Rc<Vec<DataTableHeader<NodeMembershipEntry>>> {
+        Rc::new(vec![
+            DataTableColumn::new(tr!("Node"))
+                .flex(1)
+                .get_property(|entry: &NodeMembershipEntry| entry.node.as_str())
+                .sort_order(true)
+                .into(),
+            DataTableColumn::new(tr!("Change"))
+                .flex(1)
+                .render(|entry: &NodeMembershipEntry| match entry.change {
+                    NodeMembershipChange::Joined => tr!("Joined, will be added").into(),
+                    NodeMembershipChange::Left => tr!("Left, will be removed").into(),
+                })
+                .into(),
+            DataTableColumn::new(tr!("Address"))
+                .flex(1)
+                .get_property(|entry: &NodeMembershipEntry| entry.hostname.as_str())
+                .into(),
+        ])
+    }
+}
+
+impl LoadableComponent for ClusterNodeSyncComp {
+    type Properties = ClusterNodeSync;
+    type Message = Msg;
+    type ViewState = ();
+
+    fn create(_ctx: &LoadableComponentContext<Self>) -> Self {
+        Self {
+            state: LoadableComponentState::new(),
+            store: Store::with_extract_key(|entry: &NodeMembershipEntry| {
+                Key::from(format!("{}/{}", entry.node, entry.hostname))
+            }),
+            columns: Self::columns(),
+        }
+    }
+
+    fn update(&mut self, ctx: &LoadableComponentContext<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::LoadFinished(data) => self.store.set_data(data),
+            Msg::Apply => {
+                let url = Self::url(ctx);
+                let link = ctx.link().clone();
+                ctx.link().spawn(async move {
+                    let result: Result<Vec<NodeMembershipEntry>, Error> =
+                        http_post(url, None).await;
+                    if let Err(err) = result {
+                        link.show_error(tr!("Error"), err, true);
+                    }
+                    link.send_reload();
+                });
+            }
+        }
+        true
+    }
+
+    fn toolbar(&self, ctx: &LoadableComponentContext<Self>) -> Option<Html> {
+        Some(
+            Toolbar::new()
+                .border_bottom(true)
+                .with_child(
+                    Button::new(tr!("Apply"))
+                        .disabled(self.store.data_len() == 0)
+                        .on_activate(ctx.link().callback(|_| Msg::Apply)),
+                )
+                .into(),
+        )
+    }
+
+    fn load(
+        &self,
+        ctx: &LoadableComponentContext<Self>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Error>>>> {
+        let url = Self::url(ctx);
+        let link = ctx.link().clone();
+        Box::pin(async move {
+            let data: Vec<NodeMembershipEntry> = http_get(url, None).await?;
+            link.send_message(Msg::LoadFinished(data));
+            Ok(())
+        })
+    }
+
+    fn main_view(&self, _ctx: &LoadableComponentContext<Self>) -> Html {
+        DataTable::new(self.columns.clone(), self.store.clone())
+            .class(pwt::css::FlexFit)
+            .into()
+    }
+}
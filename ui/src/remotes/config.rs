@@ -3,9 +3,12 @@ use std::pin::Pin;
 use std::rc::Rc;
 
 use anyhow::Error;
+use js_sys::Date;
 
 use proxmox_schema::property_string::PropertyString;
 
+use crate::remotes::certificates::PendingCertificates;
+use crate::remotes::cluster_nodes::ClusterNodeSync;
 use crate::remotes::edit_remote::EditRemote;
 use crate::remotes::remove_remote::RemoveRemote;
 //use pwt::widget::form::{Field, FormContext, InputType};
@@ -13,6 +16,7 @@ use crate::remotes::remove_remote::RemoveRemote;
 use pdm_api_types::remotes::Remote;
 //use proxmox_schema::{property_string::PropertyString, ApiType};
 use proxmox_yew_comp::percent_encoding::percent_encode_component;
+use proxmox_yew_comp::utils::render_epoch_short;
 
 //use pbs_api_types::CERT_FINGERPRINT_SHA256_SCHEMA;
 
@@ -27,7 +31,7 @@ use pwt::widget::data_table::{DataTable, DataTableColumn, DataTableHeader};
 //use pwt::widget::form::{delete_empty_values, Field, FormContext, InputType};
 use pwt::widget::{
     menu::{Menu, MenuButton, MenuItem},
-    Button, Column, Toolbar, Tooltip,
+    Button, Column, Dialog, Fa, Row, Toolbar, Tooltip,
 };
 //use pwt::widget::InputPanel;
 
@@ -105,6 +109,8 @@ pub enum ViewState {
     Add(RemoteType),
     Edit,
     Remove,
+    ClusterNodes,
+    Certificates,
 }
 
 pub enum Msg {
@@ -214,6 +220,16 @@ impl LoadableComponent for PbsRemoteConfigPanel {
                     .disabled(disabled)
                     .on_activate(link.change_view_callback(|_| Some(ViewState::Remove))),
             )
+            .with_child(
+                Button::new(tr!("Cluster Nodes"))
+                    .disabled(self.selected_remote_type() != Some(RemoteType::Pve))
+                    .on_activate(link.change_view_callback(|_| Some(ViewState::ClusterNodes))),
+            )
+            .with_child(
+                Button::new(tr!("Certificates"))
+                    .disabled(disabled)
+                    .on_activate(link.change_view_callback(|_| Some(ViewState::Certificates))),
+            )
             .with_flex_spacer()
             .with_child({
                 let loading = self.loading();
@@ -248,6 +264,14 @@ impl LoadableComponent for PbsRemoteConfigPanel {
                 .selected_key()
                 .map(|key| self.create_edit_dialog(ctx, key)),
             ViewState::Remove => Some(self.create_remove_remote_dialog(ctx)),
+            ViewState::ClusterNodes => self
+                .selection
+                .selected_key()
+                .map(|key| self.create_cluster_nodes_dialog(ctx, key)),
+            ViewState::Certificates => self
+                .selection
+                .selected_key()
+                .map(|key| self.create_certificates_dialog(ctx, key)),
         }
     }
 }
@@ -309,6 +333,32 @@ impl PbsRemoteConfigPanel {
             .into()
     }
 
+    fn selected_remote_type(&self) -> Option<RemoteType> {
+        let key = self.selection.selected_key()?;
+        let store = self.store.read();
+        store.lookup_record(&key).map(|remote| remote.ty)
+    }
+
+    fn create_cluster_nodes_dialog(&self, ctx: &LoadableComponentContext<Self>, key: Key) -> Html {
+        Dialog::new(tr!("Cluster Nodes") + ": " + &key.to_string())
+            .min_width(600)
+            .min_height(300)
+            .resizable(true)
+            .with_child(ClusterNodeSync::new(key.to_string()))
+            .on_close(ctx.link().change_view_callback(|_| None))
+            .into()
+    }
+
+    fn create_certificates_dialog(&self, ctx: &LoadableComponentContext<Self>, key: Key) -> Html {
+        Dialog::new(tr!("Changed Certificates") + ": " + &key.to_string())
+            .min_width(800)
+            .min_height(300)
+            .resizable(true)
+            .with_child(PendingCertificates::new(key.to_string()))
+            .on_close(ctx.link().change_view_callback(|_| None))
+            .into()
+    }
+
     fn create_remove_remote_dialog(&self, ctx: &LoadableComponentContext<Self>) -> Html {
         let link = ctx.link().clone();
         let close = link.change_view_callback(|_| None);
@@ -330,6 +380,35 @@ impl From<RemoteConfigPanel> for VNode {
     }
 }
 
+fn render_maintenance(item: &Remote) -> Html {
+    let now = (Date::now() / 1000.0) as i64;
+
+    let text = if item.in_maintenance(now) {
+        tr!("All nodes")
+    } else {
+        let nodes: Vec<&str> = item
+            .nodes_in_maintenance()
+            .filter(|node| item.node_in_maintenance(node, now))
+            .collect();
+        if nodes.is_empty() {
+            return html! {"-"};
+        }
+        nodes.join(", ")
+    };
+
+    let text = match item.maintenance_end {
+        Some(end) => tr!("{0} (until {1})", text, render_epoch_short(end)),
+        None => text,
+    };
+
+    Row::new()
+        .gap(2)
+        .class(pwt::css::AlignItems::Center)
+        .with_child(Fa::new("wrench"))
+        .with_child(text)
+        .into()
+}
+
 fn remote_list_columns() -> Rc<Vec<DataTableHeader<Remote>>> {
     Rc::new(vec![
         DataTableColumn::new(tr!("Remote ID"))
@@ -360,6 +439,18 @@ fn remote_list_columns() -> Rc<Vec<DataTableHeader<Remote>>> {
             })
             .sorter(|a: &Remote, b: &Remote| a.authid.cmp(&b.authid))
             .into(),
+        DataTableColumn::new(tr!("Token Expiry"))
+            .width("150px")
+            .render(|item: &Remote| match item.token_expire {
+                Some(expire) => render_epoch_short(expire).into(),
+                None => html! {"-"},
+            })
+            .sorter(|a: &Remote, b: &Remote| a.token_expire.cmp(&b.token_expire))
+            .into(),
+        DataTableColumn::new(tr!("Maintenance"))
+            .width("200px")
+            .render(render_maintenance)
+            .into(),
         DataTableColumn::new(tr!("Nodes"))
             .flex(1)
             .render(|item: &Remote| {
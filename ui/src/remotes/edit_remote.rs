@@ -1,13 +1,15 @@
 use std::rc::Rc;
 
 use anyhow::Error;
+use js_sys::Date;
 use serde_json::Value;
+use wasm_bindgen::JsValue;
 use yew::html::IntoEventCallback;
 use yew::virtual_dom::{VComp, VNode};
 
 use pwt::css::FlexFit;
 use pwt::prelude::*;
-use pwt::widget::form::{DisplayField, Field, FormContext, InputType};
+use pwt::widget::form::{Checkbox, DisplayField, Field, FormContext, InputType, Number};
 use pwt::widget::{Container, InputPanel};
 
 use proxmox_yew_comp::form::delete_empty_values;
@@ -15,8 +17,11 @@ use proxmox_yew_comp::percent_encoding::percent_encode_component;
 use proxmox_yew_comp::{EditWindow, SchemaValidation};
 
 use proxmox_client::ApiResponseData;
+use proxmox_schema::property_string;
 use proxmox_schema::ApiType;
 
+use pdm_api_types::remotes::{MAINTENANCE_NODE_ARRAY_SCHEMA, MAINTENANCE_NODE_LIST_SCHEMA};
+
 use super::NodeUrlList;
 
 use pwt_macros::builder;
@@ -42,7 +47,39 @@ impl EditRemote {
 pub struct PdmEditRemote {}
 
 async fn load_remote(url: AttrValue) -> Result<ApiResponseData<Value>, Error> {
-    proxmox_yew_comp::http_get_full(&*url, None).await
+    let mut response: ApiResponseData<Value> = proxmox_yew_comp::http_get_full(&*url, None).await?;
+
+    // the date time input works with local time strings
+    if let Some(end) = response.data["maintenance-end"].as_i64() {
+        response.data["maintenance-end"] = epoch_to_local_datetime(end).into();
+    }
+
+    // the node list is edited as a single property string
+    if let Some(nodes) = response.data["maintenance-nodes"].as_array() {
+        let nodes: Vec<&str> = nodes.iter().filter_map(Value::as_str).collect();
+        response.data["maintenance-nodes"] = nodes.join(",").into();
+    }
+
+    Ok(response)
+}
+
+/// Format an epoch as `YYYY-MM-DDTHH:MM` in local time.
+fn epoch_to_local_datetime(epoch: i64) -> String {
+    let date = Date::new(&JsValue::from_f64(epoch as f64 * 1000.0));
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}",
+        date.get_full_year(),
+        date.get_month() + 1,
+        date.get_date(),
+        date.get_hours(),
+        date.get_minutes(),
+    )
+}
+
+/// Parse a local `YYYY-MM-DDTHH:MM` time string into an epoch.
+fn local_datetime_to_epoch(datetime: &str) -> Option<i64> {
+    let time = Date::new(&JsValue::from_str(datetime)).get_time();
+    (!time.is_nan()).then(|| (time / 1000.0) as i64)
 }
 
 impl Component for PdmEditRemote {
@@ -76,9 +113,37 @@ impl Component for PdmEditRemote {
                 move |form_ctx: FormContext| {
                     let url = url.clone();
                     async move {
-                        let data = form_ctx.get_submit_data();
+                        let mut data = form_ctx.get_submit_data();
+
+                        if let Some(end) = data["maintenance-end"].as_str() {
+                            data["maintenance-end"] = match local_datetime_to_epoch(end) {
+                                Some(epoch) => epoch.into(),
+                                None => Value::Null,
+                            };
+                        }
+
+                        if let Some(nodes) = data["maintenance-nodes"].as_str() {
+                            if !nodes.is_empty() {
+                                data["maintenance-nodes"] = property_string::parse_with_schema(
+                                    nodes,
+                                    &MAINTENANCE_NODE_ARRAY_SCHEMA,
+                                )?;
+                            }
+                        }
 
-                        let data = delete_empty_values(&data, &["web-url"], true);
+                        let data = delete_empty_values(
+                            &data,
+                            &[
+                                "web-url",
+                                "http-proxy",
+                                "connect-timeout",
+                                "request-timeout",
+                                "max-connections",
+                                "maintenance-nodes",
+                                "maintenance-end",
+                            ],
+                            true,
+                        );
 
                         proxmox_yew_comp::http_put(&url, Some(data)).await
                     }
@@ -120,6 +185,62 @@ fn edit_remote_input_panel(_form_ctx: &FormContext, remote_id: &str) -> Html {
                 .name("web-url")
                 .placeholder(tr!("Use first endpoint.")),
         )
+        .with_field(
+            tr!("Sync Cluster Nodes"),
+            Checkbox::new().name("sync-nodes").box_label(tr!(
+                "Add and remove nodes joining or leaving the cluster (PVE only)"
+            )),
+        )
+        .with_field(
+            tr!("HTTP Proxy"),
+            Field::new()
+                .name("http-proxy")
+                .schema(&pdm_api_types::HTTP_PROXY_SCHEMA)
+                .placeholder(tr!("None")),
+        )
+        .with_field(
+            tr!("Connect Timeout (s)"),
+            Number::<u64>::new()
+                .name("connect-timeout")
+                .min(1)
+                .max(300)
+                .placeholder(tr!("None")),
+        )
+        .with_field(
+            tr!("Request Timeout (s)"),
+            Number::<u64>::new()
+                .name("request-timeout")
+                .min(1)
+                .max(3600)
+                .placeholder("60"),
+        )
+        .with_field(
+            tr!("Max. Connections"),
+            Number::<usize>::new()
+                .name("max-connections")
+                .min(1)
+                .max(64)
+                .placeholder("5"),
+        )
+        .with_field(
+            tr!("Maintenance"),
+            Checkbox::new()
+                .name("maintenance")
+                .box_label(tr!("Pause polling and do not report errors")),
+        )
+        .with_field(
+            tr!("Nodes in Maintenance"),
+            Field::new()
+                .name("maintenance-nodes")
+                .schema(&MAINTENANCE_NODE_LIST_SCHEMA)
+                .placeholder(tr!("None")),
+        )
+        .with_field(
+            tr!("Maintenance End"),
+            Field::new()
+                .name("maintenance-end")
+                .input_type(InputType::DatetimeLocal),
+        )
         .with_custom_child(
             Container::new()
                 .key("nodes-title")
@@ -18,6 +18,10 @@ pub use node_url_list::NodeUrlList;
 
 mod edit_remote;
 
+mod certificates;
+
+mod cluster_nodes;
+
 mod config;
 pub use config::{create_remote, RemoteConfigPanel};
 
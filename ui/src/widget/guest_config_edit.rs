@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+
+use anyhow::Error;
+use serde_json::{json, Value};
+use yew::html::IntoEventCallback;
+
+use proxmox_client::ApiResponseData;
+use proxmox_yew_comp::percent_encoding::percent_encode_component;
+use proxmox_yew_comp::{http_get, EditWindow};
+use pwt::css::FlexFit;
+use pwt::prelude::*;
+use pwt::widget::form::{DisplayField, Field, FormContext, Hidden, Number, TextArea};
+use pwt::widget::InputPanel;
+use pwt_macros::{builder, widget};
+
+use pdm_client::types::GuestConfigUpdate;
+
+use crate::pve::{GuestInfo, GuestType};
+
+#[widget(comp=PdmGuestConfigEditWindow)]
+#[builder]
+#[derive(Clone, Properties, PartialEq)]
+/// Window to edit the basic configuration of a single guest.
+///
+/// Changes which the remote cannot apply to the running guest right away are shown as pending
+/// changes the next time the window is opened.
+pub struct GuestConfigEditWindow {
+    /// The remote of the guest
+    pub remote: AttrValue,
+
+    /// The guest Info
+    pub guest_info: GuestInfo,
+
+    /// Done callback, called after Close, Abort or Submit.
+    #[builder_cb(IntoEventCallback, into_event_callback, ())]
+    #[prop_or_default]
+    pub on_done: Option<Callback<()>>,
+}
+
+impl GuestConfigEditWindow {
+    pub fn new(remote: impl Into<AttrValue>, guest_info: GuestInfo) -> Self {
+        yew::props!(Self {
+            remote: remote.into(),
+            guest_info,
+        })
+    }
+}
+
+pub struct PdmGuestConfigEditWindow {}
+
+impl PdmGuestConfigEditWindow {
+    async fn load(
+        remote: AttrValue,
+        guest_info: GuestInfo,
+    ) -> Result<ApiResponseData<Value>, Error> {
+        let base_url = format!(
+            "/pve/remotes/{}/{}/{}",
+            percent_encode_component(&remote),
+            guest_info.guest_type,
+            guest_info.vmid
+        );
+
+        // edit on top of the pending values, like the PVE UI does
+        let mut config: Value = http_get(
+            &format!("{base_url}/config"),
+            Some(json!({ "state": "pending" })),
+        )
+        .await?;
+        let pending: Vec<Value> = http_get(&format!("{base_url}/pending"), None).await?;
+
+        // newer qemu versions use a property string for the memory
+        if let Some(memory) = config.get("memory").and_then(parse_memory) {
+            config["memory"] = memory.into();
+        }
+        config["pending-changes"] = render_pending_changes(&pending).into();
+
+        Ok(ApiResponseData {
+            attribs: HashMap::new(),
+            data: config,
+        })
+    }
+
+    async fn submit(
+        remote: AttrValue,
+        guest_info: GuestInfo,
+        form_ctx: FormContext,
+    ) -> Result<(), Error> {
+        let data = form_ctx.get_submit_data();
+        let update = build_update(&data, guest_info.guest_type);
+
+        let client = crate::pdm_client();
+        match guest_info.guest_type {
+            GuestType::Qemu => {
+                client
+                    .pve_qemu_update_config(&remote, None, guest_info.vmid, &update)
+                    .await?
+            }
+            GuestType::Lxc => {
+                client
+                    .pve_lxc_update_config(&remote, None, guest_info.vmid, &update)
+                    .await?
+            }
+        }
+
+        Ok(())
+    }
+
+    fn input_panel(guest_type: GuestType) -> Html {
+        let is_qemu = guest_type == GuestType::Qemu;
+
+        let mut panel = InputPanel::new()
+            .class(FlexFit)
+            .padding(4)
+            .width("auto")
+            .with_custom_child(Hidden::new().key("digest").name("digest"))
+            .with_field(
+                tr!("Cores"),
+                Number::<u64>::new().name("cores").min(1).max(8192),
+            );
+
+        if is_qemu {
+            panel = panel.with_field(
+                tr!("Sockets"),
+                Number::<u64>::new().name("sockets").min(1).max(16),
+            );
+        }
+
+        panel = panel.with_field(
+            tr!("Memory (MiB)"),
+            Number::<u64>::new().name("memory").min(16).step(32),
+        );
+
+        if !is_qemu {
+            panel = panel.with_field(
+                tr!("Swap (MiB)"),
+                Number::<u64>::new().name("swap").step(32),
+            );
+        }
+
+        panel = panel.with_field(tr!("Tags"), Field::new().name("tags"));
+
+        if is_qemu {
+            panel = panel.with_field(
+                tr!("Boot Order"),
+                Field::new().name("boot").placeholder("order=scsi0;net0"),
+            );
+        }
+
+        panel
+            .with_field(
+                tr!("Network Device") + " (net0)",
+                Field::new().name("net0").placeholder(if is_qemu {
+                    "virtio,bridge=vmbr0"
+                } else {
+                    "name=eth0,bridge=vmbr0,ip=dhcp"
+                }),
+            )
+            .with_large_field(tr!("Notes"), TextArea::new().name("description"))
+            .with_large_field(
+                tr!("Pending Changes"),
+                DisplayField::new()
+                    .name("pending-changes")
+                    .key("pending-changes"),
+            )
+            .into()
+    }
+}
+
+impl Component for PdmGuestConfigEditWindow {
+    type Message = ();
+    type Properties = GuestConfigEditWindow;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {}
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+        let guest_info = props.guest_info;
+
+        let title = match guest_info.guest_type {
+            GuestType::Qemu => tr!("VM {0}", guest_info.vmid),
+            GuestType::Lxc => tr!("CT {0}", guest_info.vmid),
+        };
+
+        EditWindow::new(tr!("Edit") + ": " + &title)
+            .width(600)
+            .on_done(props.on_done.clone())
+            .loader({
+                let remote = props.remote.clone();
+                move || Self::load(remote.clone(), guest_info)
+            })
+            .renderer(move |_form_ctx| Self::input_panel(guest_info.guest_type))
+            .on_submit({
+                let remote = props.remote.clone();
+                move |form_ctx| Self::submit(remote.clone(), guest_info, form_ctx)
+            })
+            .into()
+    }
+}
+
+fn parse_memory(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(memory) => memory.as_u64(),
+        Value::String(memory) => memory
+            .split(',')
+            .find_map(|part| part.strip_prefix("current=").unwrap_or(part).parse().ok()),
+        _ => None,
+    }
+}
+
+fn render_value(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn render_pending_changes(pending: &[Value]) -> String {
+    let changes: Vec<String> = pending
+        .iter()
+        .filter_map(|item| {
+            let key = item["key"].as_str()?;
+            if item["delete"].as_u64().unwrap_or(0) > 0 {
+                return Some(format!("{key}: {}", tr!("delete")));
+            }
+            let new = render_value(item.get("pending")?);
+            match item.get("value") {
+                Some(old) => Some(format!("{key}: {} → {new}", render_value(old))),
+                None => Some(format!("{key}: {new}")),
+            }
+        })
+        .collect();
+
+    if changes.is_empty() {
+        tr!("None")
+    } else {
+        changes.join(", ")
+    }
+}
+
+/// Build the update from the form data, cleared text fields delete the property on the remote.
+fn build_update(data: &Value, guest_type: GuestType) -> GuestConfigUpdate {
+    let text = |name: &str| {
+        data[name]
+            .as_str()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+
+    let mut update = GuestConfigUpdate {
+        digest: text("digest"),
+        cores: data["cores"].as_u64(),
+        memory: data["memory"].as_u64(),
+        tags: text("tags"),
+        description: text("description"),
+        net: text("net0").map(|net0| vec![format!("net0={net0}")]),
+        ..Default::default()
+    };
+
+    let mut deletable = vec!["tags", "description"];
+
+    match guest_type {
+        GuestType::Qemu => {
+            update.sockets = data["sockets"].as_u64();
+            update.boot = text("boot");
+            deletable.push("boot");
+        }
+        GuestType::Lxc => update.swap = data["swap"].as_u64(),
+    }
+
+    let delete: Vec<String> = deletable
+        .into_iter()
+        .filter(|name| text(name).is_none())
+        .map(str::to_string)
+        .collect();
+    if !delete.is_empty() {
+        update.delete = Some(delete);
+    }
+
+    update
+}
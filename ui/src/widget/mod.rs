@@ -1,3 +1,6 @@
+mod guest_config_edit;
+pub use guest_config_edit::GuestConfigEditWindow;
+
 mod migrate_window;
 pub use migrate_window::MigrateWindow;
 
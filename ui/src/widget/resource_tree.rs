@@ -102,7 +102,8 @@ impl Default for ResourceTree {
 enum PdmTreeEntry {
     Root,
     Resource(String, Resource),
-    Remote(String, Option<String>),
+    /// A remote with the error when querying it and whether it is in maintenance mode.
+    Remote(String, Option<String>, bool),
 }
 
 impl ExtractPrimaryKey for PdmTreeEntry {
@@ -110,7 +111,7 @@ impl ExtractPrimaryKey for PdmTreeEntry {
         match self {
             PdmTreeEntry::Root => Key::from("__root__"),
             PdmTreeEntry::Resource(_, resource) => Key::from(resource.global_id()),
-            PdmTreeEntry::Remote(remote, _) => Key::from(remote.as_str()),
+            PdmTreeEntry::Remote(remote, _, _) => Key::from(remote.as_str()),
         }
     }
 }
@@ -209,8 +210,11 @@ impl Component for PdmResourceTree {
                         let mut store = self.store.write();
                         let mut root = store.set_root(PdmTreeEntry::Root);
                         for res in result.into_iter() {
-                            let mut node =
-                                root.append(PdmTreeEntry::Remote(res.remote.clone(), res.error));
+                            let mut node = root.append(PdmTreeEntry::Remote(
+                                res.remote.clone(),
+                                res.error,
+                                res.maintenance,
+                            ));
                             node.set_expanded(true);
                             for entry in res.resources.into_iter() {
                                 if let Resource::PbsNode(_) = entry {
@@ -224,9 +228,11 @@ impl Component for PdmResourceTree {
                             (PdmTreeEntry::Root, PdmTreeEntry::Root) => Ordering::Equal,
                             (PdmTreeEntry::Root, _) => Ordering::Less,
                             (_, PdmTreeEntry::Root) => Ordering::Greater,
-                            (PdmTreeEntry::Remote(a, _), PdmTreeEntry::Remote(b, _)) => a.cmp(b),
-                            (PdmTreeEntry::Remote(_, _), _) => Ordering::Less,
-                            (_, PdmTreeEntry::Remote(_, _)) => Ordering::Greater,
+                            (PdmTreeEntry::Remote(a, _, _), PdmTreeEntry::Remote(b, _, _)) => {
+                                a.cmp(b)
+                            }
+                            (PdmTreeEntry::Remote(_, _, _), _) => Ordering::Less,
+                            (_, PdmTreeEntry::Remote(_, _, _)) => Ordering::Greater,
                             (PdmTreeEntry::Resource(_, a), PdmTreeEntry::Resource(_, b)) => {
                                 a.id().cmp(&b.id())
                             }
@@ -257,7 +263,7 @@ impl Component for PdmResourceTree {
                             crate::navigate_to(ctx.link(), remote, Some(resource));
                             navigated = true;
                         }
-                        PdmTreeEntry::Remote(remote, _) => {
+                        PdmTreeEntry::Remote(remote, _, _) => {
                             crate::navigate_to(ctx.link(), remote, None);
                             navigated = true;
                         }
@@ -389,22 +395,31 @@ fn columns(
                             .into(),
                         None,
                     ),
-                    PdmTreeEntry::Remote(remote, err) => (
-                        Container::new()
-                            .class("pdm-type-icon")
-                            .with_child(Fa::new("server").fixed_width())
-                            .with_optional_child(err.is_some().then_some(
-                                Fa::from(Status::Error).fixed_width().class("status-icon"),
-                            )),
-                        match err {
-                            Some(err) => {
-                                colspan = true;
-                                format!("{remote} - {err}").into()
-                            }
-                            None => remote.into(),
-                        },
-                        err.as_ref().map(|err| err.to_string()),
-                    ),
+                    PdmTreeEntry::Remote(remote, err, maintenance) => {
+                        (
+                            Container::new()
+                                .class("pdm-type-icon")
+                                .with_child(Fa::new("server").fixed_width())
+                                .with_optional_child(err.is_some().then_some(
+                                    Fa::from(Status::Error).fixed_width().class("status-icon"),
+                                ))
+                                .with_optional_child(maintenance.then_some(
+                                    Fa::new("wrench").fixed_width().class("status-icon"),
+                                )),
+                            match err {
+                                Some(err) => {
+                                    colspan = true;
+                                    format!("{remote} - {err}").into()
+                                }
+                                None if *maintenance => {
+                                    colspan = true;
+                                    format!("{remote} - {}", tr!("in maintenance")).into()
+                                }
+                                None => remote.into(),
+                            },
+                            err.as_ref().map(|err| err.to_string()),
+                        )
+                    }
                 };
                 if colspan {
                     args.set_attribute("colspan", "2");
@@ -422,7 +437,7 @@ fn columns(
                     PdmTreeEntry::Resource(_, resource) => {
                         get_resource_node(resource).unwrap_or("")
                     }
-                    PdmTreeEntry::Remote(_, _) => "",
+                    PdmTreeEntry::Remote(_, _, _) => "",
                 }
                 .into()
             })
@@ -438,7 +453,7 @@ fn columns(
                         PdmTreeEntry::Resource(remote_id, resource) => {
                             (remote_id, resource.id(), get_resource_node(resource))
                         }
-                        PdmTreeEntry::Remote(remote_id, _) => (remote_id, String::new(), None),
+                        PdmTreeEntry::Remote(remote_id, _, _) => (remote_id, String::new(), None),
                     };
 
                     match get_deep_url(&link, remote, node, &id) {